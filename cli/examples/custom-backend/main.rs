@@ -24,6 +24,7 @@ use jj_cli::cli_util::CommandHelper;
 use jj_cli::command_error::CommandError;
 use jj_cli::ui::Ui;
 use jj_lib::backend::Backend;
+use jj_lib::backend::BackendGcStats;
 use jj_lib::backend::BackendInitError;
 use jj_lib::backend::BackendLoadError;
 use jj_lib::backend::BackendResult;
@@ -202,7 +203,12 @@ impl Backend for JitBackend {
         self.inner.get_copy_records(paths, root, head)
     }
 
-    fn gc(&self, index: &dyn Index, keep_newer: SystemTime) -> BackendResult<()> {
-        self.inner.gc(index, keep_newer)
+    fn gc(
+        &self,
+        index: &dyn Index,
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> BackendResult<BackendGcStats> {
+        self.inner.gc(index, keep_newer, dry_run)
     }
 }
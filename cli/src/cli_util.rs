@@ -13,8 +13,11 @@
 // limitations under the License.
 
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::cell::OnceCell;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
 use std::env::VarError;
@@ -30,6 +33,7 @@ use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitCode;
 use std::rc::Rc;
+use std::slice;
 use std::str;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -51,15 +55,19 @@ use clap_complete::ArgValueCandidates;
 use indexmap::IndexMap;
 use indexmap::IndexSet;
 use itertools::Itertools;
+use jj_lib::backend::BackendResult;
 use jj_lib::backend::ChangeId;
 use jj_lib::backend::CommitId;
 use jj_lib::backend::MergedTreeId;
+use jj_lib::backend::RecordedCopy;
 use jj_lib::backend::TreeValue;
 use jj_lib::commit::Commit;
+use jj_lib::copies::compose_recorded_copies;
 use jj_lib::file_util;
 use jj_lib::fileset;
 use jj_lib::fileset::FilesetDiagnostics;
 use jj_lib::fileset::FilesetExpression;
+use jj_lib::fsmonitor::FsmonitorSettings;
 use jj_lib::git;
 use jj_lib::git_backend::GitBackend;
 use jj_lib::gitignore::GitIgnoreError;
@@ -156,7 +164,10 @@ use crate::diff_util::DiffRenderer;
 use crate::formatter::FormatRecorder;
 use crate::formatter::Formatter;
 use crate::formatter::PlainTextFormatter;
+use crate::gc_util::exceeds_auto_gc_thresholds;
+use crate::gc_util::maybe_run_auto_gc;
 use crate::git_util::is_colocated_git_workspace;
+use crate::git_util::is_git_worktree_workspace;
 use crate::git_util::print_failed_git_export;
 use crate::git_util::print_git_import_stats;
 use crate::merge_tools::DiffEditor;
@@ -166,6 +177,8 @@ use crate::operation_templater::OperationTemplateLanguage;
 use crate::operation_templater::OperationTemplateLanguageExtension;
 use crate::revset_util;
 use crate::revset_util::RevsetExpressionEvaluator;
+use crate::sparse_util::sparse_profiles_changed;
+use crate::sparse_util::update_sparse_patterns_from_profile;
 use crate::template_builder;
 use crate::template_builder::TemplateLanguage;
 use crate::template_parser::TemplateAliasesMap;
@@ -284,6 +297,8 @@ struct CommandHelperData {
     maybe_workspace_loader: Result<Box<dyn WorkspaceLoader>, CommandError>,
     store_factories: StoreFactories,
     working_copy_factories: WorkingCopyFactories,
+    // Set when a new operation exceeded the `gc.auto.*` thresholds.
+    auto_gc_requested: Cell<bool>,
 }
 
 impl CommandHelper {
@@ -366,12 +381,27 @@ impl CommandHelper {
             .map_err(Clone::clone)
     }
 
+    /// Returns true if garbage collection should be run after the command.
+    pub fn is_auto_gc_requested(&self) -> bool {
+        self.data.auto_gc_requested.get()
+    }
+
+    /// Requests garbage collection after the command if the repo exceeds the
+    /// `gc.auto.*` thresholds.
+    fn request_auto_gc_if_needed(&self, repo_loader: &RepoLoader) -> Result<(), CommandError> {
+        if !self.is_auto_gc_requested() && exceeds_auto_gc_thresholds(self.settings(), repo_loader)?
+        {
+            self.data.auto_gc_requested.set(true);
+        }
+        Ok(())
+    }
+
     /// Loads workspace and repo, then snapshots the working copy if allowed.
     #[instrument(skip(self, ui))]
     pub fn workspace_helper(&self, ui: &Ui) -> Result<WorkspaceCommandHelper, CommandError> {
         let mut workspace_command = self.workspace_helper_no_snapshot(ui)?;
 
-        let workspace_command = match workspace_command.maybe_snapshot_impl(ui) {
+        let workspace_command = match workspace_command.maybe_snapshot_impl(ui, false) {
             Ok(()) => workspace_command,
             Err(SnapshotWorkingCopyError::Command(err)) => return Err(err),
             Err(SnapshotWorkingCopyError::StaleWorkingCopy(err)) => {
@@ -409,6 +439,10 @@ impl CommandHelper {
         WorkspaceCommandHelper::new(ui, workspace, repo, env, self.is_at_head_operation())
     }
 
+    pub fn get_store_factories(&self) -> &StoreFactories {
+        &self.data.store_factories
+    }
+
     pub fn get_working_copy_factory(&self) -> Result<&dyn WorkingCopyFactory, CommandError> {
         let loader = self.workspace_loader()?;
 
@@ -717,8 +751,9 @@ pub struct WorkspaceCommandEnvironment {
 impl WorkspaceCommandEnvironment {
     #[instrument(skip_all)]
     fn new(ui: &Ui, command: &CommandHelper, workspace: &Workspace) -> Result<Self, CommandError> {
-        let revset_aliases_map =
+        let mut revset_aliases_map =
             revset_util::load_revset_aliases(ui, &command.data.layered_configs)?;
+        revset_util::load_bisect_aliases(ui, &mut revset_aliases_map, workspace.repo_path())?;
         let template_aliases_map = command.load_template_aliases(ui)?;
         let path_converter = RepoPathUiConverter::Fs {
             cwd: command.cwd().to_owned(),
@@ -750,6 +785,44 @@ impl WorkspaceCommandEnvironment {
         &self.workspace_id
     }
 
+    /// Parses the given strings as file patterns.
+    pub fn parse_file_patterns(
+        &self,
+        ui: &Ui,
+        values: &[String],
+    ) -> Result<FilesetExpression, CommandError> {
+        // TODO: This function might be superseded by parse_union_filesets(),
+        // but it would be weird if parse_union_*() had a special case for the
+        // empty arguments.
+        if values.is_empty() {
+            Ok(FilesetExpression::all())
+        } else if self.settings().config().get_bool("ui.allow-filesets")? {
+            self.parse_union_filesets(ui, values)
+        } else {
+            let expressions = values
+                .iter()
+                .map(|v| self.path_converter.parse_file_path(v))
+                .map_ok(FilesetExpression::prefix_path)
+                .try_collect()?;
+            Ok(FilesetExpression::union_all(expressions))
+        }
+    }
+
+    /// Parses the given fileset expressions and concatenates them all.
+    pub fn parse_union_filesets(
+        &self,
+        ui: &Ui,
+        file_args: &[String], // TODO: introduce FileArg newtype?
+    ) -> Result<FilesetExpression, CommandError> {
+        let mut diagnostics = FilesetDiagnostics::new();
+        let expressions: Vec<_> = file_args
+            .iter()
+            .map(|arg| fileset::parse_maybe_bare(&mut diagnostics, arg, &self.path_converter))
+            .try_collect()?;
+        print_parse_diagnostics(ui, "In fileset expression", &diagnostics)?;
+        Ok(FilesetExpression::union_all(expressions))
+    }
+
     pub(crate) fn revset_parse_context(&self) -> RevsetParseContext {
         let workspace_context = RevsetWorkspaceContext {
             path_converter: &self.path_converter,
@@ -919,6 +992,8 @@ pub struct WorkspaceCommandHelper {
     op_summary_template_text: String,
     may_update_working_copy: bool,
     working_copy_shared_with_git: bool,
+    // The working copy is a linked Git worktree, which has its own HEAD.
+    working_copy_is_git_worktree: bool,
 }
 
 enum SnapshotWorkingCopyError {
@@ -957,7 +1032,9 @@ impl WorkspaceCommandHelper {
         let op_summary_template_text = settings.config().get_string("templates.op_summary")?;
         let may_update_working_copy =
             loaded_at_head && !env.command.global_args().ignore_working_copy;
-        let working_copy_shared_with_git = is_colocated_git_workspace(&workspace, &repo);
+        let working_copy_is_git_worktree = is_git_worktree_workspace(&workspace, &repo);
+        let working_copy_shared_with_git =
+            working_copy_is_git_worktree || is_colocated_git_workspace(&workspace, &repo);
         let helper = Self {
             workspace,
             user_repo: ReadonlyUserRepo::new(repo),
@@ -966,6 +1043,7 @@ impl WorkspaceCommandHelper {
             op_summary_template_text,
             may_update_working_copy,
             working_copy_shared_with_git,
+            working_copy_is_git_worktree,
         };
         // Parse commit_summary template early to report error before starting
         // mutable operation.
@@ -1000,16 +1078,22 @@ impl WorkspaceCommandHelper {
     }
 
     #[instrument(skip_all)]
-    fn maybe_snapshot_impl(&mut self, ui: &Ui) -> Result<(), SnapshotWorkingCopyError> {
+    fn maybe_snapshot_impl(
+        &mut self,
+        ui: &Ui,
+        is_auto_snapshot: bool,
+    ) -> Result<(), SnapshotWorkingCopyError> {
         if self.may_update_working_copy {
-            if self.working_copy_shared_with_git {
+            // The view only tracks the HEAD of the main worktree, so HEAD changes
+            // in linked worktrees aren't imported.
+            if self.working_copy_shared_with_git && !self.working_copy_is_git_worktree {
                 self.import_git_head(ui).map_err(snapshot_command_error)?;
             }
             // Because the Git refs (except HEAD) aren't imported yet, the ref
             // pointing to the new working-copy commit might not be exported.
             // In that situation, the ref would be conflicted anyway, so export
             // failure is okay.
-            self.snapshot_working_copy(ui)?;
+            self.snapshot_working_copy(ui, is_auto_snapshot)?;
 
             // import_git_refs() can rebase the working-copy commit.
             if self.working_copy_shared_with_git {
@@ -1023,7 +1107,15 @@ impl WorkspaceCommandHelper {
     /// copy is collocated with Git.
     #[instrument(skip_all)]
     pub fn maybe_snapshot(&mut self, ui: &Ui) -> Result<(), CommandError> {
-        self.maybe_snapshot_impl(ui)
+        self.maybe_snapshot_impl(ui, false)
+            .map_err(|err| err.into_command_error())
+    }
+
+    /// Like [`Self::maybe_snapshot()`], but describes the operation as an
+    /// automatic snapshot, which isn't the result of running a command.
+    #[instrument(skip_all)]
+    pub fn maybe_auto_snapshot(&mut self, ui: &Ui) -> Result<(), CommandError> {
+        self.maybe_snapshot_impl(ui, true)
             .map_err(|err| err.into_command_error())
     }
 
@@ -1230,21 +1322,7 @@ to the current parents may contain changes from multiple commits.
         ui: &Ui,
         values: &[String],
     ) -> Result<FilesetExpression, CommandError> {
-        // TODO: This function might be superseded by parse_union_filesets(),
-        // but it would be weird if parse_union_*() had a special case for the
-        // empty arguments.
-        if values.is_empty() {
-            Ok(FilesetExpression::all())
-        } else if self.settings().config().get_bool("ui.allow-filesets")? {
-            self.parse_union_filesets(ui, values)
-        } else {
-            let expressions = values
-                .iter()
-                .map(|v| self.parse_file_path(v))
-                .map_ok(FilesetExpression::prefix_path)
-                .try_collect()?;
-            Ok(FilesetExpression::union_all(expressions))
-        }
+        self.env.parse_file_patterns(ui, values)
     }
 
     /// Parses the given fileset expressions and concatenates them all.
@@ -1253,13 +1331,7 @@ to the current parents may contain changes from multiple commits.
         ui: &Ui,
         file_args: &[String], // TODO: introduce FileArg newtype?
     ) -> Result<FilesetExpression, CommandError> {
-        let mut diagnostics = FilesetDiagnostics::new();
-        let expressions: Vec<_> = file_args
-            .iter()
-            .map(|arg| fileset::parse_maybe_bare(&mut diagnostics, arg, self.path_converter()))
-            .try_collect()?;
-        print_parse_diagnostics(ui, "In fileset expression", &diagnostics)?;
-        Ok(FilesetExpression::union_all(expressions))
+        self.env.parse_union_filesets(ui, file_args)
     }
 
     pub fn auto_tracking_matcher(&self, ui: &Ui) -> Result<Box<dyn Matcher>, CommandError> {
@@ -1698,7 +1770,11 @@ to the current parents may contain changes from multiple commits.
     }
 
     #[instrument(skip_all)]
-    fn snapshot_working_copy(&mut self, ui: &Ui) -> Result<(), SnapshotWorkingCopyError> {
+    fn snapshot_working_copy(
+        &mut self,
+        ui: &Ui,
+        is_auto_snapshot: bool,
+    ) -> Result<(), SnapshotWorkingCopyError> {
         let workspace_id = self.workspace_id().to_owned();
         let get_wc_commit = |repo: &ReadonlyRepo| -> Result<Option<_>, _> {
             repo.view()
@@ -1719,10 +1795,8 @@ to the current parents may contain changes from multiple commits.
             .map_err(snapshot_command_error)?;
 
         // Compare working-copy tree and operation with repo's, and reload as needed.
-        let fsmonitor_settings = self
-            .settings()
-            .fsmonitor_settings()
-            .map_err(snapshot_command_error)?;
+        let fsmonitor_settings =
+            fsmonitor_settings(self.settings()).map_err(snapshot_command_error)?;
         let max_new_file_size = self
             .settings()
             .max_new_file_size()
@@ -1797,7 +1871,14 @@ See https://martinvonz.github.io/jj/latest/working-copy/#stale-working-copy \
             })
             .map_err(snapshot_command_error)?;
         drop(progress);
+        let mut new_wc_commit = None;
         if new_tree_id != *wc_commit.tree_id() {
+            let copies = recorded_copies_after_snapshot(
+                &wc_commit,
+                &new_tree_id,
+                &locked_ws.locked_wc().snapshot_copies(),
+            )
+            .map_err(snapshot_command_error)?;
             let mut tx = start_repo_transaction(
                 &self.user_repo.repo,
                 command.settings(),
@@ -1808,6 +1889,7 @@ See https://martinvonz.github.io/jj/latest/working-copy/#stale-working-copy \
             let commit = mut_repo
                 .rewrite_commit(command.settings(), &wc_commit)
                 .set_tree_id(new_tree_id)
+                .set_copies(copies)
                 .write()
                 .map_err(snapshot_command_error)?;
             mut_repo
@@ -1831,14 +1913,35 @@ See https://martinvonz.github.io/jj/latest/working-copy/#stale-working-copy \
                 print_failed_git_export(ui, &refs).map_err(snapshot_command_error)?;
             }
 
-            let repo = tx
-                .commit("snapshot working copy")
+            let description = if is_auto_snapshot {
+                "auto-snapshot working copy"
+            } else {
+                "snapshot working copy"
+            };
+            let repo = tx.commit(description).map_err(snapshot_command_error)?;
+            command
+                .request_auto_gc_if_needed(repo.loader())
                 .map_err(snapshot_command_error)?;
             self.user_repo = ReadonlyUserRepo::new(repo);
+            new_wc_commit = Some(commit);
         }
         locked_ws
             .finish(self.user_repo.repo.op_id().clone())
             .map_err(snapshot_command_error)?;
+        // The sparse profile may have been edited in the working copy.
+        if let Some(new_wc_commit) = new_wc_commit {
+            if sparse_profiles_changed(&wc_commit, &new_wc_commit)
+                .map_err(snapshot_command_error)?
+            {
+                update_sparse_patterns_from_profile(
+                    ui,
+                    &mut self.workspace,
+                    self.user_repo.repo.op_id(),
+                    &new_wc_commit,
+                )
+                .map_err(snapshot_command_error)?;
+            }
+        }
         Ok(())
     }
 
@@ -1873,6 +1976,14 @@ See https://martinvonz.github.io/jj/latest/working-copy/#stale-working-copy \
         if let Some(stats) = stats {
             print_checkout_stats(ui, stats, new_commit)?;
         }
+        if Some(new_commit) != maybe_old_commit {
+            update_sparse_patterns_from_profile(
+                ui,
+                &mut self.workspace,
+                self.user_repo.repo.op_id(),
+                new_commit,
+            )?;
+        }
         if Some(new_commit) != maybe_old_commit {
             if let Some(mut formatter) = ui.status_formatter() {
                 let conflicts = new_commit.tree()?.conflicts().collect_vec();
@@ -1946,15 +2057,23 @@ See https://martinvonz.github.io/jj/latest/working-copy/#stale-working-copy \
             .transpose()?;
 
         if self.working_copy_shared_with_git {
-            let git_repo = self.git_backend().unwrap().open_git_repo()?;
             if let Some(wc_commit) = &maybe_new_wc_commit {
-                git::reset_head(tx.repo_mut(), &git_repo, wc_commit)?;
+                if self.working_copy_is_git_worktree {
+                    let git_repo = git2::Repository::open(self.workspace_root())?;
+                    git::reset_worktree_head(&git_repo, wc_commit)?;
+                } else {
+                    let git_repo = self.git_backend().unwrap().open_git_repo()?;
+                    git::reset_head(tx.repo_mut(), &git_repo, wc_commit)?;
+                }
             }
             let refs = git::export_refs(tx.repo_mut())?;
             print_failed_git_export(ui, &refs)?;
         }
 
         self.user_repo = ReadonlyUserRepo::new(tx.commit(description)?);
+        self.env
+            .command
+            .request_auto_gc_if_needed(self.repo().loader())?;
 
         // Update working copy before reporting repo changes, so that
         // potential errors while reporting changes (broken pipe, etc)
@@ -2334,6 +2453,29 @@ impl WorkspaceCommandTransaction<'_> {
     }
 }
 
+/// Returns the filesystem monitor settings. Unless configured otherwise, the
+/// inotify daemon is run by this executable.
+pub fn fsmonitor_settings(
+    settings: &UserSettings,
+) -> Result<FsmonitorSettings, config::ConfigError> {
+    let mut fsmonitor_settings = settings.fsmonitor_settings()?;
+    if let FsmonitorSettings::Inotify(config) = &mut fsmonitor_settings {
+        if config.daemon_command.is_empty() {
+            // If the path isn't valid UTF-8, the daemon can't be started and
+            // the working copy is scanned instead.
+            if let Some(exe) = env::current_exe()
+                .ok()
+                .and_then(|exe| exe.into_os_string().into_string().ok())
+            {
+                config.daemon_command = [&exe, "debug", "inotify", "daemon"]
+                    .map(str::to_owned)
+                    .to_vec();
+            }
+        }
+    }
+    Ok(fsmonitor_settings)
+}
+
 pub fn find_workspace_dir(cwd: &Path) -> &Path {
     cwd.ancestors()
         .find(|path| path.join(".jj").is_dir())
@@ -2621,6 +2763,25 @@ pub fn print_trackable_remote_bookmarks(ui: &Ui, view: &View) -> io::Result<()>
     Ok(())
 }
 
+/// Returns the copies to record on the snapshotted working-copy commit,
+/// given the copies detected by the snapshot.
+fn recorded_copies_after_snapshot(
+    wc_commit: &Commit,
+    new_tree_id: &MergedTreeId,
+    snapshot_copies: &[RecordedCopy],
+) -> BackendResult<Vec<RecordedCopy>> {
+    let [parent_id] = wc_commit.parent_ids() else {
+        return Ok(vec![]);
+    };
+    if wc_commit.copies().is_empty() && snapshot_copies.is_empty() {
+        return Ok(vec![]);
+    }
+    let store = wc_commit.store();
+    let parent_tree = store.get_commit(parent_id)?.tree()?;
+    let new_tree = store.get_root_tree(new_tree_id)?;
+    compose_recorded_copies(wc_commit.copies(), snapshot_copies, &parent_tree, &new_tree)
+}
+
 pub fn update_working_copy(
     repo: &Arc<ReadonlyRepo>,
     workspace: &mut Workspace,
@@ -2736,6 +2897,11 @@ pub fn get_new_config_file_path(
             new_config_path()?.ok_or_else(|| user_error("No repo config path found to edit"))?
         }
         ConfigSource::Repo => command.workspace_loader()?.repo_path().join("config.toml"),
+        ConfigSource::Workspace => command
+            .workspace_loader()?
+            .workspace_root()
+            .join(".jj")
+            .join("config.toml"),
         _ => {
             return Err(user_error(format!(
                 "Can't get path for config source {config_source:?}"
@@ -2830,6 +2996,61 @@ pub fn short_operation_hash(operation_id: &OperationId) -> String {
     format!("{operation_id:.12}")
 }
 
+/// Replaces the `old_head_ops` with the reparented `new_head_ids`, and remaps
+/// the operation of the current workspace.
+pub fn update_reparented_op_heads(
+    ui: &Ui,
+    command: &CommandHelper,
+    workspace: &mut Workspace,
+    old_head_ops: &[Operation],
+    new_head_ids: &[OperationId],
+) -> Result<(), CommandError> {
+    let op_heads_store = workspace.repo_loader().op_heads_store().clone();
+    let reparented_head_ops = || iter::zip(old_head_ops, new_head_ids);
+    for (old, new_id) in reparented_head_ops().filter(|&(old, new_id)| old.id() != new_id) {
+        op_heads_store.update_op_heads(slice::from_ref(old.id()), new_id)?;
+    }
+    // Remap the operation id of the current workspace. If there were any
+    // divergent operations, user will need to re-abandon their ancestors.
+    if !command.global_args().ignore_working_copy {
+        let mut locked_ws = workspace.start_working_copy_mutation()?;
+        let old_op_id = locked_ws.locked_wc().old_operation_id();
+        if let Some((_, new_id)) = reparented_head_ops().find(|(old, _)| old.id() == old_op_id) {
+            locked_ws.finish(new_id.clone())?;
+        } else {
+            writeln!(
+                ui.warning_default(),
+                "The working copy operation {} is not updated because it differs from the repo {}.",
+                short_operation_hash(old_op_id),
+                old_head_ops
+                    .iter()
+                    .map(|op| short_operation_hash(op.id()))
+                    .join(", "),
+            )?;
+        }
+    }
+    // The working-copy operations of the other workspaces aren't remapped.
+    let mut other_workspace_ids = BTreeSet::new();
+    for op in old_head_ops {
+        let view = op.view()?;
+        other_workspace_ids.extend(
+            view.wc_commit_ids()
+                .keys()
+                .filter(|id| *id != workspace.workspace_id())
+                .cloned(),
+        );
+    }
+    if !other_workspace_ids.is_empty() {
+        writeln!(
+            ui.hint_default(),
+            "The working copies of other workspaces ({}) may now be stale. Run `jj workspace \
+             update-stale` in them to update them.",
+            other_workspace_ids.iter().map(|id| id.as_str()).join(", "),
+        )?;
+    }
+    Ok(())
+}
+
 /// Wrapper around a `DiffEditor` to conditionally start interactive session.
 #[derive(Clone, Debug)]
 pub enum DiffSelector {
@@ -2978,7 +3199,10 @@ pub struct GlobalArgs {
     /// that divergent operations will never be merged.
     ///
     /// Use `jj op log` to find the operation ID you want. Any unambiguous
-    /// prefix of the operation ID is enough.
+    /// prefix of the operation ID is enough. The operation can also be
+    /// addressed by time: `--at-op='at:2024-01-01 12:00'` and
+    /// `--at-op='ago:2 hours'` load the latest operation that had finished by
+    /// then.
     ///
     /// When loading the repo at an earlier operation, the working copy will be
     /// ignored, as if `--ignore-working-copy` had been specified.
@@ -2994,6 +3218,13 @@ pub struct GlobalArgs {
         add = ArgValueCandidates::new(complete::operations),
     )]
     pub at_operation: Option<String>,
+    /// Add a tag to the created operations (can be repeated)
+    ///
+    /// Tags are shown by `jj op log`, and operations can be filtered by tag
+    /// with e.g. `jj op log -r 'tags(build, 1234)'`. Tags can also be set in
+    /// the `operation.tags` config table.
+    #[arg(long, value_name = "KEY=VALUE", global = true, value_parser = parse_op_tag)]
+    pub op_tag: Vec<(String, String)>,
     /// Enable debug logging
     #[arg(long, global = true)]
     pub debug: bool,
@@ -3197,6 +3428,16 @@ fn resolve_aliases(
     }
 }
 
+fn parse_op_tag(s: &str) -> Result<(String, String), &'static str> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or("Expected tag in the form KEY=VALUE")?;
+    if key.is_empty() {
+        return Err("Tag key must not be empty");
+    }
+    Ok((key.to_owned(), value.to_owned()))
+}
+
 /// Parse args that must be interpreted early, e.g. before printing help.
 fn handle_early_args(
     ui: &mut Ui,
@@ -3526,14 +3767,18 @@ impl CliRunner {
             .map_err(|err| map_workspace_load_error(err, None));
         layered_configs.read_user_config()?;
         let mut repo_config_path = None;
+        let mut workspace_config_path = None;
         if let Ok(loader) = &maybe_cwd_workspace_loader {
             layered_configs.read_repo_config(loader.repo_path())?;
+            layered_configs.read_workspace_config(loader.workspace_root())?;
             repo_config_path = Some(layered_configs.repo_config_path(loader.repo_path()));
+            workspace_config_path =
+                Some(layered_configs.workspace_config_path(loader.workspace_root()));
         }
         let config = layered_configs.merge();
         ui.reset(&config).map_err(|e| {
             let user_config_path = layered_configs.user_config_path().unwrap_or(None);
-            let paths = [repo_config_path, user_config_path]
+            let paths = [workspace_config_path, repo_config_path, user_config_path]
                 .into_iter()
                 .flatten()
                 .map(|path| format!("- {}", path.display()))
@@ -3565,6 +3810,7 @@ impl CliRunner {
                 .create(&cwd.join(path))
                 .map_err(|err| map_workspace_load_error(err, Some(path)))?;
             layered_configs.read_repo_config(loader.repo_path())?;
+            layered_configs.read_workspace_config(loader.workspace_root())?;
             Ok(loader)
         } else {
             maybe_cwd_workspace_loader
@@ -3586,6 +3832,20 @@ impl CliRunner {
             }
         }
 
+        // Tags from --op-tag take precedence over the configured tags.
+        let config = if args.global_args.op_tag.is_empty() {
+            config
+        } else {
+            let mut tags = config
+                .get::<HashMap<String, String>>("operation.tags")
+                .unwrap_or_default();
+            tags.extend(args.global_args.op_tag.iter().cloned());
+            config::Config::builder()
+                .add_source(config)
+                .set_override("operation.tags", tags)?
+                .build()?
+        };
+
         let settings = UserSettings::from_config(config);
         let command_helper_data = CommandHelperData {
             app: self.app,
@@ -3601,6 +3861,7 @@ impl CliRunner {
             maybe_workspace_loader,
             store_factories: self.store_factories,
             working_copy_factories: self.working_copy_factories,
+            auto_gc_requested: Cell::new(false),
         };
         let command_helper = CommandHelper {
             data: Rc::new(command_helper_data),
@@ -3608,7 +3869,11 @@ impl CliRunner {
         for start_hook_fn in self.start_hook_fns {
             start_hook_fn(ui, &command_helper)?;
         }
-        (self.dispatch_fn)(ui, &command_helper)
+        (self.dispatch_fn)(ui, &command_helper)?;
+        if !crate::commands::is_gc_command(command_helper.matches()) {
+            maybe_run_auto_gc(ui, &command_helper)?;
+        }
+        Ok(())
     }
 
     #[must_use]
@@ -22,6 +22,7 @@ use std::sync::Arc;
 
 use itertools::Itertools as _;
 use jj_lib::backend::BackendError;
+use jj_lib::bisect::BisectStateError;
 use jj_lib::dsl_util::Diagnostics;
 use jj_lib::fileset::FilePatternParseError;
 use jj_lib::fileset::FilesetParseError;
@@ -30,12 +31,16 @@ use jj_lib::git::GitConfigParseError;
 use jj_lib::git::GitExportError;
 use jj_lib::git::GitImportError;
 use jj_lib::git::GitRemoteManagementError;
+use jj_lib::git::GitWorktreeError;
 use jj_lib::gitignore::GitIgnoreError;
+use jj_lib::op_bundle::OpBundleError;
 use jj_lib::op_heads_store::OpHeadResolutionError;
 use jj_lib::op_heads_store::OpHeadsStoreError;
 use jj_lib::op_store::OpStoreError;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
+use jj_lib::opset::OpsetParseError;
+use jj_lib::opset::OpsetParseErrorKind;
 use jj_lib::repo::CheckOutCommitError;
 use jj_lib::repo::EditCommitError;
 use jj_lib::repo::RepoLoaderError;
@@ -54,6 +59,8 @@ use jj_lib::working_copy::ResetError;
 use jj_lib::working_copy::SnapshotError;
 use jj_lib::working_copy::WorkingCopyStateError;
 use jj_lib::workspace::WorkspaceInitError;
+use jj_lib::workspace::WorkspaceMoveError;
+use jj_lib::workspace_store::WorkspaceStoreError;
 use thiserror::Error;
 
 use crate::cli_util::short_operation_hash;
@@ -315,10 +322,43 @@ impl From<WorkspaceInitError> for CommandError {
             }
             WorkspaceInitError::SignInit(err @ SignInitError::UnknownBackend(_)) => user_error(err),
             WorkspaceInitError::SignInit(err) => internal_error(err),
+            WorkspaceInitError::WorkspaceStore(err) => err.into(),
         }
     }
 }
 
+impl From<WorkspaceMoveError> for CommandError {
+    fn from(err: WorkspaceMoveError) -> Self {
+        match err {
+            WorkspaceMoveError::DestinationExists(_) => user_error(err),
+            WorkspaceMoveError::NonUnicodePath => {
+                user_error("The repo path contains non-unicode characters")
+            }
+            WorkspaceMoveError::UnknownWorkspaces(ref ids) => {
+                let names = ids.iter().map(|id| id.as_str()).join(", ");
+                user_error_with_hint(
+                    err,
+                    format!(
+                        "The repo files of these workspaces can't be updated: {names}. Run any jj \
+                         command in each of them first to record their location, or forget \
+                         them with `jj workspace forget`."
+                    ),
+                )
+            }
+            WorkspaceMoveError::Path(err) => {
+                user_error_with_message("Failed to move the workspace", err)
+            }
+            WorkspaceMoveError::WorkspaceStore(err) => err.into(),
+        }
+    }
+}
+
+impl From<WorkspaceStoreError> for CommandError {
+    fn from(err: WorkspaceStoreError) -> Self {
+        internal_error_with_message("Failed to access the workspace store", err)
+    }
+}
+
 impl From<OpHeadResolutionError> for CommandError {
     fn from(err: OpHeadResolutionError) -> Self {
         match err {
@@ -487,6 +527,17 @@ impl From<GitExportError> for CommandError {
     }
 }
 
+impl From<GitWorktreeError> for CommandError {
+    fn from(err: GitWorktreeError) -> Self {
+        match err {
+            GitWorktreeError::Path(err) => {
+                internal_error_with_message("Failed to register Git worktree", err)
+            }
+            _ => user_error_with_message("Failed to register Git worktree", err),
+        }
+    }
+}
+
 impl From<GitRemoteManagementError> for CommandError {
     fn from(err: GitRemoteManagementError) -> Self {
         user_error(err)
@@ -509,6 +560,18 @@ impl From<FilesetParseError> for CommandError {
     }
 }
 
+impl From<OpsetParseError> for CommandError {
+    fn from(err: OpsetParseError) -> Self {
+        let hint = opset_parse_error_hint(&err);
+        let mut cmd_err = user_error_with_message(
+            format!("Failed to parse operation set: {}", err.kind()),
+            err,
+        );
+        cmd_err.extend_hints(hint);
+        cmd_err
+    }
+}
+
 impl From<RecoverWorkspaceError> for CommandError {
     fn from(err: RecoverWorkspaceError) -> Self {
         match err {
@@ -586,6 +649,32 @@ impl From<WorkingCopyStateError> for CommandError {
     }
 }
 
+impl From<BisectStateError> for CommandError {
+    fn from(err: BisectStateError) -> Self {
+        internal_error_with_message("Failed to access bisection state", err)
+    }
+}
+
+impl From<OpBundleError> for CommandError {
+    fn from(err: OpBundleError) -> Self {
+        match err {
+            OpBundleError::Decode(_)
+            | OpBundleError::IncompatibleStore { .. }
+            | OpBundleError::MissingObject { .. }
+            | OpBundleError::IdMismatch { .. } => user_error(err),
+            OpBundleError::MissingParent { .. } => user_error_with_hint(
+                err,
+                "Export the missing ancestor operations as well, e.g. with `jj op export '::@'`",
+            ),
+            OpBundleError::Backend(err) => err.into(),
+            OpBundleError::OpStore(err) => err.into(),
+            OpBundleError::OpHeadsStore(err) => err.into(),
+            OpBundleError::RepoLoader(err) => err.into(),
+            OpBundleError::RevsetEvaluation(err) => err.into(),
+        }
+    }
+}
+
 impl From<GitIgnoreError> for CommandError {
     fn from(err: GitIgnoreError) -> Self {
         user_error_with_message("Failed to process .gitignore.", err)
@@ -630,6 +719,7 @@ fn file_pattern_parse_error_hint(err: &FilePatternParseError) -> Option<String>
         }
         FilePatternParseError::RelativePath(_) => None,
         FilePatternParseError::GlobPattern(_) => None,
+        FilePatternParseError::Regex(_) => None,
     }
 }
 
@@ -649,6 +739,22 @@ fn fileset_parse_error_hint(err: &FilesetParseError) -> Option<String> {
     }
 }
 
+fn opset_parse_error_hint(err: &OpsetParseError) -> Option<String> {
+    match err.kind() {
+        OpsetParseErrorKind::SyntaxError => Some(String::from(
+            "See https://martinvonz.github.io/jj/latest/operation-log/#operation-sets for \
+             operation set syntax.",
+        )),
+        OpsetParseErrorKind::NoSuchFunction {
+            name: _,
+            candidates,
+        } => format_similarity_hint(candidates),
+        OpsetParseErrorKind::InvalidArguments { .. } | OpsetParseErrorKind::Expression(_) => {
+            find_source_parse_error_hint(&err)
+        }
+    }
+}
+
 fn opset_resolution_error_hint(err: &OpsetResolutionError) -> Option<String> {
     match err {
         OpsetResolutionError::MultipleOperations {
@@ -661,7 +767,8 @@ fn opset_resolution_error_hint(err: &OpsetResolutionError) -> Option<String> {
         OpsetResolutionError::EmptyOperations(_)
         | OpsetResolutionError::InvalidIdPrefix(_)
         | OpsetResolutionError::NoSuchOperation(_)
-        | OpsetResolutionError::AmbiguousIdPrefix(_) => None,
+        | OpsetResolutionError::AmbiguousIdPrefix(_)
+        | OpsetResolutionError::InvalidTime { .. } => None,
     }
 }
 
@@ -42,6 +42,7 @@ use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPath;
 use jj_lib::repo_path::RepoPathUiConverter;
 use jj_lib::revset::ResolvedRevsetExpression;
+use jj_lib::revset::RevsetExpression;
 use jj_lib::settings::UserSettings;
 use pollster::FutureExt as _;
 use tracing::instrument;
@@ -192,6 +193,7 @@ async fn split_hunks_to_trees(
             repo,
             source.commit.id(),
             destinations,
+            &RevsetExpression::none(),
             left_path,
             left_text.clone(),
         )?;
@@ -0,0 +1,100 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::bisect::BisectState;
+use tracing::instrument;
+
+use super::bisect_next;
+use super::load_bisect_state;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Arguments shared by `jj bisect good`, `jj bisect bad`, and `jj bisect skip`
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectMarkArgs {
+    /// The revisions to mark [default: the commit being tested]
+    #[arg(add = ArgValueCandidates::new(complete::all_revisions))]
+    revisions: Vec<RevisionArg>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_good(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectMarkArgs,
+) -> Result<(), CommandError> {
+    mark_revisions(ui, command, args, BisectState::mark_good)
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_bad(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectMarkArgs,
+) -> Result<(), CommandError> {
+    mark_revisions(ui, command, args, BisectState::mark_bad)
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_skip(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectMarkArgs,
+) -> Result<(), CommandError> {
+    mark_revisions(ui, command, args, BisectState::mark_skipped)
+}
+
+fn mark_revisions(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectMarkArgs,
+    mark: fn(&mut BisectState, CommitId),
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut state = load_bisect_state(&workspace_command)?;
+    for commit_id in resolve_commit_ids(ui, &workspace_command, &state, &args.revisions)? {
+        mark(&mut state, commit_id);
+    }
+    bisect_next(ui, &mut workspace_command, &mut state)?;
+    Ok(())
+}
+
+fn resolve_commit_ids(
+    ui: &Ui,
+    workspace_command: &WorkspaceCommandHelper,
+    state: &BisectState,
+    revisions: &[RevisionArg],
+) -> Result<Vec<CommitId>, CommandError> {
+    if revisions.is_empty() {
+        let commit_id = state
+            .current
+            .clone()
+            .ok_or_else(|| user_error("No commit is being tested; specify revisions to mark"))?;
+        Ok(vec![commit_id])
+    } else {
+        let commit_ids = workspace_command
+            .parse_union_revsets(ui, revisions)?
+            .evaluate_to_commit_ids()?
+            .try_collect()?;
+        Ok(commit_ids)
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod mark;
+mod reset;
+mod run;
+mod start;
+
+use std::io::Write as _;
+
+use clap::Subcommand;
+use itertools::Itertools as _;
+use jj_lib::bisect;
+use jj_lib::bisect::BisectState;
+use jj_lib::bisect::BisectionResult;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::repo::Repo as _;
+use tracing::instrument;
+
+use self::mark::cmd_bisect_bad;
+use self::mark::cmd_bisect_good;
+use self::mark::cmd_bisect_skip;
+use self::mark::BisectMarkArgs;
+use self::reset::cmd_bisect_reset;
+use self::reset::BisectResetArgs;
+use self::run::cmd_bisect_run;
+use self::run::BisectRunArgs;
+use self::start::cmd_bisect_start;
+use self::start::BisectStartArgs;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Find the first bad commit by binary search
+///
+/// Start a bisection with `jj bisect start --good <rev> --bad <rev>`. At each
+/// step, a new working-copy commit is created on top of the commit to be
+/// tested. Test it and mark the result with `jj bisect good`, `jj bisect bad`,
+/// or `jj bisect skip`, or let `jj bisect run <command>` do it for you.
+///
+/// The commits marked so far can be selected by the `bisect_good()`,
+/// `bisect_bad()`, and `bisect_skip()` revset functions.
+#[derive(Subcommand, Clone, Debug)]
+pub(crate) enum BisectCommand {
+    /// Mark revisions as bad
+    Bad(BisectMarkArgs),
+    /// Mark revisions as good
+    Good(BisectMarkArgs),
+    Reset(BisectResetArgs),
+    Run(BisectRunArgs),
+    /// Mark revisions as untestable
+    Skip(BisectMarkArgs),
+    Start(BisectStartArgs),
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &BisectCommand,
+) -> Result<(), CommandError> {
+    match subcommand {
+        BisectCommand::Bad(args) => cmd_bisect_bad(ui, command, args),
+        BisectCommand::Good(args) => cmd_bisect_good(ui, command, args),
+        BisectCommand::Reset(args) => cmd_bisect_reset(ui, command, args),
+        BisectCommand::Run(args) => cmd_bisect_run(ui, command, args),
+        BisectCommand::Skip(args) => cmd_bisect_skip(ui, command, args),
+        BisectCommand::Start(args) => cmd_bisect_start(ui, command, args),
+    }
+}
+
+fn load_bisect_state(
+    workspace_command: &WorkspaceCommandHelper,
+) -> Result<BisectState, CommandError> {
+    BisectState::load(workspace_command.repo_path())?.ok_or_else(|| {
+        user_error_with_hint(
+            "No bisection in progress",
+            "Use `jj bisect start` to start bisecting.",
+        )
+    })
+}
+
+/// Checks out the next commit to test, or reports the first bad commit.
+///
+/// Returns `true` if there's a commit to test.
+fn bisect_next(
+    ui: &Ui,
+    workspace_command: &mut WorkspaceCommandHelper,
+    state: &mut BisectState,
+) -> Result<bool, CommandError> {
+    if state.good.is_empty() || state.bad.is_empty() {
+        state.current = None;
+        state.save(workspace_command.repo_path())?;
+        let missing = if state.good.is_empty() { "good" } else { "bad" };
+        writeln!(
+            ui.hint_default(),
+            "Mark at least one {missing} commit with `jj bisect {missing}` to continue."
+        )?;
+        return Ok(false);
+    }
+    match bisect::next_step(workspace_command.repo().as_ref(), state)? {
+        BisectionResult::Next {
+            commit_id,
+            num_candidates,
+        } => {
+            state.current = Some(commit_id.clone());
+            state.save(workspace_command.repo_path())?;
+            let commit = workspace_command.repo().store().get_commit(&commit_id)?;
+            // The candidates include the bad commit, which doesn't have to be
+            // tested.
+            let num_left = num_candidates - 1;
+            let num_steps = usize::BITS - num_left.leading_zeros();
+            writeln!(
+                ui.status(),
+                "Bisecting: {num_left} commits left to test after this (roughly {num_steps} steps)"
+            )?;
+            let mut tx = workspace_command.start_transaction();
+            tx.check_out(&commit)?;
+            tx.finish(ui, format!("bisect: check out commit {}", commit_id.hex()))?;
+            Ok(true)
+        }
+        BisectionResult::Done(commit_ids) => {
+            state.current = None;
+            state.save(workspace_command.repo_path())?;
+            let commits: Vec<_> = commit_ids
+                .iter()
+                .map(|id| workspace_command.repo().store().get_commit(id))
+                .try_collect()?;
+            let mut formatter = ui.stdout_formatter();
+            match commits.as_slice() {
+                [] => {
+                    return Err(user_error(
+                        "No commits to bisect between the good and bad commits",
+                    ));
+                }
+                [commit] => {
+                    writeln!(formatter, "The first bad commit is:")?;
+                    workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+                    writeln!(formatter)?;
+                }
+                _ => {
+                    writeln!(formatter, "The first bad commit could be any of:")?;
+                    for commit in &commits {
+                        write!(formatter, "  ")?;
+                        workspace_command.write_commit_summary(formatter.as_mut(), commit)?;
+                        writeln!(formatter)?;
+                    }
+                }
+            }
+            drop(formatter);
+            writeln!(
+                ui.hint_default(),
+                "Use `jj bisect reset` to finish the bisection."
+            )?;
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+
+use jj_lib::bisect::BisectState;
+use jj_lib::repo::Repo as _;
+use tracing::instrument;
+
+use super::load_bisect_state;
+use crate::cli_util::short_commit_hash;
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Finish the bisection
+///
+/// The working-copy commit at the time the bisection was started is edited
+/// again, unless it has been abandoned since.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectResetArgs {
+    /// Leave the working copy at the current commit
+    #[arg(long)]
+    keep_working_copy: bool,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_reset(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectResetArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let state = load_bisect_state(&workspace_command)?;
+    BisectState::remove(workspace_command.repo_path())?;
+    writeln!(ui.status(), "Bisection finished.")?;
+    let Some(start_commit_id) = state.start_commit.filter(|_| !args.keep_working_copy) else {
+        return Ok(());
+    };
+    if workspace_command.get_wc_commit_id() == Some(&start_commit_id) {
+        return Ok(());
+    }
+    let repo = workspace_command.repo().clone();
+    let is_visible = repo
+        .view()
+        .heads()
+        .iter()
+        .any(|head_id| repo.index().is_ancestor(&start_commit_id, head_id));
+    if !is_visible {
+        writeln!(
+            ui.warning_default(),
+            "The original working-copy commit {} is no longer visible",
+            short_commit_hash(&start_commit_id)
+        )?;
+        return Ok(());
+    }
+    let start_commit = repo.store().get_commit(&start_commit_id)?;
+    let mut tx = workspace_command.start_transaction();
+    tx.edit(&start_commit)?;
+    tx.finish(ui, "bisect: restore working-copy commit")?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::process::Command;
+
+use tracing::instrument;
+
+use super::bisect_next;
+use super::load_bisect_state;
+use crate::cli_util::short_commit_hash;
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Bisect automatically by running a command at each step
+///
+/// The command is run in the workspace root with the commit to test checked
+/// out. Exit status 0 marks the commit as good, 125 marks it as untestable,
+/// and any other status between 1 and 127 marks it as bad. Other statuses
+/// (including termination by signal) abort the bisection.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectRunArgs {
+    /// Command to run
+    command: String,
+    /// Arguments to pass to the command
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<String>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_run(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectRunArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let mut state = load_bisect_state(&workspace_command)?;
+    if state.current.is_none() && !bisect_next(ui, &mut workspace_command, &mut state)? {
+        return Ok(());
+    }
+    while let Some(commit_id) = state.current.clone() {
+        writeln!(
+            ui.status(),
+            "Running `{}` on commit {}",
+            args.command,
+            short_commit_hash(&commit_id)
+        )?;
+        let status = Command::new(&args.command)
+            .args(&args.args)
+            .current_dir(workspace_command.workspace_root())
+            .status()
+            .map_err(|err| {
+                user_error_with_message(
+                    format!("Failed to execute external command '{}'", &args.command),
+                    err,
+                )
+            })?;
+        match status.code() {
+            Some(0) => state.mark_good(commit_id),
+            Some(125) => state.mark_skipped(commit_id),
+            Some(1..=127) => state.mark_bad(commit_id),
+            Some(exit_code) => {
+                return Err(user_error(format!(
+                    "External command exited with {exit_code}; aborting the bisection"
+                )));
+            }
+            None => {
+                return Err(user_error(format!(
+                    "External command was terminated by: {status}; aborting the bisection"
+                )));
+            }
+        }
+        // Record changes made by the command before moving the working copy.
+        workspace_command.maybe_snapshot(ui)?;
+        bisect_next(ui, &mut workspace_command, &mut state)?;
+    }
+    Ok(())
+}
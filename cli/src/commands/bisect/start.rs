@@ -0,0 +1,74 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
+use jj_lib::bisect::BisectState;
+use tracing::instrument;
+
+use super::bisect_next;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::RevisionArg;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Start a bisection
+///
+/// If both good and bad revisions are specified, the first commit to test is
+/// checked out immediately. Otherwise, mark them later with `jj bisect good`
+/// and `jj bisect bad`.
+#[derive(clap::Args, Clone, Debug)]
+pub(crate) struct BisectStartArgs {
+    /// Revisions known to be good
+    #[arg(long, short, add = ArgValueCandidates::new(complete::all_revisions))]
+    good: Vec<RevisionArg>,
+    /// Revisions known to be bad
+    #[arg(long, short, add = ArgValueCandidates::new(complete::all_revisions))]
+    bad: Vec<RevisionArg>,
+}
+
+#[instrument(skip_all)]
+pub(crate) fn cmd_bisect_start(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &BisectStartArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    if BisectState::load(workspace_command.repo_path())?.is_some() {
+        return Err(user_error_with_hint(
+            "A bisection is already in progress",
+            "Use `jj bisect reset` to abort it.",
+        ));
+    }
+    let mut state = BisectState {
+        start_commit: workspace_command.get_wc_commit_id().cloned(),
+        ..Default::default()
+    };
+    if !args.good.is_empty() {
+        state.good = workspace_command
+            .parse_union_revsets(ui, &args.good)?
+            .evaluate_to_commit_ids()?
+            .try_collect()?;
+    }
+    if !args.bad.is_empty() {
+        state.bad = workspace_command
+            .parse_union_revsets(ui, &args.bad)?
+            .evaluate_to_commit_ids()?
+            .try_collect()?;
+    }
+    bisect_next(ui, &mut workspace_command, &mut state)?;
+    Ok(())
+}
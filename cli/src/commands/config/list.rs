@@ -51,6 +51,10 @@ pub struct ConfigListArgs {
     /// * `name: String`: Config name.
     /// * `value: String`: Serialized value in TOML syntax.
     /// * `overridden: Boolean`: True if the value is shadowed by other.
+    /// * `source: String`: Where the value is set: `default`, `env`, `user`,
+    ///   `repo`, `workspace`, or `cli`.
+    /// * `condition: String`: The `--when` condition of the `--scope` the value
+    ///   is set in, or empty if the value isn't set in a scope.
     ///
     /// For the syntax, see https://martinvonz.github.io/jj/latest/templates/
     #[arg(long, short = 'T', verbatim_doc_comment)]
@@ -133,5 +137,14 @@ fn config_template_language() -> GenericTemplateLanguage<'static, AnnotatedValue
         let out_property = self_property.map(|annotated| annotated.is_overridden);
         Ok(L::wrap_boolean(out_property))
     });
+    language.add_keyword("source", |self_property| {
+        let out_property = self_property.map(|annotated| annotated.source.to_string());
+        Ok(L::wrap_string(out_property))
+    });
+    language.add_keyword("condition", |self_property| {
+        let out_property =
+            self_property.map(|annotated| annotated.condition.clone().unwrap_or_default());
+        Ok(L::wrap_string(out_property))
+    });
     language
 }
@@ -0,0 +1,122 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Debug;
+#[cfg(target_os = "linux")]
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use clap::Subcommand;
+#[cfg(target_os = "linux")]
+use jj_lib::fsmonitor::inotify;
+#[cfg(target_os = "linux")]
+use jj_lib::fsmonitor::FsmonitorSettings;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+#[cfg(target_os = "linux")]
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum DebugInotifyCommand {
+    /// Check whether the inotify filesystem monitor is enabled and running
+    Status,
+    /// Stop the inotify daemon of the current workspace
+    Stop,
+    /// Run the inotify daemon watching the given working copy
+    ///
+    /// The daemon is started automatically when it's needed.
+    Daemon {
+        /// The root of the working copy to watch
+        path: PathBuf,
+    },
+}
+
+#[cfg(target_os = "linux")]
+pub fn cmd_debug_inotify(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    subcommand: &DebugInotifyCommand,
+) -> Result<(), CommandError> {
+    let map_err = |err| user_error_with_message("Failed to query the inotify daemon", err);
+    match subcommand {
+        DebugInotifyCommand::Status => {
+            if matches!(
+                command.settings().fsmonitor_settings()?,
+                FsmonitorSettings::Inotify(_)
+            ) {
+                writeln!(ui.stdout(), "inotify is enabled via `core.fsmonitor`.")?;
+            } else {
+                writeln!(
+                    ui.stdout(),
+                    r#"inotify is disabled. Set `core.fsmonitor="inotify"` to enable."#
+                )?;
+            }
+            let workspace_root = command.workspace_loader()?.workspace_root();
+            let socket_path = inotify::socket_path(workspace_root).map_err(map_err)?;
+            let fsmonitor = inotify::Fsmonitor::with_socket_path(socket_path.clone());
+            if fsmonitor.is_running() {
+                writeln!(
+                    ui.stdout(),
+                    "The inotify daemon is running and listening on {}.",
+                    socket_path.display()
+                )?;
+            } else {
+                writeln!(ui.stdout(), "The inotify daemon is not running.")?;
+            }
+        }
+        DebugInotifyCommand::Stop => {
+            let workspace_root = command.workspace_loader()?.workspace_root();
+            let socket_path = inotify::socket_path(workspace_root).map_err(map_err)?;
+            let fsmonitor = inotify::Fsmonitor::with_socket_path(socket_path);
+            if fsmonitor.is_running() {
+                fsmonitor.stop().map_err(map_err)?;
+                writeln!(ui.status(), "Stopped the inotify daemon.")?;
+            } else {
+                writeln!(ui.status(), "The inotify daemon is not running.")?;
+            }
+        }
+        DebugInotifyCommand::Daemon { path } => {
+            let map_err = |err| user_error_with_message("Failed to run the inotify daemon", err);
+            // The daemon is started in the working copy, so the workspace
+            // loaded from the current directory is the one being watched.
+            let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+            let workspace_root = workspace_command.workspace_root().canonicalize().ok();
+            if workspace_root.is_none() || workspace_root != path.canonicalize().ok() {
+                return Err(user_error(format!(
+                    "{} is not the root of the current workspace",
+                    path.display()
+                )));
+            }
+            let base_ignores = workspace_command.base_ignores()?;
+            let socket_path = inotify::socket_path(path).map_err(map_err)?;
+            let daemon = inotify::Daemon::new(path, socket_path, base_ignores).map_err(map_err)?;
+            daemon.run().map_err(map_err)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn cmd_debug_inotify(
+    _ui: &mut Ui,
+    _command: &CommandHelper,
+    _subcommand: &DebugInotifyCommand,
+) -> Result<(), CommandError> {
+    Err(user_error(
+        "The inotify filesystem monitor is only available on Linux",
+    ))
+}
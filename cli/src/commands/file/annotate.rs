@@ -12,18 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::io::Write as _;
+use std::ops::RangeInclusive;
+
 use clap_complete::ArgValueCandidates;
 use jj_lib::annotate::get_annotation_for_file;
 use jj_lib::annotate::FileAnnotation;
 use jj_lib::commit::Commit;
 use jj_lib::repo::Repo;
 use jj_lib::revset::RevsetExpression;
+use jj_lib::settings::ConfigResultExt as _;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
 use crate::command_error::user_error;
 use crate::command_error::CommandError;
+use crate::commit_templater::AnnotationLine;
+use crate::commit_templater::CommitTemplateLanguage;
 use crate::complete;
 use crate::templater::TemplateRenderer;
 use crate::ui::Ui;
@@ -32,8 +38,11 @@ use crate::ui::Ui;
 ///
 /// Annotates a revision line by line. Each line includes the source change that
 /// introduced the associated line. A path to the desired file must be provided.
-/// The per-line prefix for each line can be customized via
-/// template with the `templates.annotate_commit_summary` config variable.
+/// Renames and copies of the file are followed.
+///
+/// Changes made in the revisions specified by the `annotate.ignore` config
+/// variable are attributed to their parent revisions where possible. This is
+/// useful to skip revisions that only reformat the code.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileAnnotateArgs {
     /// the file to annotate
@@ -42,6 +51,23 @@ pub(crate) struct FileAnnotateArgs {
     /// an optional revision to start at
     #[arg(long, short, add = ArgValueCandidates::new(complete::all_revisions))]
     revision: Option<RevisionArg>,
+    /// Only show the lines in the given range
+    ///
+    /// The range is given as `START,END`, both inclusive and 1-based. If
+    /// `END` is omitted, the range extends to the end of the file. Can be
+    /// repeated to show multiple ranges.
+    #[arg(long = "lines", short = 'L', value_name = "START,END", value_parser = parse_line_range)]
+    line_ranges: Vec<RangeInclusive<usize>>,
+    /// Render each annotated line using the given template
+    ///
+    /// The template is evaluated for each line of the file, with the keywords
+    /// of the `AnnotationLine` type. For the syntax, see
+    /// https://martinvonz.github.io/jj/latest/templates/.
+    ///
+    /// If not specified, this defaults to the `templates.file_annotate`
+    /// setting.
+    #[arg(long, short = 'T')]
+    template: Option<String>,
 }
 
 #[instrument(skip_all)]
@@ -66,38 +92,131 @@ pub(crate) fn cmd_file_annotate(
         )));
     }
 
-    let annotate_commit_summary_text = command
-        .settings()
-        .config()
-        .get_string("templates.annotate_commit_summary")?;
-    let template = workspace_command.parse_commit_template(ui, &annotate_commit_summary_text)?;
+    let config = command.settings().config();
+    let legacy_template_text = config
+        .get_string("templates.annotate_commit_summary")
+        .optional()?;
+    let template = match (&args.template, legacy_template_text) {
+        (None, Some(text)) => {
+            // TODO: Remove in jj 0.30+
+            writeln!(
+                ui.warning_default(),
+                "The `templates.annotate_commit_summary` config option is deprecated; use \
+                 `templates.file_annotate` instead"
+            )?;
+            writeln!(
+                ui.hint_default(),
+                "The `templates.file_annotate` template renders the whole line, and the commit \
+                 can be accessed as `commit`."
+            )?;
+            AnnotationTemplate::CommitSummary(workspace_command.parse_commit_template(ui, &text)?)
+        }
+        (template_text, _) => {
+            let template_text = match template_text {
+                Some(value) => value.clone(),
+                None => config.get_string("templates.file_annotate")?,
+            };
+            let language = workspace_command.commit_template_language();
+            AnnotationTemplate::Line(workspace_command.parse_template(
+                ui,
+                &language,
+                &template_text,
+                CommitTemplateLanguage::wrap_annotation_line,
+            )?)
+        }
+    };
+    let ignored = match config.get_string("annotate.ignore").optional()? {
+        Some(text) => workspace_command
+            .parse_revset(ui, &RevisionArg::from(text))?
+            .resolve()?,
+        None => RevsetExpression::none(),
+    };
 
     // TODO: Should we add an option to limit the domain to e.g. recent commits?
-    // Note that this is probably different from "--skip REVS", which won't
-    // exclude the revisions, but will ignore diffs in those revisions as if
-    // ancestor revisions had new content.
     let domain = RevsetExpression::all();
-    let annotation = get_annotation_for_file(repo.as_ref(), &starting_commit, &domain, &file_path)?;
+    let annotation = get_annotation_for_file(
+        repo.as_ref(),
+        &starting_commit,
+        &domain,
+        &ignored,
+        &file_path,
+    )?;
+
+    let num_lines = annotation.lines().count();
+    if let Some(range) = args
+        .line_ranges
+        .iter()
+        .find(|range| *range.start() > num_lines)
+    {
+        return Err(user_error(format!(
+            "Line {start} is out of range: {ui_path} has only {num_lines} lines",
+            start = range.start()
+        )));
+    }
 
-    render_file_annotation(repo.as_ref(), ui, &template, &annotation)?;
+    render_file_annotation(repo.as_ref(), ui, &template, &annotation, &args.line_ranges)?;
     Ok(())
 }
 
+enum AnnotationTemplate<'a> {
+    /// Template rendering each line.
+    Line(TemplateRenderer<'a, AnnotationLine>),
+    /// Template rendering the commit summary of each line, which is set by the
+    /// deprecated `templates.annotate_commit_summary` config.
+    CommitSummary(TemplateRenderer<'a, Commit>),
+}
+
 fn render_file_annotation(
     repo: &dyn Repo,
     ui: &mut Ui,
-    template_render: &TemplateRenderer<Commit>,
+    template: &AnnotationTemplate,
     annotation: &FileAnnotation,
+    line_ranges: &[RangeInclusive<usize>],
 ) -> Result<(), CommandError> {
     ui.request_pager();
     let mut formatter = ui.stdout_formatter();
-    for (line_no, (commit_id, line)) in annotation.lines().enumerate() {
-        let commit_id = commit_id.expect("should reached to the empty ancestor");
-        let commit = repo.store().get_commit(commit_id)?;
-        template_render.format(&commit, formatter.as_mut())?;
-        write!(formatter, " {:>4}: ", line_no + 1)?;
-        formatter.write_all(line)?;
+    for (line_index, (origin, content)) in annotation.line_origins().enumerate() {
+        let line_number = line_index + 1;
+        if !line_ranges.is_empty() && !line_ranges.iter().any(|r| r.contains(&line_number)) {
+            continue;
+        }
+        let origin = origin.expect("should reached to the empty ancestor");
+        let line = AnnotationLine {
+            commit: repo.store().get_commit(&origin.commit_id)?,
+            content: content.to_owned(),
+            line_number,
+            original_line_number: origin.line_number + 1,
+            original_path: origin.path.clone(),
+        };
+        match template {
+            AnnotationTemplate::Line(template) => template.format(&line, formatter.as_mut())?,
+            AnnotationTemplate::CommitSummary(template) => {
+                template.format(&line.commit, formatter.as_mut())?;
+                write!(formatter, " {:>4}: ", line.line_number)?;
+                formatter.write_all(&line.content)?;
+            }
+        }
     }
 
     Ok(())
 }
+
+fn parse_line_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let (start, end) = s
+        .split_once(',')
+        .ok_or_else(|| "Expected line range in the form START,END".to_owned())?;
+    let parse_line_number = |s: &str| match s.trim().parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("Invalid line number: {s:?}")),
+    };
+    let start = parse_line_number(start)?;
+    let end = if end.trim().is_empty() {
+        usize::MAX
+    } else {
+        parse_line_number(end)?
+    };
+    if start > end {
+        return Err(format!("Invalid line range: {start} is greater than {end}"));
+    }
+    Ok(start..=end)
+}
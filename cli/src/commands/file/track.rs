@@ -13,11 +13,28 @@
 // limitations under the License.
 
 use std::io::Write;
+use std::path::Path;
 
+use jj_lib::fsmonitor::FsmonitorSettings;
+use jj_lib::gitignore::GitIgnoreMatch;
+use jj_lib::local_working_copy::LocalWorkingCopy;
+use jj_lib::matchers::Matcher;
+use jj_lib::matchers::Visit;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::repo_path::RepoPathComponent;
+use jj_lib::settings::HumanByteSize;
 use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::UntrackedPath;
+use jj_lib::working_copy::UntrackedReason;
 use tracing::instrument;
 
+use crate::cli_util::fsmonitor_settings;
 use crate::cli_util::CommandHelper;
+use crate::cli_util::WorkspaceCommandHelper;
+use crate::command_error::user_error;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
@@ -30,11 +47,21 @@ use crate::ui::Ui;
 /// `snapshot.auto-track` (e.g. to `"none()"` or `"glob:**/*.rs"`). Files that
 /// don't match the pattern can be manually tracked using this command. The
 /// default pattern is `all()` and this command has no effect.
+///
+/// Ignored files are never tracked. Use `--explain` to find out why a file
+/// isn't tracked.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct FileTrackArgs {
     /// Paths to track
     #[arg(required = true, value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Explain why the matching files aren't tracked instead of tracking them
+    ///
+    /// Lists the untracked files matching the paths along with the ignore
+    /// pattern, the `snapshot.auto-track` setting, or the
+    /// `snapshot.max-new-file-size` limit that keeps each of them untracked.
+    #[arg(long)]
+    explain: bool,
 }
 
 #[instrument(skip_all)]
@@ -47,13 +74,16 @@ pub(crate) fn cmd_file_track(
     let matcher = workspace_command
         .parse_file_patterns(ui, &args.paths)?
         .to_matcher();
+    if args.explain {
+        return explain_untracked_files(ui, command, &workspace_command, matcher.as_ref());
+    }
 
     let mut tx = workspace_command.start_transaction().into_inner();
     let base_ignores = workspace_command.base_ignores()?;
     let (mut locked_ws, _wc_commit) = workspace_command.start_working_copy_mutation()?;
     locked_ws.locked_wc().snapshot(&SnapshotOptions {
         base_ignores,
-        fsmonitor_settings: command.settings().fsmonitor_settings()?,
+        fsmonitor_settings: fsmonitor_settings(command.settings())?,
         progress: None,
         start_tracking_matcher: &matcher,
         max_new_file_size: command.settings().max_new_file_size()?,
@@ -66,3 +96,158 @@ pub(crate) fn cmd_file_track(
     locked_ws.finish(repo.op_id().clone())?;
     Ok(())
 }
+
+/// Why a file isn't tracked.
+enum UntrackedFileReason {
+    Snapshot(UntrackedReason),
+    /// The file is in a directory that the snapshot doesn't visit.
+    InDirectory(RepoPathBuf, UntrackedReason),
+}
+
+/// Lists the untracked files in `dir` on disk that match the `matcher`.
+fn untracked_files_in_dir(
+    dir: &RepoPath,
+    disk_dir: &Path,
+    matcher: &dyn Matcher,
+    tree: &MergedTree,
+    files: &mut Vec<RepoPathBuf>,
+) -> Result<(), CommandError> {
+    if matcher.visit(dir).is_nothing() {
+        return Ok(());
+    }
+    for entry in disk_dir.read_dir()? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str() else {
+            continue;
+        };
+        let path = dir.join(RepoPathComponent::new(name));
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            untracked_files_in_dir(&path, &entry.path(), matcher, tree, files)?;
+        } else if (file_type.is_file() || file_type.is_symlink())
+            && matcher.matches(&path)
+            && tree.path_value(&path)?.is_absent()
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn explain_untracked_files(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_command: &WorkspaceCommandHelper,
+    matcher: &dyn Matcher,
+) -> Result<(), CommandError> {
+    let wc_commit_id = workspace_command
+        .get_wc_commit_id()
+        .ok_or_else(|| user_error("This command requires a working copy"))?;
+    let tree = workspace_command
+        .repo()
+        .store()
+        .get_commit(wc_commit_id)?
+        .tree()?;
+    let auto_tracking_matcher = workspace_command.auto_tracking_matcher(ui)?;
+    let max_new_file_size = command.settings().max_new_file_size()?;
+    let Some(working_copy) = workspace_command
+        .working_copy()
+        .as_any()
+        .downcast_ref::<LocalWorkingCopy>()
+    else {
+        return Err(user_error(
+            "Explaining untracked files is only supported by the local working copy",
+        ));
+    };
+    let untracked_paths = working_copy.untracked_paths(
+        &SnapshotOptions {
+            base_ignores: workspace_command.base_ignores()?,
+            fsmonitor_settings: FsmonitorSettings::None,
+            progress: None,
+            start_tracking_matcher: auto_tracking_matcher.as_ref(),
+            max_new_file_size,
+        },
+        matcher,
+    )?;
+    let mut untracked = vec![];
+    for untracked_path in untracked_paths {
+        let UntrackedPath {
+            path,
+            is_dir,
+            reason,
+        } = untracked_path;
+        if !is_dir {
+            untracked.push((path, false, UntrackedFileReason::Snapshot(reason)));
+        } else if matcher.visit(&path) == Visit::AllRecursively {
+            // Report the whole directory rather than each file in it
+            untracked.push((path, true, UntrackedFileReason::Snapshot(reason)));
+        } else {
+            let mut files = vec![];
+            untracked_files_in_dir(
+                &path,
+                &path.to_fs_path_unchecked(workspace_command.workspace_root()),
+                matcher,
+                &tree,
+                &mut files,
+            )?;
+            untracked.extend(files.into_iter().map(|file| {
+                let reason = UntrackedFileReason::InDirectory(path.clone(), reason.clone());
+                (file, false, reason)
+            }));
+        }
+    }
+    untracked.sort_by(|(path1, ..), (path2, ..)| path1.cmp(path2));
+    if untracked.is_empty() {
+        writeln!(ui.status(), "No untracked files match the given paths.")?;
+        return Ok(());
+    }
+
+    let format_ignore = |ignore: &GitIgnoreMatch| {
+        let pattern = &ignore.pattern;
+        let Some(source) = &ignore.source else {
+            return format!("pattern `{pattern}`");
+        };
+        // Show files in the workspace like other paths, e.g. ".gitignore"
+        let source_path = source
+            .strip_prefix(workspace_command.workspace_root())
+            .ok()
+            .and_then(|path| RepoPathBuf::from_relative_path(path).ok())
+            .map_or_else(
+                || source.display().to_string(),
+                |path| workspace_command.format_file_path(&path),
+            );
+        match ignore.line_num {
+            Some(line_num) => format!("pattern `{pattern}` in {source_path}:{line_num}"),
+            None => format!("pattern `{pattern}` in {source_path}"),
+        }
+    };
+    let format_reason = |reason: &UntrackedReason| match reason {
+        UntrackedReason::Ignored(ignore) => format!("ignored by {}", format_ignore(ignore)),
+        UntrackedReason::NotAutoTracked => "not matched by `snapshot.auto-track`".to_owned(),
+        UntrackedReason::ConflictSidecar => "named like a side of a conflict".to_owned(),
+        UntrackedReason::FileTooLarge { size, max_size } => format!(
+            "larger than `snapshot.max-new-file-size` ({} > {})",
+            HumanByteSize(*size),
+            HumanByteSize(*max_size)
+        ),
+    };
+    let mut formatter = ui.stdout_formatter();
+    for (path, is_dir, reason) in untracked {
+        let mut ui_path = workspace_command.format_file_path(&path);
+        if is_dir {
+            ui_path.push(std::path::MAIN_SEPARATOR);
+        }
+        let reason = match &reason {
+            UntrackedFileReason::Snapshot(reason) => format_reason(reason),
+            UntrackedFileReason::InDirectory(dir, reason) => format!(
+                "in directory {}{} {}",
+                workspace_command.format_file_path(dir),
+                std::path::MAIN_SEPARATOR,
+                format_reason(reason)
+            ),
+        };
+        writeln!(formatter, "{ui_path}: {reason}")?;
+    }
+    Ok(())
+}
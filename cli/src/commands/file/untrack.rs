@@ -21,6 +21,7 @@ use jj_lib::repo::Repo;
 use jj_lib::working_copy::SnapshotOptions;
 use tracing::instrument;
 
+use crate::cli_util::fsmonitor_settings;
 use crate::cli_util::CommandHelper;
 use crate::command_error::user_error_with_hint;
 use crate::command_error::CommandError;
@@ -71,7 +72,7 @@ pub(crate) fn cmd_file_untrack(
     // untracked because they're not ignored.
     let wc_tree_id = locked_ws.locked_wc().snapshot(&SnapshotOptions {
         base_ignores,
-        fsmonitor_settings: command.settings().fsmonitor_settings()?,
+        fsmonitor_settings: fsmonitor_settings(command.settings())?,
         progress: None,
         start_tracking_matcher: &auto_tracking_matcher,
         max_new_file_size: command.settings().max_new_file_size()?,
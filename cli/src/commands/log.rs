@@ -12,28 +12,42 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+
 use clap_complete::ArgValueCandidates;
 use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::copies::CopiesTreeDiffEntryPath;
+use jj_lib::copies::CopyFollower;
+use jj_lib::copies::CopyOperation;
+use jj_lib::fileset::FilesetExpression;
 use jj_lib::graph::GraphEdgeType;
 use jj_lib::graph::ReverseGraphIterator;
 use jj_lib::graph::TopoGroupedGraphIterator;
+use jj_lib::matchers::Matcher;
 use jj_lib::repo::Repo;
+use jj_lib::revset::RevsetContainingFn;
 use jj_lib::revset::RevsetEvaluationError;
 use jj_lib::revset::RevsetExpression;
 use jj_lib::revset::RevsetFilterPredicate;
 use jj_lib::revset::RevsetIteratorExt;
 use jj_lib::settings::ConfigResultExt as _;
 use jj_lib::settings::UserSettings;
+use jj_lib::store::Store;
 use tracing::instrument;
 
 use crate::cli_util::format_template;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
 use crate::cli_util::RevisionArg;
+use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::CommandError;
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::complete;
 use crate::diff_util::DiffFormatArgs;
+use crate::formatter::Formatter;
 use crate::graphlog::get_graphlog;
 use crate::graphlog::Edge;
 use crate::graphlog::GraphStyle;
@@ -66,6 +80,12 @@ pub(crate) struct LogArgs {
     /// Show revisions modifying the given paths
     #[arg(value_hint = clap::ValueHint::AnyPath)]
     paths: Vec<String>,
+    /// Follow the given paths across copies and renames
+    ///
+    /// Revisions modifying the files the given paths were copied or renamed
+    /// from are also shown, and the copies and renames are marked.
+    #[arg(long, requires = "paths")]
+    follow: bool,
     /// Show revisions in the opposite order (older revisions first)
     #[arg(long)]
     reversed: bool,
@@ -125,7 +145,11 @@ pub(crate) fn cmd_log(
         if !args.paths.is_empty() {
             // Beware that args.paths = ["root:."] is not identical to []. The
             // former will filter out empty commits.
-            let predicate = RevsetFilterPredicate::File(fileset_expression.clone());
+            let predicate = if args.follow {
+                RevsetFilterPredicate::FileFollow(fileset_expression.clone())
+            } else {
+                RevsetFilterPredicate::File(fileset_expression.clone())
+            };
             expression.intersect_with(&RevsetExpression::filter(predicate));
         }
         expression
@@ -171,6 +195,15 @@ pub(crate) fn cmd_log(
             .labeled("node");
     }
 
+    let mut followed_copies = if args.follow {
+        Some(FollowedCopies::new(
+            &workspace_command,
+            fileset_expression.clone(),
+        )?)
+    } else {
+        None
+    };
+
     {
         ui.request_pager();
         let mut formatter = ui.stdout_formatter();
@@ -235,13 +268,23 @@ pub(crate) fn cmd_log(
                 if !buffer.ends_with(b"\n") {
                     buffer.push(b'\n');
                 }
+                let followed = match &mut followed_copies {
+                    Some(followed_copies) => followed_copies.get(store, &commit)?,
+                    None => FollowedCommit::default(),
+                };
+                if !followed.copies.is_empty() {
+                    let mut formatter = ui.new_formatter(&mut buffer);
+                    within_graph.write(formatter.as_mut(), |formatter| {
+                        write_followed_copies(formatter, &workspace_command, &followed.copies)
+                    })?;
+                }
                 if let Some(renderer) = &diff_renderer {
                     let mut formatter = ui.new_formatter(&mut buffer);
                     renderer.show_patch(
                         ui,
                         formatter.as_mut(),
                         &commit,
-                        matcher.as_ref(),
+                        followed.matcher.as_deref().unwrap_or(matcher.as_ref()),
                         within_graph.width(),
                     )?;
                 }
@@ -283,9 +326,19 @@ pub(crate) fn cmd_log(
                 let commit = commit_or_error?;
                 with_content_format
                     .write(formatter, |formatter| template.format(&commit, formatter))?;
+                let followed = match &mut followed_copies {
+                    Some(followed_copies) => followed_copies.get(store, &commit)?,
+                    None => FollowedCommit::default(),
+                };
+                if !followed.copies.is_empty() {
+                    with_content_format.write(formatter, |formatter| {
+                        write_followed_copies(formatter, &workspace_command, &followed.copies)
+                    })?;
+                }
                 if let Some(renderer) = &diff_renderer {
                     let width = ui.term_width();
-                    renderer.show_patch(ui, formatter, &commit, matcher.as_ref(), width)?;
+                    let matcher = followed.matcher.as_deref().unwrap_or(matcher.as_ref());
+                    renderer.show_patch(ui, formatter, &commit, matcher, width)?;
                 }
             }
         }
@@ -333,3 +386,88 @@ pub fn get_node_template(
     };
     Ok(symbol.unwrap_or_else(|| default.to_owned()))
 }
+
+/// Copies and renames of the files followed by `jj log --follow`.
+///
+/// The followed paths depend on the descendants, so all visible commits are
+/// visited, not only the displayed ones.
+struct FollowedCopies<'a> {
+    follower: CopyFollower,
+    /// All visible commits, from descendants to ancestors.
+    commits: Box<dyn Iterator<Item = Result<CommitId, RevsetEvaluationError>> + 'a>,
+    /// Whether the commit modifies the files followed in it.
+    modifies: Box<RevsetContainingFn<'a>>,
+    followed: HashMap<CommitId, FollowedCommit>,
+}
+
+/// Copies and renames in a commit modifying the followed files.
+#[derive(Default)]
+struct FollowedCommit {
+    copies: Vec<CopiesTreeDiffEntryPath>,
+    /// Matcher of the files followed in the commit, which should be used to
+    /// render its diff.
+    matcher: Option<Rc<dyn Matcher>>,
+}
+
+impl<'a> FollowedCopies<'a> {
+    fn new(
+        workspace_command: &'a WorkspaceCommandHelper,
+        expression: FilesetExpression,
+    ) -> Result<Self, CommandError> {
+        let predicate = RevsetFilterPredicate::FileFollow(expression.clone());
+        let modifying_revset = workspace_command
+            .attach_revset_evaluator(RevsetExpression::filter(predicate))
+            .evaluate()?;
+        let all_revset = workspace_command
+            .attach_revset_evaluator(RevsetExpression::visible_heads().ancestors())
+            .evaluate()?;
+        Ok(FollowedCopies {
+            follower: CopyFollower::new(expression),
+            commits: all_revset.iter(),
+            modifies: modifying_revset.containing_fn(),
+            followed: HashMap::new(),
+        })
+    }
+
+    fn get(&mut self, store: &Arc<Store>, commit: &Commit) -> Result<FollowedCommit, CommandError> {
+        // Visit the commits from descendants to ancestors until the given one.
+        while !self.followed.contains_key(commit.id()) {
+            let Some(id) = self.commits.next().transpose()? else {
+                break;
+            };
+            let matcher = self.follower.matcher(&id);
+            let modifies = (self.modifies)(&id)?;
+            let copies = self
+                .follower
+                .follow_commit(store, &store.get_commit(&id)?, modifies)?;
+            if modifies {
+                let followed = FollowedCommit {
+                    copies,
+                    matcher: Some(matcher),
+                };
+                self.followed.insert(id, followed);
+            }
+        }
+        Ok(self.followed.remove(commit.id()).unwrap_or_default())
+    }
+}
+
+fn write_followed_copies(
+    formatter: &mut dyn Formatter,
+    workspace_command: &WorkspaceCommandHelper,
+    copies: &[CopiesTreeDiffEntryPath],
+) -> std::io::Result<()> {
+    for copy in copies {
+        let (label, verb) = match copy.copy_operation() {
+            Some(CopyOperation::Rename) => ("renamed", "Renamed"),
+            Some(CopyOperation::Copy) | None => ("copied", "Copied"),
+        };
+        writeln!(
+            formatter.labeled(label),
+            "({verb} from {} to {})",
+            workspace_command.format_file_path(copy.source()),
+            workspace_command.format_file_path(copy.target()),
+        )?;
+    }
+    Ok(())
+}
@@ -17,6 +17,7 @@ mod absorb;
 mod backout;
 #[cfg(feature = "bench")]
 mod bench;
+mod bisect;
 mod bookmark;
 mod checkout;
 mod commit;
@@ -60,6 +61,7 @@ mod workspace;
 
 use std::fmt::Debug;
 
+use clap::ArgMatches;
 use clap::CommandFactory;
 use clap::FromArgMatches;
 use clap::Subcommand;
@@ -85,6 +87,8 @@ enum Command {
     #[command(subcommand)]
     Bench(bench::BenchCommand),
     #[command(subcommand)]
+    Bisect(bisect::BisectCommand),
+    #[command(subcommand)]
     Bookmark(bookmark::BookmarkCommand),
     // TODO: Remove in jj 0.28+
     #[command(subcommand, hide = true)]
@@ -187,6 +191,15 @@ pub fn default_app() -> clap::Command {
 }
 
 #[instrument(skip_all)]
+/// Returns true if the parsed command is `jj util gc`, which shouldn't trigger
+/// automatic garbage collection.
+pub(crate) fn is_gc_command(matches: &ArgMatches) -> bool {
+    matches!(
+        Command::from_arg_matches(matches),
+        Ok(Command::Util(util::UtilCommand::Gc(_)))
+    )
+}
+
 pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), CommandError> {
     let subcommand = Command::from_arg_matches(command_helper.matches()).unwrap();
     match &subcommand {
@@ -195,6 +208,7 @@ pub fn run_command(ui: &mut Ui, command_helper: &CommandHelper) -> Result<(), Co
         Command::Backout(args) => backout::cmd_backout(ui, command_helper, args),
         #[cfg(feature = "bench")]
         Command::Bench(args) => bench::cmd_bench(ui, command_helper, args),
+        Command::Bisect(args) => bisect::cmd_bisect(ui, command_helper, args),
         Command::Bookmark(args) => bookmark::cmd_bookmark(ui, command_helper, args),
         Command::Branch(args) => {
             let cmd = renamed_cmd("branch", "bookmark", bookmark::cmd_bookmark);
@@ -0,0 +1,67 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::time::SystemTime;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::cli_error;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::gc_util::compact_snapshot_operations;
+use crate::gc_util::parse_time;
+use crate::ui::Ui;
+
+/// Collapse old working-copy snapshot operations
+///
+/// Each run of consecutive snapshot operations older than the threshold is
+/// squashed into its newest operation, which keeps its view. Descendant
+/// operations are rewritten onto the squashed operations, so no other
+/// operations are lost.
+///
+/// The operations removed from the log can later be garbage collected by
+/// using `jj util gc` command. Compaction can also be done as part of garbage
+/// collection by setting `gc.op-log.compact-snapshots`.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationCompactArgs {
+    /// Only compact snapshot operations older than this
+    ///
+    /// It can be a duration such as "2 weeks", a relative time such as "3
+    /// days ago", an absolute date, or "now".
+    #[arg(long, default_value = "2 weeks")]
+    older_than: String,
+    /// Report how many operations would be compacted without rewriting
+    /// anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+pub fn cmd_op_compact(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationCompactArgs,
+) -> Result<(), CommandError> {
+    // It doesn't make sense to create divergent operations that will be merged
+    // with the current head.
+    if command.global_args().at_operation.is_some() {
+        return Err(cli_error("--at-op is not respected"));
+    }
+    let older_than = parse_time(&args.older_than, SystemTime::now()).map_err(|err| {
+        user_error_with_message(format!("Invalid time: {}", args.older_than), err)
+    })?;
+    if compact_snapshot_operations(ui, command, older_than, args.dry_run)? == 0 {
+        writeln!(ui.status(), "Nothing changed.")?;
+    }
+    Ok(())
+}
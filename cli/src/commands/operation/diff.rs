@@ -17,6 +17,7 @@ use std::convert::Infallible;
 use std::sync::Arc;
 
 use clap_complete::ArgValueCandidates;
+use futures::StreamExt as _;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use jj_lib::backend::ChangeId;
@@ -27,15 +28,21 @@ use jj_lib::git::REMOTE_NAME_FOR_LOCAL_GIT_REPO;
 use jj_lib::graph::GraphEdge;
 use jj_lib::graph::TopoGroupedGraphIterator;
 use jj_lib::matchers::EverythingMatcher;
+use jj_lib::matchers::Matcher;
 use jj_lib::op_store::RefTarget;
 use jj_lib::op_store::RemoteRef;
 use jj_lib::op_store::RemoteRefState;
+use jj_lib::operation::Operation;
 use jj_lib::refs::diff_named_ref_targets;
 use jj_lib::refs::diff_named_remote_refs;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo::RepoLoader;
 use jj_lib::revset;
 use jj_lib::revset::RevsetIteratorExt as _;
+use jj_lib::rewrite::rebase_to_dest_parent;
+use jj_lib::settings::UserSettings;
+use pollster::FutureExt as _;
 
 use crate::cli_util::short_change_hash;
 use crate::cli_util::CommandHelper;
@@ -44,6 +51,7 @@ use crate::command_error::CommandError;
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::complete;
 use crate::diff_util::diff_formats_for_log;
+use crate::diff_util::DiffFormat;
 use crate::diff_util::DiffFormatArgs;
 use crate::diff_util::DiffRenderer;
 use crate::formatter::Formatter;
@@ -77,6 +85,9 @@ pub struct OperationDiffArgs {
         add = ArgValueCandidates::new(complete::operations),
     )]
     to: Option<String>,
+    /// Show only changes modifying the given paths
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
     /// Don't show the graph, show a flat list of modified changes
     #[arg(long)]
     no_graph: bool,
@@ -111,6 +122,12 @@ pub fn cmd_op_diff(
     }
     let graph_style = GraphStyle::from_settings(command.settings())?;
     let with_content_format = LogContentFormat::new(ui, command.settings())?;
+    let matcher = if args.paths.is_empty() {
+        None
+    } else {
+        let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
+        Some(fileset_expression.to_matcher())
+    };
 
     let from_repo = repo_loader.load_at(&from_op)?;
     let to_repo = repo_loader.load_at(&to_op)?;
@@ -123,7 +140,11 @@ pub fn cmd_op_diff(
     let merged_repo = tx.repo();
 
     let diff_renderer = {
-        let formats = diff_formats_for_log(command.settings(), &args.diff_format, args.patch)?;
+        let formats = if args.from.is_some() || args.to.is_some() {
+            diff_formats_for_log(command.settings(), &args.diff_format, args.patch)?
+        } else {
+            diff_formats_for_op(command.settings(), &args.diff_format, args.patch, &to_op)?
+        };
         let path_converter = workspace_env.path_converter();
         (!formats.is_empty()).then(|| DiffRenderer::new(merged_repo, path_converter, formats))
     };
@@ -157,13 +178,33 @@ pub fn cmd_op_diff(
         (!args.no_graph).then_some(graph_style),
         &with_content_format,
         diff_renderer.as_ref(),
+        matcher.as_deref(),
     )
 }
 
+/// Returns the diff formats to show for the operation. Snapshot operations
+/// show a summary of the snapshotted files if no format is requested.
+pub(super) fn diff_formats_for_op(
+    settings: &UserSettings,
+    args: &DiffFormatArgs,
+    patch: bool,
+    op: &Operation,
+) -> Result<Vec<DiffFormat>, config::ConfigError> {
+    let formats = diff_formats_for_log(settings, args, patch)?;
+    if formats.is_empty() && op.metadata().is_snapshot {
+        Ok(vec![DiffFormat::Summary])
+    } else {
+        Ok(formats)
+    }
+}
+
 /// Computes and shows the differences between two operations, using the given
 /// `ReadonlyRepo`s for the operations.
 /// `current_repo` should contain a `Repo` with the indices of both repos merged
 /// into it.
+///
+/// If `matcher` is specified, only changes modifying the matching files are
+/// shown, and their diffs are limited to these files.
 #[allow(clippy::too_many_arguments)]
 pub fn show_op_diff(
     ui: &Ui,
@@ -175,8 +216,19 @@ pub fn show_op_diff(
     graph_style: Option<GraphStyle>,
     with_content_format: &LogContentFormat,
     diff_renderer: Option<&DiffRenderer>,
+    matcher: Option<&dyn Matcher>,
 ) -> Result<(), CommandError> {
-    let changes = compute_operation_commits_diff(current_repo, from_repo, to_repo)?;
+    let mut changes = compute_operation_commits_diff(current_repo, from_repo, to_repo)?;
+    if let Some(matcher) = matcher {
+        let mut matching_changes = IndexMap::new();
+        for (change_id, modified_change) in changes {
+            if change_modifies_files(current_repo, &modified_change, matcher)? {
+                matching_changes.insert(change_id, modified_change);
+            }
+        }
+        changes = matching_changes;
+    }
+    let matcher = matcher.unwrap_or(&EverythingMatcher);
 
     let commit_id_change_id_map: HashMap<CommitId, ChangeId> = changes
         .iter()
@@ -254,6 +306,7 @@ pub fn show_op_diff(
                         formatter.as_mut(),
                         diff_renderer,
                         modified_change,
+                        matcher,
                         within_graph.width(),
                     )?;
                 }
@@ -280,7 +333,14 @@ pub fn show_op_diff(
                 })?;
                 if let Some(diff_renderer) = &diff_renderer {
                     let width = with_content_format.width();
-                    show_change_diff(ui, formatter, diff_renderer, modified_change, width)?;
+                    show_change_diff(
+                        ui,
+                        formatter,
+                        diff_renderer,
+                        modified_change,
+                        matcher,
+                        width,
+                    )?;
                 }
             }
         }
@@ -498,15 +558,15 @@ fn get_parent_changes(
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-struct ModifiedChange {
-    added_commits: Vec<Commit>,
-    removed_commits: Vec<Commit>,
+pub(super) struct ModifiedChange {
+    pub added_commits: Vec<Commit>,
+    pub removed_commits: Vec<Commit>,
 }
 
 /// Compute the changes in commits between two operations, returned as a
 /// `HashMap` from `ChangeId` to a `ModifiedChange` struct containing the added
 /// and removed commits for the change ID.
-fn compute_operation_commits_diff(
+pub(super) fn compute_operation_commits_diff(
     repo: &dyn Repo,
     from_repo: &ReadonlyRepo,
     to_repo: &ReadonlyRepo,
@@ -561,27 +621,63 @@ fn show_change_diff(
     formatter: &mut dyn Formatter,
     diff_renderer: &DiffRenderer,
     change: &ModifiedChange,
+    matcher: &dyn Matcher,
     width: usize,
 ) -> Result<(), CommandError> {
     match (&*change.removed_commits, &*change.added_commits) {
         (predecessors @ ([] | [_]), [commit]) => {
             // New or modified change. If the modification involved a rebase,
             // show diffs from the rebased tree.
-            diff_renderer.show_inter_diff(
-                ui,
-                formatter,
-                predecessors,
-                commit,
-                &EverythingMatcher,
-                width,
-            )?;
+            diff_renderer.show_inter_diff(ui, formatter, predecessors, commit, matcher, width)?;
         }
         ([commit], []) => {
             // TODO: Should we show a reverse diff?
-            diff_renderer.show_patch(ui, formatter, commit, &EverythingMatcher, width)?;
+            diff_renderer.show_patch(ui, formatter, commit, matcher, width)?;
         }
         ([_, _, ..], _) | (_, [_, _, ..]) => {}
         ([], []) => panic!("ModifiedChange should have at least one entry"),
     }
     Ok(())
 }
+
+/// Returns true if the modified change has differences in files matching the
+/// `matcher`. The differences are computed in the same way as
+/// `show_change_diff()`. Changes with multiple added or removed commits are
+/// considered matching.
+fn change_modifies_files(
+    repo: &dyn Repo,
+    change: &ModifiedChange,
+    matcher: &dyn Matcher,
+) -> Result<bool, CommandError> {
+    let (from_tree, to_tree) = match (&*change.removed_commits, &*change.added_commits) {
+        (predecessors @ ([] | [_]), [commit]) => (
+            rebase_to_dest_parent(repo, predecessors, commit)?,
+            commit.tree()?,
+        ),
+        ([commit], []) => (commit.parent_tree(repo)?, commit.tree()?),
+        _ => return Ok(true),
+    };
+    let mut diff_stream = from_tree.diff_stream(&to_tree, matcher);
+    Ok(diff_stream.next().block_on().is_some())
+}
+
+/// Returns true if the operation changed commits modifying files matching the
+/// `matcher`, compared to its parent operations.
+pub(super) fn op_modifies_files(
+    repo_loader: &RepoLoader,
+    settings: &UserSettings,
+    op: &Operation,
+    matcher: &dyn Matcher,
+) -> Result<bool, CommandError> {
+    let parents: Vec<_> = op.parents().try_collect()?;
+    let parent_op = repo_loader.merge_operations(settings, parents, None)?;
+    let parent_repo = repo_loader.load_at(&parent_op)?;
+    let repo = repo_loader.load_at(op)?;
+    let changes = compute_operation_commits_diff(repo.as_ref(), &parent_repo, &repo)?;
+    for modified_change in changes.values() {
+        if change_modifies_files(repo.as_ref(), modified_change, matcher)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,79 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+use std::slice;
+
+use clap_complete::ArgValueCandidates;
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::op_bundle;
+
+use super::parse_opsets;
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Export operations to a bundle file
+///
+/// The bundle contains the given operations, their views, and the commits,
+/// trees, and files added since their parent operations. It can be imported
+/// into another clone of the repo with `jj op import`, as long as the parents
+/// of the exported operations and their commits exist there. To export the
+/// whole history, use `jj op export '::@'`.
+///
+/// For the syntax of the operation set, see
+/// https://martinvonz.github.io/jj/latest/operation-log/#operation-sets
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationExportArgs {
+    /// The operations to export
+    #[arg(
+        value_name = "OPSET",
+        required = true,
+        add = ArgValueCandidates::new(complete::operations),
+    )]
+    operations: Vec<String>,
+    /// The bundle file to write
+    #[arg(long, short, value_hint = clap::ValueHint::FilePath)]
+    output: PathBuf,
+}
+
+pub fn cmd_op_export(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationExportArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader();
+    let current_op = workspace_command.repo().operation();
+    let expression = parse_opsets(ui, command.settings(), &args.operations)?;
+    let operations = expression.evaluate(repo_loader.op_store(), slice::from_ref(current_op))?;
+    let bundle = op_bundle::export_operations(repo_loader, &operations)?;
+    let num_operations = bundle.operation_ids().count();
+    if num_operations == 0 {
+        return Err(user_error("No operations to export"));
+    }
+    let output_path = command.cwd().join(&args.output);
+    fs::write(&output_path, bundle.to_bytes()).context(&output_path)?;
+    writeln!(
+        ui.status(),
+        "Exported {num_operations} operations and {} commits to {}",
+        bundle.num_commits(),
+        args.output.display()
+    )?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+use std::path::PathBuf;
+
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::op_bundle;
+use jj_lib::op_bundle::OperationBundle;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::cli_error;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Import operations from a bundle file
+///
+/// The bundle is usually created by `jj op export` in another clone of the
+/// repo. The imported operations are added next to the current operation, and
+/// are then merged with it like concurrent operations would be. Operations
+/// that already exist in the repo are skipped.
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationImportArgs {
+    /// The bundle file to read
+    #[arg(value_hint = clap::ValueHint::FilePath)]
+    bundle: PathBuf,
+}
+
+pub fn cmd_op_import(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationImportArgs,
+) -> Result<(), CommandError> {
+    // The imported operations are merged with the current head.
+    if command.global_args().at_operation.is_some() {
+        return Err(cli_error("--at-op is not respected"));
+    }
+    let workspace_command = command.workspace_helper(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader();
+    let bundle_path = command.cwd().join(&args.bundle);
+    let bytes = fs::read(&bundle_path).context(&bundle_path)?;
+    let bundle = OperationBundle::from_bytes(&bytes)?;
+    let stats = op_bundle::import_operations(repo_loader, &bundle)?;
+    if stats.new_operation_ids.is_empty() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    writeln!(
+        ui.status(),
+        "Imported {} operations and {} commits",
+        stats.new_operation_ids.len(),
+        stats.num_new_commits
+    )?;
+    // Merge the new operation heads right away instead of leaving it to the
+    // next command.
+    command.resolve_operation(ui, repo_loader)?;
+    Ok(())
+}
@@ -12,16 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::slice;
 
 use itertools::Itertools as _;
+use jj_lib::op_store::OpStoreResult;
+use jj_lib::op_store::OperationId;
 use jj_lib::op_walk;
 use jj_lib::operation::Operation;
 use jj_lib::repo::RepoLoader;
 use jj_lib::settings::ConfigResultExt as _;
 use jj_lib::settings::UserSettings;
 
+use super::diff::diff_formats_for_op;
+use super::diff::op_modifies_files;
 use super::diff::show_op_diff;
+use super::parse_opsets;
 use crate::cli_util::format_template;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
@@ -45,6 +52,20 @@ use crate::ui::Ui;
 /// to inspect the current state without mutation.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationLogArgs {
+    /// Which operations to show
+    ///
+    /// If no operations are specified, all ancestors of the current operation
+    /// are shown.
+    ///
+    /// For the syntax, see
+    /// https://martinvonz.github.io/jj/latest/operation-log/#operation-sets
+    #[arg(long = "operations", short = 'r', value_name = "OPSET")]
+    operations: Vec<String>,
+    /// Show operations that changed commits modifying the given paths
+    ///
+    /// The changes shown by `--op-diff` are also limited to these paths.
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
     /// Limit number of operations to show
     #[arg(long, short = 'n')]
     limit: Option<usize>,
@@ -142,6 +163,13 @@ fn do_op_log(
             .labeled("node");
     }
 
+    let path_matcher = if args.paths.is_empty() {
+        None
+    } else {
+        let fileset_expression = workspace_env.parse_file_patterns(ui, &args.paths)?;
+        Some(fileset_expression.to_matcher())
+    };
+    let matcher = path_matcher.as_deref();
     let diff_formats = diff_formats_for_log(settings, &args.diff_format, args.patch)?;
     let maybe_show_op_diff = if args.op_diff || !diff_formats.is_empty() {
         let template_text = settings.config().get_string("templates.commit_summary")?;
@@ -166,8 +194,13 @@ fn do_op_log(
                 )?
             };
             let path_converter = workspace_env.path_converter();
+            let diff_formats = if diff_formats.is_empty() {
+                diff_formats_for_op(settings, &args.diff_format, args.patch, op)?
+            } else {
+                diff_formats.clone()
+            };
             let diff_renderer = (!diff_formats.is_empty())
-                .then(|| DiffRenderer::new(repo.as_ref(), path_converter, diff_formats.clone()));
+                .then(|| DiffRenderer::new(repo.as_ref(), path_converter, diff_formats));
 
             show_op_diff(
                 ui,
@@ -179,6 +212,7 @@ fn do_op_log(
                 (!args.no_graph).then_some(graph_style),
                 with_content_format,
                 diff_renderer.as_ref(),
+                matcher,
             )
         };
         Some(show)
@@ -196,16 +230,51 @@ fn do_op_log(
         )?;
     }
     let limit = args.limit.or(args.deprecated_limit).unwrap_or(usize::MAX);
-    let iter = op_walk::walk_ancestors(slice::from_ref(current_op)).take(limit);
+    let selected_ops = if args.operations.is_empty() {
+        None
+    } else {
+        let expression = parse_opsets(ui, settings, &args.operations)?;
+        Some(expression.evaluate(repo_loader.op_store(), slice::from_ref(current_op))?)
+    };
+    let walk_ops = |selected_ops: &Option<Vec<Operation>>| {
+        let iter: Box<dyn Iterator<Item = OpStoreResult<Operation>>> = match selected_ops {
+            Some(ops) => Box::new(ops.clone().into_iter().map(Ok)),
+            None => Box::new(op_walk::walk_ancestors(slice::from_ref(current_op))),
+        };
+        iter
+    };
+    // Operations filtered by paths are shown as if they were selected by an
+    // opset.
+    let selected_ops = if let Some(matcher) = matcher {
+        let mut ops = vec![];
+        for op in walk_ops(&selected_ops) {
+            if ops.len() >= limit {
+                break;
+            }
+            let op = op?;
+            if op_modifies_files(repo_loader, settings, &op, matcher)? {
+                ops.push(op);
+            }
+        }
+        Some(ops)
+    } else {
+        selected_ops
+    };
+    let iter = walk_ops(&selected_ops);
+    let iter = iter.take(limit);
     if !args.no_graph {
+        let op_graph = selected_ops
+            .as_deref()
+            .map(|ops| SelectedOpGraph::new(current_op, ops))
+            .transpose()?;
         let mut raw_output = formatter.raw()?;
         let mut graph = get_graphlog(graph_style, raw_output.as_mut());
         for op in iter {
             let op = op?;
-            let mut edges = vec![];
-            for id in op.parent_ids() {
-                edges.push(Edge::Direct(id.clone()));
-            }
+            let edges = match &op_graph {
+                Some(op_graph) => op_graph.edges(&op),
+                None => op.parent_ids().iter().cloned().map(Edge::Direct).collect(),
+            };
             let mut buffer = vec![];
             let within_graph = with_content_format.sub_width(graph.width(op.id(), &edges));
             within_graph.write(ui.new_formatter(&mut buffer).as_mut(), |formatter| {
@@ -239,6 +308,64 @@ fn do_op_log(
     Ok(())
 }
 
+/// Ancestry of operations selected by an operation set expression.
+struct SelectedOpGraph {
+    selected_ids: HashSet<OperationId>,
+    parent_ids: HashMap<OperationId, Vec<OperationId>>,
+}
+
+impl SelectedOpGraph {
+    fn new(current_op: &Operation, selected_ops: &[Operation]) -> OpStoreResult<Self> {
+        let selected_ids = selected_ops.iter().map(|op| op.id().clone()).collect();
+        let head_ops = [slice::from_ref(current_op), selected_ops].concat();
+        let parent_ids = op_walk::walk_ancestors(&head_ops)
+            .map_ok(|op| (op.id().clone(), op.parent_ids().to_vec()))
+            .try_collect()?;
+        Ok(SelectedOpGraph {
+            selected_ids,
+            parent_ids,
+        })
+    }
+
+    /// Returns edges to the nearest selected ancestors of the `op`. If an
+    /// ancestry path doesn't reach any selected operation, a missing edge is
+    /// added.
+    fn edges(&self, op: &Operation) -> Vec<Edge<OperationId>> {
+        let mut edges = vec![];
+        let mut has_missing = false;
+        let mut visited = HashSet::new();
+        let mut to_visit = op
+            .parent_ids()
+            .iter()
+            .rev()
+            .map(|id| (id, true))
+            .collect_vec();
+        while let Some((id, is_direct)) = to_visit.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if self.selected_ids.contains(id) {
+                edges.push(if is_direct {
+                    Edge::Direct(id.clone())
+                } else {
+                    Edge::Indirect(id.clone())
+                });
+                continue;
+            }
+            match self.parent_ids.get(id) {
+                Some(parent_ids) if !parent_ids.is_empty() => {
+                    to_visit.extend(parent_ids.iter().rev().map(|id| (id, false)));
+                }
+                _ => has_missing = true,
+            }
+        }
+        if has_missing {
+            edges.push(Edge::Missing);
+        }
+        edges
+    }
+}
+
 fn get_node_template(
     style: GraphStyle,
     settings: &UserSettings,
@@ -13,27 +13,48 @@
 // limitations under the License.
 
 mod abandon;
+mod compact;
 mod diff;
+mod export;
+mod import;
 mod log;
 mod restore;
+mod revert;
 mod show;
 pub mod undo;
 
+use std::rc::Rc;
+
 use abandon::cmd_op_abandon;
 use abandon::OperationAbandonArgs;
+use chrono::TimeZone as _;
 use clap::Subcommand;
+use compact::cmd_op_compact;
+use compact::OperationCompactArgs;
 use diff::cmd_op_diff;
 use diff::OperationDiffArgs;
+use export::cmd_op_export;
+use export::OperationExportArgs;
+use import::cmd_op_import;
+use import::OperationImportArgs;
+use itertools::Itertools as _;
+use jj_lib::opset;
+use jj_lib::opset::OpsetDiagnostics;
+use jj_lib::opset::OpsetExpression;
+use jj_lib::settings::UserSettings;
 use log::cmd_op_log;
 use log::OperationLogArgs;
 use restore::cmd_op_restore;
 use restore::OperationRestoreArgs;
+use revert::cmd_op_revert;
+use revert::OperationRevertArgs;
 use show::cmd_op_show;
 use show::OperationShowArgs;
 use undo::cmd_op_undo;
 use undo::OperationUndoArgs;
 
 use crate::cli_util::CommandHelper;
+use crate::command_error::print_parse_diagnostics;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
 
@@ -44,9 +65,13 @@ use crate::ui::Ui;
 #[derive(Subcommand, Clone, Debug)]
 pub enum OperationCommand {
     Abandon(OperationAbandonArgs),
+    Compact(OperationCompactArgs),
     Diff(OperationDiffArgs),
+    Export(OperationExportArgs),
+    Import(OperationImportArgs),
     Log(OperationLogArgs),
     Restore(OperationRestoreArgs),
+    Revert(OperationRevertArgs),
     Show(OperationShowArgs),
     Undo(OperationUndoArgs),
 }
@@ -58,14 +83,44 @@ pub fn cmd_operation(
 ) -> Result<(), CommandError> {
     match subcommand {
         OperationCommand::Abandon(args) => cmd_op_abandon(ui, command, args),
+        OperationCommand::Compact(args) => cmd_op_compact(ui, command, args),
         OperationCommand::Diff(args) => cmd_op_diff(ui, command, args),
+        OperationCommand::Export(args) => cmd_op_export(ui, command, args),
+        OperationCommand::Import(args) => cmd_op_import(ui, command, args),
         OperationCommand::Log(args) => cmd_op_log(ui, command, args),
         OperationCommand::Restore(args) => cmd_op_restore(ui, command, args),
+        OperationCommand::Revert(args) => cmd_op_revert(ui, command, args),
         OperationCommand::Show(args) => cmd_op_show(ui, command, args),
         OperationCommand::Undo(args) => cmd_op_undo(ui, command, args),
     }
 }
 
+/// Parses the given operation set expressions, and returns the union of them.
+fn parse_opsets(
+    ui: &Ui,
+    settings: &UserSettings,
+    texts: &[String],
+) -> Result<Rc<OpsetExpression>, CommandError> {
+    let now = if let Some(timestamp) = settings.operation_timestamp() {
+        chrono::Local
+            .timestamp_millis_opt(timestamp.timestamp.0)
+            .unwrap()
+    } else {
+        chrono::Local::now()
+    };
+    let mut diagnostics = OpsetDiagnostics::new();
+    let expressions: Vec<_> = texts
+        .iter()
+        .map(|text| opset::parse(&mut diagnostics, text, &now.into()))
+        .try_collect()?;
+    print_parse_diagnostics(ui, "In operation set expression", &diagnostics)?;
+    let expression = expressions
+        .into_iter()
+        .reduce(|acc, expression| Rc::new(OpsetExpression::Union(acc, expression)))
+        .unwrap_or_else(|| Rc::new(OpsetExpression::None));
+    Ok(expression)
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
 enum UndoWhatToRestore {
     /// The jj repo state and local bookmarks
@@ -0,0 +1,342 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::io::Write as _;
+use std::rc::Rc;
+use std::slice;
+
+use clap_complete::ArgValueCandidates;
+use itertools::Itertools as _;
+use jj_lib::backend::CommitId;
+use jj_lib::commit::Commit;
+use jj_lib::git::REMOTE_NAME_FOR_LOCAL_GIT_REPO;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::refs::diff_named_ref_targets;
+use jj_lib::refs::diff_named_remote_refs;
+use jj_lib::repo::MutableRepo;
+use jj_lib::repo::ReadonlyRepo;
+use jj_lib::repo::Repo;
+use jj_lib::revset::ResolvedRevsetExpression;
+use jj_lib::revset::RevsetExpression;
+
+use super::diff::compute_operation_commits_diff;
+use super::parse_opsets;
+use crate::cli_util::short_change_hash;
+use crate::cli_util::short_operation_hash;
+use crate::cli_util::CommandHelper;
+use crate::command_error::user_error;
+use crate::command_error::CommandError;
+use crate::complete;
+use crate::ui::Ui;
+
+/// Create a new operation that reverts earlier operations
+///
+/// Unlike `jj op undo`, which merges the inverse of an operation into the
+/// current state, this computes what each of the given operations changed and
+/// restores only the commits, bookmarks, tags, and working-copy commits that
+/// haven't been changed again since. Anything that couldn't be restored is
+/// reported.
+///
+/// Operations are reverted newest first. For the syntax of the operation set,
+/// see https://martinvonz.github.io/jj/latest/operation-log/#operation-sets
+#[derive(clap::Args, Clone, Debug)]
+pub struct OperationRevertArgs {
+    /// The operations to revert
+    #[arg(
+        value_name = "OPSET",
+        default_value = "@",
+        add = ArgValueCandidates::new(complete::operations),
+    )]
+    operations: Vec<String>,
+}
+
+pub fn cmd_op_revert(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &OperationRevertArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let repo_loader = workspace_command.workspace().repo_loader().clone();
+    let expression = parse_opsets(ui, command.settings(), &args.operations)?;
+    let current_op = workspace_command.repo().operation().clone();
+    let revert_ops = expression.evaluate(repo_loader.op_store(), slice::from_ref(&current_op))?;
+    if revert_ops.is_empty() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    let mut parent_ops = vec![];
+    for op in &revert_ops {
+        let mut parents = op.parents();
+        let Some(parent_op) = parents.next().transpose()? else {
+            return Err(user_error("Cannot revert repo initialization"));
+        };
+        if parents.next().is_some() {
+            return Err(user_error(format!(
+                "Cannot revert a merge operation {}",
+                short_operation_hash(op.id())
+            )));
+        }
+        parent_ops.push(parent_op);
+    }
+
+    let mut tx = workspace_command.start_transaction();
+    let mut reports = vec![];
+    for (op, parent_op) in revert_ops.iter().zip(&parent_ops) {
+        let before_repo = repo_loader.load_at(parent_op)?;
+        let after_repo = repo_loader.load_at(op)?;
+        reports.push(revert_operation(tx.repo_mut(), &before_repo, &after_repo)?);
+    }
+
+    if let Some(mut formatter) = ui.status_formatter() {
+        let template = tx.base_workspace_helper().operation_summary_template();
+        for (op, report) in revert_ops.iter().zip(&reports) {
+            write!(formatter, "Reverted operation: ")?;
+            template.format(op, formatter.as_mut())?;
+            writeln!(formatter)?;
+            if !report.restored.is_empty() {
+                writeln!(formatter, "Restored:")?;
+                for item in &report.restored {
+                    writeln!(formatter, "  {item}")?;
+                }
+            }
+            if !report.not_restored.is_empty() {
+                writeln!(formatter, "Not restored because of later changes:")?;
+                for item in &report.not_restored {
+                    writeln!(formatter, "  {item}")?;
+                }
+            }
+        }
+    }
+    if !tx.repo().has_changes() {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+    let op_ids = revert_ops.iter().map(|op| op.id().hex()).join(", ");
+    tx.finish(ui, format!("revert operation {op_ids}"))?;
+    Ok(())
+}
+
+/// Descriptions of the items that were or weren't restored by reverting an
+/// operation.
+#[derive(Debug, Default)]
+struct RevertReport {
+    restored: Vec<String>,
+    not_restored: Vec<String>,
+}
+
+/// How an item changed by the reverted operation relates to the current state.
+enum RevertAction {
+    /// The item still has the value set by the operation, and can be restored.
+    Restore,
+    /// The item already has the value from before the operation.
+    Unchanged,
+    /// The item was changed again by a later operation.
+    Conflict,
+}
+
+impl RevertAction {
+    fn new<T: PartialEq>(current: T, before: T, after: T) -> Self {
+        if current == before {
+            RevertAction::Unchanged
+        } else if current == after {
+            RevertAction::Restore
+        } else {
+            RevertAction::Conflict
+        }
+    }
+}
+
+/// Applies the inverse of the changes from `before_repo` to `after_repo` to
+/// `mut_repo`, skipping items that were changed since.
+fn revert_operation(
+    mut_repo: &mut MutableRepo,
+    before_repo: &ReadonlyRepo,
+    after_repo: &ReadonlyRepo,
+) -> Result<RevertReport, CommandError> {
+    // Commits of the reverted operation might have been abandoned since.
+    mut_repo.merge_index(before_repo);
+    mut_repo.merge_index(after_repo);
+    let before_view = before_repo.view();
+    let after_view = after_repo.view();
+    let mut report = RevertReport::default();
+
+    // Hide the commits added by the operation unless later commits were built
+    // on top of them, and make the commits it hid visible again.
+    let changes = compute_operation_commits_diff(mut_repo, before_repo, after_repo)?;
+    let added_ids = changes
+        .values()
+        .flat_map(|change| &change.added_commits)
+        .map(|commit| commit.id().clone())
+        .collect_vec();
+    let removed_ids = changes
+        .values()
+        .flat_map(|change| &change.removed_commits)
+        .map(|commit| commit.id().clone())
+        .collect_vec();
+    let visible_added = visible_commits(added_ids);
+    let blocked = visible_added.intersection(
+        &visible_added
+            .descendants()
+            .minus(&visible_added)
+            .ancestors(),
+    );
+    let visible_added_ids = evaluate_commit_ids(mut_repo, &visible_added)?;
+    let blocked_ids = evaluate_commit_ids(mut_repo, &blocked)?;
+    let visible_removed_ids = evaluate_commit_ids(mut_repo, &visible_commits(removed_ids))?;
+    let mut hidden_commits = vec![];
+    let mut restored_commits = vec![];
+    for (change_id, change) in &changes {
+        let is_added_visible = |commit: &Commit| visible_added_ids.contains(commit.id());
+        let is_removed_visible = |commit: &Commit| visible_removed_ids.contains(commit.id());
+        let action = if !change.added_commits.iter().any(is_added_visible)
+            && change.removed_commits.iter().all(is_removed_visible)
+        {
+            RevertAction::Unchanged
+        } else if change
+            .added_commits
+            .iter()
+            .all(|commit| is_added_visible(commit) && !blocked_ids.contains(commit.id()))
+        {
+            RevertAction::Restore
+        } else {
+            RevertAction::Conflict
+        };
+        let description = format!("change {}", short_change_hash(change_id));
+        match action {
+            RevertAction::Restore => {
+                hidden_commits.extend(&change.added_commits);
+                restored_commits.extend(change.removed_commits.iter().cloned());
+                report.restored.push(description);
+            }
+            RevertAction::Unchanged => {}
+            RevertAction::Conflict => report.not_restored.push(description),
+        }
+    }
+    let hidden_ids: HashSet<_> = hidden_commits.iter().map(|commit| commit.id()).collect();
+    for commit in &hidden_commits {
+        mut_repo.remove_head(commit.id());
+    }
+    for commit in &hidden_commits {
+        for parent in commit.parents() {
+            let parent = parent?;
+            if !hidden_ids.contains(parent.id()) {
+                restored_commits.push(parent);
+            }
+        }
+    }
+    mut_repo.add_heads(&restored_commits)?;
+
+    for (name, (before_target, after_target)) in
+        diff_named_ref_targets(before_view.local_bookmarks(), after_view.local_bookmarks())
+    {
+        let current_target = mut_repo.get_local_bookmark(name);
+        let description = format!("bookmark {name}");
+        match RevertAction::new(&current_target, before_target, after_target) {
+            RevertAction::Restore => {
+                mut_repo.set_local_bookmark_target(name, before_target.clone());
+                report.restored.push(description);
+            }
+            RevertAction::Unchanged => {}
+            RevertAction::Conflict => report.not_restored.push(description),
+        }
+    }
+
+    for (name, (before_target, after_target)) in
+        diff_named_ref_targets(before_view.tags(), after_view.tags())
+    {
+        let current_target = mut_repo.get_tag(name);
+        let description = format!("tag {name}");
+        match RevertAction::new(&current_target, before_target, after_target) {
+            RevertAction::Restore => {
+                mut_repo.set_tag_target(name, before_target.clone());
+                report.restored.push(description);
+            }
+            RevertAction::Unchanged => {}
+            RevertAction::Conflict => report.not_restored.push(description),
+        }
+    }
+
+    // Like `jj op diff`, skip the local git repo, which is kept in sync with
+    // the local bookmarks.
+    for ((name, remote_name), (before_ref, after_ref)) in diff_named_remote_refs(
+        before_view.all_remote_bookmarks(),
+        after_view.all_remote_bookmarks(),
+    )
+    .filter(|((_, remote_name), _)| *remote_name != REMOTE_NAME_FOR_LOCAL_GIT_REPO)
+    {
+        let current_ref = mut_repo.get_remote_bookmark(name, remote_name);
+        let description = format!("remote bookmark {name}@{remote_name}");
+        match RevertAction::new(&current_ref, before_ref, after_ref) {
+            RevertAction::Restore => {
+                mut_repo.set_remote_bookmark(name, remote_name, before_ref.clone());
+                report.restored.push(description);
+            }
+            RevertAction::Unchanged => {}
+            RevertAction::Conflict => report.not_restored.push(description),
+        }
+    }
+
+    let workspace_ids = before_view
+        .wc_commit_ids()
+        .keys()
+        .chain(after_view.wc_commit_ids().keys())
+        .unique()
+        .sorted();
+    for workspace_id in workspace_ids {
+        let before_id = before_view.get_wc_commit_id(workspace_id);
+        let after_id = after_view.get_wc_commit_id(workspace_id);
+        if before_id == after_id {
+            continue;
+        }
+        let current_id = mut_repo.view().get_wc_commit_id(workspace_id).cloned();
+        let description = format!("working copy of {}", workspace_id.as_str());
+        match RevertAction::new(current_id.as_ref(), before_id, after_id) {
+            RevertAction::Restore => {
+                if let Some(before_id) = before_id {
+                    // Don't check out a commit that is still hidden because
+                    // its change couldn't be restored.
+                    let visible_ids =
+                        evaluate_commit_ids(mut_repo, &visible_commits(vec![before_id.clone()]))?;
+                    if visible_ids.is_empty() {
+                        report.not_restored.push(description);
+                        continue;
+                    }
+                    mut_repo.set_wc_commit(workspace_id.clone(), before_id.clone())?;
+                } else {
+                    mut_repo.remove_wc_commit(workspace_id)?;
+                }
+                report.restored.push(description);
+            }
+            RevertAction::Unchanged => {}
+            RevertAction::Conflict => report.not_restored.push(description),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Returns an expression for the given commits that are visible.
+fn visible_commits(commit_ids: Vec<CommitId>) -> Rc<ResolvedRevsetExpression> {
+    RevsetExpression::commits(commit_ids)
+        .intersection(&RevsetExpression::visible_heads().ancestors())
+}
+
+fn evaluate_commit_ids(
+    repo: &dyn Repo,
+    expression: &Rc<ResolvedRevsetExpression>,
+) -> Result<HashSet<CommitId>, CommandError> {
+    let commit_ids = expression.clone().evaluate(repo)?.iter().try_collect()?;
+    Ok(commit_ids)
+}
@@ -15,13 +15,13 @@
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools;
 
+use super::diff::diff_formats_for_op;
 use super::diff::show_op_diff;
 use crate::cli_util::CommandHelper;
 use crate::cli_util::LogContentFormat;
 use crate::command_error::CommandError;
 use crate::commit_templater::CommitTemplateLanguage;
 use crate::complete;
-use crate::diff_util::diff_formats_for_log;
 use crate::diff_util::DiffFormatArgs;
 use crate::diff_util::DiffRenderer;
 use crate::graphlog::GraphStyle;
@@ -33,6 +33,9 @@ pub struct OperationShowArgs {
     /// Show repository changes in this operation, compared to its parent(s)
     #[arg(default_value = "@", add = ArgValueCandidates::new(complete::operations))]
     operation: String,
+    /// Show only changes modifying the given paths
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    paths: Vec<String>,
     /// Don't show the graph, show a flat list of modified changes
     #[arg(long)]
     no_graph: bool,
@@ -73,8 +76,14 @@ pub fn cmd_op_show(
 
     let graph_style = GraphStyle::from_settings(command.settings())?;
     let with_content_format = LogContentFormat::new(ui, command.settings())?;
+    let matcher = if args.paths.is_empty() {
+        None
+    } else {
+        let fileset_expression = workspace_command.parse_file_patterns(ui, &args.paths)?;
+        Some(fileset_expression.to_matcher())
+    };
     let diff_renderer = {
-        let formats = diff_formats_for_log(command.settings(), &args.diff_format, args.patch)?;
+        let formats = diff_formats_for_op(command.settings(), &args.diff_format, args.patch, &op)?;
         let path_converter = workspace_env.path_converter();
         (!formats.is_empty()).then(|| DiffRenderer::new(repo.as_ref(), path_converter, formats))
     };
@@ -101,5 +110,6 @@ pub fn cmd_op_show(
         (!args.no_graph).then_some(graph_style),
         &with_content_format,
         diff_renderer.as_ref(),
+        matcher.as_deref(),
     )
 }
@@ -29,6 +29,9 @@ use crate::ui::Ui;
 ///
 /// This undoes an individual operation by applying the inverse of the
 /// operation.
+///
+/// To see which changes couldn't be undone because of later operations, use
+/// `jj op revert` instead.
 #[derive(clap::Args, Clone, Debug)]
 pub struct OperationUndoArgs {
     /// The operation to undo
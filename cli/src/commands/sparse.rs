@@ -19,6 +19,7 @@ use std::path::Path;
 
 use clap::Subcommand;
 use itertools::Itertools;
+use jj_lib::repo::Repo;
 use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::settings::UserSettings;
 use tracing::instrument;
@@ -29,8 +30,13 @@ use crate::cli_util::CommandHelper;
 use crate::cli_util::WorkspaceCommandHelper;
 use crate::command_error::internal_error;
 use crate::command_error::internal_error_with_message;
+use crate::command_error::user_error;
 use crate::command_error::user_error_with_message;
 use crate::command_error::CommandError;
+use crate::sparse_util::find_sparse_profile;
+use crate::sparse_util::read_active_sparse_profile;
+use crate::sparse_util::resolve_sparse_profile;
+use crate::sparse_util::write_active_sparse_profile;
 use crate::ui::Ui;
 
 /// Manage which paths from the working-copy commit are present in the working
@@ -41,6 +47,7 @@ pub(crate) enum SparseCommand {
     List(SparseListArgs),
     Reset(SparseResetArgs),
     Set(SparseSetArgs),
+    Use(SparseUseArgs),
 }
 
 /// List the patterns that are currently present in the working copy
@@ -48,6 +55,9 @@ pub(crate) enum SparseCommand {
 /// By default, a newly cloned or initialized repo will have have a pattern
 /// matching all files from the repo root. That pattern is rendered as `.` (a
 /// single period).
+///
+/// If the patterns come from a sparse profile, the name of the profile is
+/// printed first.
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct SparseListArgs {}
 
@@ -86,6 +96,31 @@ pub(crate) struct SparseResetArgs {}
 #[derive(clap::Args, Clone, Debug)]
 pub(crate) struct SparseEditArgs {}
 
+/// Use the patterns of a sparse profile defined in the repo
+///
+/// Sparse profiles are defined in the `.jj-sparse.toml` file at the root of
+/// the working-copy commit. Each entry of its `[profiles]` table maps a
+/// profile name to a fileset of whole directories and individual files, for
+/// example:
+///
+/// ```toml
+/// [profiles]
+/// frontend = "web | shared/lib.rs"
+/// ```
+///
+/// A profile can't include only part of a directory (e.g. `web ~ web/test`),
+/// since files added to the directory later wouldn't be checked out.
+///
+/// The patterns are recomputed whenever another commit is checked out or the
+/// profile definition changes. Setting the patterns by any other `jj sparse`
+/// command stops using the profile.
+#[derive(clap::Args, Clone, Debug)]
+#[command(verbatim_doc_comment)]
+pub(crate) struct SparseUseArgs {
+    /// The name of the profile
+    profile: String,
+}
+
 #[instrument(skip_all)]
 pub(crate) fn cmd_sparse(
     ui: &mut Ui,
@@ -97,6 +132,7 @@ pub(crate) fn cmd_sparse(
         SparseCommand::List(args) => cmd_sparse_list(ui, command, args),
         SparseCommand::Reset(args) => cmd_sparse_reset(ui, command, args),
         SparseCommand::Set(args) => cmd_sparse_set(ui, command, args),
+        SparseCommand::Use(args) => cmd_sparse_use(ui, command, args),
     }
 }
 
@@ -107,6 +143,9 @@ fn cmd_sparse_list(
     _args: &SparseListArgs,
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
+    if let Some(profile) = read_active_sparse_profile(workspace_command.workspace_root())? {
+        writeln!(ui.status(), "Using sparse profile '{profile}'")?;
+    }
     for path in workspace_command.working_copy().sparse_patterns()? {
         writeln!(
             ui.stdout(),
@@ -124,7 +163,7 @@ fn cmd_sparse_set(
     args: &SparseSetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
+    update_sparse_patterns_with(ui, &mut workspace_command, None, |_ui, old_patterns| {
         let mut new_patterns = HashSet::new();
         if !args.clear {
             new_patterns.extend(old_patterns.iter().cloned());
@@ -146,7 +185,7 @@ fn cmd_sparse_reset(
     _args: &SparseResetArgs,
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, _old_patterns| {
+    update_sparse_patterns_with(ui, &mut workspace_command, None, |_ui, _old_patterns| {
         Ok(vec![RepoPathBuf::root()])
     })
 }
@@ -159,7 +198,7 @@ fn cmd_sparse_edit(
 ) -> Result<(), CommandError> {
     let mut workspace_command = command.workspace_helper(ui)?;
     let repo_path = workspace_command.repo_path().to_owned();
-    update_sparse_patterns_with(ui, &mut workspace_command, |_ui, old_patterns| {
+    update_sparse_patterns_with(ui, &mut workspace_command, None, |_ui, old_patterns| {
         let mut new_patterns = edit_sparse(&repo_path, old_patterns, command.settings())?;
         new_patterns.sort_unstable();
         new_patterns.dedup();
@@ -167,6 +206,27 @@ fn cmd_sparse_edit(
     })
 }
 
+#[instrument(skip_all)]
+fn cmd_sparse_use(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &SparseUseArgs,
+) -> Result<(), CommandError> {
+    let mut workspace_command = command.workspace_helper(ui)?;
+    let wc_commit_id = workspace_command
+        .get_wc_commit_id()
+        .ok_or_else(|| user_error("This command requires a working copy"))?;
+    let wc_commit = workspace_command.repo().store().get_commit(wc_commit_id)?;
+    let definition = find_sparse_profile(&wc_commit, &args.profile)?;
+    let new_patterns = resolve_sparse_profile(ui, &wc_commit, &args.profile, &definition)?;
+    update_sparse_patterns_with(
+        ui,
+        &mut workspace_command,
+        Some(&args.profile),
+        |_ui, _old_patterns| Ok(new_patterns),
+    )
+}
+
 fn edit_sparse(
     repo_path: &Path,
     sparse: &[RepoPathBuf],
@@ -209,6 +269,7 @@ fn edit_sparse(
 fn update_sparse_patterns_with(
     ui: &mut Ui,
     workspace_command: &mut WorkspaceCommandHelper,
+    profile: Option<&str>,
     f: impl FnOnce(&mut Ui, &[RepoPathBuf]) -> Result<Vec<RepoPathBuf>, CommandError>,
 ) -> Result<(), CommandError> {
     let (mut locked_ws, wc_commit) = workspace_command.start_working_copy_mutation()?;
@@ -219,6 +280,7 @@ fn update_sparse_patterns_with(
         .map_err(|err| internal_error_with_message("Failed to update working copy paths", err))?;
     let operation_id = locked_ws.locked_wc().old_operation_id().clone();
     locked_ws.finish(operation_id)?;
+    write_active_sparse_profile(workspace_command.workspace_root(), profile)?;
     print_checkout_stats(ui, stats, &wc_commit)?;
     Ok(())
 }
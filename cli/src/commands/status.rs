@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use itertools::Itertools;
+use jj_lib::conflicts::conflict_sidecar_paths;
 use jj_lib::copies::CopyRecords;
 use jj_lib::repo::Repo;
 use jj_lib::revset::RevsetExpression;
@@ -94,6 +95,51 @@ pub(crate) fn cmd_status(
                 "There are unresolved conflicts at these paths:"
             )?;
             print_conflicted_paths(&conflicts, formatter, &workspace_command)?;
+
+            // Conflicts that can't be materialized with conflict markers have
+            // their sides written to sidecar files.
+            let workspace_root = workspace_command.workspace_root();
+            let sidecars = conflicts
+                .iter()
+                .map(|(path, conflict)| {
+                    let num_sides = conflict.clone().simplify().num_sides();
+                    let sidecar_paths = conflict_sidecar_paths(path, num_sides);
+                    let sidecar_paths =
+                        itertools::chain(sidecar_paths.removes(), sidecar_paths.adds())
+                            .filter(|sidecar_path| {
+                                sidecar_path
+                                    .to_fs_path_unchecked(workspace_root)
+                                    .symlink_metadata()
+                                    .is_ok()
+                            })
+                            .cloned()
+                            .collect_vec();
+                    (path, sidecar_paths)
+                })
+                .filter(|(_, sidecar_paths)| !sidecar_paths.is_empty())
+                .collect_vec();
+            if !sidecars.is_empty() {
+                writeln!(
+                    formatter,
+                    "The sides of these conflicts were written to separate files:"
+                )?;
+                for (path, sidecar_paths) in sidecars {
+                    let sidecar_paths = sidecar_paths
+                        .iter()
+                        .map(|sidecar_path| workspace_command.format_file_path(sidecar_path))
+                        .join(", ");
+                    writeln!(
+                        formatter,
+                        "{}: {sidecar_paths}",
+                        workspace_command.format_file_path(path)
+                    )?;
+                }
+                writeln!(
+                    formatter,
+                    "To resolve one of them, write the resolved content to the conflicted file \
+                     and delete its separate files."
+                )?;
+            }
         }
 
         let template = workspace_command.commit_summary_template();
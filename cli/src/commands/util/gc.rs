@@ -12,30 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::slice;
-use std::time::Duration;
-use std::time::SystemTime;
-
-use jj_lib::repo::Repo as _;
-
 use crate::cli_util::CommandHelper;
-use crate::command_error::user_error;
 use crate::command_error::CommandError;
+use crate::gc_util::run_gc;
+use crate::gc_util::GcOptions;
 use crate::ui::Ui;
 
-/// Run backend-dependent garbage collection.
+/// Run backend-dependent garbage collection
+///
+/// Snapshot operations older than `gc.op-log.compact-snapshots` are compacted
+/// and operations expired by the `gc.op-log.keep-count` and
+/// `gc.op-log.keep-newer` settings are abandoned first. Then unreachable
+/// operations, views, index files, and commits are pruned.
 #[derive(clap::Args, Clone, Debug)]
 pub struct UtilGcArgs {
     /// Time threshold
     ///
-    /// By default, only obsolete objects and operations older than 2 weeks are
-    /// pruned.
-    ///
-    /// Only the string "now" can be passed to this parameter. Support for
-    /// arbitrary absolute and relative timestamps will come in a subsequent
-    /// release.
+    /// Only obsolete objects and operations older than this are pruned. It
+    /// can be a duration such as "2 weeks", a relative time such as "3 days
+    /// ago", an absolute date, or "now". Defaults to the `gc.expire` setting,
+    /// which is "2 weeks" by default.
     #[arg(long)]
     expire: Option<String>,
+    /// Report what would be pruned without removing anything
+    #[arg(long)]
+    dry_run: bool,
 }
 
 pub fn cmd_util_gc(
@@ -43,21 +44,9 @@ pub fn cmd_util_gc(
     command: &CommandHelper,
     args: &UtilGcArgs,
 ) -> Result<(), CommandError> {
-    if !command.is_at_head_operation() {
-        return Err(user_error(
-            "Cannot garbage collect from a non-head operation",
-        ));
-    }
-    let keep_newer = match args.expire.as_deref() {
-        None => SystemTime::now() - Duration::from_secs(14 * 86400),
-        Some("now") => SystemTime::now() - Duration::ZERO,
-        _ => return Err(user_error("--expire only accepts 'now'")),
+    let options = GcOptions {
+        dry_run: args.dry_run,
+        ..GcOptions::from_settings(command.settings(), args.expire.as_deref())?
     };
-    let workspace_command = command.workspace_helper(ui)?;
-
-    let repo = workspace_command.repo();
-    repo.op_store()
-        .gc(slice::from_ref(repo.op_id()), keep_newer)?;
-    repo.store().gc(repo.index(), keep_newer)?;
-    Ok(())
+    run_gc(ui, command, &options)
 }
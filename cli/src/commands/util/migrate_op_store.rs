@@ -0,0 +1,165 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::io::Write as _;
+
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::log_op_store::LogOpStore;
+use jj_lib::object_id::ObjectId as _;
+use jj_lib::op_store;
+use jj_lib::op_store::OpStore;
+use jj_lib::op_store::RootOperationData;
+use jj_lib::repo::Repo as _;
+use jj_lib::simple_op_store::SimpleOpStore;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::internal_error;
+use crate::command_error::internal_error_with_message;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Convert the operation store to another format
+///
+/// All operations and their views are copied to a store of the new format,
+/// which then replaces the old store. Operation and view ids don't change. The
+/// old store is kept in `.jj/repo/op_store.old` until you remove it.
+///
+/// New repositories use the format specified by the `format.op-store` setting.
+#[derive(clap::Args, Clone, Debug)]
+pub struct UtilMigrateOpStoreArgs {
+    /// The new operation store format
+    #[arg(value_enum)]
+    format: OpStoreFormatArg,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+enum OpStoreFormatArg {
+    /// One file per operation and view
+    Simple,
+    /// A single append-only file, storing views incrementally
+    Log,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_util_migrate_op_store(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &UtilMigrateOpStoreArgs,
+) -> Result<(), CommandError> {
+    if command.global_args().at_operation.is_some() {
+        return Err(user_error(
+            "Cannot migrate the operation store at an operation",
+        ));
+    }
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let repo = workspace_command.repo();
+    let old_op_store = repo.op_store();
+    let new_name = match args.format {
+        OpStoreFormatArg::Simple => SimpleOpStore::name(),
+        OpStoreFormatArg::Log => LogOpStore::name(),
+    };
+    if old_op_store.name() == new_name {
+        writeln!(ui.status(), "Nothing changed.")?;
+        return Ok(());
+    }
+
+    let repo_path = workspace_command.repo_path();
+    let old_path = repo_path.join("op_store");
+    let new_path = repo_path.join("op_store.new");
+    let backup_path = repo_path.join("op_store.old");
+    if backup_path.exists() {
+        return Err(user_error_with_hint(
+            format!(
+                "The operation store of a previous migration still exists at {}",
+                backup_path.display()
+            ),
+            "Remove it and try again.",
+        ));
+    }
+
+    // Hold the lock so no operation head is added while the objects are copied.
+    let op_heads_store = repo.op_heads_store();
+    let _lock = op_heads_store.lock()?;
+
+    if new_path.exists() {
+        fs::remove_dir_all(&new_path).context(&new_path)?;
+    }
+    fs::create_dir(&new_path).context(&new_path)?;
+    let root_data = RootOperationData {
+        root_commit_id: repo.store().root_commit_id().clone(),
+    };
+    let new_op_store: Box<dyn OpStore> = match args.format {
+        OpStoreFormatArg::Simple if command.settings().op_store_view_deltas()? => Box::new(
+            SimpleOpStore::init_with_view_deltas(&new_path, root_data.clone()),
+        ),
+        OpStoreFormatArg::Simple => Box::new(SimpleOpStore::init(&new_path, root_data.clone())),
+        OpStoreFormatArg::Log => Box::new(LogOpStore::init(&new_path, root_data.clone())),
+    };
+    let type_path = new_path.join("type");
+    fs::write(&type_path, new_op_store.name()).context(&type_path)?;
+    // Copy unreachable operations too. They may be referenced by stale
+    // workspaces, or by heads that other processes are about to add.
+    let all_ids = old_op_store.all_operation_ids()?;
+    let num_copied =
+        op_store::copy_operations(old_op_store.as_ref(), new_op_store.as_ref(), &all_ids)?;
+    drop(new_op_store);
+
+    fs::rename(&old_path, &backup_path).context(&old_path)?;
+    fs::rename(&new_path, &old_path).context(&new_path)?;
+
+    // Other processes write objects before taking the lock, so some may have
+    // been written to the old store while it was being copied.
+    let load_op_store = |path| {
+        command
+            .get_store_factories()
+            .load_op_store(command.settings(), path, root_data.clone())
+            .map_err(internal_error)
+    };
+    let backup_op_store = load_op_store(&backup_path)?;
+    let new_op_store = load_op_store(&old_path)?;
+    let all_ids = backup_op_store.all_operation_ids()?;
+    op_store::copy_operations(backup_op_store.as_ref(), new_op_store.as_ref(), &all_ids)?;
+    for id in op_heads_store.get_op_heads()? {
+        if let Err(err) = new_op_store.read_operation(&id) {
+            return Err(internal_error_with_message(
+                format!(
+                    "Operation {} is missing from the migrated store. The old store was kept \
+                     at {}",
+                    id.hex(),
+                    backup_path.display()
+                ),
+                err,
+            ));
+        }
+    }
+
+    let format_name = match args.format {
+        OpStoreFormatArg::Simple => "simple",
+        OpStoreFormatArg::Log => "log",
+    };
+    writeln!(
+        ui.status(),
+        "Migrated {num_copied} operations to the {format_name} format."
+    )?;
+    writeln!(
+        ui.status(),
+        "The old operation store was kept at {}. Remove it once you no longer need it.",
+        backup_path.display()
+    )?;
+    Ok(())
+}
@@ -18,6 +18,8 @@ mod exec;
 mod gc;
 mod mangen;
 mod markdown_help;
+mod migrate_op_store;
+mod watch;
 
 use clap::Subcommand;
 use tracing::instrument;
@@ -34,6 +36,10 @@ use self::mangen::cmd_util_mangen;
 use self::mangen::UtilMangenArgs;
 use self::markdown_help::cmd_util_markdown_help;
 use self::markdown_help::UtilMarkdownHelp;
+use self::migrate_op_store::cmd_util_migrate_op_store;
+use self::migrate_op_store::UtilMigrateOpStoreArgs;
+use self::watch::cmd_util_watch;
+use self::watch::UtilWatchArgs;
 use crate::cli_util::CommandHelper;
 use crate::command_error::CommandError;
 use crate::ui::Ui;
@@ -47,6 +53,8 @@ pub(crate) enum UtilCommand {
     Gc(UtilGcArgs),
     Mangen(UtilMangenArgs),
     MarkdownHelp(UtilMarkdownHelp),
+    MigrateOpStore(UtilMigrateOpStoreArgs),
+    Watch(UtilWatchArgs),
 }
 
 #[instrument(skip_all)]
@@ -62,5 +70,7 @@ pub(crate) fn cmd_util(
         UtilCommand::Gc(args) => cmd_util_gc(ui, command, args),
         UtilCommand::Mangen(args) => cmd_util_mangen(ui, command, args),
         UtilCommand::MarkdownHelp(args) => cmd_util_markdown_help(ui, command, args),
+        UtilCommand::MigrateOpStore(args) => cmd_util_migrate_op_store(ui, command, args),
+        UtilCommand::Watch(args) => cmd_util_watch(ui, command, args),
     }
 }
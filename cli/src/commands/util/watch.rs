@@ -0,0 +1,265 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write as _;
+use std::path::Path;
+#[cfg(not(target_os = "linux"))]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(target_os = "linux")]
+use jj_lib::fsmonitor::inotify;
+use jj_lib::gitignore::GitIgnoreFile;
+use jj_lib::time_util;
+use tracing::instrument;
+
+use crate::cli_util::short_operation_hash;
+use crate::cli_util::CommandHelper;
+use crate::cli_util::WorkspaceCommandHelper;
+#[cfg(target_os = "linux")]
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// How often to check that the workspace still exists while files don't
+/// change.
+const WORKSPACE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Snapshot the working copy automatically when files change
+///
+/// Watches the working copy and snapshots it once files stop changing for a
+/// moment, so that edits made between `jj` commands are recorded in the
+/// operation log. The snapshots are ordinary snapshot operations, shown as
+/// "auto-snapshot working copy" in `jj op log`.
+///
+/// The command keeps running until it's interrupted, or until the workspace is
+/// removed or forgotten.
+///
+/// On platforms other than Linux, the working copy is snapshotted periodically
+/// instead of when files change.
+#[derive(clap::Args, Clone, Debug)]
+pub struct UtilWatchArgs {
+    /// How long files must stay unchanged before a snapshot is taken
+    ///
+    /// Accepts durations such as "500ms", "2s", "2 seconds", or "1 minute".
+    #[arg(long, default_value = "1s", value_parser = time_util::parse_duration)]
+    debounce: Duration,
+    /// The minimum time between two snapshots
+    #[arg(long, default_value = "10s", value_parser = time_util::parse_duration)]
+    min_interval: Duration,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_util_watch(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &UtilWatchArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    workspace_command.check_working_copy_writable()?;
+    let workspace_root = workspace_command.workspace_root().to_owned();
+    // Changes in ignored directories can't be snapshotted, so there's no need
+    // to watch them.
+    let ignores = workspace_command
+        .base_ignores()?
+        .chain_with_file("", workspace_root.join(".gitignore"))?;
+    drop(workspace_command);
+
+    // Start watching before the initial snapshot so no change is missed.
+    let poll_interval = args.min_interval.max(args.debounce);
+    let mut watcher = ChangeWatcher::new(&workspace_root, ignores, poll_interval)?;
+    writeln!(
+        ui.status(),
+        "Watching the working copy for changes. Press Ctrl-C to stop."
+    )?;
+    if !auto_snapshot(ui, command, &workspace_root)? {
+        return Ok(());
+    }
+    let mut last_snapshot = Instant::now();
+    // The time of the last change not yet snapshotted
+    let mut pending: Option<Instant> = None;
+    loop {
+        let timeout = match pending {
+            Some(last_change) => {
+                let due = (last_change + args.debounce).max(last_snapshot + args.min_interval);
+                due.saturating_duration_since(Instant::now())
+            }
+            None => WORKSPACE_CHECK_INTERVAL,
+        };
+        if pending.is_some() && timeout.is_zero() {
+            pending = None;
+            last_snapshot = Instant::now();
+            if !auto_snapshot(ui, command, &workspace_root)? {
+                return Ok(());
+            }
+            continue;
+        }
+        let changed = watcher.wait(timeout)?;
+        if watcher.is_root_removed() {
+            writeln!(ui.status(), "The workspace was removed. Stopping.")?;
+            return Ok(());
+        }
+        if changed {
+            pending = Some(Instant::now());
+        } else if pending.is_none() && !workspace_exists(ui, command, &workspace_root)? {
+            return Ok(());
+        }
+    }
+}
+
+/// Checks that the workspace wasn't forgotten or removed while files didn't
+/// change.
+fn workspace_exists(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_root: &Path,
+) -> Result<bool, CommandError> {
+    let result = command
+        .workspace_helper_no_snapshot(ui)
+        .map(|workspace_command| is_forgotten(&workspace_command));
+    handle_workspace_result(ui, workspace_root, result)
+}
+
+/// Snapshots the working copy at the latest operation. Returns `false` if the
+/// workspace no longer exists.
+fn auto_snapshot(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    workspace_root: &Path,
+) -> Result<bool, CommandError> {
+    let result = command
+        .workspace_helper_no_snapshot(ui)
+        .and_then(|mut workspace_command| {
+            let old_op_id = workspace_command.repo().op_id().clone();
+            workspace_command.maybe_auto_snapshot(ui)?;
+            let repo = workspace_command.repo();
+            if *repo.op_id() != old_op_id && repo.operation().metadata().is_snapshot {
+                writeln!(
+                    ui.status(),
+                    "Snapshotted the working copy in operation {}",
+                    short_operation_hash(repo.op_id())
+                )?;
+            }
+            Ok(is_forgotten(&workspace_command))
+        });
+    handle_workspace_result(ui, workspace_root, result)
+}
+
+fn is_forgotten(workspace_command: &WorkspaceCommandHelper) -> bool {
+    workspace_command
+        .repo()
+        .view()
+        .get_wc_commit_id(workspace_command.workspace_id())
+        .is_none()
+}
+
+/// Returns whether to keep watching given whether the workspace was
+/// forgotten, or the error from loading or snapshotting it.
+fn handle_workspace_result(
+    ui: &Ui,
+    workspace_root: &Path,
+    result: Result<bool, CommandError>,
+) -> Result<bool, CommandError> {
+    match result {
+        Ok(false) => Ok(true),
+        Ok(true) => {
+            writeln!(ui.status(), "The workspace was forgotten. Stopping.")?;
+            Ok(false)
+        }
+        Err(_) if !workspace_root.join(".jj").exists() => {
+            writeln!(ui.status(), "The workspace was removed. Stopping.")?;
+            Ok(false)
+        }
+        Err(err) => {
+            // The next change will be snapshotted if the problem is resolved.
+            writeln!(
+                ui.warning_default(),
+                "Failed to snapshot the working copy: {}",
+                err.error
+            )?;
+            Ok(true)
+        }
+    }
+}
+
+/// Reports changes to the working copy. Without inotify, pretends that files
+/// changed every `poll_interval`.
+struct ChangeWatcher {
+    #[cfg(target_os = "linux")]
+    watcher: inotify::Watcher,
+    #[cfg(not(target_os = "linux"))]
+    workspace_root: PathBuf,
+    #[cfg(not(target_os = "linux"))]
+    poll_interval: Duration,
+    #[cfg(not(target_os = "linux"))]
+    last_poll: Instant,
+}
+
+impl ChangeWatcher {
+    #[cfg(target_os = "linux")]
+    fn new(
+        workspace_root: &Path,
+        ignores: Arc<GitIgnoreFile>,
+        _poll_interval: Duration,
+    ) -> Result<Self, CommandError> {
+        let watcher = inotify::Watcher::new(workspace_root, ignores)
+            .map_err(|err| user_error_with_message("Failed to watch the working copy", err))?;
+        Ok(ChangeWatcher { watcher })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn new(
+        workspace_root: &Path,
+        _ignores: Arc<GitIgnoreFile>,
+        poll_interval: Duration,
+    ) -> Result<Self, CommandError> {
+        Ok(ChangeWatcher {
+            workspace_root: workspace_root.to_owned(),
+            poll_interval,
+            last_poll: Instant::now(),
+        })
+    }
+
+    /// Waits up to `timeout` for changes. Returns whether anything changed.
+    #[cfg(target_os = "linux")]
+    fn wait(&mut self, timeout: Duration) -> Result<bool, CommandError> {
+        self.watcher
+            .wait(Some(timeout))
+            .map_err(|err| user_error_with_message("Failed to watch the working copy", err))
+    }
+
+    /// Waits up to `timeout` for changes. Returns whether anything changed.
+    #[cfg(not(target_os = "linux"))]
+    fn wait(&mut self, timeout: Duration) -> Result<bool, CommandError> {
+        let next_poll = self.last_poll + self.poll_interval;
+        std::thread::sleep(timeout.min(next_poll.saturating_duration_since(Instant::now())));
+        if Instant::now() < next_poll {
+            return Ok(false);
+        }
+        self.last_poll = Instant::now();
+        Ok(true)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn is_root_removed(&self) -> bool {
+        self.watcher.is_root_removed()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn is_root_removed(&self) -> bool {
+        !self.workspace_root.exists()
+    }
+}
@@ -13,13 +13,18 @@
 // limitations under the License.
 
 use std::fs;
+use std::slice;
 
 use itertools::Itertools;
 use jj_lib::commit::CommitIteratorExt;
 use jj_lib::file_util;
 use jj_lib::file_util::IoResultExt;
+use jj_lib::fileset::FilePattern;
+use jj_lib::fileset::FilesetExpression;
+use jj_lib::git;
 use jj_lib::op_store::WorkspaceId;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPathBuf;
 use jj_lib::rewrite::merge_commit_trees;
 use jj_lib::workspace::Workspace;
 use tracing::instrument;
@@ -28,11 +33,13 @@ use crate::cli_util::CommandHelper;
 use crate::cli_util::RevisionArg;
 use crate::command_error::internal_error_with_message;
 use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
 use crate::command_error::CommandError;
+use crate::commands::git::maybe_add_gitignore;
 use crate::ui::Ui;
 
 /// How to handle sparse patterns when creating a new workspace.
-#[derive(clap::ValueEnum, Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 enum SparseInheritance {
     /// Copy all sparse patterns from the current workspace.
     Copy,
@@ -40,6 +47,18 @@ enum SparseInheritance {
     Full,
     /// Clear all files from the workspace (it will be empty).
     Empty,
+    /// Include the paths of the given fileset expression.
+    Fileset(String),
+}
+
+fn parse_sparse_inheritance(value: &str) -> Result<SparseInheritance, String> {
+    match value {
+        "copy" => Ok(SparseInheritance::Copy),
+        "full" => Ok(SparseInheritance::Full),
+        "empty" => Ok(SparseInheritance::Empty),
+        "" => Err("expected `copy`, `full`, `empty`, or a fileset".to_owned()),
+        _ => Ok(SparseInheritance::Fileset(value.to_owned())),
+    }
 }
 
 /// Add a workspace
@@ -70,9 +89,27 @@ pub struct WorkspaceAddArgs {
     /// new r1 r2 r3 ...`.
     #[arg(long, short)]
     revision: Vec<RevisionArg>,
-    /// How to handle sparse patterns when creating a new workspace.
-    #[arg(long, value_enum, default_value_t = SparseInheritance::Copy)]
+    /// How to handle sparse patterns when creating a new workspace
+    ///
+    /// `copy` copies all sparse patterns from the current workspace, `full`
+    /// includes all files, and `empty` clears all files from the new
+    /// workspace. Any other value is parsed as a fileset expression, which
+    /// must be a union of paths (e.g. `src | docs`). Use `root:copy` to
+    /// refer to a directory named like one of the keywords.
+    #[arg(
+        long,
+        value_name = "copy|full|empty|FILESET",
+        value_parser = parse_sparse_inheritance,
+        default_value = "copy"
+    )]
     sparse_patterns: SparseInheritance,
+    /// Register the new workspace as a Git worktree
+    ///
+    /// This is only allowed in a repo colocated with Git. Git commands can
+    /// then be used in the new workspace, and jj keeps its Git HEAD in sync
+    /// with the parent of its working-copy commit.
+    #[arg(long)]
+    git_worktree: bool,
 }
 
 #[instrument(skip_all)]
@@ -82,6 +119,38 @@ pub fn cmd_workspace_add(
     args: &WorkspaceAddArgs,
 ) -> Result<(), CommandError> {
     let old_workspace_command = command.workspace_helper(ui)?;
+    let sparsity = match &args.sparse_patterns {
+        SparseInheritance::Full => None,
+        SparseInheritance::Empty => Some(vec![]),
+        SparseInheritance::Copy => {
+            let sparse_patterns = old_workspace_command
+                .working_copy()
+                .sparse_patterns()?
+                .to_vec();
+            Some(sparse_patterns)
+        }
+        SparseInheritance::Fileset(text) => {
+            let expression =
+                old_workspace_command.parse_union_filesets(ui, slice::from_ref(text))?;
+            let mut sparse_patterns =
+                sparse_patterns_from_fileset(&expression).ok_or_else(|| {
+                    user_error_with_hint(
+                        format!("Cannot use fileset `{text}` as sparse patterns"),
+                        "Sparse patterns must be a union of file or directory paths.",
+                    )
+                })?;
+            sparse_patterns.sort_unstable();
+            sparse_patterns.dedup();
+            Some(sparse_patterns)
+        }
+    };
+
+    if args.git_worktree && !old_workspace_command.working_copy_shared_with_git() {
+        return Err(user_error_with_hint(
+            "Cannot register a Git worktree in a repo that isn't colocated with Git",
+            "Run this command from a workspace colocated with Git.",
+        ));
+    }
     let destination_path = command.cwd().join(&args.destination);
     if destination_path.exists() {
         return Err(user_error("Workspace already exists"));
@@ -131,19 +200,19 @@ pub fn cmd_workspace_add(
         )?;
     }
 
-    let mut new_workspace_command = command.for_workable_repo(ui, new_workspace, repo)?;
+    if args.git_worktree {
+        let git_backend = old_workspace_command.git_backend().unwrap();
+        git::add_worktree(
+            &git_backend.git_repo(),
+            &name,
+            new_workspace.workspace_root(),
+        )?;
+    }
 
-    let sparsity = match args.sparse_patterns {
-        SparseInheritance::Full => None,
-        SparseInheritance::Empty => Some(vec![]),
-        SparseInheritance::Copy => {
-            let sparse_patterns = old_workspace_command
-                .working_copy()
-                .sparse_patterns()?
-                .to_vec();
-            Some(sparse_patterns)
-        }
-    };
+    let mut new_workspace_command = command.for_workable_repo(ui, new_workspace, repo)?;
+    if args.git_worktree {
+        maybe_add_gitignore(&new_workspace_command)?;
+    }
 
     if let Some(sparse_patterns) = sparsity {
         let (mut locked_ws, _wc_commit) = new_workspace_command.start_working_copy_mutation()?;
@@ -196,3 +265,26 @@ pub fn cmd_workspace_add(
     )?;
     Ok(())
 }
+
+/// Converts a union of paths to sparse patterns. Returns `None` if the
+/// expression can't be represented as path prefixes.
+fn sparse_patterns_from_fileset(expression: &FilesetExpression) -> Option<Vec<RepoPathBuf>> {
+    match expression {
+        FilesetExpression::None => Some(vec![]),
+        FilesetExpression::All => Some(vec![RepoPathBuf::root()]),
+        FilesetExpression::Pattern(pattern) => match pattern {
+            FilePattern::FilePath(path) | FilePattern::PrefixPath(path) => Some(vec![path.clone()]),
+            FilePattern::FileGlob { .. }
+            | FilePattern::FileGlobI { .. }
+            | FilePattern::FileRegex { .. } => None,
+        },
+        FilesetExpression::UnionAll(expressions) => {
+            let patterns: Vec<_> = expressions
+                .iter()
+                .map(sparse_patterns_from_fileset)
+                .collect::<Option<_>>()?;
+            Some(patterns.concat())
+        }
+        FilesetExpression::Intersection(_, _) | FilesetExpression::Difference(_, _) => None,
+    }
+}
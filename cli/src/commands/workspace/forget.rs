@@ -14,7 +14,9 @@
 
 use clap_complete::ArgValueCandidates;
 use itertools::Itertools;
+use jj_lib::git;
 use jj_lib::op_store::WorkspaceId;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -26,7 +28,8 @@ use crate::ui::Ui;
 /// Stop tracking a workspace's working-copy commit in the repo
 ///
 /// The workspace will not be touched on disk. It can be deleted from disk
-/// before or after running this command.
+/// before or after running this command. If the workspace is a Git worktree,
+/// it's unregistered from the Git repo too.
 #[derive(clap::Args, Clone, Debug)]
 pub struct WorkspaceForgetArgs {
     /// Names of the workspaces to forget. By default, forgets only the current
@@ -78,5 +81,15 @@ pub fn cmd_workspace_forget(
     };
 
     tx.finish(ui, description)?;
+    let store = SimpleWorkspaceStore::load(workspace_command.repo_path());
+    if let Some(git_backend) = workspace_command.git_backend() {
+        let git_repo = git_backend.git_repo();
+        for ws in &wss {
+            if let Some(workspace_root) = store.get_workspace_path(ws)? {
+                git::remove_worktree(&git_repo, &workspace_root)?;
+            }
+        }
+    }
+    store.forget(&wss)?;
     Ok(())
 }
@@ -12,8 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
+use std::path::Path;
+
 use itertools::Itertools;
+use jj_lib::commit::Commit;
+use jj_lib::file_util;
+use jj_lib::op_store::WorkspaceId;
+use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::working_copy::WorkingCopyFactory;
+use jj_lib::working_copy::WorkingCopyFreshness;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -21,6 +31,10 @@ use crate::command_error::CommandError;
 use crate::ui::Ui;
 
 /// List workspaces
+///
+/// Each workspace is listed with its working-copy commit, followed by the
+/// path to its root directory if it's known. Workspaces whose working copy
+/// hasn't been updated to the latest operation are marked as stale.
 #[derive(clap::Args, Clone, Debug)]
 pub struct WorkspaceListArgs {}
 
@@ -32,6 +46,11 @@ pub fn cmd_workspace_list(
 ) -> Result<(), CommandError> {
     let workspace_command = command.workspace_helper(ui)?;
     let repo = workspace_command.repo();
+    let workspace_paths: HashMap<_, _> = SimpleWorkspaceStore::load(workspace_command.repo_path())
+        .workspace_paths()?
+        .into_iter()
+        .collect();
+    let working_copy_factory = command.get_working_copy_factory()?;
     let mut formatter = ui.stdout_formatter();
     let template = workspace_command.commit_summary_template();
     for (workspace_id, wc_commit_id) in repo.view().wc_commit_ids().iter().sorted() {
@@ -39,6 +58,63 @@ pub fn cmd_workspace_list(
         let commit = repo.store().get_commit(wc_commit_id)?;
         template.format(&commit, formatter.as_mut())?;
         writeln!(formatter)?;
+        // The current workspace has just been snapshotted, so it can't be stale.
+        let (path, is_stale) = if workspace_id == workspace_command.workspace_id() {
+            (Some(workspace_command.workspace_root()), false)
+        } else if let Some(path) = workspace_paths.get(workspace_id) {
+            let is_stale =
+                is_workspace_stale(working_copy_factory, repo, workspace_id, path, &commit);
+            (Some(path.as_path()), is_stale)
+        } else {
+            (None, false)
+        };
+        if let Some(path) = path {
+            writeln!(
+                formatter,
+                "  Path: {}",
+                file_util::relative_path(command.cwd(), path).display()
+            )?;
+        }
+        if is_stale {
+            writeln!(formatter.labeled("warning"), "  Working copy is stale")?;
+        }
     }
     Ok(())
 }
+
+/// Checks if the working copy at `path` is behind the operation `repo` was
+/// loaded at. Workspaces whose state can't be read (e.g. because they were
+/// deleted, or their operation was garbage-collected) aren't considered stale.
+fn is_workspace_stale(
+    working_copy_factory: &dyn WorkingCopyFactory,
+    repo: &ReadonlyRepo,
+    workspace_id: &WorkspaceId,
+    path: &Path,
+    wc_commit: &Commit,
+) -> bool {
+    let state_path = path.join(".jj").join("working_copy");
+    if !state_path.is_dir() {
+        return false;
+    }
+    let Ok(working_copy) =
+        working_copy_factory.load_working_copy(repo.store().clone(), path.to_owned(), state_path)
+    else {
+        return false;
+    };
+    if working_copy.workspace_id() != workspace_id {
+        return false;
+    }
+    let Ok(tree_id) = working_copy.tree_id() else {
+        return false;
+    };
+    let freshness = WorkingCopyFreshness::check_stale_state(
+        tree_id,
+        working_copy.operation_id(),
+        wc_commit,
+        repo,
+    );
+    matches!(
+        freshness,
+        Ok(WorkingCopyFreshness::WorkingCopyStale | WorkingCopyFreshness::SiblingOperation)
+    )
+}
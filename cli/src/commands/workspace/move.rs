@@ -0,0 +1,63 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::file_util;
+use jj_lib::git;
+use tracing::instrument;
+
+use crate::cli_util::CommandHelper;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+/// Move the current workspace to another directory
+///
+/// The workspace directory is renamed to the new path, which must not exist
+/// yet. If the repo is stored in this workspace, the other workspaces sharing
+/// it are updated to point at its new location. If the workspace is a Git
+/// worktree, the Git repo is updated to point at its new location too. If it
+/// contains a Git repo with linked worktrees, the worktrees are updated to
+/// point at the repo's new location.
+#[derive(clap::Args, Clone, Debug)]
+pub struct WorkspaceMoveArgs {
+    /// Where to move the workspace
+    destination: String,
+}
+
+#[instrument(skip_all)]
+pub fn cmd_workspace_move(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    args: &WorkspaceMoveArgs,
+) -> Result<(), CommandError> {
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let workspace = workspace_command.workspace();
+    // Resolve `..` before the workspace (which may contain the cwd) is moved.
+    let destination_path = file_util::normalize_path(&command.cwd().join(&args.destination));
+    let new_root = workspace.move_to(workspace_command.repo().as_ref(), &destination_path)?;
+    git::update_worktree_path(&new_root)?;
+    writeln!(
+        ui.status(),
+        "Moved workspace to \"{}\"",
+        file_util::relative_path(command.cwd(), &new_root).display()
+    )?;
+    if command.cwd().starts_with(workspace.workspace_root()) {
+        writeln!(
+            ui.hint_default(),
+            "The current directory was moved along with the workspace. Run `cd {}` to get back \
+             into it.",
+            new_root.display()
+        )?;
+    }
+    Ok(())
+}
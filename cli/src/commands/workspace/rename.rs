@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use jj_lib::op_store::WorkspaceId;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use tracing::instrument;
 
 use crate::cli_util::CommandHelper;
@@ -66,13 +67,15 @@ pub fn cmd_workspace_rename(
         .rename_workspace(new_workspace_id.clone());
 
     tx.repo_mut()
-        .rename_workspace(&old_workspace_id, new_workspace_id)?;
+        .rename_workspace(&old_workspace_id, new_workspace_id.clone())?;
     let repo = tx.commit(format!(
         "Renamed workspace '{}' to '{}'",
         old_workspace_id.as_str(),
         args.new_workspace_name
     ))?;
     locked_ws.finish(repo.op_id().clone())?;
+    SimpleWorkspaceStore::load(workspace_command.repo_path())
+        .rename(&old_workspace_id, &new_workspace_id)?;
 
     Ok(())
 }
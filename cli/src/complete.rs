@@ -286,6 +286,8 @@ pub fn workspaces() -> Vec<CompletionCandidate> {
 
         Ok(stdout
             .lines()
+            // Skip the indented lines with details about each workspace
+            .filter(|line| !line.starts_with(' '))
             .map(|line| {
                 let (name, desc) = line.split_once(": ").unwrap_or((line, ""));
                 CompletionCandidate::new(name).help(Some(desc.to_string().into()))
@@ -405,6 +407,7 @@ fn get_jj_command() -> Result<(std::process::Command, Config), CommandError> {
     let _ = layered_configs.read_user_config();
     if let Ok(loader) = &maybe_cwd_workspace_loader {
         let _ = layered_configs.read_repo_config(loader.repo_path());
+        let _ = layered_configs.read_workspace_config(loader.workspace_root());
     }
     let mut config = layered_configs.merge();
     // skip 2 because of the clap_complete prelude: jj -- jj <actual args...>
@@ -422,6 +425,7 @@ fn get_jj_command() -> Result<(std::process::Command, Config), CommandError> {
         // Try to update repo-specific config on a best-effort basis.
         if let Ok(loader) = DefaultWorkspaceLoaderFactory.create(&cwd.join(&repository)) {
             let _ = layered_configs.read_repo_config(loader.repo_path());
+            let _ = layered_configs.read_workspace_config(loader.workspace_root());
             config = layered_configs.merge();
         }
         cmd_args.push("--repository".into());
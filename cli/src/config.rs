@@ -22,10 +22,13 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::slice;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
 use config::Source;
 use itertools::Itertools;
+use jj_lib::file_util;
 use jj_lib::settings::ConfigResultExt as _;
+use jj_lib::str_util::StringPattern;
 use regex::Captures;
 use regex::Regex;
 use thiserror::Error;
@@ -197,17 +200,176 @@ pub enum ConfigSource {
     // TODO: Track explicit file paths, especially for when user config is a dir.
     User,
     Repo,
+    Workspace,
     CommandArg,
 }
 
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConfigSource::Default => "default",
+            ConfigSource::Env => "env",
+            ConfigSource::User => "user",
+            ConfigSource::Repo => "repo",
+            ConfigSource::Workspace => "workspace",
+            ConfigSource::CommandArg => "cli",
+        };
+        f.write_str(name)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct AnnotatedValue {
     pub path: ConfigNamePathBuf,
     pub value: config::Value,
     pub source: ConfigSource,
+    /// Description of the `--when` condition of the scope the value is set in.
+    pub condition: Option<String>,
     pub is_overridden: bool,
 }
 
+/// Key of the tables in a config file that apply only if their conditions
+/// match, e.g.:
+///
+/// ```toml
+/// [[--scope]]
+/// --when.workspaces = ["~/oss/*"]
+/// [--scope.user]
+/// email = "me@example.org"
+/// ```
+const SCOPE_KEY: &str = "--scope";
+const SCOPE_CONDITION_KEY: &str = "--when";
+
+/// Config table that applies only if its `--when` condition matches.
+#[derive(Clone, Debug)]
+struct ConditionalConfig {
+    condition: ConfigCondition,
+    config: config::Config,
+}
+
+/// Condition of a config scope. Each of the specified fields must have at
+/// least one matching pattern.
+#[derive(Clone, Debug)]
+struct ConfigCondition {
+    /// Glob patterns matched against the workspace root path.
+    workspaces: Option<Vec<StringPattern>>,
+    /// Glob patterns matched against the URLs of the Git remotes.
+    remotes: Option<Vec<StringPattern>>,
+    /// The condition as written, e.g. `workspaces = ["~/oss/*"]`.
+    description: String,
+}
+
+impl ConfigCondition {
+    fn from_table(table: &toml_edit::Table) -> Result<Self, String> {
+        let invalid = |message: String| format!("{message} in `{SCOPE_CONDITION_KEY}`");
+        let mut workspaces = None;
+        let mut remotes = None;
+        for (key, item) in table {
+            let patterns = item
+                .as_array()
+                .and_then(|array| {
+                    array
+                        .iter()
+                        .map(|value| value.as_str())
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| invalid(format!("`{key}` should be an array of strings")))?;
+            let globs = |patterns: Vec<&str>| {
+                patterns
+                    .into_iter()
+                    .map(|pattern| {
+                        let pattern = if key == "workspaces" {
+                            // Allow patterns like "~/oss/*"
+                            file_util::expand_home_path(pattern)
+                                .to_string_lossy()
+                                .into_owned()
+                        } else {
+                            pattern.to_owned()
+                        };
+                        StringPattern::glob(&pattern)
+                            .map_err(|err| invalid(format!("`{key}`: {err}")))
+                    })
+                    .try_collect()
+            };
+            match key {
+                "workspaces" => workspaces = Some(globs(patterns)?),
+                "remotes" => remotes = Some(globs(patterns)?),
+                _ => return Err(invalid(format!("unknown condition `{key}`"))),
+            }
+        }
+        let description = table
+            .iter()
+            .map(|(key, item)| format!("{key} = {}", item.to_string().trim()))
+            .join(", ");
+        Ok(ConfigCondition {
+            workspaces,
+            remotes,
+            description,
+        })
+    }
+
+    fn matches(&self, context: &ConfigConditionContext) -> bool {
+        let workspace_matches = self.workspaces.as_ref().map_or(true, |patterns| {
+            context
+                .workspace_root
+                .as_ref()
+                .is_some_and(|workspace_root| {
+                    let workspace_root = workspace_root.to_string_lossy();
+                    patterns
+                        .iter()
+                        .any(|pattern| pattern.matches(&workspace_root))
+                })
+        });
+        let remote_matches = self.remotes.as_ref().map_or(true, |patterns| {
+            context
+                .git_remote_urls()
+                .iter()
+                .any(|url| patterns.iter().any(|pattern| pattern.matches(url)))
+        });
+        workspace_matches && remote_matches
+    }
+}
+
+/// The workspace the `--when` conditions of config scopes are evaluated
+/// against.
+#[derive(Clone, Debug, Default)]
+struct ConfigConditionContext {
+    workspace_root: Option<PathBuf>,
+    repo_path: Option<PathBuf>,
+    git_remote_urls: OnceLock<Vec<String>>,
+}
+
+impl ConfigConditionContext {
+    fn git_remote_urls(&self) -> &[String] {
+        self.git_remote_urls.get_or_init(|| {
+            self.repo_path
+                .as_deref()
+                .map(read_git_remote_urls)
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Reads the URLs of the remotes of the Git repo backing the repo at
+/// `repo_path`. Returns an empty list if it isn't backed by Git.
+fn read_git_remote_urls(repo_path: &Path) -> Vec<String> {
+    let store_path = repo_path.join("store");
+    let Ok(git_target) = std::fs::read_to_string(store_path.join("git_target")) else {
+        return vec![];
+    };
+    let Ok(git_repo) = git2::Repository::open(store_path.join(git_target)) else {
+        return vec![];
+    };
+    let Ok(remote_names) = git_repo.remotes() else {
+        return vec![];
+    };
+    remote_names
+        .iter()
+        .flatten()
+        .filter_map(|name| Some(git_repo.find_remote(name).ok()?.url()?.to_owned()))
+        .collect()
+}
+
 /// Set of configs which can be merged as needed.
 ///
 /// Sources from the lowest precedence:
@@ -215,17 +377,24 @@ pub struct AnnotatedValue {
 /// 2. Base environment variables
 /// 3. [User config](https://martinvonz.github.io/jj/latest/config/)
 /// 4. Repo config `.jj/repo/config.toml`
-/// 5. TODO: Workspace config `.jj/config.toml`
+/// 5. Workspace config `.jj/config.toml`
 /// 6. Override environment variables
 /// 7. Command-line arguments `--config-toml`
+///
+/// The `--scope` tables of the user, repo, and workspace configs apply right
+/// after the config they're defined in if their conditions match the
+/// workspace.
 #[derive(Clone, Debug)]
 pub struct LayeredConfigs {
     default: config::Config,
     env_base: config::Config,
     user: Option<config::Config>,
     repo: Option<config::Config>,
+    workspace: Option<config::Config>,
     env_overrides: config::Config,
     arg_overrides: Option<config::Config>,
+    scopes: Vec<(ConfigSource, ConditionalConfig)>,
+    condition_context: ConfigConditionContext,
 }
 
 impl LayeredConfigs {
@@ -236,16 +405,25 @@ impl LayeredConfigs {
             env_base: env_base(),
             user: None,
             repo: None,
+            workspace: None,
             env_overrides: env_overrides(),
             arg_overrides: None,
+            scopes: vec![],
+            condition_context: ConfigConditionContext::default(),
         }
     }
 
     #[instrument]
     pub fn read_user_config(&mut self) -> Result<(), ConfigError> {
-        self.user = existing_config_path()?
-            .map(|path| read_config_path(&path))
-            .transpose()?;
+        let (config, scopes) = match existing_config_path()? {
+            Some(path) => {
+                let (config, scopes) = read_config_path(&path)?;
+                (Some(config), scopes)
+            }
+            None => (None, vec![]),
+        };
+        self.user = config;
+        self.set_scopes(ConfigSource::User, scopes);
         Ok(())
     }
 
@@ -255,7 +433,11 @@ impl LayeredConfigs {
 
     #[instrument]
     pub fn read_repo_config(&mut self, repo_path: &Path) -> Result<(), ConfigError> {
-        self.repo = Some(read_config_file(&self.repo_config_path(repo_path))?);
+        let (config, scopes) = read_config_file(&self.repo_config_path(repo_path))?;
+        self.repo = Some(config);
+        self.set_scopes(ConfigSource::Repo, scopes);
+        self.condition_context.repo_path = Some(repo_path.to_owned());
+        self.condition_context.git_remote_urls = OnceLock::new();
         Ok(())
     }
 
@@ -263,6 +445,26 @@ impl LayeredConfigs {
         repo_path.join("config.toml")
     }
 
+    #[instrument]
+    pub fn read_workspace_config(&mut self, workspace_root: &Path) -> Result<(), ConfigError> {
+        let (config, scopes) = read_config_file(&self.workspace_config_path(workspace_root))?;
+        self.workspace = Some(config);
+        self.set_scopes(ConfigSource::Workspace, scopes);
+        self.condition_context.workspace_root = Some(workspace_root.to_owned());
+        Ok(())
+    }
+
+    fn set_scopes(&mut self, source: ConfigSource, scopes: Vec<ConditionalConfig>) {
+        self.scopes
+            .retain(|(scope_source, _)| *scope_source != source);
+        self.scopes
+            .extend(scopes.into_iter().map(|scope| (source.clone(), scope)));
+    }
+
+    pub fn workspace_config_path(&self, workspace_root: &Path) -> PathBuf {
+        workspace_root.join(".jj").join("config.toml")
+    }
+
     pub fn parse_config_args(&mut self, toml_strs: &[String]) -> Result<(), ConfigError> {
         let config = toml_strs
             .iter()
@@ -287,18 +489,41 @@ impl LayeredConfigs {
     }
 
     pub fn sources(&self) -> Vec<(ConfigSource, &config::Config)> {
+        self.conditional_sources()
+            .into_iter()
+            .map(|(source, _, config)| (source, config))
+            .collect_vec()
+    }
+
+    /// Like `sources()`, but also returns the condition of the scope each
+    /// config is defined in.
+    fn conditional_sources(
+        &self,
+    ) -> Vec<(ConfigSource, Option<&ConfigCondition>, &config::Config)> {
         let config_sources = [
             (ConfigSource::Default, Some(&self.default)),
             (ConfigSource::Env, Some(&self.env_base)),
             (ConfigSource::User, self.user.as_ref()),
             (ConfigSource::Repo, self.repo.as_ref()),
+            (ConfigSource::Workspace, self.workspace.as_ref()),
             (ConfigSource::Env, Some(&self.env_overrides)),
             (ConfigSource::CommandArg, self.arg_overrides.as_ref()),
         ];
-        config_sources
-            .into_iter()
-            .filter_map(|(source, config)| config.map(|c| (source, c)))
-            .collect_vec()
+        let mut sources = vec![];
+        for (source, config) in config_sources {
+            if let Some(config) = config {
+                sources.push((source.clone(), None, config));
+            }
+            if source == ConfigSource::Env || source == ConfigSource::CommandArg {
+                continue;
+            }
+            for (scope_source, scope) in &self.scopes {
+                if *scope_source == source && scope.condition.matches(&self.condition_context) {
+                    sources.push((source.clone(), Some(&scope.condition), &scope.config));
+                }
+            }
+        }
+        sources
     }
 
     pub fn resolved_config_values(
@@ -307,7 +532,7 @@ impl LayeredConfigs {
     ) -> Result<Vec<AnnotatedValue>, ConfigError> {
         // Collect annotated values from each config.
         let mut config_vals = vec![];
-        for (source, config) in self.sources() {
+        for (source, condition, config) in self.conditional_sources() {
             let Some(top_value) = filter_prefix.lookup_value(config).optional()? else {
                 continue;
             };
@@ -327,6 +552,7 @@ impl LayeredConfigs {
                             path,
                             value: value.to_owned(),
                             source: source.clone(),
+                            condition: condition.map(|condition| condition.description.clone()),
                             // Note: Value updated below.
                             is_overridden: false,
                         });
@@ -546,17 +772,73 @@ fn env_overrides() -> config::Config {
     builder.build().unwrap()
 }
 
-fn read_config_file(path: &Path) -> Result<config::Config, config::ConfigError> {
-    config::Config::builder()
-        .add_source(
-            config::File::from(path)
-                .required(false)
-                .format(config::FileFormat::Toml),
-        )
-        .build()
+type ConfigWithScopes = (config::Config, Vec<ConditionalConfig>);
+
+/// Reads the config file at `path`, and splits the `--scope` tables from it.
+fn read_config_file(path: &Path) -> Result<ConfigWithScopes, config::ConfigError> {
+    let file_source = || {
+        let config = config::Config::builder()
+            .add_source(
+                config::File::from(path)
+                    .required(false)
+                    .format(config::FileFormat::Toml),
+            )
+            .build()?;
+        Ok((config, vec![]))
+    };
+    // Let the config crate report missing or malformed files.
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return file_source();
+    };
+    let Ok(mut doc) = text.parse::<toml_edit::Document>() else {
+        return file_source();
+    };
+    let Some(scopes_item) = doc.remove(SCOPE_KEY) else {
+        return file_source();
+    };
+    let invalid_scope = |message: &str| {
+        config::ConfigError::Message(format!(
+            "Invalid `{SCOPE_KEY}` in {path}: {message}",
+            path = path.display()
+        ))
+    };
+    let scope_tables = scopes_item
+        .as_array_of_tables()
+        .ok_or_else(|| invalid_scope("should be an array of tables"))?;
+    let scopes = scope_tables
+        .iter()
+        .map(|table| {
+            let mut table = table.clone();
+            let condition = match table.remove(SCOPE_CONDITION_KEY) {
+                Some(item) => {
+                    let condition_table = item.into_table().map_err(|_| {
+                        invalid_scope(&format!("`{SCOPE_CONDITION_KEY}` should be a table"))
+                    })?;
+                    ConfigCondition::from_table(&condition_table)
+                        .map_err(|message| invalid_scope(&message))?
+                }
+                None => ConfigCondition::from_table(&toml_edit::Table::new())
+                    .map_err(|message| invalid_scope(&message))?,
+            };
+            let config = config::Config::builder()
+                .add_source(config::File::from_str(
+                    &toml_edit::Document::from(table).to_string(),
+                    config::FileFormat::Toml,
+                ))
+                .build()?;
+            Ok(ConditionalConfig { condition, config })
+        })
+        .try_collect()?;
+    let config = config::Config::builder()
+        .add_source(config::File::from_str(
+            &doc.to_string(),
+            config::FileFormat::Toml,
+        ))
+        .build()?;
+    Ok((config, scopes))
 }
 
-fn read_config_path(config_path: &Path) -> Result<config::Config, config::ConfigError> {
+fn read_config_path(config_path: &Path) -> Result<ConfigWithScopes, config::ConfigError> {
     let mut files = vec![];
     if config_path.is_dir() {
         if let Ok(read_dir) = config_path.read_dir() {
@@ -573,17 +855,15 @@ fn read_config_path(config_path: &Path) -> Result<config::Config, config::Config
         files.push(config_path.to_owned());
     }
 
-    files
-        .iter()
-        .fold(config::Config::builder(), |builder, path| {
-            // TODO: Accept other formats and/or accept only certain file extensions?
-            builder.add_source(
-                config::File::from(path.as_ref())
-                    .required(false)
-                    .format(config::FileFormat::Toml),
-            )
-        })
-        .build()
+    let mut builder = config::Config::builder();
+    let mut scopes = vec![];
+    for path in &files {
+        // TODO: Accept other formats and/or accept only certain file extensions?
+        let (config, file_scopes) = read_config_file(path)?;
+        builder = builder.add_source(config);
+        scopes.extend(file_scopes);
+    }
+    Ok((builder.build()?, scopes))
 }
 
 fn read_config(path: &Path) -> Result<toml_edit::Document, CommandError> {
@@ -938,8 +1218,11 @@ mod tests {
             env_base: empty_config.clone(),
             user: None,
             repo: None,
+            workspace: None,
             env_overrides: empty_config,
             arg_overrides: None,
+            scopes: vec![],
+            condition_context: ConfigConditionContext::default(),
         };
         assert_eq!(
             layered_configs
@@ -969,8 +1252,11 @@ mod tests {
             env_base: env_base_config,
             user: None,
             repo: Some(repo_config),
+            workspace: None,
             env_overrides: empty_config,
             arg_overrides: None,
+            scopes: vec![],
+            condition_context: ConfigConditionContext::default(),
         };
         // Note: "email" is alphabetized, before "name" from same layer.
         insta::assert_debug_snapshot!(
@@ -1005,6 +1291,7 @@ mod tests {
                     ),
                 },
                 source: Env,
+                condition: None,
                 is_overridden: true,
             },
             AnnotatedValue {
@@ -1035,6 +1322,7 @@ mod tests {
                     ),
                 },
                 source: Env,
+                condition: None,
                 is_overridden: false,
             },
             AnnotatedValue {
@@ -1065,6 +1353,7 @@ mod tests {
                     ),
                 },
                 source: Repo,
+                condition: None,
                 is_overridden: false,
             },
         ]
@@ -1092,8 +1381,11 @@ mod tests {
             env_base: empty_config.clone(),
             user: Some(user_config),
             repo: Some(repo_config),
+            workspace: None,
             env_overrides: empty_config,
             arg_overrides: None,
+            scopes: vec![],
+            condition_context: ConfigConditionContext::default(),
         };
         insta::assert_debug_snapshot!(
             layered_configs
@@ -1129,6 +1421,7 @@ mod tests {
                     ),
                 },
                 source: User,
+                condition: None,
                 is_overridden: false,
             },
             AnnotatedValue {
@@ -1159,6 +1452,7 @@ mod tests {
                     ),
                 },
                 source: Repo,
+                condition: None,
                 is_overridden: false,
             },
         ]
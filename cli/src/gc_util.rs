@@ -0,0 +1,369 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Garbage collection of operations, index files, and backend objects.
+
+use std::collections::HashSet;
+use std::io::Write as _;
+use std::slice;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use itertools::Itertools as _;
+use jj_lib::backend::MillisSinceEpoch;
+use jj_lib::default_index::DefaultIndexStore;
+use jj_lib::default_index::DefaultIndexStoreGcStats;
+use jj_lib::op_store::OpStoreGcStats;
+use jj_lib::op_walk;
+use jj_lib::op_walk::OpRetention;
+use jj_lib::operation::Operation;
+use jj_lib::repo::Repo as _;
+use jj_lib::repo::RepoLoader;
+use jj_lib::settings::ConfigResultExt as _;
+use jj_lib::settings::UserSettings;
+use jj_lib::time_util::parse_past_time;
+use jj_lib::time_util::DatePatternParseError;
+
+use crate::cli_util::update_reparented_op_heads;
+use crate::cli_util::CommandHelper;
+use crate::command_error::config_error_with_message;
+use crate::command_error::internal_error;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::ui::Ui;
+
+const DEFAULT_EXPIRE: &str = "2 weeks";
+
+/// Parameters of garbage collection.
+#[derive(Clone, Debug)]
+pub struct GcOptions {
+    /// Unreachable objects created after this time are preserved.
+    pub keep_newer: SystemTime,
+    /// Operations to keep in the operation log. Older operations are
+    /// abandoned.
+    pub op_retention: OpRetention,
+    /// Snapshot operations older than this are squashed into their
+    /// successors.
+    pub compact_snapshots: Option<SystemTime>,
+    /// Reports what would be pruned without removing anything.
+    pub dry_run: bool,
+}
+
+impl GcOptions {
+    /// Loads options from the `gc.*` settings. The `expire` time overrides
+    /// the `gc.expire` setting.
+    pub fn from_settings(
+        settings: &UserSettings,
+        expire: Option<&str>,
+    ) -> Result<Self, CommandError> {
+        let config = settings.config();
+        let now = SystemTime::now();
+        let keep_newer = if let Some(expire) = expire {
+            parse_time(expire, now)
+                .map_err(|err| user_error_with_message(format!("Invalid time: {expire}"), err))?
+        } else {
+            let expire = config
+                .get_string("gc.expire")
+                .optional()?
+                .unwrap_or_else(|| DEFAULT_EXPIRE.to_owned());
+            parse_time(&expire, now).map_err(|err| {
+                config_error_with_message(format!("Invalid `gc.expire`: {expire}"), err)
+            })?
+        };
+        let keep_count = config.get::<usize>("gc.op-log.keep-count").optional()?;
+        let keep_newer_ops = config
+            .get_string("gc.op-log.keep-newer")
+            .optional()?
+            .map(|text| {
+                parse_time(&text, now).map_err(|err| {
+                    config_error_with_message(
+                        format!("Invalid `gc.op-log.keep-newer`: {text}"),
+                        err,
+                    )
+                })
+            })
+            .transpose()?;
+        let compact_snapshots = config
+            .get_string("gc.op-log.compact-snapshots")
+            .optional()?
+            .map(|text| {
+                parse_time(&text, now).map_err(|err| {
+                    config_error_with_message(
+                        format!("Invalid `gc.op-log.compact-snapshots`: {text}"),
+                        err,
+                    )
+                })
+            })
+            .transpose()?;
+        let op_retention = OpRetention {
+            keep_count,
+            keep_newer: keep_newer_ops.map(to_millis_since_epoch),
+        };
+        Ok(GcOptions {
+            keep_newer,
+            op_retention,
+            compact_snapshots,
+            dry_run: false,
+        })
+    }
+}
+
+/// Parses a past time relative to `now`. A bare duration such as "2 weeks"
+/// means the time that long ago.
+pub fn parse_time(text: &str, now: SystemTime) -> Result<SystemTime, DatePatternParseError> {
+    let now_local = chrono::DateTime::<chrono::Local>::from(now);
+    let MillisSinceEpoch(millis) = parse_past_time(text, now_local)?;
+    // Apply the difference to the system time so "now" is exactly the current
+    // time, not truncated to milliseconds.
+    let ago = now_local.timestamp_millis().saturating_sub(millis).max(0);
+    Ok(now
+        .checked_sub(Duration::from_millis(ago as u64))
+        .unwrap_or(SystemTime::UNIX_EPOCH))
+}
+
+fn to_millis_since_epoch(time: SystemTime) -> MillisSinceEpoch {
+    let millis = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis());
+    MillisSinceEpoch(millis.try_into().unwrap_or(i64::MAX))
+}
+
+/// Compacts snapshot operations and abandons expired operations, then prunes
+/// unreachable operations, index files, and backend objects.
+pub fn run_gc(
+    ui: &mut Ui,
+    command: &CommandHelper,
+    options: &GcOptions,
+) -> Result<(), CommandError> {
+    if !command.is_at_head_operation() {
+        return Err(user_error(
+            "Cannot garbage collect from a non-head operation",
+        ));
+    }
+    if let Some(older_than) = options.compact_snapshots {
+        compact_snapshot_operations(ui, command, older_than, options.dry_run)?;
+    }
+    let abandoned_stats = abandon_expired_operations(ui, command, options)?;
+
+    let workspace_command = command.workspace_helper_no_snapshot(ui)?;
+    let repo = workspace_command.repo();
+    let mut op_stats = repo.op_store().gc(
+        slice::from_ref(repo.op_id()),
+        options.keep_newer,
+        options.dry_run,
+    )?;
+    op_stats.pruned_operations += abandoned_stats.pruned_operations;
+    op_stats.pruned_views += abandoned_stats.pruned_views;
+    let index_store = repo.index_store();
+    let index_stats =
+        if let Some(index_store) = index_store.as_any().downcast_ref::<DefaultIndexStore>() {
+            let reachable_op_ids: HashSet<_> =
+                op_walk::walk_ancestors(slice::from_ref(repo.operation()))
+                    .map_ok(|op| op.id().clone())
+                    .try_collect()?;
+            index_store
+                .gc(&reachable_op_ids, options.keep_newer, options.dry_run)
+                .map_err(internal_error)?
+        } else {
+            DefaultIndexStoreGcStats::default()
+        };
+    let backend_stats = repo
+        .store()
+        .gc(repo.index(), options.keep_newer, options.dry_run)?;
+
+    let counts = [
+        (op_stats.pruned_operations, "operations"),
+        (op_stats.pruned_views, "views"),
+        (
+            index_stats.pruned_operation_links + index_stats.pruned_segments,
+            "index files",
+        ),
+        (backend_stats.pruned_refs, "commit references"),
+    ];
+    let summary = counts
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, noun)| format!("{count} {noun}"))
+        .join(", ");
+    if summary.is_empty() {
+        writeln!(ui.status(), "Nothing to prune.")?;
+    } else if options.dry_run {
+        writeln!(ui.status(), "Would prune {summary}.")?;
+    } else {
+        writeln!(ui.status(), "Pruned {summary}.")?;
+    }
+    Ok(())
+}
+
+/// Abandons operations that are expired by the retention policy.
+///
+/// In dry-run mode, returns the numbers of operations and views that would be
+/// pruned after abandoning. They aren't counted by the op store since they are
+/// still reachable.
+fn abandon_expired_operations(
+    ui: &Ui,
+    command: &CommandHelper,
+    options: &GcOptions,
+) -> Result<OpStoreGcStats, CommandError> {
+    if options.op_retention.is_unlimited() {
+        return Ok(OpStoreGcStats::default());
+    }
+    let mut workspace = command.load_workspace()?;
+    let repo_loader = workspace.repo_loader().clone();
+    let op_store = repo_loader.op_store();
+    let current_head_ops =
+        op_walk::get_current_head_ops(op_store, repo_loader.op_heads_store().as_ref())?;
+    let Some(expired_op) = op_walk::find_expired_op(&current_head_ops, &options.op_retention)?
+    else {
+        return Ok(OpStoreGcStats::default());
+    };
+    if options.dry_run {
+        // The root operation is never abandoned.
+        let root_op_id = op_store.root_operation_id();
+        let abandoned_ops: Vec<_> = op_walk::walk_ancestors(slice::from_ref(&expired_op))
+            .filter_ok(|op| op.id() != root_op_id)
+            .try_collect()?;
+        writeln!(
+            ui.status(),
+            "Would abandon {} operations.",
+            abandoned_ops.len()
+        )?;
+        let abandoned_ids: HashSet<_> = abandoned_ops.iter().map(|op| op.id()).collect();
+        // The descendants of the abandoned operations would be rewritten, so
+        // the current versions of them would become unreachable as well.
+        let rewritten_ops: Vec<_> = op_walk::walk_ancestors(&current_head_ops)
+            .filter_ok(|op| !abandoned_ids.contains(op.id()) && op.id() != root_op_id)
+            .try_collect()?;
+        let kept_view_ids: HashSet<_> = rewritten_ops.iter().map(|op| op.view_id()).collect();
+        // The file modification time is approximated by the end time.
+        let keep_newer = to_millis_since_epoch(options.keep_newer);
+        let is_old = |op: &&Operation| op.metadata().end_time.timestamp <= keep_newer;
+        let pruned_ops = abandoned_ops
+            .iter()
+            .chain(&rewritten_ops)
+            .filter(is_old)
+            .collect_vec();
+        let pruned_view_ids: HashSet<_> = abandoned_ops
+            .iter()
+            .filter(is_old)
+            .map(|op| op.view_id())
+            .filter(|id| !kept_view_ids.contains(id))
+            .collect();
+        return Ok(OpStoreGcStats {
+            pruned_operations: pruned_ops.len(),
+            pruned_views: pruned_view_ids.len(),
+        });
+    }
+    let stats = op_walk::reparent_range(
+        op_store.as_ref(),
+        slice::from_ref(&expired_op),
+        &current_head_ops,
+        &repo_loader.root_operation(),
+    )?;
+    writeln!(
+        ui.status(),
+        "Abandoned {} operations and reparented {} descendant operations.",
+        stats.unreachable_count,
+        stats.rewritten_count,
+    )?;
+    update_reparented_op_heads(
+        ui,
+        command,
+        &mut workspace,
+        &current_head_ops,
+        &stats.new_head_ids,
+    )?;
+    Ok(OpStoreGcStats::default())
+}
+
+/// Squashes runs of snapshot operations older than `older_than` into their
+/// newest operations. Returns the number of squashed operations.
+pub fn compact_snapshot_operations(
+    ui: &Ui,
+    command: &CommandHelper,
+    older_than: SystemTime,
+    dry_run: bool,
+) -> Result<usize, CommandError> {
+    let mut workspace = command.load_workspace()?;
+    let repo_loader = workspace.repo_loader().clone();
+    let op_store = repo_loader.op_store();
+    let current_head_ops =
+        op_walk::get_current_head_ops(op_store, repo_loader.op_heads_store().as_ref())?;
+    let squash_ids = op_walk::find_squashable_snapshot_ops(
+        &current_head_ops,
+        to_millis_since_epoch(older_than),
+    )?;
+    if squash_ids.is_empty() {
+        return Ok(0);
+    }
+    if dry_run {
+        writeln!(
+            ui.status(),
+            "Would compact {} snapshot operations.",
+            squash_ids.len()
+        )?;
+        return Ok(squash_ids.len());
+    }
+    let stats = op_walk::squash_ops(op_store.as_ref(), &squash_ids, &current_head_ops)?;
+    writeln!(
+        ui.status(),
+        "Compacted {} snapshot operations and rewrote {} descendant operations.",
+        stats.unreachable_count,
+        stats.rewritten_count,
+    )?;
+    update_reparented_op_heads(
+        ui,
+        command,
+        &mut workspace,
+        &current_head_ops,
+        &stats.new_head_ids,
+    )?;
+    Ok(stats.unreachable_count)
+}
+
+/// Returns true if the number of operation heads or index segment files of the
+/// repo exceeds the `gc.auto.*` thresholds.
+pub fn exceeds_auto_gc_thresholds(
+    settings: &UserSettings,
+    repo_loader: &RepoLoader,
+) -> Result<bool, CommandError> {
+    let config = settings.config();
+    let op_heads_limit = config.get::<usize>("gc.auto.op-heads").optional()?;
+    let segments_limit = config.get::<usize>("gc.auto.index-segments").optional()?;
+    let op_heads_limit = op_heads_limit.filter(|&limit| limit > 0);
+    let segments_limit = segments_limit.filter(|&limit| limit > 0);
+    if let Some(limit) = op_heads_limit {
+        if repo_loader.op_heads_store().get_op_heads()?.len() > limit {
+            return Ok(true);
+        }
+    }
+    if let Some(limit) = segments_limit {
+        let index_store = repo_loader.index_store();
+        if let Some(index_store) = index_store.as_any().downcast_ref::<DefaultIndexStore>() {
+            return Ok(index_store.segment_file_count()? > limit);
+        }
+    }
+    Ok(false)
+}
+
+/// Runs garbage collection if a command exceeded the `gc.auto.*` thresholds.
+pub fn maybe_run_auto_gc(ui: &mut Ui, command: &CommandHelper) -> Result<(), CommandError> {
+    if !command.is_auto_gc_requested() || command.global_args().at_operation.is_some() {
+        return Ok(());
+    }
+    writeln!(ui.status(), "Running garbage collection automatically.")?;
+    let options = GcOptions::from_settings(command.settings(), None)?;
+    run_gc(ui, command, &options)
+}
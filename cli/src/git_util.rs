@@ -91,6 +91,15 @@ pub fn is_colocated_git_workspace(workspace: &Workspace, repo: &ReadonlyRepo) ->
     git_workdir.canonicalize().ok().as_deref() == dot_git_path.parent()
 }
 
+/// Returns true if the workspace is registered as a linked worktree of the Git
+/// repo backing `repo`.
+pub fn is_git_worktree_workspace(workspace: &Workspace, repo: &ReadonlyRepo) -> bool {
+    let Some(git_backend) = repo.store().backend_impl().downcast_ref::<GitBackend>() else {
+        return false;
+    };
+    git::is_linked_worktree(&git_backend.git_repo(), workspace.workspace_root())
+}
+
 fn terminal_get_username(ui: &Ui, url: &str) -> Option<String> {
     ui.prompt(&format!("Username for {url}")).ok()
 }
@@ -24,6 +24,7 @@ pub mod config;
 pub mod description_util;
 pub mod diff_util;
 pub mod formatter;
+pub mod gc_util;
 pub mod generic_templater;
 pub mod git_util;
 pub mod graphlog;
@@ -32,6 +33,7 @@ pub mod movement_util;
 pub mod operation_templater;
 mod progress;
 pub mod revset_util;
+pub mod sparse_util;
 pub mod template_builder;
 pub mod template_parser;
 pub mod templater;
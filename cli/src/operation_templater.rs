@@ -283,12 +283,28 @@ fn builtin_operation_methods() -> OperationTemplateBuildMethodFnMap<Operation> {
                 op.metadata()
                     .tags
                     .iter()
+                    .sorted()
                     .map(|(key, value)| format!("{key}: {value}"))
                     .join("\n")
             });
             Ok(L::wrap_string(out_property))
         },
     );
+    map.insert(
+        "tag",
+        |language, diagnostics, build_ctx, self_property, function| {
+            let [name_node] = function.expect_exact_arguments()?;
+            let name_property = template_builder::expect_plain_text_expression(
+                language,
+                diagnostics,
+                build_ctx,
+                name_node,
+            )?;
+            let out_property = (self_property, name_property)
+                .map(|(op, name)| op.metadata().tags.get(&name).cloned().unwrap_or_default());
+            Ok(L::wrap_string(out_property))
+        },
+    );
     map.insert(
         "snapshot",
         |_language, _diagnostics, _build_ctx, self_property, function| {
@@ -15,13 +15,16 @@
 //! Utility for parsing and evaluating user-provided revset expressions.
 
 use std::io;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
+use jj_lib::bisect::BisectState;
 use jj_lib::commit::Commit;
 use jj_lib::id_prefix::IdPrefixContext;
+use jj_lib::object_id::ObjectId as _;
 use jj_lib::repo::Repo;
 use jj_lib::revset;
 use jj_lib::revset::DefaultSymbolResolver;
@@ -141,7 +144,11 @@ fn warn_user_redefined_builtin(
 ) -> Result<(), CommandError> {
     match source {
         ConfigSource::Default => (),
-        ConfigSource::Env | ConfigSource::User | ConfigSource::Repo | ConfigSource::CommandArg => {
+        ConfigSource::Env
+        | ConfigSource::User
+        | ConfigSource::Repo
+        | ConfigSource::Workspace
+        | ConfigSource::CommandArg => {
             let checked_mutability_builtins =
                 ["mutable()", "immutable()", "builtin_immutable_heads()"];
 
@@ -191,6 +198,40 @@ pub fn load_revset_aliases(
     Ok(aliases_map)
 }
 
+/// Defines `bisect_good()`, `bisect_bad()`, and `bisect_skip()` aliases from
+/// the state of the ongoing bisection.
+///
+/// The default aliases defined in config evaluate to `none()`.
+pub fn load_bisect_aliases(
+    ui: &Ui,
+    aliases_map: &mut RevsetAliasesMap,
+    repo_path: &Path,
+) -> Result<(), CommandError> {
+    let state = match BisectState::load(repo_path) {
+        Ok(Some(state)) => state,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            writeln!(ui.warning_default(), "{err}")?;
+            return Ok(());
+        }
+    };
+    let to_defn = |ids: &[CommitId]| {
+        if ids.is_empty() {
+            "none()".to_owned()
+        } else {
+            ids.iter().map(|id| id.hex()).join(" | ")
+        }
+    };
+    for (decl, ids) in [
+        ("bisect_good()", &state.good),
+        ("bisect_bad()", &state.bad),
+        ("bisect_skip()", &state.skipped),
+    ] {
+        aliases_map.insert(decl, to_defn(ids)).unwrap();
+    }
+    Ok(())
+}
+
 /// Wraps the given `IdPrefixContext` in `SymbolResolver` to be passed in to
 /// `evaluate()`.
 pub fn default_symbol_resolver<'a>(
@@ -0,0 +1,315 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sparse profiles, which are named sparse patterns defined in a file checked
+//! into the repo.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::Read as _;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use itertools::Itertools as _;
+use jj_lib::backend::BackendError;
+use jj_lib::backend::BackendResult;
+use jj_lib::backend::TreeValue;
+use jj_lib::commit::Commit;
+use jj_lib::file_util::IoResultExt as _;
+use jj_lib::fileset;
+use jj_lib::fileset::FilesetDiagnostics;
+use jj_lib::matchers::Matcher;
+use jj_lib::matchers::Visit;
+use jj_lib::matchers::VisitFiles;
+use jj_lib::merged_tree::MergedTree;
+use jj_lib::op_store::OperationId;
+use jj_lib::repo_path::RepoPath;
+use jj_lib::repo_path::RepoPathBuf;
+use jj_lib::repo_path::RepoPathUiConverter;
+use jj_lib::workspace::Workspace;
+use serde::Deserialize;
+
+use crate::cli_util::print_checkout_stats;
+use crate::command_error::internal_error_with_message;
+use crate::command_error::print_parse_diagnostics;
+use crate::command_error::user_error;
+use crate::command_error::user_error_with_hint;
+use crate::command_error::user_error_with_message;
+use crate::command_error::CommandError;
+use crate::command_error::ErrorHint;
+use crate::ui::Ui;
+
+/// Path of the file defining the sparse profiles, relative to the repo root.
+pub const SPARSE_PROFILES_PATH: &str = ".jj-sparse.toml";
+
+/// Name of the file in the `.jj` directory that records the active profile.
+const ACTIVE_PROFILE_FILE_NAME: &str = "sparse_profile";
+
+#[derive(Debug, Default, Deserialize)]
+struct SparseProfilesFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, String>,
+}
+
+fn sparse_profiles_path() -> &'static RepoPath {
+    RepoPath::from_internal_string(SPARSE_PROFILES_PATH)
+}
+
+/// Loads the sparse profile definitions from the given commit. Returns an
+/// empty map if the commit has no sparse profiles file.
+pub fn load_sparse_profiles(commit: &Commit) -> Result<BTreeMap<String, String>, CommandError> {
+    let path = sparse_profiles_path();
+    let tree = commit.tree()?;
+    let mut reader = match tree.path_value(path)?.into_resolved() {
+        Ok(None) => return Ok(BTreeMap::new()),
+        Ok(Some(TreeValue::File { id, .. })) => commit.store().read_file(path, &id)?,
+        _ => {
+            return Err(user_error(format!(
+                "{SPARSE_PROFILES_PATH} is not a file or has conflicts"
+            )));
+        }
+    };
+    let mut content = String::new();
+    reader.read_to_string(&mut content).map_err(|err| {
+        user_error_with_message(format!("Failed to read {SPARSE_PROFILES_PATH}"), err)
+    })?;
+    let file: SparseProfilesFile = toml_edit::de::from_str(&content).map_err(|err| {
+        user_error_with_message(format!("Failed to parse {SPARSE_PROFILES_PATH}"), err)
+    })?;
+    Ok(file.profiles)
+}
+
+/// Returns the definition of the sparse profile `name` in `commit`.
+pub fn find_sparse_profile(commit: &Commit, name: &str) -> Result<String, CommandError> {
+    let mut profiles = load_sparse_profiles(commit)?;
+    if let Some(definition) = profiles.remove(name) {
+        return Ok(definition);
+    }
+    let hint = if profiles.is_empty() {
+        format!("Sparse profiles are defined in the {SPARSE_PROFILES_PATH} file.")
+    } else {
+        format!("Available profiles: {}", profiles.keys().join(", "))
+    };
+    Err(user_error_with_hint(
+        format!("No sparse profile named '{name}'"),
+        hint,
+    ))
+}
+
+/// Computes the sparse patterns of the profile `name` from its fileset
+/// `definition`.
+///
+/// The fileset is resolved against the tree of `commit`. Directories that are
+/// entirely included by the fileset become a single pattern, and files named
+/// explicitly get a pattern each. Since sparse patterns can't express a
+/// directory without some of its contents, the profile is rejected if it
+/// includes only part of a directory (e.g. by excluding a path from it), as new
+/// files there would never be checked out. The profiles file itself is always
+/// included so it can be edited.
+pub fn resolve_sparse_profile(
+    ui: &Ui,
+    commit: &Commit,
+    name: &str,
+    definition: &str,
+) -> Result<Vec<RepoPathBuf>, CommandError> {
+    let mut diagnostics = FilesetDiagnostics::new();
+    let expression = fileset::parse(
+        &mut diagnostics,
+        definition,
+        &RepoPathUiConverter::Fs {
+            cwd: "".into(),
+            base: "".into(),
+        },
+    )?;
+    print_parse_diagnostics(ui, &format!("In sparse profile '{name}'"), &diagnostics)?;
+    let matcher = expression.to_matcher();
+    let mut patterns = vec![];
+    collect_sparse_patterns(&commit.tree()?, matcher.as_ref(), &mut patterns).map_err(|err| {
+        match err {
+            CollectSparsePatternsError::PartialDirectory(dir) => {
+                let dir = if dir.is_root() {
+                    "the repo root".to_owned()
+                } else {
+                    format!("directory '{}'", dir.as_internal_file_string())
+                };
+                user_error_with_hint(
+                    format!("Sparse profile '{name}' includes only part of {dir}"),
+                    "Sparse profiles can only include whole directories or name individual \
+                     files. Include the wanted subdirectories and files instead of excluding \
+                     paths.",
+                )
+            }
+            CollectSparsePatternsError::Backend(err) => err.into(),
+        }
+    })?;
+    if !patterns
+        .iter()
+        .any(|pattern| sparse_profiles_path().starts_with(pattern))
+    {
+        patterns.push(sparse_profiles_path().to_owned());
+    }
+    patterns.sort_unstable();
+    patterns.dedup();
+    Ok(patterns)
+}
+
+enum CollectSparsePatternsError {
+    /// The matcher includes some, but not all, of the files that may appear in
+    /// the directory.
+    PartialDirectory(RepoPathBuf),
+    Backend(BackendError),
+}
+
+impl From<BackendError> for CollectSparsePatternsError {
+    fn from(err: BackendError) -> Self {
+        CollectSparsePatternsError::Backend(err)
+    }
+}
+
+fn collect_sparse_patterns(
+    tree: &MergedTree,
+    matcher: &dyn Matcher,
+    patterns: &mut Vec<RepoPathBuf>,
+) -> Result<(), CollectSparsePatternsError> {
+    match matcher.visit(tree.dir()) {
+        Visit::AllRecursively => {
+            patterns.push(tree.dir().to_owned());
+            return Ok(());
+        }
+        Visit::Nothing => return Ok(()),
+        // Only an explicit set of files can be matched here. Otherwise, files
+        // added to the directory later might match but wouldn't be covered by
+        // any pattern.
+        Visit::Specific {
+            files: VisitFiles::All,
+            ..
+        } => {
+            return Err(CollectSparsePatternsError::PartialDirectory(
+                tree.dir().to_owned(),
+            ));
+        }
+        Visit::Specific { .. } => {}
+    }
+    for name in tree.names() {
+        let path = tree.dir().join(name);
+        if tree.value(name).is_tree() {
+            if let Some(sub_tree) = tree.sub_tree(name)? {
+                collect_sparse_patterns(&sub_tree, matcher, patterns)?;
+            }
+        } else if matcher.matches(&path) {
+            patterns.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if the sparse profiles file differs between the two commits.
+pub fn sparse_profiles_changed(old_commit: &Commit, new_commit: &Commit) -> BackendResult<bool> {
+    let path = sparse_profiles_path();
+    Ok(old_commit.tree()?.path_value(path)? != new_commit.tree()?.path_value(path)?)
+}
+
+fn active_profile_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".jj").join(ACTIVE_PROFILE_FILE_NAME)
+}
+
+/// Returns the name of the sparse profile the workspace uses, if any.
+pub fn read_active_sparse_profile(workspace_root: &Path) -> Result<Option<String>, CommandError> {
+    let path = active_profile_path(workspace_root);
+    match fs::read_to_string(&path) {
+        Ok(name) => Ok(Some(name.trim_end().to_owned())),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).context(&path)?,
+    }
+}
+
+/// Records the sparse profile the workspace uses. `None` means the sparse
+/// patterns were set by hand.
+pub fn write_active_sparse_profile(
+    workspace_root: &Path,
+    name: Option<&str>,
+) -> Result<(), CommandError> {
+    let path = active_profile_path(workspace_root);
+    match name {
+        Some(name) => {
+            let mut file = fs::File::create(&path).context(&path)?;
+            writeln!(file, "{name}").context(&path)?;
+        }
+        None => match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => Err(err).context(&path)?,
+        },
+    }
+    Ok(())
+}
+
+/// Re-resolves the workspace's active sparse profile against the new
+/// working-copy commit, and updates the sparse patterns if they changed.
+pub fn update_sparse_patterns_from_profile(
+    ui: &Ui,
+    workspace: &mut Workspace,
+    operation_id: &OperationId,
+    wc_commit: &Commit,
+) -> Result<(), CommandError> {
+    let Some(name) = read_active_sparse_profile(workspace.workspace_root())? else {
+        return Ok(());
+    };
+    // The working-copy commit has already been committed, so the profile
+    // can't be allowed to fail the command.
+    let resolved = load_sparse_profiles(wc_commit).and_then(|mut profiles| {
+        profiles
+            .remove(&name)
+            .map(|definition| resolve_sparse_profile(ui, wc_commit, &name, &definition))
+            .transpose()
+    });
+    let new_patterns = match resolved {
+        Ok(Some(patterns)) => patterns,
+        Ok(None) => {
+            writeln!(
+                ui.warning_default(),
+                "Sparse profile '{name}' is not defined in the working-copy commit. Keeping the \
+                 current sparse patterns."
+            )?;
+            return Ok(());
+        }
+        Err(err) => {
+            writeln!(
+                ui.warning_default(),
+                "{}. Keeping the current sparse patterns.",
+                err.error
+            )?;
+            for hint in &err.hints {
+                if let ErrorHint::PlainText(message) = hint {
+                    writeln!(ui.hint_default(), "{message}")?;
+                }
+            }
+            return Ok(());
+        }
+    };
+    if workspace.working_copy().sparse_patterns()? == new_patterns {
+        return Ok(());
+    }
+    let mut locked_ws = workspace.start_working_copy_mutation()?;
+    let stats = locked_ws
+        .locked_wc()
+        .set_sparse_patterns(new_patterns)
+        .map_err(|err| internal_error_with_message("Failed to update working copy paths", err))?;
+    locked_ws.finish(operation_id.clone())?;
+    writeln!(ui.status(), "Updated sparse patterns from profile '{name}'")?;
+    print_checkout_stats(ui, stats, wc_commit)?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use crate::common::TestEnvironment;
+
+fn create_linear_history(test_env: &TestEnvironment, repo_path: &Path, num_commits: usize) {
+    for i in 1..=num_commits {
+        test_env.jj_cmd_ok(repo_path, &["new", "-m", &format!("commit{i}")]);
+        std::fs::write(repo_path.join("file"), format!("{i}\n")).unwrap();
+        test_env.jj_cmd_ok(repo_path, &["bookmark", "create", &format!("c{i}")]);
+    }
+}
+
+fn get_log_output(test_env: &TestEnvironment, repo_path: &Path, revset: &str) -> String {
+    let template = r#"separate(" ", description.first_line(), bookmarks) ++ "\n""#;
+    test_env.jj_cmd_success(repo_path, &["log", "-r", revset, "-T", template])
+}
+
+#[test]
+fn test_bisect_manual() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    create_linear_history(&test_env, &repo_path, 7);
+
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["bisect", "start", "--good=c1", "--bad=c7"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Bisecting: 5 commits left to test after this (roughly 3 steps)
+    Working copy now at: nkmrtpmo 77ce6993 (empty) (no description set)
+    Parent commit      : vruxwmqv 816330c7 c4 | commit4
+    Added 0 files, modified 1 files, removed 0 files
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path, "@-"), @r"
+    ○  commit4 c4
+    │
+    ~
+    ");
+
+    // Bad commits are exposed as revset
+    test_env.jj_cmd_ok(&repo_path, &["bisect", "bad"]);
+    let marked = "bisect_good() | bisect_bad()";
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path, marked), @r"
+    ○  commit7 c7
+    ~  (elided revisions)
+    ○  commit4 c4
+    ~  (elided revisions)
+    ○  commit1 c1
+    │
+    ~
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path, "@-"), @r"
+    ○  commit3 c3
+    │
+    ~
+    ");
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "good"]);
+    insta::assert_snapshot!(stdout, @r"
+    The first bad commit is:
+    vruxwmqv 816330c7 c4 | commit4
+    ");
+    insta::assert_snapshot!(stderr, @"Hint: Use `jj bisect reset` to finish the bisection.");
+
+    // The bisection is complete
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["bisect", "good"]);
+    insta::assert_snapshot!(stderr, @"Error: No commit is being tested; specify revisions to mark");
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "reset"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Bisection finished.
+    Working copy now at: lylxulpl 5f0e183c c7 | commit7
+    Parent commit      : kmkuslsw 48e861d0 c6 | commit6
+    Added 0 files, modified 1 files, removed 0 files
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path, "@"), @r"
+    @  commit7 c7
+    │
+    ~
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path, marked), @"");
+}
+
+#[test]
+fn test_bisect_start_errors() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    create_linear_history(&test_env, &repo_path, 2);
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["bisect", "good"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: No bisection in progress
+    Hint: Use `jj bisect start` to start bisecting.
+    ");
+
+    // Without a good commit, there's nothing to check out
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "start", "--bad=c2"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Hint: Mark at least one good commit with `jj bisect good` to continue.");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["bisect", "start", "--bad=c2"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: A bisection is already in progress
+    Hint: Use `jj bisect reset` to abort it.
+    ");
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "good", "c1"]);
+    insta::assert_snapshot!(stdout, @r"
+    The first bad commit is:
+    zsuskuln 9bdacea3 c2 | commit2
+    ");
+    insta::assert_snapshot!(stderr, @"Hint: Use `jj bisect reset` to finish the bisection.");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_bisect_run() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    create_linear_history(&test_env, &repo_path, 10);
+
+    // Commits from 6 on are bad, 4 can't be tested
+    let script = r#"
+        n=$(cat file)
+        if [ "$n" -eq 4 ]; then exit 125; fi
+        [ "$n" -lt 6 ]
+    "#;
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["bisect", "start", "--good=c1", "--bad=c10"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Bisecting: 8 commits left to test after this (roughly 4 steps)
+    Working copy now at: wvuyspvk de3d0043 (empty) (no description set)
+    Parent commit      : kmkuslsw 48e861d0 c6 | commit6
+    Added 0 files, modified 1 files, removed 0 files
+    ");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["bisect", "run", "sh", "-c", script]);
+    insta::assert_snapshot!(stdout, @r"
+    The first bad commit is:
+    kmkuslsw 48e861d0 c6 | commit6
+    ");
+    insta::assert_snapshot!(stderr, @r"
+    Running `sh` on commit 48e861d0ad0d
+    Bisecting: 4 commits left to test after this (roughly 3 steps)
+    Working copy now at: nlrtlrxv 6536076c (empty) (no description set)
+    Parent commit      : vruxwmqv 816330c7 c4 | commit4
+    Added 0 files, modified 1 files, removed 0 files
+    Running `sh` on commit 816330c7628f
+    Bisecting: 4 commits left to test after this (roughly 3 steps)
+    Working copy now at: plymsszl 2c88c467 (empty) (no description set)
+    Parent commit      : royxmykx e3076a37 c3 | commit3
+    Added 0 files, modified 1 files, removed 0 files
+    Running `sh` on commit e3076a376810
+    Bisecting: 2 commits left to test after this (roughly 2 steps)
+    Working copy now at: urrlptpw 5671e6c8 (empty) (no description set)
+    Parent commit      : znkkpsqq 9a8aa0eb c5 | commit5
+    Added 0 files, modified 1 files, removed 0 files
+    Running `sh` on commit 9a8aa0eb38ba
+    Hint: Use `jj bisect reset` to finish the bisection.
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &repo_path, "bisect_skip()"), @r"
+    ○  commit4 c4
+    │
+    ~
+    ");
+}
@@ -78,6 +78,7 @@ fn test_bookmark_names() {
     --ignore-working-copy	Don't snapshot the working copy, and don't update it
     --ignore-immutable	Allow rewriting immutable commits
     --at-operation	Operation to load the repo at
+    --op-tag	Add a tag to the created operations (can be repeated)
     --debug	Enable debug logging
     --color	When to colorize output (always, never, debug, auto)
     --quiet	Silence non-primary command output
@@ -484,7 +485,8 @@ fn test_config() {
 
     let stdout = test_env.jj_cmd_success(dir, &["--", "jj", "config", "get", "c"]);
     insta::assert_snapshot!(stdout, @r"
-    core.fsmonitor	Whether to use an external filesystem monitor, useful for large repos
+    core.fsmonitor	Whether to use a filesystem monitor, useful for large repos
+    core.inotify.daemon-command	Command that runs the inotify daemon. The path to the working copy is appended to it. Defaults to running `jj debug inotify daemon` with the current executable.
     core.watchman.register_snapshot_trigger	Whether to use triggers to monitor for changes in the background.
     ");
 
@@ -492,7 +494,9 @@ fn test_config() {
     insta::assert_snapshot!(stdout, @r"
     colors	Mapping from jj formatter labels to colors
     core
-    core.fsmonitor	Whether to use an external filesystem monitor, useful for large repos
+    core.fsmonitor	Whether to use a filesystem monitor, useful for large repos
+    core.inotify
+    core.inotify.daemon-command	Command that runs the inotify daemon. The path to the working copy is appended to it. Defaults to running `jj debug inotify daemon` with the current executable.
     core.watchman
     core.watchman.register_snapshot_trigger	Whether to use triggers to monitor for changes in the background.
     ");
@@ -420,22 +420,60 @@ fn test_config_layer_workspace() {
     insta::assert_snapshot!(stdout, @r###"
     ui.editor = "main-repo"
     "###);
+
+    // Workspace
+    test_env.jj_cmd_ok(
+        &secondary_path,
+        &[
+            "config",
+            "set",
+            "--workspace",
+            config_key,
+            "secondary-workspace",
+        ],
+    );
+    let stdout = test_env.jj_cmd_success(&main_path, &["config", "list", config_key]);
+    insta::assert_snapshot!(stdout, @r#"ui.editor = "main-repo""#);
+    let stdout = test_env.jj_cmd_success(&secondary_path, &["config", "list", config_key]);
+    insta::assert_snapshot!(stdout, @r#"ui.editor = "secondary-workspace""#);
+    insta::assert_snapshot!(
+        std::fs::read_to_string(secondary_path.join(".jj/config.toml")).unwrap(), @r#"
+    [ui]
+    editor = "secondary-workspace"
+    "#);
+    let stdout = test_env.jj_cmd_success(
+        &secondary_path,
+        &[
+            "config",
+            "list",
+            config_key,
+            "--include-overridden",
+            "-T",
+            r#"source ++ ": " ++ name ++ " = " ++ value ++ if(overridden, " (overridden)") ++ "\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r#"
+    repo: ui.editor = "main-repo" (overridden)
+    workspace: ui.editor = "secondary-workspace"
+    "#);
+    let stdout = test_env.jj_cmd_success(&secondary_path, &["config", "list", "--workspace"]);
+    insta::assert_snapshot!(stdout, @r#"ui.editor = "secondary-workspace""#);
 }
 
 #[test]
 fn test_config_set_bad_opts() {
     let test_env = TestEnvironment::default();
     let stderr = test_env.jj_cmd_cli_error(test_env.env_root(), &["config", "set"]);
-    insta::assert_snapshot!(stderr, @r###"
+    insta::assert_snapshot!(stderr, @r#"
     error: the following required arguments were not provided:
-      <--user|--repo>
+      <--user|--repo|--workspace>
       <NAME>
       <VALUE>
 
-    Usage: jj config set <--user|--repo> <NAME> <VALUE>
+    Usage: jj config set <--user|--repo|--workspace> <NAME> <VALUE>
 
     For more information, try '--help'.
-    "###);
+    "#);
 
     let stderr =
         test_env.jj_cmd_cli_error(test_env.env_root(), &["config", "set", "--user", "", "x"]);
@@ -730,14 +768,14 @@ fn test_config_unset_for_repo() {
 fn test_config_edit_missing_opt() {
     let test_env = TestEnvironment::default();
     let stderr = test_env.jj_cmd_cli_error(test_env.env_root(), &["config", "edit"]);
-    insta::assert_snapshot!(stderr, @r###"
+    insta::assert_snapshot!(stderr, @r#"
     error: the following required arguments were not provided:
-      <--user|--repo>
+      <--user|--repo|--workspace>
 
-    Usage: jj config edit <--user|--repo>
+    Usage: jj config edit <--user|--repo|--workspace>
 
     For more information, try '--help'.
-    "###);
+    "#);
 }
 
 #[test]
@@ -1026,3 +1064,96 @@ fn find_stdout_lines(keyname_pattern: &str, stdout: &str) -> String {
         .collect_vec()
         .join("\n")
 }
+
+#[test]
+fn test_config_conditional_scopes() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(
+        r#"
+        test-key = "default"
+
+        [[--scope]]
+        --when.workspaces = ["*/oss-*"]
+        test-key = "oss"
+
+        [[--scope]]
+        --when.remotes = ["*example.com:work/*"]
+        [--scope.test-table]
+        key = "work"
+        "#,
+    );
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "oss-repo"]);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "work-repo"]);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "other-repo"]);
+    let oss_path = test_env.env_root().join("oss-repo");
+    let work_path = test_env.env_root().join("work-repo");
+    let other_path = test_env.env_root().join("other-repo");
+    test_env.jj_cmd_ok(
+        &work_path,
+        &[
+            "git",
+            "remote",
+            "add",
+            "origin",
+            "git@example.com:work/repo.git",
+        ],
+    );
+
+    // Scopes matching the workspace path
+    let stdout = test_env.jj_cmd_success(&oss_path, &["config", "get", "test-key"]);
+    insta::assert_snapshot!(stdout, @"oss");
+    let stdout = test_env.jj_cmd_success(
+        &oss_path,
+        &["config", "list", "test-key", "--include-overridden"],
+    );
+    insta::assert_snapshot!(stdout, @r#"
+    # test-key = "default"
+    test-key = "oss" # when workspaces = ["*/oss-*"]
+    "#);
+
+    // Scopes matching a Git remote
+    let stdout = test_env.jj_cmd_success(&work_path, &["config", "list", "test-table"]);
+    insta::assert_snapshot!(stdout, @r#"test-table.key = "work" # when remotes = ["*example.com:work/*"]"#);
+    let stdout = test_env.jj_cmd_success(&work_path, &["config", "get", "test-key"]);
+    insta::assert_snapshot!(stdout, @"default");
+
+    // No scopes match
+    let stdout = test_env.jj_cmd_success(&other_path, &["config", "list", "test-key"]);
+    insta::assert_snapshot!(stdout, @r#"test-key = "default""#);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&other_path, &["config", "list", "test-table"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Warning: No matching config key for test-table");
+
+    // Scopes can be defined in the repo config too, with invalid conditions
+    // reported
+    std::fs::write(
+        other_path.join(".jj/repo/config.toml"),
+        "[[--scope]]\n--when.branches = [\"main\"]\ntest-key = \"repo\"\n",
+    )
+    .unwrap();
+    let stderr = test_env.jj_cmd_failure(&other_path, &["config", "get", "test-key"]);
+    insta::assert_snapshot!(stderr, @r"
+    Config error: Invalid `--scope` in $TEST_ENV/other-repo/.jj/repo/config.toml: unknown condition `branches` in `--when`
+    For help, see https://martinvonz.github.io/jj/latest/config/.
+    ");
+    std::fs::write(
+        other_path.join(".jj/repo/config.toml"),
+        "[[--scope]]\n--when.workspaces = [\"*/other-repo\"]\ntest-key = \"repo\"\n",
+    )
+    .unwrap();
+    let stdout = test_env.jj_cmd_success(
+        &other_path,
+        &[
+            "config",
+            "list",
+            "test-key",
+            "--include-overridden",
+            "-T",
+            r#"source ++ " " ++ name ++ " = " ++ value ++ " [" ++ condition ++ "]\n""#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r#"
+    user test-key = "default" []
+    repo test-key = "repo" [workspaces = ["*/other-repo"]]
+    "#);
+}
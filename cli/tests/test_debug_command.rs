@@ -240,6 +240,82 @@ fn test_debug_operation_id() {
     );
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn test_debug_inotify() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_path = test_env.env_root().join("repo");
+    let filter_socket = |text: &str| {
+        let regex = Regex::new(r"listening on .*\.sock").unwrap();
+        regex.replace_all(text, "listening on $$SOCKET").to_string()
+    };
+
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "inotify", "status"]);
+    assert_snapshot!(stdout, @r#"
+    inotify is disabled. Set `core.fsmonitor="inotify"` to enable.
+    The inotify daemon is not running.
+    "#);
+
+    // The daemon is started by the first snapshot
+    test_env.add_config(r#"core.fsmonitor = "inotify""#);
+    std::fs::write(workspace_path.join("file1"), "").unwrap();
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["file", "list"]);
+    assert_snapshot!(stdout, @"file1");
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "inotify", "status"]);
+    assert_snapshot!(filter_socket(&stdout), @r"
+    inotify is enabled via `core.fsmonitor`.
+    The inotify daemon is running and listening on $SOCKET.
+    ");
+
+    // Changes are picked up from the daemon
+    std::fs::create_dir_all(workspace_path.join("dir/subdir")).unwrap();
+    std::fs::write(workspace_path.join("dir/subdir/file2"), "").unwrap();
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["file", "list"]);
+    assert_snapshot!(stdout, @r"
+    dir/subdir/file2
+    file1
+    ");
+    std::fs::remove_dir_all(workspace_path.join("dir")).unwrap();
+    std::fs::remove_file(workspace_path.join("file1")).unwrap();
+    std::fs::write(workspace_path.join("file3"), "").unwrap();
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["file", "list"]);
+    assert_snapshot!(stdout, @"file3");
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&workspace_path, &["debug", "inotify", "stop"]);
+    assert_snapshot!(stdout, @"");
+    assert_snapshot!(stderr, @"Stopped the inotify daemon.");
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["debug", "inotify", "status"]);
+    assert_snapshot!(stdout, @r"
+    inotify is enabled via `core.fsmonitor`.
+    The inotify daemon is not running.
+    ");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_debug_inotify_base_ignores() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "--colocate", "repo"]);
+    let workspace_path = test_env.env_root().join("repo");
+    std::fs::create_dir(workspace_path.join("ignored")).unwrap();
+    std::fs::write(workspace_path.join("ignored/tracked"), "").unwrap();
+    test_env.jj_cmd_ok(&workspace_path, &["new"]);
+    std::fs::write(workspace_path.join(".git/info/exclude"), "ignored/\n").unwrap();
+
+    // The daemon doesn't watch the ignored directory, but tracked files in it
+    // are still snapshotted
+    test_env.add_config(r#"core.fsmonitor = "inotify""#);
+    test_env.jj_cmd_success(&workspace_path, &["file", "list"]);
+    std::fs::write(workspace_path.join("ignored/tracked"), "modified").unwrap();
+    std::fs::write(workspace_path.join("ignored/untracked"), "").unwrap();
+    let stdout = test_env.jj_cmd_success(&workspace_path, &["diff", "--summary"]);
+    assert_snapshot!(stdout, @"M ignored/tracked");
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&workspace_path, &["debug", "inotify", "stop"]);
+    assert_snapshot!(stderr, @"Stopped the inotify daemon.");
+}
+
 fn filter_index_stats(text: &str) -> String {
     let regex = Regex::new(r"    Name: [0-9a-z]+").unwrap();
     regex.replace_all(text, "    Name: [hash]").to_string()
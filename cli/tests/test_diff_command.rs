@@ -222,6 +222,55 @@ fn test_diff_basic() {
     insta::assert_snapshot!(stderr, @"");
 }
 
+#[test]
+fn test_diff_recorded_renames() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "foo\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+
+    // The rename is recorded when the working copy is snapshotted
+    std::fs::rename(repo_path.join("file1"), repo_path.join("file2")).unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"R {file1 => file2}");
+
+    // Renames made in later snapshots are combined
+    std::fs::rename(repo_path.join("file2"), repo_path.join("file3")).unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"R {file1 => file3}");
+
+    // The rename is kept even if the content is no longer similar
+    std::fs::write(repo_path.join("file3"), "bar\n").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--git"]);
+    insta::assert_snapshot!(stdout, @r"
+    diff --git a/file1 b/file3
+    rename from file1
+    rename to file3
+    index 257cc5642c..5716ca5987 100644
+    --- a/file1
+    +++ b/file3
+    @@ -1,1 +1,1 @@
+    -foo
+    +bar
+    ");
+
+    // Moving the file back leaves no rename
+    std::fs::rename(repo_path.join("file3"), repo_path.join("file1")).unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"M file1");
+
+    // A new commit doesn't inherit the recorded renames
+    std::fs::rename(repo_path.join("file1"), repo_path.join("file4")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    std::fs::write(repo_path.join("file4"), "baz\n").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s"]);
+    insta::assert_snapshot!(stdout, @"M file4");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "-s", "-r@-"]);
+    insta::assert_snapshot!(stdout, @"R {file1 => file4}");
+}
+
 #[test]
 fn test_diff_empty() {
     let test_env = TestEnvironment::default();
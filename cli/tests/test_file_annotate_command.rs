@@ -47,6 +47,40 @@ fn test_annotate_linear() {
     ");
 }
 
+#[test]
+fn test_annotate_deprecated_commit_summary_template() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file.txt"), "line1\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=initial"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=next"]);
+    append_to_file(&repo_path.join("file.txt"), "new text from new commit");
+
+    test_env.add_config(r#"templates.annotate_commit_summary = 'description.first_line()'"#);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["file", "annotate", "file.txt"]);
+    insta::assert_snapshot!(stdout, @r"
+    initial    1: line1
+    next    2: new text from new commit
+    ");
+    insta::assert_snapshot!(stderr, @r"
+    Warning: The `templates.annotate_commit_summary` config option is deprecated; use `templates.file_annotate` instead
+    Hint: The `templates.file_annotate` template renders the whole line, and the commit can be accessed as `commit`.
+    ");
+
+    // The -T option takes precedence
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["file", "annotate", "file.txt", "-T", "line_number ++ '\n'"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    1
+    2
+    ");
+    insta::assert_snapshot!(stderr, @"");
+}
+
 #[test]
 fn test_annotate_merge() {
     let test_env = TestEnvironment::default();
@@ -149,3 +183,118 @@ fn test_annotate_merge_one_sided_conflict_resolution() {
     zsuskuln test.use 2001-02-03 08:05:11    2: new text from new commit 1
     ");
 }
+
+#[test]
+fn test_annotate_rename() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("old.txt"), "line1\nline2\nline3\nline4\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=initial"]);
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=rename"]);
+    std::fs::rename(repo_path.join("old.txt"), repo_path.join("new.txt")).unwrap();
+    append_to_file(&repo_path.join("new.txt"), "line5");
+
+    let template = r#"commit.description().first_line() ++ " " ++ original_path ++ ":" ++ original_line_number ++ ": " ++ content"#;
+    let stdout =
+        test_env.jj_cmd_success(&repo_path, &["file", "annotate", "new.txt", "-T", template]);
+    insta::assert_snapshot!(stdout, @r"
+    initial old.txt:1: line1
+    initial old.txt:2: line2
+    initial old.txt:3: line3
+    initial old.txt:4: line4
+    rename new.txt:5: line5
+    ");
+}
+
+#[test]
+fn test_annotate_ignore() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file.txt"), "line1\nline2\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=initial"]);
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=style: reformat"]);
+    std::fs::write(repo_path.join("file.txt"), "LINE1\nline2\n").unwrap();
+
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m=next"]);
+    append_to_file(&repo_path.join("file.txt"), "line3");
+
+    let template = r#"commit.description().first_line() ++ ": " ++ content"#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["file", "annotate", "file.txt", "-T", template],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    style: reformat: LINE1
+    initial: line2
+    next: line3
+    ");
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "file",
+            "annotate",
+            "file.txt",
+            "-T",
+            template,
+            r#"--config-toml=annotate.ignore='description(glob:"style:*")'"#,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    initial: LINE1
+    initial: line2
+    next: line3
+    ");
+}
+
+#[test]
+fn test_annotate_line_ranges() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(
+        repo_path.join("file.txt"),
+        "line1\nline2\nline3\nline4\nline5\n",
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m=initial"]);
+
+    let template = r#"line_number ++ ": " ++ content"#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["file", "annotate", "file.txt", "-T", template, "-L2,3"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    2: line2
+    3: line3
+    ");
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "file", "annotate", "file.txt", "-T", template, "-L1,1", "-L4,",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    1: line1
+    4: line4
+    5: line5
+    ");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["file", "annotate", "file.txt", "-L6,7"]);
+    insta::assert_snapshot!(stderr, @"Error: Line 6 is out of range: file.txt has only 5 lines");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["file", "annotate", "file.txt", "-L3,2"]);
+    insta::assert_snapshot!(stderr, @r"
+    error: invalid value '3,2' for '--lines <START,END>': Invalid line range: 3 is greater than 2
+
+    For more information, try '--help'.
+    ");
+}
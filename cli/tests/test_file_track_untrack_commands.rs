@@ -220,3 +220,66 @@ fn test_track_ignored() {
     file1
     "###);
 }
+
+#[test]
+fn test_track_explain() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"snapshot.auto-track = 'glob:"**/*.txt"'"#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join(".gitignore"), "*.bak\n!keep.bak\ntarget/\n").unwrap();
+    std::fs::create_dir(repo_path.join("sub")).unwrap();
+    std::fs::write(repo_path.join("sub").join(".gitignore"), "*.log\n").unwrap();
+    std::fs::create_dir(repo_path.join("target")).unwrap();
+    std::fs::write(repo_path.join("target").join("out.txt"), "").unwrap();
+    std::fs::write(repo_path.join("file.txt"), "").unwrap();
+    std::fs::write(repo_path.join("file.rs"), "").unwrap();
+    std::fs::write(repo_path.join("file.bak"), "").unwrap();
+    std::fs::write(repo_path.join("keep.bak"), "").unwrap();
+    std::fs::write(repo_path.join("sub").join("file.log"), "").unwrap();
+    std::fs::write(repo_path.join("sub").join("file.txt"), "").unwrap();
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["file", "track", "--explain", "."]);
+    insta::assert_snapshot!(stdout, @r"
+    .gitignore: not matched by `snapshot.auto-track`
+    file.bak: ignored by pattern `*.bak` in .gitignore:1
+    file.rs: not matched by `snapshot.auto-track`
+    keep.bak: not matched by `snapshot.auto-track`
+    sub/.gitignore: not matched by `snapshot.auto-track`
+    sub/file.log: ignored by pattern `*.log` in sub/.gitignore:1
+    target/: ignored by pattern `target/` in .gitignore:3
+    ");
+    insta::assert_snapshot!(stderr, @"");
+
+    // Files in an ignored directory are explained individually if the
+    // directory isn't matched as a whole
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &["file", "track", "--explain", "target/out.txt"],
+    );
+    insta::assert_snapshot!(stdout, @"target/out.txt: in directory target/ ignored by pattern `target/` in .gitignore:3");
+    insta::assert_snapshot!(stderr, @"");
+
+    // Files too large to be tracked
+    std::fs::write(repo_path.join("large.txt"), "a lot of text").unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "file",
+            "track",
+            "--explain",
+            "large.txt",
+            "--ignore-working-copy",
+            "--config-toml=snapshot.max-new-file-size=10",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @"large.txt: larger than `snapshot.max-new-file-size` (13.0B > 10.0B)");
+    insta::assert_snapshot!(stderr, @"");
+
+    // Tracked files aren't listed
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["file", "track", "--explain", "sub/file.txt"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"No untracked files match the given paths.");
+}
@@ -771,6 +771,148 @@ fn test_git_colocated_undo_head_move() {
     "#);
 }
 
+#[test]
+fn test_git_colocated_workspace_git_worktree() {
+    let test_env = TestEnvironment::default();
+    let workspace_root = test_env.env_root().join("repo");
+    let git_repo = git2::Repository::init(&workspace_root).unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["git", "init", "--git-repo", "."]);
+    std::fs::write(workspace_root.join("file"), "contents").unwrap();
+    test_env.jj_cmd_ok(&workspace_root, &["commit", "-m", "initial"]);
+    test_env.jj_cmd_ok(&workspace_root, &["bookmark", "create", "-r@-", "main"]);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &workspace_root,
+        &["workspace", "add", "--git-worktree", "../second"],
+    );
+    insta::assert_snapshot!(stderr, @r#"
+    Created workspace in "../second"
+    Working copy now at: rzvqmyuk 5ed2222c (empty) (no description set)
+    Parent commit      : qpvuntsm 751b12b7 main | initial
+    Added 1 files, modified 0 files, removed 0 files
+    "#);
+    let second_root = test_env.env_root().join("second");
+
+    // The new workspace is a Git worktree whose HEAD is the parent of its
+    // working-copy commit
+    let worktree_repo = git2::Repository::open(&second_root).unwrap();
+    assert!(worktree_repo.is_worktree());
+    assert!(worktree_repo.head_detached().unwrap());
+    insta::assert_snapshot!(
+        worktree_repo.head().unwrap().target().unwrap().to_string(),
+        @"751b12b7b9817f1688295ed393079df5a3305550"
+    );
+    assert!(is_git_status_clean(&worktree_repo));
+    insta::assert_snapshot!(get_log_output(&test_env, &second_root), @r"
+    @  5ed2222c28e2c548ec1033cdb20ab4537072b68f
+    │ ○  8183d0fcaa4cab922613b1af702b0e5f2d7e40f3
+    ├─╯
+    ○  751b12b7b9817f1688295ed393079df5a3305550 main git_head() initial
+    ◆  0000000000000000000000000000000000000000
+    ");
+
+    // Checking out another commit in the worktree moves its HEAD, but not the
+    // HEAD of the main worktree
+    std::fs::write(second_root.join("file"), "modified").unwrap();
+    test_env.jj_cmd_ok(&second_root, &["commit", "-m", "second"]);
+    insta::assert_snapshot!(
+        worktree_repo.head().unwrap().target().unwrap().to_string(),
+        @"eea5f4a2be58eed20e333c8a8e13a33e8319332f"
+    );
+    assert!(is_git_status_clean(&worktree_repo));
+    insta::assert_snapshot!(
+        git_repo.head().unwrap().target().unwrap().to_string(),
+        @"751b12b7b9817f1688295ed393079df5a3305550"
+    );
+
+    // A bookmark checked out in the worktree is detached before it's moved
+    worktree_repo.set_head("refs/heads/main").unwrap();
+    test_env.jj_cmd_ok(
+        &workspace_root,
+        &["bookmark", "set", "main", "-r", "second@-"],
+    );
+    assert!(worktree_repo.head_detached().unwrap());
+    insta::assert_snapshot!(
+        worktree_repo.head().unwrap().target().unwrap().to_string(),
+        @"751b12b7b9817f1688295ed393079df5a3305550"
+    );
+    insta::assert_snapshot!(
+        git_repo.find_reference("refs/heads/main").unwrap().target().unwrap().to_string(),
+        @"eea5f4a2be58eed20e333c8a8e13a33e8319332f"
+    );
+
+    // Moving the workspace updates the worktree's location in the Git repo
+    test_env.jj_cmd_ok(&second_root, &["workspace", "move", "../moved"]);
+    let moved_root = test_env.env_root().join("moved");
+    let worktree = git_repo.find_worktree("second").unwrap();
+    assert_eq!(
+        worktree.path().canonicalize().unwrap(),
+        moved_root.canonicalize().unwrap()
+    );
+    assert!(worktree.validate().is_ok());
+    let stdout = test_env.jj_cmd_success(&moved_root, &["status"]);
+    insta::assert_snapshot!(stdout, @r"
+    The working copy is clean
+    Working copy : royxmykx adf1e90b (empty) (no description set)
+    Parent commit: rzvqmyuk eea5f4a2 main | second
+    ");
+
+    // Moving the main workspace updates the worktree's pointer to the Git repo
+    test_env.jj_cmd_ok(&workspace_root, &["workspace", "move", "../main"]);
+    let main_root = test_env.env_root().join("main");
+    let git_repo = git2::Repository::open(&main_root).unwrap();
+    let worktree = git_repo.find_worktree("second").unwrap();
+    assert!(worktree.validate().is_ok());
+    let worktree_repo = git2::Repository::open(&moved_root).unwrap();
+    assert!(worktree_repo.is_worktree());
+    assert_eq!(
+        worktree_repo.path().canonicalize().unwrap(),
+        main_root
+            .join(".git/worktrees/second")
+            .canonicalize()
+            .unwrap()
+    );
+    let stdout = test_env.jj_cmd_success(&moved_root, &["status"]);
+    insta::assert_snapshot!(stdout, @r"
+    The working copy is clean
+    Working copy : royxmykx adf1e90b (empty) (no description set)
+    Parent commit: rzvqmyuk eea5f4a2 main | second
+    ");
+
+    // Forgetting the workspace unregisters the worktree
+    test_env.jj_cmd_ok(&main_root, &["workspace", "forget", "second"]);
+    assert!(git_repo.find_worktree("second").is_err());
+    assert!(!main_root
+        .join(".git")
+        .join("worktrees")
+        .join("second")
+        .exists());
+    assert!(moved_root.join("file").exists());
+}
+
+#[test]
+fn test_git_colocated_workspace_git_worktree_not_colocated() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let workspace_root = test_env.env_root().join("repo");
+
+    let stderr = test_env.jj_cmd_failure(
+        &workspace_root,
+        &["workspace", "add", "--git-worktree", "../second"],
+    );
+    insta::assert_snapshot!(stderr, @r"
+    Error: Cannot register a Git worktree in a repo that isn't colocated with Git
+    Hint: Run this command from a workspace colocated with Git.
+    ");
+    assert!(!test_env.env_root().join("second").exists());
+}
+
+fn is_git_status_clean(git_repo: &git2::Repository) -> bool {
+    let mut options = git2::StatusOptions::new();
+    options.include_ignored(false);
+    git_repo.statuses(Some(&mut options)).unwrap().is_empty()
+}
+
 fn get_log_output_divergence(test_env: &TestEnvironment, repo_path: &Path) -> String {
     let template = r#"
     separate(" ",
@@ -924,6 +924,110 @@ fn test_log_filtered_by_path() {
     "###);
 }
 
+#[test]
+fn test_log_follow_renames() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    let content = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    std::fs::write(repo_path.join("file1"), content).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "second"]);
+    std::fs::write(repo_path.join("other"), "other\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "rename"]);
+    std::fs::rename(repo_path.join("file1"), repo_path.join("file2")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "fourth"]);
+    std::fs::write(repo_path.join("file2"), format!("{content}9\n")).unwrap();
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description", "file2"]);
+    insta::assert_snapshot!(stdout, @r"
+    @  fourth
+    ○  rename
+    │
+    ~
+    ");
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "-T", "description", "--follow", "-s", "file2"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    @  fourth
+    │  M file2
+    ○  rename
+    │  (Renamed from file1 to file2)
+    │  R {file1 => file2}
+    ~  (elided revisions)
+    ○  first
+    │  A file1
+    ~
+    ");
+
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-T",
+            r#"description.first_line() ++ "\n""#,
+            "--follow",
+            "--reversed",
+            "--no-graph",
+            "file2",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    first
+    rename
+    (Renamed from file1 to file2)
+    fourth
+    ");
+
+    // The rename is followed even if it isn't displayed.
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-T",
+            "description",
+            "--follow",
+            "-s",
+            "-r",
+            "::@--",
+            "file2",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    ○  first
+    │  A file1
+    ~
+    ");
+
+    // The revset form doesn't mark the renames.
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "-T", "description", "-r", "files(follow:file2)"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    @  fourth
+    ○  rename
+    ~  (elided revisions)
+    ○  first
+    │
+    ~
+    ");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["log", "--follow"]);
+    insta::assert_snapshot!(stderr, @r"
+    error: the following required arguments were not provided:
+      <PATHS>...
+
+    Usage: jj log --follow <PATHS>...
+
+    For more information, try '--help'.
+    ");
+}
+
 #[test]
 fn test_log_limit() {
     let test_env = TestEnvironment::default();
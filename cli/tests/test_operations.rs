@@ -366,6 +366,7 @@ fn test_op_log_word_wrap() {
     │     description set)
     │     - qpvuntsm hidden 230dd059 (empty)
     │     (no description set)
+    │     A file1
     ○  eac759b9ab75
     │  test-username@host.example.com
     │  2001-02-03 04:05:07.000 +07:00 -
@@ -430,6 +431,7 @@ fn test_op_log_word_wrap() {
     │     - 0 1 2 3
     │     4 5 6 7 8
     │     9
+    │     A file1
     "#);
 }
 
@@ -453,6 +455,246 @@ fn test_op_log_configurable() {
     assert!(stdout.contains("my-username@my-hostname"));
 }
 
+#[test]
+fn test_op_log_op_tags() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"operation.tags.bot = "nightly""#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["--op-tag", "build=1234", "describe", "-m", "first"],
+    );
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "--op-tag=build=5678",
+            "--op-tag=bot=manual",
+            "describe",
+            "-m",
+            "second",
+        ],
+    );
+    let template = r#"separate(" ", id.short(), self.tag("build"), self.tag("bot")) ++ "\n""#;
+
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", template]);
+    insta::assert_snapshot!(stdout, @r"
+    @  91e1b92ea62b 5678 manual
+    ○  a18c39b300b9 1234 nightly
+    ○  6763340154bd nightly
+    ○  000000000000
+    ");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "-T", template, "-r", "tags(build)"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    @  91e1b92ea62b 5678 manual
+    ○  a18c39b300b9 1234 nightly
+    │
+    ~
+    ");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "-T", template, "-r", "tags(bot, nightly)"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    ○  a18c39b300b9 1234 nightly
+    ○  6763340154bd nightly
+    │
+    ~
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "--no-graph"]);
+    insta::assert_snapshot!(stdout, @r"
+    91e1b92ea62b test-username@host.example.com 2001-02-03 04:05:09.000 +07:00 - 2001-02-03 04:05:09.000 +07:00
+    describe commit fa15625b4a986997697639dfc2844138900c79f2
+    args: jj '--op-tag=build=5678' '--op-tag=bot=manual' describe -m second
+    bot: manual
+    build: 5678
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm 53aecb7c (empty) second
+    - qpvuntsm hidden fa15625b (empty) first
+    ");
+
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["--op-tag", "build", "status"]);
+    insta::assert_snapshot!(stderr, @r"
+    error: invalid value 'build' for '--op-tag <KEY=VALUE>': Expected tag in the form KEY=VALUE
+
+    For more information, try '--help'.
+    ");
+}
+
+#[test]
+fn test_op_resolve_at_time() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+    let template = r#"id.short() ++ " " ++ time.end() ++ " " ++ description"#;
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", template]);
+    insta::assert_snapshot!(stdout, @r"
+    @  3d5b67a0e95f 2001-02-03 04:05:09.000 +07:00 describe commit fa15625b4a986997697639dfc2844138900c79f2
+    ○  ed907a7a52ab 2001-02-03 04:05:08.000 +07:00 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ○  eac759b9ab75 2001-02-03 04:05:07.000 +07:00 add workspace 'default'
+    ○  000000000000 1970-01-01 00:00:00.000 +00:00
+    ");
+
+    // The latest operation that had finished by the given time
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "--at-op=at:2001-02-03 04:05:09.500+07:00",
+            "-r@",
+            "-Tdescription",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    @  second
+    │
+    ~
+    ");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "--at-op=at:2001-02-03 04:05:08+07:00",
+            "-T",
+            template,
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    @  ed907a7a52ab 2001-02-03 04:05:08.000 +07:00 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ○  eac759b9ab75 2001-02-03 04:05:07.000 +07:00 add workspace 'default'
+    ○  000000000000 1970-01-01 00:00:00.000 +00:00
+    ");
+
+    // Time-based operations can be used in revsets
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "log",
+            "-r",
+            r#"at_operation(at:"2001-02-03 04:05:08+07:00", @)"#,
+            "-Tdescription",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    ○  first
+    │
+    ~
+    ");
+
+    // All operations finished before an hour ago
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["log", "--at-op=ago:1 hour", "-r@", "-Tdescription"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    @  second
+    │
+    ~
+    ");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["log", "--at-op=at:bogus"]);
+    insta::assert_snapshot!(stderr, @r#"
+    Error: Invalid time "at:bogus"
+    Caused by: expected week day or month name
+    "#);
+}
+
+#[test]
+fn test_op_log_opset() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "description 0"]);
+    std::fs::write(repo_path.join("file"), "a").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["status"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "description 1"]);
+    std::fs::write(repo_path.join("file"), "b").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["status"]);
+    let op_log = |args: &[&str]| {
+        let template = r#"id.short() ++ " " ++ description.first_line() ++ "\n""#;
+        test_env.jj_cmd_success(&repo_path, &[&["op", "log", "-T", template], args].concat())
+    };
+
+    insta::assert_snapshot!(op_log(&["-r", "snapshot()"]), @r"
+    @  dd93ebbb2030 snapshot working copy
+    ○  dfaf7de61355 snapshot working copy
+    │
+    ~
+    ");
+    insta::assert_snapshot!(op_log(&["-r", "description(describe) | root()"]), @r"
+    ○  05f1aa0eeff3 describe commit 8db6c898b6bafd2b653131365150c43fcc12be6d
+    ○  d009cfc04993 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    ○  000000000000
+    ");
+    insta::assert_snapshot!(op_log(&["-r", "@-", "-r", "@---"]), @r"
+    ○  05f1aa0eeff3 describe commit 8db6c898b6bafd2b653131365150c43fcc12be6d
+    ○  d009cfc04993 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    │
+    ~
+    ");
+    insta::assert_snapshot!(op_log(&["-r", "::@-- ~ snapshot()", "--no-graph"]), @r"
+    d009cfc04993 describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    eac759b9ab75 add workspace 'default'
+    000000000000
+    ");
+    let opset = "user(test-username) & hostname(exact:host.example.com) & \
+                 time(after:'2001-02-03T04:05:10+07:00')";
+    insta::assert_snapshot!(op_log(&["-r", opset]), @r"
+    @  dd93ebbb2030 snapshot working copy
+    ○  05f1aa0eeff3 describe commit 8db6c898b6bafd2b653131365150c43fcc12be6d
+    │
+    ~
+    ");
+    insta::assert_snapshot!(op_log(&["-r", "none()"]), @"");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "log", "-r", "snapshot("]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: Failed to parse operation set: Syntax error
+    Caused by:  --> 1:10
+      |
+    1 | snapshot(
+      |          ^---
+      |
+      = expected <identifier> or <expression>
+    Hint: See https://martinvonz.github.io/jj/latest/operation-log/#operation-sets for operation set syntax.
+    ");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "log", "-r", "snapshots()"]);
+    insta::assert_snapshot!(stderr, @r#"
+    Error: Failed to parse operation set: Function "snapshots" doesn't exist
+    Caused by:  --> 1:1
+      |
+    1 | snapshots()
+      | ^-------^
+      |
+      = Function "snapshots" doesn't exist
+    Hint: Did you mean "snapshot"?
+    "#);
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "log", "-r", "time(yesterday)"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: Failed to parse operation set: Invalid date pattern
+    Caused by:
+    1:  --> 1:6
+      |
+    1 | time(yesterday)
+      |      ^-------^
+      |
+      = Invalid date pattern
+    2: Date pattern must specify 'after' or 'before'
+    ");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "log", "-r", "abc123"]);
+    insta::assert_snapshot!(stderr, @r#"
+    Error: No operation ID matching "abc123"
+    "#);
+}
+
 #[test]
 fn test_op_abandon_ancestors() {
     let test_env = TestEnvironment::default();
@@ -653,7 +895,7 @@ fn test_op_abandon_multiple_heads() {
     // heads store.
     let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "abandon", prev_op_id]);
     insta::assert_snapshot!(stderr, @r###"
-    Abandoned 1 operations and reparented 2 descendant operations.
+    Abandoned 1 operations and reparented 1 descendant operations.
     "###);
 
     let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "log"]);
@@ -679,6 +921,146 @@ fn test_op_abandon_multiple_heads() {
     "###);
 }
 
+#[test]
+fn test_op_abandon_opset() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "a").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "commit 1"]);
+    std::fs::write(repo_path.join("file"), "b").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "commit 2"]);
+    std::fs::write(repo_path.join("file"), "c").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["status"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "description 3"]);
+    let op_log = || {
+        let template = r#"id.short() ++ " " ++ description.first_line() ++ "\n""#;
+        test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", template])
+    };
+    insta::assert_snapshot!(op_log(), @r"
+    @  c374bcfa4f42 describe commit b40e57748417d8304ad182579b7130ee8d560cea
+    ○  2b18e0d045d8 snapshot working copy
+    ○  c5334af6f148 commit cd160618738105d69ff6813d78f1bdeb030e7e51
+    ○  a56d5de691d9 snapshot working copy
+    ○  76082ef435d6 commit f3220d78b25353bd8fd65988018004b962d0894a
+    ○  76d6f6671515 snapshot working copy
+    ○  eac759b9ab75 add workspace 'default'
+    ○  000000000000
+    ");
+
+    // Abandon operations scattered across the history.
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "abandon", "snapshot()"]);
+    insta::assert_snapshot!(stderr, @"Abandoned 3 operations and reparented 3 descendant operations.");
+    insta::assert_snapshot!(op_log(), @r"
+    @  83b1a762f21c describe commit b40e57748417d8304ad182579b7130ee8d560cea
+    ○  5f21d429cd0f commit cd160618738105d69ff6813d78f1bdeb030e7e51
+    ○  79aec5ce7437 commit f3220d78b25353bd8fd65988018004b962d0894a
+    ○  eac759b9ab75 add workspace 'default'
+    ○  000000000000
+    ");
+
+    // Nothing to abandon.
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "abandon", "snapshot()"]);
+    insta::assert_snapshot!(stderr, @"Nothing changed.");
+
+    // The root operation can't be abandoned.
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "abandon", "::@-"]);
+    insta::assert_snapshot!(stderr, @"Error: Cannot abandon the root operation");
+}
+
+#[test]
+fn test_op_compact() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let template = r#"id.short() ++ " " ++ time.start() ++ " " ++ description.first_line()"#;
+    let get_op_log = || test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", template]);
+
+    // Take three consecutive snapshots, then describe the change
+    for contents in ["a", "b", "c"] {
+        std::fs::write(repo_path.join("file"), contents).unwrap();
+        test_env.jj_cmd_ok(&repo_path, &["debug", "snapshot"]);
+    }
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "description"]);
+    insta::assert_snapshot!(get_op_log(), @r"
+    @  3d599f9aa6dd 2001-02-03 04:05:11.000 +07:00 describe commit e103e737b1d45ebbd2639fd5e917c0e2d1897f1f
+    ○  5720673456c0 2001-02-03 04:05:10.000 +07:00 snapshot working copy
+    ○  e84436c28f85 2001-02-03 04:05:09.000 +07:00 snapshot working copy
+    ○  787e328b8d35 2001-02-03 04:05:08.000 +07:00 snapshot working copy
+    ○  eac759b9ab75 2001-02-03 04:05:07.000 +07:00 add workspace 'default'
+    ○  000000000000 1970-01-01 00:00:00.000 +00:00
+    ");
+
+    // The operations are newer than the threshold
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["op", "compact", "--older-than=2001-01-01"]);
+    insta::assert_snapshot!(stderr, @r"
+    Nothing changed.
+    ");
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "compact", "--dry-run"]);
+    insta::assert_snapshot!(stderr, @r"
+    Would compact 2 snapshot operations.
+    ");
+
+    // The first two snapshots are squashed into the last one, which starts at
+    // the time of the first one.
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "compact"]);
+    insta::assert_snapshot!(stderr, @r"
+    Compacted 2 snapshot operations and rewrote 2 descendant operations.
+    ");
+    insta::assert_snapshot!(get_op_log(), @r"
+    @  b25c58816347 2001-02-03 04:05:11.000 +07:00 describe commit e103e737b1d45ebbd2639fd5e917c0e2d1897f1f
+    ○  2b1eb89d8c4d 2001-02-03 04:05:08.000 +07:00 snapshot working copy
+    ○  eac759b9ab75 2001-02-03 04:05:07.000 +07:00 add workspace 'default'
+    ○  000000000000 1970-01-01 00:00:00.000 +00:00
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "show", "file"]);
+    insta::assert_snapshot!(stdout, @r"
+    c
+    ");
+
+    // Compaction can be done by gc
+    for contents in ["d", "e"] {
+        std::fs::write(repo_path.join("file"), contents).unwrap();
+        test_env.jj_cmd_ok(&repo_path, &["debug", "snapshot"]);
+    }
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(
+        &repo_path,
+        &[
+            "util",
+            "gc",
+            "--config-toml=gc.op-log.compact-snapshots='now'",
+        ],
+    );
+    insta::assert_snapshot!(stderr, @r"
+    Compacted 1 snapshot operations and rewrote 2 descendant operations.
+    Nothing to prune.
+    ");
+    insta::assert_snapshot!(get_op_log(), @r"
+    @  b891d6d9a536 2001-02-03 04:05:20.000 +07:00 new empty commit
+    ○  ed2de4f348e1 2001-02-03 04:05:18.000 +07:00 snapshot working copy
+    ○  b25c58816347 2001-02-03 04:05:11.000 +07:00 describe commit e103e737b1d45ebbd2639fd5e917c0e2d1897f1f
+    ○  2b1eb89d8c4d 2001-02-03 04:05:08.000 +07:00 snapshot working copy
+    ○  eac759b9ab75 2001-02-03 04:05:07.000 +07:00 add workspace 'default'
+    ○  000000000000 1970-01-01 00:00:00.000 +00:00
+    ");
+
+    // Other workspaces may become stale
+    test_env.jj_cmd_ok(&repo_path, &["workspace", "add", "../secondary"]);
+    for contents in ["f", "g"] {
+        std::fs::write(repo_path.join("file"), contents).unwrap();
+        test_env.jj_cmd_ok(&repo_path, &["debug", "snapshot"]);
+    }
+    test_env.jj_cmd_ok(&repo_path, &["new"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "compact"]);
+    insta::assert_snapshot!(stderr, @r"
+    Compacted 1 snapshot operations and rewrote 2 descendant operations.
+    Hint: The working copies of other workspaces (secondary) may now be stale. Run `jj workspace update-stale` in them to update them.
+    ");
+}
+
 #[test]
 fn test_op_recover_from_bad_gc() {
     let test_env = TestEnvironment::default();
@@ -1955,6 +2337,142 @@ fn test_op_show() {
     "#);
 }
 
+#[test]
+fn test_op_show_snapshot_files() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file1"), "a\n").unwrap();
+    std::fs::write(repo_path.join("file2"), "b\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["debug", "snapshot"]);
+    std::fs::write(repo_path.join("file1"), "a2\n").unwrap();
+    std::fs::remove_file(repo_path.join("file2")).unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["debug", "snapshot"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "description"]);
+
+    // Snapshot operations show the snapshotted files by default
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "@-", "--no-graph"]);
+    insta::assert_snapshot!(stdout, @r"
+    40fa5b8a6e40 test-username@host.example.com 2001-02-03 04:05:09.000 +07:00 - 2001-02-03 04:05:09.000 +07:00
+    snapshot working copy
+    args: jj debug snapshot
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm e3ca0bb0 (no description set)
+    - qpvuntsm hidden 93327778 (no description set)
+    M file1
+    D file2
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "diff", "--op", "@--", "--no-graph"]);
+    insta::assert_snapshot!(stdout, @r"
+    From operation: eac759b9ab75 (2001-02-03 08:05:07) add workspace 'default'
+      To operation: c6cbfb01bb55 (2001-02-03 08:05:08) snapshot working copy
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm 93327778 (no description set)
+    - qpvuntsm hidden 230dd059 (empty) (no description set)
+    A file1
+    A file2
+    ");
+
+    // Other operations don't
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "@", "--no-graph"]);
+    insta::assert_snapshot!(stdout, @r"
+    ec9bf964ad83 test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
+    describe commit e3ca0bb00efed73af827a64ec649f25db76a8f9d
+    args: jj describe -m description
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm 89da6f3a description
+    - qpvuntsm hidden e3ca0bb0 (no description set)
+    ");
+
+    // Changes can be filtered by paths
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "show", "@-", "--no-graph", "--git", "file2"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    40fa5b8a6e40 test-username@host.example.com 2001-02-03 04:05:09.000 +07:00 - 2001-02-03 04:05:09.000 +07:00
+    snapshot working copy
+    args: jj debug snapshot
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm e3ca0bb0 (no description set)
+    - qpvuntsm hidden 93327778 (no description set)
+    diff --git a/file2 b/file2
+    deleted file mode 100644
+    index 6178079822..0000000000
+    --- a/file2
+    +++ /dev/null
+    @@ -1,1 +1,0 @@
+    -b
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "show", "@", "--no-graph", "file2"]);
+    insta::assert_snapshot!(stdout, @r"
+    ec9bf964ad83 test-username@host.example.com 2001-02-03 04:05:10.000 +07:00 - 2001-02-03 04:05:10.000 +07:00
+    describe commit e3ca0bb00efed73af827a64ec649f25db76a8f9d
+    args: jj describe -m description
+    ");
+
+    // Operation log can be filtered by paths
+    let template = r#"id.short() ++ " " ++ description ++ "\n""#;
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &[
+            "op",
+            "log",
+            "--no-graph",
+            "-T",
+            template,
+            "--op-diff",
+            "file2",
+        ],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    40fa5b8a6e40 snapshot working copy
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm e3ca0bb0 (no description set)
+    - qpvuntsm hidden 93327778 (no description set)
+    D file2
+    c6cbfb01bb55 snapshot working copy
+
+    Changed commits:
+    Change qpvuntsmwlqt
+    + qpvuntsm 93327778 (no description set)
+    - qpvuntsm hidden 230dd059 (empty) (no description set)
+    A file2
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", template, "file2"]);
+    insta::assert_snapshot!(stdout, @r"
+    ○  40fa5b8a6e40 snapshot working copy
+    ○  c6cbfb01bb55 snapshot working copy
+    │
+    ~
+    ");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "-T", template, "-n1", "--stat", "file1"],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    ○  40fa5b8a6e40 snapshot working copy
+    │
+    ~  Changed commits:
+       ○  Change qpvuntsmwlqt
+          + qpvuntsm e3ca0bb0 (no description set)
+          - qpvuntsm hidden 93327778 (no description set)
+          file1 | 2 +-
+          1 file changed, 1 insertion(+), 1 deletion(-)
+    ");
+}
+
 #[test]
 fn test_op_show_patch() {
     let test_env = TestEnvironment::default();
@@ -2142,6 +2660,100 @@ fn test_op_show_patch() {
     "#);
 }
 
+#[test]
+fn test_op_export_import() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "other"]);
+    let repo_path = test_env.env_root().join("repo");
+    let other_path = test_env.env_root().join("other");
+    let get_log_output = |repo_path: &Path| {
+        let template = r#"separate(" ", description.first_line(), bookmarks) ++ "\n""#;
+        test_env.jj_cmd_success(repo_path, &["log", "-T", template, "-r", "all()"])
+    };
+
+    std::fs::write(repo_path.join("file"), "a\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["bookmark", "create", "foo", "-r", "@-"]);
+
+    // Export the whole history, which can be imported into an unrelated repo.
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["op", "export", "::@", "-o", "../all.bundle"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Exported 4 operations and 4 commits to ../all.bundle
+    ");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&other_path, &["op", "import", "../all.bundle"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Imported 4 operations and 4 commits
+    Concurrent modification detected, resolving automatically.
+    ");
+    insta::assert_snapshot!(get_log_output(&other_path), @r"
+    ○
+    ○  first foo
+    │ @
+    ├─╯
+    ◆
+    ");
+    let stdout = test_env.jj_cmd_success(&other_path, &["file", "show", "-r", "foo", "file"]);
+    insta::assert_snapshot!(stdout, @r"
+    a
+    ");
+
+    // Importing the same operations again does nothing.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&other_path, &["op", "import", "../all.bundle"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Nothing changed.
+    ");
+
+    // Later operations can be exported on their own once their parents exist
+    // in the other repo.
+    test_env.jj_cmd_ok(
+        &repo_path,
+        &["describe", "-r", "foo", "-m", "first modified"],
+    );
+    let (stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["op", "export", "@", "-o", "../new.bundle"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Exported 1 operations and 2 commits to ../new.bundle");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&other_path, &["op", "import", "../new.bundle"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Imported 1 operations and 2 commits
+    Concurrent modification detected, resolving automatically.
+    ");
+    insta::assert_snapshot!(get_log_output(&other_path), @r"
+    ○
+    ○  first modified foo
+    │ @
+    ├─╯
+    ◆
+    ");
+
+    // The parent operation of the exported operation is missing.
+    test_env.jj_cmd_ok(&repo_path, &["bookmark", "create", "bar"]);
+    test_env.jj_cmd_ok(&repo_path, &["bookmark", "create", "baz"]);
+    test_env.jj_cmd_ok(&repo_path, &["op", "export", "@", "-o", "../new.bundle"]);
+    let stderr = test_env.jj_cmd_failure(&other_path, &["op", "import", "../new.bundle"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: Operation 3c19e110552134929163f07cb8867a5a8cd983cdc4336fc5e0d3172b0d587784ca0292dd9fa4770b5c57e5fd34c349f3f4ce31de551428fa75b819d137e7fd0c has parent operation 4355a56ef8045717d49a83dc6f2ed9ed5f3335b9029f15540819afbec056a0668d17b32262fbc4f469bd3a583e79d37e0ecd8a338d83df2d0d38f1a66f1d0352, which is neither in the bundle nor in the repo
+    Hint: Export the missing ancestor operations as well, e.g. with `jj op export '::@'`
+    ");
+
+    std::fs::write(test_env.env_root().join("bad.bundle"), "bad").unwrap();
+    let stderr = test_env.jj_cmd_failure(&other_path, &["op", "import", "../bad.bundle"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: Failed to decode operation bundle
+    Caused by: failed to decode Protobuf message: buffer underflow
+    ");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "export", "none()", "-o", "x"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: No operations to export
+    ");
+}
+
 fn init_bare_git_repo(git_repo_path: &Path) -> git2::Repository {
     let git_repo = git2::Repository::init_bare(git_repo_path).unwrap();
     let git_blob_oid = git_repo.blob(b"some content").unwrap();
@@ -194,3 +194,156 @@ fn test_sparse_editor_avoids_unc() {
     // over 260 chars.
     assert_eq!(edited_path, dunce::simplified(&edited_path));
 }
+
+#[test]
+fn test_sparse_profiles() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    for path in [
+        "README",
+        "backend/main.rs",
+        "shared/lib.rs",
+        "shared/legacy/old.rs",
+        "web/index.html",
+        "web/src/app.js",
+        "web/test/app_test.js",
+    ] {
+        let path = repo_path.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "contents").unwrap();
+    }
+    std::fs::write(
+        repo_path.join(".jj-sparse.toml"),
+        r#"
+        [profiles]
+        frontend = "web/index.html | web/src | shared/lib.rs"
+        backend = "backend | shared"
+        partial = "web ~ web/test"
+        "#,
+    )
+    .unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "profiles"]);
+
+    // Unknown profile
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sparse", "use", "infra"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: No sparse profile named 'infra'
+    Hint: Available profiles: backend, frontend, partial
+    ");
+
+    // A profile can't include only part of a directory, since new files there
+    // wouldn't be checked out
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["sparse", "use", "partial"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: Sparse profile 'partial' includes only part of directory 'web'
+    Hint: Sparse profiles can only include whole directories or name individual files. Include the wanted subdirectories and files instead of excluding paths.
+    ");
+
+    // Directories that are entirely included become a single pattern, and
+    // files named explicitly get a pattern each. The profiles file is always
+    // included.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "use", "frontend"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @"Added 0 files, modified 0 files, removed 4 files");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    .jj-sparse.toml
+    shared/lib.rs
+    web/index.html
+    web/src
+    ");
+    insta::assert_snapshot!(stderr, @"Using sparse profile 'frontend'");
+    assert!(!repo_path.join("web/test").exists());
+    assert!(!repo_path.join("backend").exists());
+
+    // New files in an included directory are snapshotted
+    std::fs::write(repo_path.join("web/src/new.js"), "new").unwrap();
+    let stdout = test_env.jj_cmd_success(&repo_path, &["diff", "--summary"]);
+    insta::assert_snapshot!(stdout, @"A web/src/new.js");
+
+    // Editing the profile in the working copy updates the patterns
+    std::fs::write(
+        repo_path.join(".jj-sparse.toml"),
+        r#"
+        [profiles]
+        frontend = "web | shared/lib.rs"
+        backend = "backend | shared"
+        partial = "web ~ web/test"
+        "#,
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    .jj-sparse.toml
+    shared/lib.rs
+    web
+    ");
+    insta::assert_snapshot!(stderr, @r"
+    Updated sparse patterns from profile 'frontend'
+    Added 1 files, modified 0 files, removed 0 files
+    Using sparse profile 'frontend'
+    ");
+    assert!(repo_path.join("web/test").exists());
+
+    // An invalid profile edited in the working copy is reported, but the
+    // snapshot isn't affected
+    std::fs::write(
+        repo_path.join(".jj-sparse.toml"),
+        r#"
+        [profiles]
+        frontend = "web ~ web/test | shared/lib.rs"
+        "#,
+    )
+    .unwrap();
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    .jj-sparse.toml
+    shared/lib.rs
+    web
+    ");
+    insta::assert_snapshot!(stderr, @r"
+    Warning: Sparse profile 'frontend' includes only part of directory 'web'. Keeping the current sparse patterns.
+    Hint: Sparse profiles can only include whole directories or name individual files. Include the wanted subdirectories and files instead of excluding paths.
+    Using sparse profile 'frontend'
+    ");
+
+    // Checking out a commit with another definition of the profile updates the
+    // patterns
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["new", "description(profiles)"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Working copy now at: znkkpsqq 4e2b14c7 (empty) (no description set)
+    Parent commit      : qpvuntsm fa27c24b profiles
+    Added 0 files, modified 1 files, removed 1 files
+    Updated sparse patterns from profile 'frontend'
+    Added 0 files, modified 0 files, removed 1 files
+    ");
+    assert!(!repo_path.join("web/test").exists());
+
+    // The patterns are kept if the profile isn't defined in the new commit
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["new", "root()"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Working copy now at: kpqxywon ce15e300 (empty) (no description set)
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    Added 0 files, modified 0 files, removed 4 files
+    Warning: Sparse profile 'frontend' is not defined in the working-copy commit. Keeping the current sparse patterns.
+    ");
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    .jj-sparse.toml
+    shared/lib.rs
+    web/index.html
+    web/src
+    ");
+    insta::assert_snapshot!(stderr, @"Using sparse profile 'frontend'");
+
+    // Setting the patterns by hand stops using the profile
+    test_env.jj_cmd_ok(&repo_path, &["new", "description(profiles)"]);
+    test_env.jj_cmd_ok(&repo_path, &["sparse", "reset"]);
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @".");
+    insta::assert_snapshot!(stderr, @"");
+}
@@ -368,3 +368,78 @@ fn test_status_simplify_conflict_sides() {
     Then run `jj squash` to move the resolution into the conflicted commit.
     "#);
 }
+
+#[test]
+fn test_status_conflict_sidecars() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+
+    // Conflict markers can't be written to binary files
+    create_commit(&test_env, &repo_path, "base", &[], &[("file", "base\0")]);
+    create_commit(&test_env, &repo_path, "a", &["base"], &[("file", "a\0")]);
+    create_commit(&test_env, &repo_path, "b", &["base"], &[("file", "b\0")]);
+    create_commit(&test_env, &repo_path, "conflict", &["a", "b"], &[]);
+
+    let read = |name: &str| std::fs::read(repo_path.join(name)).unwrap();
+    insta::assert_snapshot!(String::from_utf8(read("file")).unwrap(), @r"
+    Conflict:
+      Removing file with id d249428de78af3bdc1ab483fa486615df535e3c9
+      Adding file with id 90802fedc2462f10bf2114d086cacb9e3af99ffb
+      Adding file with id 28eacf2b08bcaecd186cfa2d9d93e8672a37d6a2
+    ");
+    assert_eq!(read("file.jjconflict-base"), b"base\0");
+    assert_eq!(read("file.jjconflict-side-1"), b"a\0");
+    assert_eq!(read("file.jjconflict-side-2"), b"b\0");
+
+    // The sidecar files aren't snapshotted
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["status"]), @r"
+    The working copy is clean
+    There are unresolved conflicts at these paths:
+    file    2-sided conflict
+    The sides of these conflicts were written to separate files:
+    file: file.jjconflict-base, file.jjconflict-side-1, file.jjconflict-side-2
+    To resolve one of them, write the resolved content to the conflicted file and delete its separate files.
+    Working copy : vruxwmqv c5b286a1 conflict | (conflict) (empty) conflict
+    Parent commit: zsuskuln b75dc23f a | a
+    Parent commit: royxmykx 9a0e8351 b | b
+    ");
+
+    // The sidecar files are removed when the conflict is checked out away
+    test_env.jj_cmd_ok(&repo_path, &["new", "a"]);
+    assert!(!repo_path.join("file.jjconflict-base").exists());
+    assert!(!repo_path.join("file.jjconflict-side-1").exists());
+    assert!(!repo_path.join("file.jjconflict-side-2").exists());
+    test_env.jj_cmd_ok(&repo_path, &["edit", "conflict"]);
+    assert!(repo_path.join("file.jjconflict-side-1").exists());
+
+    // Untracked files in place of the sidecar files are neither overwritten
+    // nor removed
+    test_env.jj_cmd_ok(&repo_path, &["new", "a"]);
+    std::fs::write(repo_path.join("file.jjconflict-base"), "mine").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["edit", "conflict"]);
+    assert_eq!(read("file.jjconflict-base"), b"mine");
+    assert_eq!(read("file.jjconflict-side-1"), b"a\0");
+    test_env.jj_cmd_ok(&repo_path, &["new", "a"]);
+    assert_eq!(read("file.jjconflict-base"), b"mine");
+    assert!(!repo_path.join("file.jjconflict-side-1").exists());
+    test_env.jj_cmd_ok(&repo_path, &["edit", "conflict"]);
+
+    // Writing the chosen side to the conflicted file resolves the conflict.
+    // Sidecar files left behind aren't tracked.
+    std::fs::remove_file(repo_path.join("file.jjconflict-side-1")).unwrap();
+    std::fs::rename(
+        repo_path.join("file.jjconflict-side-2"),
+        repo_path.join("file"),
+    )
+    .unwrap();
+    insta::assert_snapshot!(test_env.jj_cmd_success(&repo_path, &["status"]), @r"
+    Working copy changes:
+    M file
+    Working copy : vruxwmqv fba95efe conflict | conflict
+    Parent commit: zsuskuln b75dc23f a | a
+    Parent commit: royxmykx 9a0e8351 b | b
+    ");
+    assert_eq!(read("file"), b"b\0");
+    assert_eq!(read("file.jjconflict-base"), b"mine");
+}
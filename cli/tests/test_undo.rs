@@ -385,6 +385,137 @@ fn test_bookmark_track_untrack_undo() {
     "###);
 }
 
+#[test]
+fn test_op_revert() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let get_log_output = || {
+        let template = r#"description.first_line() ++ " " ++ bookmarks"#;
+        test_env.jj_cmd_success(&repo_path, &["log", "-T", template])
+    };
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "initial"]);
+    test_env.jj_cmd_ok(&repo_path, &["bookmark", "create", "foo"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "child"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "modified"]);
+    insta::assert_snapshot!(get_log_output(), @r"
+    @  modified
+    ○  initial foo
+    ◆
+    ");
+
+    // Revert an older operation. The later operations are kept.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "revert", "@--"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Reverted operation: b6240d3af32c (2001-02-03 08:05:09) create bookmark foo pointing to commit 68e1101283b0a6c4694f92fab85bcd90f0c83652
+    Restored:
+      bookmark foo
+    ");
+    insta::assert_snapshot!(get_log_output(), @r"
+    @  modified
+    ○  initial
+    ◆
+    ");
+
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "revert", "@-"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Reverted operation: 4445c59c664d (2001-02-03 08:05:11) describe commit 5f1088ff77f2283a876ac3bce735b17da2fffbfd
+    Restored:
+      change zsuskulnrvyr
+      working copy of default
+    Working copy now at: zsuskuln 5f1088ff (empty) child
+    Parent commit      : qpvuntsm 68e11012 (empty) initial
+    ");
+    insta::assert_snapshot!(get_log_output(), @r"
+    @  child
+    ○  initial
+    ◆
+    ");
+
+    // Reverting it again is a no-op.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "revert", "@--"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Reverted operation: 4445c59c664d (2001-02-03 08:05:11) describe commit 5f1088ff77f2283a876ac3bce735b17da2fffbfd
+    Nothing changed.
+    ");
+
+    // Revert multiple operations at once.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "revert", "@---", "@-----"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Reverted operation: 29fcd24fd0e4 (2001-02-03 08:05:10) new empty commit
+    Restored:
+      change zsuskulnrvyr
+      working copy of default
+    Reverted operation: 99c501f5b89a (2001-02-03 08:05:08) describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    Restored:
+      change qpvuntsmwlqt
+      working copy of default
+    Working copy now at: qpvuntsm 230dd059 (empty) (no description set)
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    ");
+    insta::assert_snapshot!(get_log_output(), @r"
+    @
+    ◆
+    ");
+}
+
+#[test]
+fn test_op_revert_changed_later() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let get_log_output = || test_env.jj_cmd_success(&repo_path, &["log", "-T", "description"]);
+
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "first"]);
+    test_env.jj_cmd_ok(&repo_path, &["describe", "-m", "second"]);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "child"]);
+
+    // The first description was changed again, and the second one was built
+    // upon.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "revert", "@- | @--"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Reverted operation: 3d5b67a0e95f (2001-02-03 08:05:09) describe commit fa15625b4a986997697639dfc2844138900c79f2
+    Not restored because of later changes:
+      change qpvuntsmwlqt
+      working copy of default
+    Reverted operation: ed907a7a52ab (2001-02-03 08:05:08) describe commit 230dd059e1b059aefc0da06a2e5a7dbf22362f22
+    Not restored because of later changes:
+      change qpvuntsmwlqt
+      working copy of default
+    Nothing changed.
+    ");
+    insta::assert_snapshot!(get_log_output(), @r"
+    @  child
+    ○  second
+    ◆
+    ");
+
+    // The child commit can still be hidden.
+    let (stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["op", "revert"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r"
+    Reverted operation: 40319b98f9fc (2001-02-03 08:05:10) new empty commit
+    Restored:
+      change zsuskulnrvyr
+      working copy of default
+    Working copy now at: qpvuntsm 53aecb7c (empty) second
+    Parent commit      : zzzzzzzz 00000000 (empty) (no description set)
+    ");
+    insta::assert_snapshot!(get_log_output(), @r"
+    @  second
+    ◆
+    ");
+
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["op", "revert", "root()"]);
+    insta::assert_snapshot!(stderr, @"Error: Cannot revert repo initialization");
+}
+
 fn get_bookmark_output(test_env: &TestEnvironment, repo_path: &Path) -> String {
     // --quiet to suppress deleted bookmarks hint
     test_env.jj_cmd_success(repo_path, &["bookmark", "list", "--all-remotes", "--quiet"])
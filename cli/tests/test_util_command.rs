@@ -14,6 +14,7 @@
 
 use insta::assert_snapshot;
 
+use crate::common::get_stderr_string;
 use crate::common::strip_last_line;
 use crate::common::TestEnvironment;
 
@@ -50,7 +51,7 @@ fn test_gc_args() {
     let repo_path = test_env.env_root().join("repo");
 
     let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc"]);
-    insta::assert_snapshot!(stderr, @"");
+    insta::assert_snapshot!(stderr, @"Nothing to prune.");
 
     let stderr = test_env.jj_cmd_failure(&repo_path, &["util", "gc", "--at-op=@-"]);
     insta::assert_snapshot!(stderr, @r###"
@@ -58,9 +59,21 @@ fn test_gc_args() {
     "###);
 
     let stderr = test_env.jj_cmd_failure(&repo_path, &["util", "gc", "--expire=foobar"]);
-    insta::assert_snapshot!(stderr, @r###"
-    Error: --expire only accepts 'now'
-    "###);
+    insta::assert_snapshot!(stderr, @r"
+    Error: Invalid time: foobar
+    Caused by: expected week day or month name
+    ");
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--expire=3 days"]);
+    insta::assert_snapshot!(stderr, @"Nothing to prune.");
+
+    test_env.add_config("gc.expire = 'foobar'");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["util", "gc"]);
+    insta::assert_snapshot!(stderr, @r"
+    Config error: Invalid `gc.expire`: foobar
+    Caused by: expected week day or month name
+    For help, see https://martinvonz.github.io/jj/latest/config/.
+    ");
 }
 
 #[test]
@@ -96,6 +109,252 @@ fn test_gc_operation_log() {
     "#);
 }
 
+#[test]
+fn test_gc_dry_run() {
+    let test_env = TestEnvironment::default();
+    // Use the local backend because GitBackend::gc() depends on the git CLI.
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["init", "repo", "--config-toml=ui.allow-init-native=true"],
+    );
+    let repo_path = test_env.env_root().join("repo");
+
+    std::fs::write(repo_path.join("file"), "a change\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "a change"]);
+    let op_to_remove = test_env.current_operation_id(&repo_path);
+    std::fs::write(repo_path.join("file"), "another change\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "another change"]);
+    test_env.jj_cmd_ok(&repo_path, &["operation", "abandon", "..@-"]);
+
+    // Nothing is removed by dry run
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--expire=now", "--dry-run"]);
+    insta::assert_snapshot!(stderr, @"Would prune 5 operations, 4 views, 9 index files.");
+    test_env.jj_cmd_ok(&repo_path, &["debug", "operation", &op_to_remove]);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--expire=now"]);
+    insta::assert_snapshot!(stderr, @"Pruned 5 operations, 4 views, 9 index files.");
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["debug", "operation", &op_to_remove]);
+    insta::assert_snapshot!(stderr, @r#"
+    Error: No operation ID matching "8382f401329617b0c91a63354b86ca48fc28dee8d7a916fdad5310030f9a1260e969c43ed2b13d1d48eaf38f6f45541ecf593bcb6105495d514d21b3b6a98846"
+    "#);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--expire=now"]);
+    insta::assert_snapshot!(stderr, @"Nothing to prune.");
+}
+
+#[test]
+fn test_gc_operation_log_retention() {
+    let test_env = TestEnvironment::default();
+    // Use the local backend because GitBackend::gc() depends on the git CLI.
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["init", "repo", "--config-toml=ui.allow-init-native=true"],
+    );
+    let repo_path = test_env.env_root().join("repo");
+    for i in 0..4 {
+        test_env.jj_cmd_ok(&repo_path, &["new", "-m", &format!("commit {i}")]);
+    }
+    let op_log_template = "description ++ \"\\n\"";
+
+    // Operations are created at 2001-02-03T04:05:06+07:00 in tests.
+    test_env.add_config("gc.op-log.keep-count = 2");
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--dry-run"]);
+    insta::assert_snapshot!(stderr, @r"
+    Would abandon 3 operations.
+    Would prune 5 operations, 3 views.
+    ");
+    let (_stdout, stderr) =
+        test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--dry-run", "--expire=now"]);
+    insta::assert_snapshot!(stderr, @r"
+    Would abandon 3 operations.
+    Would prune 5 operations, 3 views.
+    ");
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--expire=now"]);
+    insta::assert_snapshot!(stderr, @r"
+    Abandoned 3 operations and reparented 2 descendant operations.
+    Pruned 5 operations, 3 views, 10 index files.
+    ");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "--no-graph", "-T", op_log_template],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    new empty commit
+    new empty commit
+    ");
+
+    // Operations are kept if either criterion is satisfied
+    test_env.add_config(r#"gc.op-log.keep-newer = "2001-01-01""#);
+    test_env.jj_cmd_ok(&repo_path, &["new", "-m", "commit 4"]);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc"]);
+    insta::assert_snapshot!(stderr, @"Nothing to prune.");
+    test_env.add_config(r#"gc.op-log.keep-newer = "2002-01-01""#);
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc"]);
+    insta::assert_snapshot!(stderr, @r"
+    Abandoned 1 operations and reparented 2 descendant operations.
+    Nothing to prune.
+    ");
+    let stdout = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "--no-graph", "-T", op_log_template],
+    );
+    insta::assert_snapshot!(stdout, @r"
+    new empty commit
+    new empty commit
+    ");
+}
+
+#[test]
+fn test_util_migrate_op_store() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file"), "a change\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "a change"]);
+    let op_log = test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", "id.short()"]);
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "migrate-op-store", "simple"]);
+    insta::assert_snapshot!(stderr, @"Nothing changed.");
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "migrate-op-store", "log"]);
+    insta::assert_snapshot!(stderr, @r"
+    Migrated 3 operations to the log format.
+    The old operation store was kept at $TEST_ENV/repo/.jj/repo/op_store.old. Remove it once you no longer need it.
+    ");
+    let op_store_type = std::fs::read_to_string(repo_path.join(".jj/repo/op_store/type")).unwrap();
+    assert_eq!(op_store_type, "log_op_store");
+    let backup_path = repo_path.join(".jj/repo/op_store.old");
+    let backup_type = std::fs::read_to_string(backup_path.join("type")).unwrap();
+    assert_eq!(backup_type, "simple_op_store");
+
+    // Operation ids are preserved, and new operations can be added
+    let new_op_log = test_env.jj_cmd_success(
+        &repo_path,
+        &["op", "log", "-T", "id.short()", "--ignore-working-copy"],
+    );
+    assert_eq!(new_op_log, op_log);
+    std::fs::write(repo_path.join("file"), "another change\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "another change"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r"
+    @
+    ○  another change
+    ○  a change
+    ◆
+    ");
+
+    // The old store must be removed before migrating again
+    let stderr = test_env.jj_cmd_failure(&repo_path, &["util", "migrate-op-store", "simple"]);
+    insta::assert_snapshot!(stderr, @r"
+    Error: The operation store of a previous migration still exists at $TEST_ENV/repo/.jj/repo/op_store.old
+    Hint: Remove it and try again.
+    ");
+    std::fs::remove_dir_all(&backup_path).unwrap();
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "migrate-op-store", "simple"]);
+    insta::assert_snapshot!(stderr, @r"
+    Migrated 5 operations to the simple format.
+    The old operation store was kept at $TEST_ENV/repo/.jj/repo/op_store.old. Remove it once you no longer need it.
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r"
+    @
+    ○  another change
+    ○  a change
+    ◆
+    ");
+}
+
+#[test]
+fn test_op_store_format_config() {
+    let test_env = TestEnvironment::default();
+    test_env.add_config(r#"format.op-store = "log""#);
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let op_store_type = std::fs::read_to_string(repo_path.join(".jj/repo/op_store/type")).unwrap();
+    assert_eq!(op_store_type, "log_op_store");
+
+    std::fs::write(repo_path.join("file"), "a change\n").unwrap();
+    test_env.jj_cmd_ok(&repo_path, &["commit", "-m", "a change"]);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "-T", "description"]);
+    insta::assert_snapshot!(stdout, @r"
+    @  commit 81c99e06c332b90b5dacd38b27834b0c3e8fb554
+    ○  snapshot working copy
+    ○  add workspace 'default'
+    ○
+    ");
+}
+
+#[test]
+fn test_op_store_view_deltas_config() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo1"]);
+    let repo1_path = test_env.env_root().join("repo1");
+    assert!(!repo1_path.join(".jj/repo/op_store/view_deltas").exists());
+
+    test_env.add_config("format.view-deltas = true");
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo2"]);
+    let repo2_path = test_env.env_root().join("repo2");
+    assert!(repo2_path.join(".jj/repo/op_store/view_deltas").exists());
+    // Existing repos aren't affected
+    test_env.jj_cmd_ok(&repo1_path, &["new"]);
+    assert!(!repo1_path.join(".jj/repo/op_store/view_deltas").exists());
+
+    test_env.add_config(r#"format.view-deltas = "maybe""#);
+    let stderr = test_env.jj_cmd_failure(test_env.env_root(), &["git", "init", "repo3"]);
+    insta::assert_snapshot!(stderr, @r#"
+    Error: Failed to access the repository
+    Caused by: invalid type: string "maybe", expected a boolean
+    "#);
+}
+
+#[test]
+fn test_gc_auto() {
+    let test_env = TestEnvironment::default();
+    // Use the local backend because GitBackend::gc() depends on the git CLI.
+    test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["init", "repo", "--config-toml=ui.allow-init-native=true"],
+    );
+    let repo_path = test_env.env_root().join("repo");
+    test_env.add_config(
+        r#"
+        gc.expire = "now"
+        gc.auto.index-segments = 3
+        "#,
+    );
+
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["new"]);
+    insta::assert_snapshot!(stderr, @r"
+    Working copy now at: rlvkpnrz 5c6dbdb0 (empty) (no description set)
+    Parent commit      : qpvuntsm 91667046 (empty) (no description set)
+    ");
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["new"]);
+    insta::assert_snapshot!(stderr, @r"
+    Working copy now at: kkmpptxz e145e3bb (empty) (no description set)
+    Parent commit      : rlvkpnrz 5c6dbdb0 (empty) (no description set)
+    Running garbage collection automatically.
+    Nothing to prune.
+    ");
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["new"]);
+    insta::assert_snapshot!(stderr, @r"
+    Working copy now at: zsuskuln b5a792a6 (empty) (no description set)
+    Parent commit      : kkmpptxz e145e3bb (empty) (no description set)
+    Running garbage collection automatically.
+    Nothing to prune.
+    ");
+
+    // Not triggered by commands that don't create operations
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["log", "-r@"]);
+    insta::assert_snapshot!(stderr, @"");
+
+    // Not triggered by the gc command itself, nor by commands outside of a repo
+    let (_stdout, stderr) = test_env.jj_cmd_ok(&repo_path, &["util", "gc", "--dry-run"]);
+    insta::assert_snapshot!(stderr, @"Nothing to prune.");
+    let (_stdout, stderr) = test_env.jj_cmd_ok(test_env.env_root(), &["version"]);
+    insta::assert_snapshot!(stderr, @"");
+}
+
 #[test]
 fn test_shell_completions() {
     #[track_caller]
@@ -143,3 +402,87 @@ fn test_util_exec_fail() {
     );
     insta::assert_snapshot!(strip_last_line(&err), @"Error: Failed to execute external command 'missing-program'");
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_util_watch() {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    std::fs::write(repo_path.join("file1"), "").unwrap();
+
+    let mut cmd = test_env.jj_cmd(
+        &repo_path,
+        &["util", "watch", "--debounce=100ms", "--min-interval=0s"],
+    );
+    cmd.timeout(Duration::from_secs(60));
+    let watch = std::thread::spawn(move || get_stderr_string(&cmd.assert().success()));
+    let wait_for_auto_snapshots = |count: usize| {
+        let start = Instant::now();
+        loop {
+            let stdout = test_env.jj_cmd_success(
+                &repo_path,
+                &[
+                    "op",
+                    "log",
+                    "--ignore-working-copy",
+                    "-T",
+                    "description ++ '\n'",
+                ],
+            );
+            if stdout.matches("auto-snapshot working copy").count() == count {
+                break;
+            }
+            assert!(start.elapsed() < Duration::from_secs(30));
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    };
+
+    // The working copy is snapshotted when the command starts
+    wait_for_auto_snapshots(1);
+    std::fs::write(repo_path.join("file2"), "").unwrap();
+    wait_for_auto_snapshots(2);
+    let stdout = test_env.jj_cmd_success(&repo_path, &["file", "list", "--ignore-working-copy"]);
+    insta::assert_snapshot!(stdout, @r"
+    file1
+    file2
+    ");
+    let stdout = test_env.jj_cmd_success(&repo_path, &["op", "log", "--ignore-working-copy"]);
+    insta::assert_snapshot!(stdout, @r"
+    @  a03a54255683 test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    │  auto-snapshot working copy
+    │  args: jj util watch '--debounce=100ms' '--min-interval=0s'
+    ○  a81cd999b07a test-username@host.example.com 2001-02-03 04:05:08.000 +07:00 - 2001-02-03 04:05:08.000 +07:00
+    │  auto-snapshot working copy
+    │  args: jj util watch '--debounce=100ms' '--min-interval=0s'
+    ○  eac759b9ab75 test-username@host.example.com 2001-02-03 04:05:07.000 +07:00 - 2001-02-03 04:05:07.000 +07:00
+    │  add workspace 'default'
+    ○  000000000000 root()
+    ");
+
+    // The command stops when the workspace is removed
+    std::fs::remove_dir_all(&repo_path).unwrap();
+    let stderr = watch.join().unwrap();
+    insta::assert_snapshot!(stderr, @r"
+    Watching the working copy for changes. Press Ctrl-C to stop.
+    Snapshotted the working copy in operation a81cd999b07a
+    Snapshotted the working copy in operation a03a54255683
+    The workspace was removed. Stopping.
+    ");
+}
+
+#[test]
+fn test_util_watch_invalid_duration() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "repo"]);
+    let repo_path = test_env.env_root().join("repo");
+    let stderr = test_env.jj_cmd_cli_error(&repo_path, &["util", "watch", "--debounce=1 month"]);
+    insta::assert_snapshot!(stderr, @r#"
+    error: invalid value '1 month' for '--debounce <DEBOUNCE>': Invalid duration "1 month"
+
+    For more information, try '--help'.
+    "#);
+}
@@ -30,9 +30,10 @@ fn test_workspaces_add_second_workspace() {
     test_env.jj_cmd_ok(&main_path, &["commit", "-m", "initial"]);
 
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 8183d0fc (empty) (no description set)
-    "###);
+      Path: .
+    "#);
 
     let (stdout, stderr) = test_env.jj_cmd_ok(
         &main_path,
@@ -65,13 +66,37 @@ fn test_workspaces_add_second_workspace() {
 
     // Both workspaces show up when we list them
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 8183d0fc (empty) (no description set)
+      Path: .
     second: rzvqmyuk 5ed2222c (empty) (no description set)
-    "###);
+      Path: ../secondary
+    "#);
 }
 
 /// Test how sparse patterns are inherited
+
+#[test]
+fn test_workspaces_list_unreadable_working_copy() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "main"]);
+    let main_path = test_env.env_root().join("main");
+    let secondary_path = test_env.env_root().join("secondary");
+    test_env.jj_cmd_ok(&main_path, &["workspace", "add", "../secondary"]);
+
+    // A workspace whose state can't be read isn't reported as stale
+    std::fs::remove_file(secondary_path.join(".jj/working_copy/tree_state")).unwrap();
+    std::fs::write(main_path.join("file"), "contents").unwrap();
+    test_env.jj_cmd_ok(&main_path, &["commit", "-m", "initial"]);
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    default: kkmpptxz 084fe0b5 (empty) (no description set)
+      Path: .
+    secondary: uuqppmxq 57d63245 (empty) (no description set)
+      Path: ../secondary
+    ");
+}
+
 #[test]
 fn test_workspaces_sparse_patterns() {
     let test_env = TestEnvironment::default();
@@ -82,6 +107,7 @@ fn test_workspaces_sparse_patterns() {
     let ws4_path = test_env.env_root().join("ws4");
     let ws5_path = test_env.env_root().join("ws5");
     let ws6_path = test_env.env_root().join("ws6");
+    let ws7_path = test_env.env_root().join("ws7");
 
     test_env.jj_cmd_ok(&ws1_path, &["sparse", "set", "--clear", "--add=foo"]);
     test_env.jj_cmd_ok(&ws1_path, &["workspace", "add", "../ws2"]);
@@ -120,6 +146,28 @@ fn test_workspaces_sparse_patterns() {
     );
     let stdout = test_env.jj_cmd_success(&ws6_path, &["sparse", "list"]);
     insta::assert_snapshot!(stdout, @"");
+    test_env.jj_cmd_ok(
+        &ws3_path,
+        &[
+            "workspace",
+            "add",
+            "--sparse-patterns=baz/qux | root:foo",
+            "../ws7",
+        ],
+    );
+    let stdout = test_env.jj_cmd_success(&ws7_path, &["sparse", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    baz/qux
+    foo
+    ");
+    let stderr = test_env.jj_cmd_failure(
+        &ws3_path,
+        &["workspace", "add", "--sparse-patterns=glob:*.rs", "../ws8"],
+    );
+    insta::assert_snapshot!(stderr, @r#"
+    Error: Cannot use fileset `glob:*.rs` as sparse patterns
+    Hint: Sparse patterns must be a union of file or directory paths.
+    "#);
 }
 
 /// Test adding a second workspace while the current workspace is editing a
@@ -135,9 +183,10 @@ fn test_workspaces_add_second_workspace_on_merge() {
     test_env.jj_cmd_ok(&main_path, &["new", "all:@-+", "-m=merge"]);
 
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: zsuskuln 35e47bff (empty) merge
-    "###);
+      Path: .
+    "#);
 
     test_env.jj_cmd_ok(
         &main_path,
@@ -257,9 +306,10 @@ fn test_workspaces_add_workspace_at_revision() {
     test_env.jj_cmd_ok(&main_path, &["commit", "-m", "second"]);
 
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: kkmpptxz dadeedb4 (empty) (no description set)
-    "###);
+      Path: .
+    "#);
 
     let (_, stderr) = test_env.jj_cmd_ok(
         &main_path,
@@ -379,9 +429,10 @@ fn test_workspaces_add_workspace_from_subdir() {
     test_env.jj_cmd_ok(&main_path, &["commit", "-m", "initial"]);
 
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz e1038e77 (empty) (no description set)
-    "###);
+      Path: .
+    "#);
 
     // Create workspace while in sub-directory of current workspace
     let (stdout, stderr) =
@@ -396,10 +447,12 @@ fn test_workspaces_add_workspace_from_subdir() {
 
     // Both workspaces show up when we list them
     let stdout = test_env.jj_cmd_success(&secondary_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz e1038e77 (empty) (no description set)
+      Path: ../main
     secondary: rzvqmyuk 7ad84461 (empty) (no description set)
-    "###);
+      Path: .
+    "#);
 }
 
 #[test]
@@ -424,10 +477,12 @@ fn test_workspaces_add_workspace_in_current_workspace() {
 
     // Workspace created despite warning
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 46d9ba8b (no description set)
+      Path: .
     secondary: pmmvwywv 0a77a39d (empty) (no description set)
-    "###);
+      Path: secondary
+    "#);
 
     // Use explicit path instead (no warning)
     let (stdout, stderr) = test_env.jj_cmd_ok(&main_path, &["workspace", "add", "./third"]);
@@ -441,11 +496,14 @@ fn test_workspaces_add_workspace_in_current_workspace() {
 
     // Both workspaces created
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 477c647f (no description set)
+      Path: .
     secondary: pmmvwywv 0a77a39d (empty) (no description set)
+      Path: secondary
     third: zxsnswpr 64746d4b (empty) (no description set)
-    "###);
+      Path: third
+    "#);
 
     // Can see files from the other workspaces in main workspace, since they are
     // child directories and will therefore be snapshotted
@@ -594,6 +652,15 @@ fn test_workspaces_updated_by_other() {
     Hint: Run `jj workspace update-stale` to update it.
     See https://martinvonz.github.io/jj/latest/working-copy/#stale-working-copy for more information.
     "##);
+    // The stale workspace is marked as such in the list
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    default: mzvwutvl a58c9a9b (empty) (no description set)
+      Path: .
+    secondary: pmmvwywv e82cd4ee (empty) (no description set)
+      Path: ../secondary
+      Working copy is stale
+    ");
     let (stdout, stderr) = test_env.jj_cmd_ok(&secondary_path, &["workspace", "update-stale"]);
     // It was detected that the working copy is now stale, but clean. So no
     // divergent commit should be created.
@@ -920,9 +987,10 @@ fn test_workspaces_forget() {
 
     // When listing workspaces, only the secondary workspace shows up
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     secondary: pmmvwywv 18463f43 (empty) (no description set)
-    "###);
+      Path: ../secondary
+    "#);
 
     // `jj status` tells us that there's no working copy here
     let (stdout, stderr) = test_env.jj_cmd_ok(&main_path, &["st"]);
@@ -980,18 +1048,22 @@ fn test_workspaces_forget_multi_transaction() {
 
     // there should be three workspaces
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 909d51b1 (empty) (no description set)
+      Path: .
     second: pmmvwywv 18463f43 (empty) (no description set)
+      Path: ../second
     third: rzvqmyuk cc383fa2 (empty) (no description set)
-    "###);
+      Path: ../third
+    "#);
 
     // delete two at once, in a single tx
     test_env.jj_cmd_ok(&main_path, &["workspace", "forget", "second", "third"]);
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 909d51b1 (empty) (no description set)
-    "###);
+      Path: .
+    "#);
 
     // the op log should have multiple workspaces forgotten in a single tx
     let stdout = test_env.jj_cmd_success(&main_path, &["op", "log", "--limit", "1"]);
@@ -1006,11 +1078,12 @@ fn test_workspaces_forget_multi_transaction() {
 
     // finally, there should be three workspaces at the end
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: rlvkpnrz 909d51b1 (empty) (no description set)
+      Path: .
     second: pmmvwywv 18463f43 (empty) (no description set)
     third: rzvqmyuk cc383fa2 (empty) (no description set)
-    "###);
+    "#);
 }
 
 #[test]
@@ -1031,12 +1104,16 @@ fn test_workspaces_forget_abandon_commits() {
 
     // there should be four workspaces, three of which are at the same empty commit
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: qpvuntsm 4e8f9d2b (no description set)
+      Path: .
     fourth: uuqppmxq 57d63245 (empty) (no description set)
+      Path: ../fourth
     second: uuqppmxq 57d63245 (empty) (no description set)
+      Path: ../second
     third: uuqppmxq 57d63245 (empty) (no description set)
-    "###);
+      Path: ../third
+    "#);
     insta::assert_snapshot!(get_log_output(&test_env, &main_path), @r###"
     ○  57d63245a308 fourth@ second@ third@
     │ @  4e8f9d2be039 default@
@@ -1095,16 +1172,20 @@ fn test_list_workspaces_template() {
 
     // "current_working_copy" should point to the workspace we operate on
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: 8183d0fcaa4c  (current)
+      Path: .
     second: 0a77a39d7d6f 
-    "###);
+      Path: ../secondary
+    "#);
 
     let stdout = test_env.jj_cmd_success(&secondary_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: 8183d0fcaa4c 
+      Path: ../main
     second: 0a77a39d7d6f  (current)
-    "###);
+      Path: .
+    "#);
 }
 
 /// Test getting the workspace root from primary and secondary workspaces
@@ -1232,19 +1313,23 @@ fn test_workspaces_rename_workspace() {
 
     // Both workspaces show up when we list them
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: qpvuntsm 230dd059 (empty) (no description set)
+      Path: .
     second: uuqppmxq 57d63245 (empty) (no description set)
-    "###);
+      Path: ../secondary
+    "#);
 
     let stdout = test_env.jj_cmd_success(&secondary_path, &["workspace", "rename", "third"]);
     insta::assert_snapshot!(stdout, @"");
 
     let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
-    insta::assert_snapshot!(stdout, @r###"
+    insta::assert_snapshot!(stdout, @r#"
     default: qpvuntsm 230dd059 (empty) (no description set)
+      Path: .
     third: uuqppmxq 57d63245 (empty) (no description set)
-    "###);
+      Path: ../secondary
+    "#);
 
     // Can see the working-copy commit in each workspace in the log output.
     insta::assert_snapshot!(get_log_output(&test_env, &main_path), @r###"
@@ -1261,6 +1346,66 @@ fn test_workspaces_rename_workspace() {
     "###);
 }
 
+#[test]
+fn test_workspaces_move() {
+    let test_env = TestEnvironment::default();
+    test_env.jj_cmd_ok(test_env.env_root(), &["git", "init", "main"]);
+    let main_path = test_env.env_root().join("main");
+    test_env.jj_cmd_ok(
+        &main_path,
+        &["workspace", "add", "--name", "second", "../secondary"],
+    );
+    let secondary_path = test_env.env_root().join("secondary");
+    let stdout = test_env.jj_cmd_success(&main_path, &["workspace", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    default: qpvuntsm 230dd059 (empty) (no description set)
+      Path: .
+    second: uuqppmxq 57d63245 (empty) (no description set)
+      Path: ../secondary
+    ");
+
+    // Cannot move onto an existing path
+    let stderr = test_env.jj_cmd_failure(&main_path, &["workspace", "move", "../secondary"]);
+    insta::assert_snapshot!(stderr, @"Error: The destination ($TEST_ENV/secondary) already exists");
+
+    // Moving the workspace that contains the repo updates the other workspaces
+    let (stdout, stderr) = test_env.jj_cmd_ok(&secondary_path, &["workspace", "move", "../moved"]);
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r#"
+    Moved workspace to "../moved"
+    Hint: The current directory was moved along with the workspace. Run `cd $TEST_ENV/moved` to get back into it.
+    "#);
+    let moved_path = test_env.env_root().join("moved");
+    let (stdout, stderr) = test_env.jj_cmd_ok(
+        test_env.env_root(),
+        &["-R", "main", "workspace", "move", "renamed-main"],
+    );
+    insta::assert_snapshot!(stdout, @"");
+    insta::assert_snapshot!(stderr, @r#"Moved workspace to "renamed-main""#);
+    let main_path = test_env.env_root().join("renamed-main");
+    assert!(!test_env.env_root().join("main").exists());
+
+    let stdout = test_env.jj_cmd_success(&moved_path, &["workspace", "list"]);
+    insta::assert_snapshot!(stdout, @r"
+    default: qpvuntsm 230dd059 (empty) (no description set)
+      Path: ../renamed-main
+    second: uuqppmxq 57d63245 (empty) (no description set)
+      Path: .
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &moved_path), @r"
+    @  57d63245a308 second@
+    │ ○  230dd059e1b0 default@
+    ├─╯
+    ◆  000000000000
+    ");
+    insta::assert_snapshot!(get_log_output(&test_env, &main_path), @r"
+    ○  57d63245a308 second@
+    │ @  230dd059e1b0 default@
+    ├─╯
+    ◆  000000000000
+    ");
+}
+
 fn get_log_output(test_env: &TestEnvironment, cwd: &Path) -> String {
     let template = r#"
     separate(" ",
@@ -19,6 +19,8 @@ fn main() -> Result<()> {
     let input = [
         "git_store.proto",
         "local_store.proto",
+        "log_op_store.proto",
+        "op_bundle.proto",
         "op_store.proto",
         "working_copy.proto",
     ];
@@ -29,6 +31,12 @@ fn main() -> Result<()> {
     prost_build::Config::new()
         .out_dir(&protos_dir)
         .include_file("mod.rs")
+        // The view variants are much larger than the operation variant, but
+        // only one entry is decoded at a time.
+        .type_attribute(
+            ".log_op_store.LogEntry.object",
+            "#[allow(clippy::large_enum_variant)]",
+        )
         // For old protoc versions. 3.12.4 needs this, but 3.21.12 doesn't.
         .protoc_arg("--experimental_allow_proto3_optional")
         .compile_protos(
@@ -26,6 +26,7 @@ use std::rc::Rc;
 
 use bstr::BStr;
 use bstr::BString;
+use futures::executor::block_on_stream;
 use itertools::Itertools as _;
 use pollster::FutureExt;
 
@@ -43,6 +44,7 @@ use crate::graph::GraphEdgeType;
 use crate::merged_tree::MergedTree;
 use crate::repo::Repo;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
 use crate::revset::ResolvedRevsetExpression;
 use crate::revset::RevsetEvaluationError;
 use crate::revset::RevsetExpression;
@@ -56,14 +58,36 @@ pub struct FileAnnotation {
     text: BString,
 }
 
+/// Origin of an annotated line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineOrigin {
+    /// Commit that introduced the line.
+    pub commit_id: CommitId,
+    /// 0-based line number in the file at the originating commit.
+    pub line_number: usize,
+    /// Path to the file at the originating commit. This differs from the
+    /// annotated path if the file was renamed or copied since then.
+    pub path: RepoPathBuf,
+}
+
 impl FileAnnotation {
     /// Returns iterator over `(commit_id, line)`s.
     ///
     /// For each line, the `commit_id` points to the originator commit of the
     /// line. The `line` includes newline character.
     pub fn lines(&self) -> impl Iterator<Item = (Option<&CommitId>, &BStr)> {
+        self.line_origins()
+            .map(|(origin, line)| (origin.map(|origin| &origin.commit_id), line))
+    }
+
+    /// Returns iterator over `(origin, line)`s.
+    ///
+    /// For each line, the `origin` points to the originator commit and the
+    /// location of the line in that commit. The `line` includes newline
+    /// character.
+    pub fn line_origins(&self) -> impl Iterator<Item = (Option<&LineOrigin>, &BStr)> {
         itertools::zip_eq(&self.line_map, self.text.split_inclusive(|b| *b == b'\n'))
-            .map(|(commit_id, line)| (commit_id.as_ref(), line.as_ref()))
+            .map(|(origin, line)| (origin.as_ref(), line.as_ref()))
     }
 
     /// Returns iterator over `(commit_id, line_range)`s.
@@ -81,7 +105,7 @@ impl FileAnnotation {
                 Some(start..*total)
             });
         itertools::zip_eq(&self.line_map, ranges)
-            .map(|(commit_id, range)| (commit_id.as_ref(), range))
+            .map(|(origin, range)| (origin.as_ref().map(|origin| &origin.commit_id), range))
     }
 
     /// Returns iterator over compacted `(commit_id, line_range)`s.
@@ -118,14 +142,17 @@ struct Source {
     /// Mapping of line numbers in the file at the current commit to the
     /// original file, sorted by the line numbers at the current commit.
     line_map: Vec<(usize, usize)>,
+    /// Path to the file at the current commit.
+    path: RepoPathBuf,
     /// File content at the current commit.
     text: BString,
 }
 
 impl Source {
-    fn new(text: BString) -> Self {
+    fn new(path: RepoPathBuf, text: BString) -> Self {
         Source {
             line_map: Vec::new(),
+            path,
             text,
         }
     }
@@ -133,7 +160,7 @@ impl Source {
     fn load(commit: &Commit, file_path: &RepoPath) -> Result<Self, BackendError> {
         let tree = commit.tree()?;
         let text = get_file_contents(commit.store(), file_path, &tree)?;
-        Ok(Self::new(text.into()))
+        Ok(Self::new(file_path.to_owned(), text.into()))
     }
 
     fn fill_line_map(&mut self) {
@@ -142,25 +169,30 @@ impl Source {
     }
 }
 
-/// List of commit IDs that originated lines, indexed by line numbers in the
-/// original file.
-type OriginalLineMap = Vec<Option<CommitId>>;
+/// List of line origins, indexed by line numbers in the original file.
+type OriginalLineMap = Vec<Option<LineOrigin>>;
 
 /// Get line by line annotations for a specific file path in the repo.
 ///
 /// The `domain` expression narrows the range of ancestors to search. It will be
 /// intersected as `domain & ::starting_commit & files(file_path)`. The
-/// `starting_commit` is assumed to be included in the `domain`.
+/// `starting_commit` is assumed to be included in the `domain`. Renames and
+/// copies recorded by the backend are followed.
+///
+/// Changes made in the `ignored` commits are attributed to their parents where
+/// possible. Changed lines are mapped to the lines at the same position in the
+/// parent, and lines added in excess remain attributed to the ignored commit.
 ///
 /// If the file is not found, returns empty results.
 pub fn get_annotation_for_file(
     repo: &dyn Repo,
     starting_commit: &Commit,
     domain: &Rc<ResolvedRevsetExpression>,
+    ignored: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
     let source = Source::load(starting_commit, file_path)?;
-    compute_file_annotation(repo, starting_commit.id(), domain, file_path, source)
+    compute_file_annotation(repo, starting_commit.id(), domain, ignored, source)
 }
 
 /// Get line by line annotations for a specific file path starting with the
@@ -174,23 +206,24 @@ pub fn get_annotation_with_file_content(
     repo: &dyn Repo,
     starting_commit_id: &CommitId,
     domain: &Rc<ResolvedRevsetExpression>,
+    ignored: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
     starting_text: impl Into<Vec<u8>>,
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
-    let source = Source::new(BString::new(starting_text.into()));
-    compute_file_annotation(repo, starting_commit_id, domain, file_path, source)
+    let source = Source::new(file_path.to_owned(), BString::new(starting_text.into()));
+    compute_file_annotation(repo, starting_commit_id, domain, ignored, source)
 }
 
 fn compute_file_annotation(
     repo: &dyn Repo,
     starting_commit_id: &CommitId,
     domain: &Rc<ResolvedRevsetExpression>,
-    file_path: &RepoPath,
+    ignored: &Rc<ResolvedRevsetExpression>,
     mut source: Source,
 ) -> Result<FileAnnotation, RevsetEvaluationError> {
     source.fill_line_map();
     let text = source.text.clone();
-    let line_map = process_commits(repo, starting_commit_id, source, domain, file_path)?;
+    let line_map = process_commits(repo, starting_commit_id, source, domain, ignored)?;
     Ok(FileAnnotation { line_map, text })
 }
 
@@ -202,31 +235,42 @@ fn process_commits(
     starting_commit_id: &CommitId,
     starting_source: Source,
     domain: &Rc<ResolvedRevsetExpression>,
-    file_name: &RepoPath,
+    ignored: &Rc<ResolvedRevsetExpression>,
 ) -> Result<OriginalLineMap, RevsetEvaluationError> {
-    let predicate = RevsetFilterPredicate::File(FilesetExpression::file_path(file_name.to_owned()));
+    let predicate = RevsetFilterPredicate::FileFollow(FilesetExpression::file_path(
+        starting_source.path.clone(),
+    ));
     // TODO: If the domain isn't a contiguous range, changes masked out by it
     // might not be caught by the closest ancestor revision. For example,
     // domain=merges() would pick up almost nothing because merge revisions
     // are usually empty. Perhaps, we want to query `files(file_path,
     // within_sub_graph=domain)`, not `domain & files(file_path)`.
     let ancestors = RevsetExpression::commit(starting_commit_id.clone()).ancestors();
-    let revset = RevsetExpression::commit(starting_commit_id.clone())
-        .union(&domain.intersection(&ancestors).filtered(predicate))
+    // Copies and renames are followed from the starting commit, which might
+    // not be visible, not from the visible heads.
+    let candidates = Rc::new(RevsetExpression::WithinVisibility {
+        candidates: domain.intersection(&ancestors).filtered(predicate),
+        visible_heads: vec![starting_commit_id.clone()],
+    });
+    let revset = candidates
+        .union(&RevsetExpression::commit(starting_commit_id.clone()))
         .evaluate(repo)?;
+    let ignored_revset = ignored.intersection(&ancestors).evaluate(repo)?;
+    let is_ignored = ignored_revset.containing_fn();
 
     let mut original_line_map = vec![None; starting_source.line_map.len()];
     let mut commit_source_map = HashMap::from([(starting_commit_id.clone(), starting_source)]);
 
     for node in revset.iter_graph() {
         let (commit_id, edge_list) = node?;
+        let ignored = is_ignored(&commit_id)?;
         process_commit(
             repo,
-            file_name,
             &mut original_line_map,
             &mut commit_source_map,
             &commit_id,
             &edge_list,
+            ignored,
         )?;
         if commit_source_map.is_empty() {
             // No more lines to propagate to ancestors.
@@ -238,65 +282,48 @@ fn process_commits(
 
 /// For a given commit, for each parent, we compare the version in the parent
 /// tree with the current version, updating the mappings for any lines in
-/// common. If the parent doesn't have the file, we skip it.
+/// common. If the parent doesn't have the file, we look for the source of the
+/// copy or rename, and skip the parent if there's none.
+///
+/// If the commit is `ignored`, lines that are still unresolved after that are
+/// mapped to the lines at the same position in the changed hunks.
 fn process_commit(
     repo: &dyn Repo,
-    file_name: &RepoPath,
     original_line_map: &mut OriginalLineMap,
     commit_source_map: &mut CommitSourceMap,
     current_commit_id: &CommitId,
     edges: &[GraphEdge<CommitId>],
+    ignored: bool,
 ) -> Result<(), BackendError> {
     let Some(mut current_source) = commit_source_map.remove(current_commit_id) else {
         return Ok(());
     };
 
     for parent_edge in edges {
-        let parent_commit_id = &parent_edge.target;
-        let parent_source = match commit_source_map.entry(parent_commit_id.clone()) {
-            hash_map::Entry::Occupied(entry) => entry.into_mut(),
-            hash_map::Entry::Vacant(entry) => {
-                let commit = repo.store().get_commit(entry.key())?;
-                entry.insert(Source::load(&commit, file_name)?)
-            }
-        };
+        if let hash_map::Entry::Vacant(entry) = commit_source_map.entry(parent_edge.target.clone())
+        {
+            let commit = repo.store().get_commit(entry.key())?;
+            let path = find_parent_path(repo, &commit, current_commit_id, &current_source.path)?;
+            entry.insert(Source::load(&commit, &path)?);
+        }
+    }
 
-        // For two versions of the same file, for all the lines in common,
-        // overwrite the new mapping in the results for the new commit. Let's
-        // say I have a file in commit A and commit B. We know that according to
-        // local line_map, in commit A, line 3 corresponds to line 7 of the
-        // original file. Now, line 3 in Commit A corresponds to line 6 in
-        // commit B. Then, we update local line_map to say that "Commit B line 6
-        // goes to line 7 of the original file". We repeat this for all lines in
-        // common in the two commits.
-        let mut current_lines = current_source.line_map.iter().copied().peekable();
-        let mut new_current_line_map = Vec::new();
-        let mut new_parent_line_map = Vec::new();
-        copy_same_lines_with(
-            &current_source.text,
-            &parent_source.text,
-            |current_start, parent_start, count| {
-                new_current_line_map
-                    .extend(current_lines.peeking_take_while(|&(cur, _)| cur < current_start));
-                while let Some((current, original)) =
-                    current_lines.next_if(|&(cur, _)| cur < current_start + count)
-                {
-                    let parent = parent_start + (current - current_start);
-                    new_parent_line_map.push((parent, original));
-                }
-            },
-        );
-        new_current_line_map.extend(current_lines);
-        current_source.line_map = new_current_line_map;
-        parent_source.line_map = if parent_source.line_map.is_empty() {
-            new_parent_line_map
-        } else {
-            itertools::merge(parent_source.line_map.iter().copied(), new_parent_line_map).collect()
-        };
+    let passes: &[bool] = if ignored { &[false, true] } else { &[false] };
+    for &copy_changed in passes {
+        for parent_edge in edges {
+            let parent_source = commit_source_map.get_mut(&parent_edge.target).unwrap();
+            copy_lines_to_parent(&mut current_source, parent_source, copy_changed);
+        }
+    }
+
+    for parent_edge in edges {
+        let parent_commit_id = &parent_edge.target;
         // If an omitted parent had the file, leave these lines unresolved.
         // TODO: These unresolved lines could be copied to the original_line_map
         // as Err(commit_id) or something instead of None.
-        if parent_source.line_map.is_empty() || parent_edge.edge_type == GraphEdgeType::Missing {
+        if commit_source_map[parent_commit_id].line_map.is_empty()
+            || parent_edge.edge_type == GraphEdgeType::Missing
+        {
             commit_source_map.remove(parent_commit_id);
         }
     }
@@ -304,18 +331,92 @@ fn process_commit(
     // Once we've looked at all parents of a commit, any leftover lines must be
     // original to the current commit, so we save this information in
     // original_line_map.
-    for (_, original_line_number) in current_source.line_map {
-        original_line_map[original_line_number] = Some(current_commit_id.clone());
+    for (line_number, original_line_number) in current_source.line_map {
+        original_line_map[original_line_number] = Some(LineOrigin {
+            commit_id: current_commit_id.clone(),
+            line_number,
+            path: current_source.path.clone(),
+        });
     }
 
     Ok(())
 }
 
+/// For two versions of the same file, for all the lines in common, overwrite
+/// the new mapping in the results for the new commit. Let's say I have a file
+/// in commit A and commit B. We know that according to local line_map, in
+/// commit A, line 3 corresponds to line 7 of the original file. Now, line 3 in
+/// Commit A corresponds to line 6 in commit B. Then, we update local line_map
+/// to say that "Commit B line 6 goes to line 7 of the original file". We repeat
+/// this for all lines in common in the two commits.
+///
+/// If `copy_changed` is true, lines in changed hunks are mapped as if they
+/// were in common.
+fn copy_lines_to_parent(
+    current_source: &mut Source,
+    parent_source: &mut Source,
+    copy_changed: bool,
+) {
+    let mut current_lines = current_source.line_map.iter().copied().peekable();
+    let mut new_current_line_map = Vec::new();
+    let mut new_parent_line_map = Vec::new();
+    copy_same_lines_with(
+        &current_source.text,
+        &parent_source.text,
+        copy_changed,
+        |current_start, parent_start, count| {
+            new_current_line_map
+                .extend(current_lines.peeking_take_while(|&(cur, _)| cur < current_start));
+            while let Some((current, original)) =
+                current_lines.next_if(|&(cur, _)| cur < current_start + count)
+            {
+                let parent = parent_start + (current - current_start);
+                new_parent_line_map.push((parent, original));
+            }
+        },
+    );
+    new_current_line_map.extend(current_lines);
+    current_source.line_map = new_current_line_map;
+    parent_source.line_map = if parent_source.line_map.is_empty() {
+        new_parent_line_map
+    } else {
+        itertools::merge(parent_source.line_map.iter().copied(), new_parent_line_map).collect()
+    };
+}
+
+/// Returns the path to the file in the `parent` commit. If the file doesn't
+/// exist in the parent, the source path of the copy or rename is looked up.
+fn find_parent_path(
+    repo: &dyn Repo,
+    parent: &Commit,
+    current_commit_id: &CommitId,
+    path: &RepoPath,
+) -> Result<RepoPathBuf, BackendError> {
+    if !parent.tree()?.path_value(path)?.is_absent() {
+        return Ok(path.to_owned());
+    }
+    let paths = [path.to_owned()];
+    let stream = repo
+        .store()
+        .get_copy_records(Some(&paths), parent.id(), current_commit_id)?;
+    for record in block_on_stream(stream) {
+        let record = record?;
+        if record.target.as_ref() == path {
+            return Ok(record.source);
+        }
+    }
+    Ok(path.to_owned())
+}
+
 /// For two files, calls `copy(current_start, parent_start, count)` for each
 /// range of contiguous lines in common (e.g. line 8-10 maps to line 9-11.)
+///
+/// If `copy_changed` is true, `copy()` is also called for the leading lines of
+/// each changed hunk, up to the number of lines on the shorter side.
 fn copy_same_lines_with(
     current_contents: &[u8],
     parent_contents: &[u8],
+    copy_changed: bool,
     mut copy: impl FnMut(usize, usize, usize),
 ) {
     let diff = Diff::by_line([current_contents, parent_contents]);
@@ -330,10 +431,14 @@ fn copy_same_lines_with(
                 parent_line_counter += count;
             }
             DiffHunkKind::Different => {
-                let current_output = hunk.contents[0];
-                let parent_output = hunk.contents[1];
-                current_line_counter += current_output.split_inclusive(|b| *b == b'\n').count();
-                parent_line_counter += parent_output.split_inclusive(|b| *b == b'\n').count();
+                let current_count = hunk.contents[0].split_inclusive(|b| *b == b'\n').count();
+                let parent_count = hunk.contents[1].split_inclusive(|b| *b == b'\n').count();
+                if copy_changed {
+                    let count = current_count.min(parent_count);
+                    copy(current_line_counter, parent_line_counter, count);
+                }
+                current_line_counter += current_count;
+                parent_line_counter += parent_count;
             }
         }
     }
@@ -377,6 +482,20 @@ fn get_file_contents(
 mod tests {
     use super::*;
 
+    fn make_line_map(commit_ids: &[&CommitId]) -> OriginalLineMap {
+        commit_ids
+            .iter()
+            .enumerate()
+            .map(|(line_number, &commit_id)| {
+                Some(LineOrigin {
+                    commit_id: commit_id.clone(),
+                    line_number,
+                    path: RepoPathBuf::from_internal_string("file"),
+                })
+            })
+            .collect()
+    }
+
     #[test]
     fn test_lines_iterator_empty() {
         let annotation = FileAnnotation {
@@ -394,11 +513,7 @@ mod tests {
         let commit_id2 = CommitId::from_hex("222222");
         let commit_id3 = CommitId::from_hex("333333");
         let annotation = FileAnnotation {
-            line_map: vec![
-                Some(commit_id1.clone()),
-                Some(commit_id2.clone()),
-                Some(commit_id3.clone()),
-            ],
+            line_map: make_line_map(&[&commit_id1, &commit_id2, &commit_id3]),
             text: "foo\n\nbar\n".into(),
         };
         assert_eq!(
@@ -433,15 +548,15 @@ mod tests {
         let commit_id2 = CommitId::from_hex("222222");
         let commit_id3 = CommitId::from_hex("333333");
         let annotation = FileAnnotation {
-            line_map: vec![
-                Some(commit_id1.clone()),
-                Some(commit_id1.clone()),
-                Some(commit_id2.clone()),
-                Some(commit_id1.clone()),
-                Some(commit_id3.clone()),
-                Some(commit_id3.clone()),
-                Some(commit_id3.clone()),
-            ],
+            line_map: make_line_map(&[
+                &commit_id1,
+                &commit_id1,
+                &commit_id2,
+                &commit_id1,
+                &commit_id3,
+                &commit_id3,
+                &commit_id3,
+            ]),
             text: "\n".repeat(7).into(),
         };
         assert_eq!(
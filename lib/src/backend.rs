@@ -25,6 +25,7 @@ use futures::stream::BoxStream;
 use thiserror::Error;
 
 use crate::content_hash::ContentHash;
+use crate::content_hash::DigestUpdate;
 use crate::hex_util;
 use crate::index::Index;
 use crate::merge::Merge;
@@ -139,7 +140,7 @@ impl MergedTreeId {
     }
 }
 
-#[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Commit {
     pub parents: Vec<CommitId>,
     pub predecessors: Vec<CommitId>,
@@ -149,6 +150,51 @@ pub struct Commit {
     pub author: Signature,
     pub committer: Signature,
     pub secure_sig: Option<SecureSig>,
+    /// Copies and renames from the parent commit recorded when the commit was
+    /// created, e.g. by the working copy.
+    pub copies: Vec<RecordedCopy>,
+}
+
+impl ContentHash for Commit {
+    fn hash(&self, state: &mut impl DigestUpdate) {
+        let Commit {
+            parents,
+            predecessors,
+            root_tree,
+            change_id,
+            description,
+            author,
+            committer,
+            secure_sig,
+            copies,
+        } = self;
+        parents.hash(state);
+        predecessors.hash(state);
+        root_tree.hash(state);
+        change_id.hash(state);
+        description.hash(state);
+        author.hash(state);
+        committer.hash(state);
+        secure_sig.hash(state);
+        // Hashed only if present so that commits without copies keep the ids
+        // they had before copies could be recorded.
+        if !copies.is_empty() {
+            copies.hash(state);
+        }
+    }
+}
+
+/// A copy or rename of a file from the parent commit, recorded on a commit.
+///
+/// Unlike [`CopyRecord`], which the backend may produce by comparing trees,
+/// recorded copies are stored along with the commit. They're only meaningful
+/// for commits with a single parent.
+#[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
+pub struct RecordedCopy {
+    /// The path the file was copied from, in the parent commit.
+    pub source: RepoPathBuf,
+    /// The path the file was copied to.
+    pub target: RepoPathBuf,
 }
 
 #[derive(ContentHash, Debug, PartialEq, Eq, Clone)]
@@ -388,6 +434,7 @@ pub fn make_root_commit(root_change_id: ChangeId, empty_tree_id: TreeId) -> Comm
         author: signature.clone(),
         committer: signature,
         secure_sig: None,
+        copies: vec![],
     }
 }
 
@@ -488,5 +535,21 @@ pub trait Backend: Send + Sync + Debug {
     /// All commits found in the `index` won't be removed. In addition to that,
     /// objects created after `keep_newer` will be preserved. This mitigates a
     /// risk of deleting new commits created concurrently by another process.
-    fn gc(&self, index: &dyn Index, keep_newer: SystemTime) -> BackendResult<()>;
+    ///
+    /// If `dry_run` is true, nothing is removed, but the returned stats count
+    /// the objects that would be removed.
+    fn gc(
+        &self,
+        index: &dyn Index,
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> BackendResult<BackendGcStats>;
+}
+
+/// Stats about objects pruned by [`Backend::gc()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BackendGcStats {
+    /// The number of removed references which kept unreachable commits from
+    /// being pruned by the underlying storage.
+    pub pruned_refs: usize,
 }
@@ -0,0 +1,286 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bisection of a commit range to find the first bad commit.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use itertools::Itertools as _;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::backend::CommitId;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::graph::GraphEdgeType;
+use crate::object_id::ObjectId as _;
+use crate::repo::Repo;
+use crate::revset::ResolvedRevsetExpression;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
+
+const STATE_FILE_NAME: &str = "bisect_state";
+
+/// Error that may occur while loading or saving the bisection state.
+#[derive(Debug, Error)]
+pub enum BisectStateError {
+    /// I/O error.
+    #[error(transparent)]
+    Path(#[from] PathError),
+    /// The state file couldn't be parsed.
+    #[error("Failed to parse bisection state {path}")]
+    Parse {
+        /// Path to the state file.
+        path: PathBuf,
+        /// Underlying parse error.
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// Persistent state of an ongoing bisection.
+///
+/// The state is stored in the repo directory, so it's shared by all
+/// workspaces.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BisectState {
+    /// Commits marked as good.
+    pub good: Vec<CommitId>,
+    /// Commits marked as bad.
+    pub bad: Vec<CommitId>,
+    /// Commits that couldn't be tested.
+    pub skipped: Vec<CommitId>,
+    /// Commit that is currently being tested.
+    pub current: Option<CommitId>,
+    /// Working-copy commit at the time the bisection was started.
+    pub start_commit: Option<CommitId>,
+}
+
+/// On-disk representation of `BisectState`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct BisectStateData {
+    #[serde(default)]
+    good: Vec<String>,
+    #[serde(default)]
+    bad: Vec<String>,
+    #[serde(default)]
+    skipped: Vec<String>,
+    #[serde(default)]
+    current: Option<String>,
+    #[serde(default)]
+    start_commit: Option<String>,
+}
+
+impl BisectState {
+    /// Returns the path to the state file in the given `repo_path`.
+    pub fn state_path(repo_path: &Path) -> PathBuf {
+        repo_path.join(STATE_FILE_NAME)
+    }
+
+    /// Loads the state from `repo_path`. Returns `None` if no bisection is in
+    /// progress.
+    pub fn load(repo_path: &Path) -> Result<Option<Self>, BisectStateError> {
+        let path = Self::state_path(repo_path);
+        let content = match fs::read(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).context(&path)?,
+        };
+        let parse_error =
+            |source: Box<dyn std::error::Error + Send + Sync>| BisectStateError::Parse {
+                path: path.clone(),
+                source,
+            };
+        let data: BisectStateData =
+            serde_json::from_slice(&content).map_err(|err| parse_error(err.into()))?;
+        let parse_id =
+            |hex: &str| CommitId::try_from_hex(hex).map_err(|err| parse_error(err.into()));
+        let parse_ids = |hexes: &[String]| -> Result<Vec<_>, _> {
+            hexes.iter().map(|hex| parse_id(hex)).try_collect()
+        };
+        Ok(Some(BisectState {
+            good: parse_ids(&data.good)?,
+            bad: parse_ids(&data.bad)?,
+            skipped: parse_ids(&data.skipped)?,
+            current: data.current.as_deref().map(parse_id).transpose()?,
+            start_commit: data.start_commit.as_deref().map(parse_id).transpose()?,
+        }))
+    }
+
+    /// Writes the state to `repo_path`.
+    pub fn save(&self, repo_path: &Path) -> Result<(), BisectStateError> {
+        let to_hexes = |ids: &[CommitId]| ids.iter().map(|id| id.hex()).collect();
+        let data = BisectStateData {
+            good: to_hexes(&self.good),
+            bad: to_hexes(&self.bad),
+            skipped: to_hexes(&self.skipped),
+            current: self.current.as_ref().map(|id| id.hex()),
+            start_commit: self.start_commit.as_ref().map(|id| id.hex()),
+        };
+        let path = Self::state_path(repo_path);
+        let mut temp_file = tempfile::NamedTempFile::new_in(repo_path).context(repo_path)?;
+        let content = serde_json::to_vec_pretty(&data).expect("state should be serializable");
+        temp_file.write_all(&content).context(temp_file.path())?;
+        temp_file
+            .persist(&path)
+            .map_err(|err| err.error)
+            .context(&path)?;
+        Ok(())
+    }
+
+    /// Removes the state file from `repo_path`. Returns `false` if no
+    /// bisection was in progress.
+    pub fn remove(repo_path: &Path) -> Result<bool, BisectStateError> {
+        let path = Self::state_path(repo_path);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err).context(&path)?,
+        }
+    }
+
+    /// Marks the `commit_id` as good.
+    pub fn mark_good(&mut self, commit_id: CommitId) {
+        self.unmark(&commit_id);
+        self.good.push(commit_id);
+    }
+
+    /// Marks the `commit_id` as bad.
+    pub fn mark_bad(&mut self, commit_id: CommitId) {
+        self.unmark(&commit_id);
+        self.bad.push(commit_id);
+    }
+
+    /// Marks the `commit_id` as untestable.
+    pub fn mark_skipped(&mut self, commit_id: CommitId) {
+        self.unmark(&commit_id);
+        self.skipped.push(commit_id);
+    }
+
+    fn unmark(&mut self, commit_id: &CommitId) {
+        self.good.retain(|id| id != commit_id);
+        self.bad.retain(|id| id != commit_id);
+        self.skipped.retain(|id| id != commit_id);
+    }
+
+    /// Commits that may be the first bad commit.
+    ///
+    /// This is the range between good and bad commits, excluding descendants
+    /// of bad commits which can't be the first bad commit.
+    pub fn candidates_expression(&self) -> Rc<ResolvedRevsetExpression> {
+        let bad_ancestors = self
+            .bad
+            .iter()
+            .map(|id| RevsetExpression::commit(id.clone()).ancestors())
+            .reduce(|acc, ancestors| acc.intersection(&ancestors))
+            .unwrap_or_else(RevsetExpression::none);
+        let good_ancestors = RevsetExpression::commits(self.good.clone()).ancestors();
+        let range = bad_ancestors.minus(&good_ancestors);
+        let bad_in_range = RevsetExpression::commits(self.bad.clone()).intersection(&range);
+        range.minus(&bad_in_range.children().descendants())
+    }
+}
+
+/// Outcome of a bisection step.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BisectionResult {
+    /// The given commit should be tested next.
+    Next {
+        /// Commit that splits the remaining candidates into halves.
+        commit_id: CommitId,
+        /// Number of commits that may be the first bad commit.
+        num_candidates: usize,
+    },
+    /// No more commits to test. There's exactly one first bad commit if the
+    /// list has one element, and multiple possible first bad commits if some
+    /// commits were skipped.
+    Done(Vec<CommitId>),
+}
+
+/// Finds the next commit to test, or the first bad commit if the bisection is
+/// complete.
+///
+/// The next commit is chosen such that the numbers of candidates in its
+/// ancestors and in the others are as close as possible, which halves the
+/// candidates even if the history contains merges.
+pub fn next_step(
+    repo: &dyn Repo,
+    state: &BisectState,
+) -> Result<BisectionResult, RevsetEvaluationError> {
+    let candidates_expression = state.candidates_expression();
+    let revset = candidates_expression.clone().evaluate(repo)?;
+    // Nodes are sorted topologically with children before parents.
+    let nodes: Vec<_> = revset.iter_graph().try_collect()?;
+    let tested: HashSet<&CommitId> = state.bad.iter().chain(&state.skipped).collect();
+    let untested_positions = nodes
+        .iter()
+        .positions(|(id, _)| !tested.contains(id))
+        .collect_vec();
+    if untested_positions.is_empty() {
+        let candidates = nodes.into_iter().map(|(id, _)| id).collect();
+        return Ok(BisectionResult::Done(candidates));
+    }
+
+    // Count ancestors of each node within the candidates. The candidates are
+    // convex, so all edges to ancestors in the set should be direct. A commit
+    // with a single parent in the set has one more ancestor than its parent.
+    // Merges may share ancestors between parents, so they are counted by
+    // walking the index.
+    let num_candidates = nodes.len();
+    let positions: HashMap<&CommitId, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(pos, (id, _))| (id, pos))
+        .collect();
+    let mut ancestor_counts = vec![0; num_candidates];
+    for (pos, (id, edges)) in nodes.iter().enumerate().rev() {
+        let parent_positions = edges
+            .iter()
+            .filter(|edge| edge.edge_type != GraphEdgeType::Missing)
+            .map(|edge| positions[&edge.target])
+            .collect_vec();
+        ancestor_counts[pos] = match parent_positions[..] {
+            [] => 1,
+            [parent_pos] => ancestor_counts[parent_pos] + 1,
+            _ => RevsetExpression::commit(id.clone())
+                .ancestors()
+                .intersection(&candidates_expression)
+                .evaluate(repo)?
+                .iter()
+                .process_results(|ids| ids.count())?,
+        };
+    }
+    let best_pos = untested_positions
+        .into_iter()
+        .max_by_key(|&pos| {
+            let num_ancestors = ancestor_counts[pos];
+            // Prefer the newest commit if there are equally good choices.
+            (
+                num_ancestors.min(num_candidates - num_ancestors),
+                usize::MAX - pos,
+            )
+        })
+        .unwrap();
+    Ok(BisectionResult::Next {
+        commit_id: nodes[best_pos].0.clone(),
+        num_candidates,
+    })
+}
@@ -23,6 +23,7 @@ use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
 use crate::backend::MergedTreeId;
+use crate::backend::RecordedCopy;
 use crate::backend::Signature;
 use crate::commit::Commit;
 use crate::repo::MutableRepo;
@@ -114,6 +115,15 @@ impl CommitBuilder<'_> {
         self
     }
 
+    pub fn copies(&self) -> &[RecordedCopy] {
+        self.inner.copies()
+    }
+
+    pub fn set_copies(mut self, copies: Vec<RecordedCopy>) -> Self {
+        self.inner.set_copies(copies);
+        self
+    }
+
     pub fn sign_settings(&self) -> &SignSettings {
         self.inner.sign_settings()
     }
@@ -165,6 +175,7 @@ impl DetachedCommitBuilder {
             author: signature.clone(),
             committer: signature,
             secure_sig: None,
+            copies: vec![],
         };
         DetachedCommitBuilder {
             store,
@@ -295,6 +306,15 @@ impl DetachedCommitBuilder {
         self
     }
 
+    pub fn copies(&self) -> &[RecordedCopy] {
+        &self.commit.copies
+    }
+
+    pub fn set_copies(&mut self, copies: Vec<RecordedCopy>) -> &mut Self {
+        self.commit.copies = copies;
+        self
+    }
+
     pub fn sign_settings(&self) -> &SignSettings {
         &self.sign_settings
     }
@@ -47,6 +47,8 @@ use crate::merge::Merge;
 use crate::merge::MergeBuilder;
 use crate::merge::MergedTreeValue;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::repo_path::RepoPathComponent;
 use crate::store::Store;
 
 const CONFLICT_START_LINE: &[u8] = b"<<<<<<<";
@@ -231,6 +233,43 @@ async fn materialize_tree_value_no_access_denied(
     }
 }
 
+/// Infix of the names of the files that the sides of a conflict are written to
+/// when the conflict can't be materialized with conflict markers, e.g.
+/// `image.png.jjconflict-side-1`.
+pub const CONFLICT_SIDECAR_INFIX: &str = ".jjconflict-";
+
+/// Returns the paths of the files to write the terms of a conflict at `path`
+/// to. The returned merge has the same shape as a conflict with `num_sides`
+/// sides.
+pub fn conflict_sidecar_paths(path: &RepoPath, num_sides: usize) -> Merge<RepoPathBuf> {
+    let (dir, name) = path.split().expect("conflict can't be at the root");
+    let sidecar_path = |suffix: String| {
+        let name = [name.as_internal_str(), CONFLICT_SIDECAR_INFIX, &suffix].concat();
+        dir.join(RepoPathComponent::new(&name))
+    };
+    let removes = if num_sides == 2 {
+        vec![sidecar_path("base".to_owned())]
+    } else {
+        (1..num_sides)
+            .map(|i| sidecar_path(format!("base-{i}")))
+            .collect()
+    };
+    let adds = (1..=num_sides).map(|i| sidecar_path(format!("side-{i}")));
+    Merge::from_removes_adds(removes, adds)
+}
+
+/// If `path` looks like a file written by `conflict_sidecar_paths()`, returns
+/// the path of the conflict.
+pub fn conflict_sidecar_base_path(path: &RepoPath) -> Option<RepoPathBuf> {
+    let (dir, name) = path.split()?;
+    let (base_name, suffix) = name.as_internal_str().rsplit_once(CONFLICT_SIDECAR_INFIX)?;
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let is_sidecar = suffix == "base"
+        || suffix.strip_prefix("base-").is_some_and(is_number)
+        || suffix.strip_prefix("side-").is_some_and(is_number);
+    (is_sidecar && !base_name.is_empty()).then(|| dir.join(RepoPathComponent::new(base_name)))
+}
+
 pub fn materialize_merge_result(
     single_hunk: &Merge<BString>,
     output: &mut dyn Write,
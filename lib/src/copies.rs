@@ -14,21 +14,33 @@
 
 //! Code for working with copies and renames.
 
+use std::collections::hash_map::Entry;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
 use std::task::ready;
 use std::task::Context;
 use std::task::Poll;
 
+use futures::executor::block_on_stream;
 use futures::Stream;
 
 use crate::backend::BackendResult;
+use crate::backend::CommitId;
 use crate::backend::CopyRecord;
+use crate::backend::RecordedCopy;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::fileset::FilesetExpression;
+use crate::matchers::Matcher;
 use crate::merge::MergedTreeValue;
 use crate::merged_tree::MergedTree;
 use crate::merged_tree::TreeDiffStream;
 use crate::repo_path::RepoPath;
 use crate::repo_path::RepoPathBuf;
+use crate::store::Store;
 
 /// A collection of CopyRecords.
 #[derive(Default, Debug)]
@@ -214,3 +226,196 @@ impl Stream for CopiesTreeDiffStream<'_> {
         Poll::Ready(None)
     }
 }
+
+/// Combines the copies recorded on a commit with the copies made on top of it,
+/// such as the ones detected by a working-copy snapshot.
+///
+/// The `old_copies` are relative to the `parent_tree`, and the `new_copies`
+/// are relative to the commit's previous tree. Returns the copies relative to
+/// the `parent_tree` that still apply to the `new_tree`. For example, renaming
+/// `a` to `b` and then `b` to `c` results in a rename from `a` to `c`.
+pub fn compose_recorded_copies(
+    old_copies: &[RecordedCopy],
+    new_copies: &[RecordedCopy],
+    parent_tree: &MergedTree,
+    new_tree: &MergedTree,
+) -> BackendResult<Vec<RecordedCopy>> {
+    let old_sources: BTreeMap<&RepoPath, &RepoPath> = old_copies
+        .iter()
+        .map(|copy| (copy.target.as_ref(), copy.source.as_ref()))
+        .collect();
+    let mut sources = old_sources.clone();
+    for copy in new_copies {
+        let source = old_sources
+            .get(copy.source.as_ref())
+            .copied()
+            .unwrap_or(&copy.source);
+        sources.insert(&copy.target, source);
+    }
+    let is_file = |tree: &MergedTree, path: &RepoPath| -> BackendResult<bool> {
+        let value = tree.path_value(path)?;
+        Ok(matches!(value.as_normal(), Some(TreeValue::File { .. })))
+    };
+    let mut copies = vec![];
+    for (target, source) in sources {
+        if source != target && is_file(new_tree, target)? && is_file(parent_tree, source)? {
+            copies.push(RecordedCopy {
+                source: source.to_owned(),
+                target: target.to_owned(),
+            });
+        }
+    }
+    Ok(copies)
+}
+
+/// Follows files back in history across copies and renames.
+///
+/// Commits should be visited in topological order, children before parents,
+/// and no commit should be skipped. The followed paths are tracked per commit:
+/// once a followed file turns out to be copied or renamed from another path in
+/// some commit, the source path is followed in the ancestors of that commit.
+#[derive(Debug)]
+pub struct CopyFollower {
+    initial_paths: FollowedPaths,
+    /// Paths followed in the commits that haven't been visited yet, collected
+    /// from their visited children.
+    pending_paths: HashMap<CommitId, FollowedPaths>,
+}
+
+#[derive(Clone, Debug)]
+struct FollowedPaths {
+    expression: FilesetExpression,
+    matcher: Rc<dyn Matcher>,
+}
+
+impl FollowedPaths {
+    fn new(expression: FilesetExpression) -> Self {
+        let matcher = expression.to_matcher().into();
+        FollowedPaths {
+            expression,
+            matcher,
+        }
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        if Rc::ptr_eq(&self.matcher, &other.matcher) {
+            return self.clone();
+        }
+        FollowedPaths::new(FilesetExpression::union_all(vec![
+            self.expression.clone(),
+            other.expression.clone(),
+        ]))
+    }
+}
+
+impl CopyFollower {
+    /// Creates new follower of the files specified by the `expression`.
+    pub fn new(expression: FilesetExpression) -> Self {
+        CopyFollower {
+            initial_paths: FollowedPaths::new(expression),
+            pending_paths: HashMap::new(),
+        }
+    }
+
+    /// Matcher of the files followed in the commit, which must not have been
+    /// visited yet.
+    pub fn matcher(&self, commit_id: &CommitId) -> Rc<dyn Matcher> {
+        let paths = self
+            .pending_paths
+            .get(commit_id)
+            .unwrap_or(&self.initial_paths);
+        paths.matcher.clone()
+    }
+
+    /// Visits the `commit` and passes the followed files on to its parents.
+    ///
+    /// If the commit `modifies` the followed files, copies and renames of
+    /// them are looked up, and their sources are followed in the parents.
+    /// Returns the copies and renames found.
+    pub fn follow_commit(
+        &mut self,
+        store: &Arc<Store>,
+        commit: &Commit,
+        modifies: bool,
+    ) -> BackendResult<Vec<CopiesTreeDiffEntryPath>> {
+        let paths = self
+            .pending_paths
+            .remove(commit.id())
+            .unwrap_or_else(|| self.initial_paths.clone());
+        let copies = if modifies {
+            find_copies(store, commit, paths.matcher.as_ref())?
+        } else {
+            vec![]
+        };
+        let new_sources = copies
+            .iter()
+            .map(|copy| copy.source())
+            .filter(|source| !paths.matcher.matches(source))
+            .map(|source| FilesetExpression::file_path(source.to_owned()))
+            .collect::<Vec<_>>();
+        let paths = if new_sources.is_empty() {
+            paths
+        } else {
+            FollowedPaths::new(FilesetExpression::union_all(
+                [paths.expression].into_iter().chain(new_sources).collect(),
+            ))
+        };
+        for parent_id in commit.parent_ids() {
+            match self.pending_paths.entry(parent_id.clone()) {
+                Entry::Occupied(mut entry) => {
+                    let merged = entry.get().union(&paths);
+                    entry.insert(merged);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(paths.clone());
+                }
+            }
+        }
+        Ok(copies)
+    }
+}
+
+/// Finds copies and renames of the files matched by the `matcher` in the
+/// `commit`.
+fn find_copies(
+    store: &Arc<Store>,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+) -> BackendResult<Vec<CopiesTreeDiffEntryPath>> {
+    let mut copies = vec![];
+    let mut tree = None;
+    for parent_id in commit.parent_ids() {
+        let stream = store.get_copy_records(None, parent_id, commit.id())?;
+        for record in block_on_stream(stream) {
+            let CopyRecord { source, target, .. } = record?;
+            if !matcher.matches(&target) || copies_contain(&copies, &source, &target) {
+                continue;
+            }
+            // If the source path is deleted in the target tree, it's a rename.
+            let tree = match &tree {
+                Some(tree) => tree,
+                None => tree.insert(commit.tree()?),
+            };
+            let copy_op = if tree.path_value(&source)?.is_absent() {
+                CopyOperation::Rename
+            } else {
+                CopyOperation::Copy
+            };
+            copies.push(CopiesTreeDiffEntryPath {
+                source: Some((source, copy_op)),
+                target,
+            });
+        }
+    }
+    Ok(copies)
+}
+
+fn copies_contain(
+    copies: &[CopiesTreeDiffEntryPath],
+    source: &RepoPath,
+    target: &RepoPath,
+) -> bool {
+    copies
+        .iter()
+        .any(|copy| copy.source() == source && copy.target() == target)
+}
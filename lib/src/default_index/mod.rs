@@ -41,6 +41,7 @@ pub use self::readonly::DefaultReadonlyIndex;
 pub use self::readonly::ReadonlyIndexLoadError;
 pub use self::store::DefaultIndexStore;
 pub use self::store::DefaultIndexStoreError;
+pub use self::store::DefaultIndexStoreGcStats;
 pub use self::store::DefaultIndexStoreInitError;
 
 #[cfg(test)]
@@ -258,6 +258,39 @@ impl ReadonlyIndexSegment {
         Self::load_from(&mut file, dir, name, commit_id_length, change_id_length)
     }
 
+    /// Reads the name of the parent segment file from the header of the
+    /// segment file `name`.
+    pub(super) fn read_parent_name(
+        dir: &Path,
+        name: &str,
+    ) -> Result<Option<String>, ReadonlyIndexLoadError> {
+        let from_io_err = |err| ReadonlyIndexLoadError::from_io_err(name, err);
+        let mut file = File::open(dir.join(name)).map_err(from_io_err)?;
+        let mut read_u32 = || {
+            let mut buf = [0; 4];
+            file.read_exact(&mut buf).map_err(from_io_err)?;
+            Ok(u32::from_le_bytes(buf))
+        };
+        let format_version = read_u32()?;
+        if format_version != INDEX_SEGMENT_FILE_FORMAT_VERSION {
+            return Err(ReadonlyIndexLoadError::UnexpectedVersion {
+                found_version: format_version,
+                expected_version: INDEX_SEGMENT_FILE_FORMAT_VERSION,
+            });
+        }
+        let parent_filename_len = read_u32()?;
+        if parent_filename_len == 0 {
+            return Ok(None);
+        }
+        let mut parent_filename_bytes = vec![0; parent_filename_len as usize];
+        file.read_exact(&mut parent_filename_bytes)
+            .map_err(from_io_err)?;
+        let parent_filename = String::from_utf8(parent_filename_bytes).map_err(|_| {
+            ReadonlyIndexLoadError::invalid_data(name, "parent file name is not valid UTF-8")
+        })?;
+        Ok(Some(parent_filename))
+    }
+
     /// Loads both parent segments and local entries from the given `file`.
     pub(super) fn load_from(
         file: &mut dyn Read,
@@ -27,7 +27,11 @@ use std::rc::Rc;
 use std::str;
 use std::sync::Arc;
 
+use futures::future;
+use futures::stream;
+use futures::stream::BoxStream;
 use futures::StreamExt as _;
+use futures::TryStreamExt as _;
 use itertools::Itertools;
 use pollster::FutureExt as _;
 
@@ -45,15 +49,21 @@ use crate::commit::Commit;
 use crate::conflicts::materialize_merge_result;
 use crate::conflicts::materialize_tree_value;
 use crate::conflicts::MaterializedTreeValue;
+use crate::copies::CopyFollower;
 use crate::default_index::AsCompositeIndex;
 use crate::default_index::CompositeIndex;
 use crate::default_index::IndexPosition;
+use crate::diff::Diff;
+use crate::diff::DiffHunkKind;
+use crate::fileset::FilesetExpression;
 use crate::graph::GraphNode;
 use crate::matchers::Matcher;
 use crate::matchers::Visit;
+use crate::merge::MergedTreeValue;
 use crate::merged_tree::resolve_file_values;
 use crate::object_id::ObjectId as _;
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
 use crate::revset::ResolvedExpression;
 use crate::revset::ResolvedPredicateExpression;
 use crate::revset::Revset;
@@ -1026,6 +1036,20 @@ impl EvaluationContext<'_> {
                 let set2 = self.evaluate_predicate(expression2)?;
                 Ok(Box::new(UnionRevset { set1, set2 }))
             }
+            ResolvedPredicateExpression::FileFollow { files, heads } => {
+                let index = self.index;
+                let head_set = self.evaluate(heads)?;
+                let head_positions = head_set.positions().attach(index);
+                let walk = RevWalkBuilder::new(index)
+                    .wanted_heads(head_positions.try_collect()?)
+                    .ancestors()
+                    .detach();
+                Ok(Box::new(FileFollowPredicate {
+                    store: self.store.clone(),
+                    expression: files.clone(),
+                    walk,
+                }))
+            }
         }
     }
 
@@ -1140,6 +1164,53 @@ fn box_pure_predicate_fn<'a>(
     Box::new(PurePredicateFn(f))
 }
 
+/// Predicate that follows files across copies and renames.
+///
+/// The followed paths depend on the descendants, so all ancestors of the heads
+/// are visited from descendants to ancestors regardless of which commits the
+/// predicate is evaluated against.
+struct FileFollowPredicate<W> {
+    store: Arc<Store>,
+    expression: FilesetExpression,
+    walk: W,
+}
+
+impl<W> fmt::Debug for FileFollowPredicate<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileFollowPredicate")
+            .field("expression", &self.expression)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W> ToPredicateFn for FileFollowPredicate<W>
+where
+    W: RevWalk<CompositeIndex, Item = IndexPosition> + Clone,
+{
+    fn to_predicate_fn<'a>(&self) -> BoxedPredicateFn<'a>
+    where
+        Self: 'a,
+    {
+        let store = self.store.clone();
+        let mut follower = CopyFollower::new(self.expression.clone());
+        let mut walk = self.walk.clone().peekable();
+        let mut matched_positions = HashSet::new();
+        Box::new(move |index, entry_pos| {
+            while let Some(pos) = walk.next_if(index, |&pos| pos >= entry_pos) {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let matcher = follower.matcher(commit.id());
+                let modifies = has_diff_from_parent(&store, index, &commit, matcher.as_ref())?;
+                follower.follow_commit(&store, &commit, modifies)?;
+                if modifies {
+                    matched_positions.insert(pos);
+                }
+            }
+            Ok(matched_positions.contains(&entry_pos))
+        })
+    }
+}
+
 fn build_predicate_fn(
     store: Arc<Store>,
     predicate: &RevsetFilterPredicate,
@@ -1206,6 +1277,9 @@ fn build_predicate_fn(
                 Ok(has_diff_from_parent(&store, index, &commit, &*matcher)?)
             })
         }
+        RevsetFilterPredicate::FileFollow(_) => {
+            panic!("File follow predicate should have been resolved with the visible heads")
+        }
         RevsetFilterPredicate::DiffContains { text, files } => {
             let text_pattern = text.clone();
             let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
@@ -1221,6 +1295,51 @@ fn build_predicate_fn(
                 )?)
             })
         }
+        RevsetFilterPredicate::DiffLines { range, files } => {
+            let range = range.clone();
+            let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let count = count_changed_lines_from_parent(
+                    &store,
+                    index,
+                    &commit,
+                    &*files_matcher,
+                    range.end,
+                )?;
+                Ok(range.contains(&count))
+            })
+        }
+        RevsetFilterPredicate::FilesChanged { range, files } => {
+            let range = range.clone();
+            let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                let count = count_changed_files_from_parent(
+                    &store,
+                    index,
+                    &commit,
+                    &*files_matcher,
+                    range.end,
+                )?;
+                Ok(range.contains(&count))
+            })
+        }
+        RevsetFilterPredicate::AddsBinary(files) => {
+            let files_matcher: Rc<dyn Matcher> = files.to_matcher().into();
+            box_pure_predicate_fn(move |index, pos| {
+                let entry = index.entry_by_pos(pos);
+                let commit = store.get_commit(&entry.commit_id())?;
+                Ok(adds_binary_from_parent(
+                    &store,
+                    index,
+                    &commit,
+                    &*files_matcher,
+                )?)
+            })
+        }
         RevsetFilterPredicate::HasConflict => box_pure_predicate_fn(move |index, pos| {
             let entry = index.entry_by_pos(pos);
             let commit = store.get_commit(&entry.commit_id())?;
@@ -1315,6 +1434,134 @@ fn matches_diff_from_parent(
     .block_on()
 }
 
+/// Counts files changed from the parents, up to the `limit`.
+fn count_changed_files_from_parent(
+    store: &Arc<Store>,
+    index: &CompositeIndex,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+    limit: usize,
+) -> BackendResult<usize> {
+    let mut tree_diff = diff_from_parent(store, index, commit, matcher)?;
+    async {
+        let mut count = 0;
+        while let Some(entry) = tree_diff.next().await {
+            entry?;
+            count += 1;
+            if count >= limit {
+                break;
+            }
+        }
+        Ok(count)
+    }
+    .block_on()
+}
+
+/// Counts lines added or removed from the parents, up to the `limit`.
+fn count_changed_lines_from_parent(
+    store: &Arc<Store>,
+    index: &CompositeIndex,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+    limit: usize,
+) -> BackendResult<usize> {
+    let mut tree_diff = diff_from_parent(store, index, commit, matcher)?;
+    async {
+        let mut count = 0;
+        while let Some(entry) = tree_diff.next().await {
+            let (path, left_value, right_value) = entry?;
+            let left_future = materialize_tree_value(store, &path, left_value);
+            let right_future = materialize_tree_value(store, &path, right_value);
+            let (left_value, right_value) = futures::try_join!(left_future, right_future)?;
+            let left_content = to_file_content(&path, left_value)?;
+            let right_content = to_file_content(&path, right_value)?;
+            // Binary files don't have meaningful lines.
+            if is_binary_content(&left_content) || is_binary_content(&right_content) {
+                continue;
+            }
+            // Lines are counted in the same way as `jj diff --stat`.
+            let diff = Diff::by_line([&left_content, &right_content]);
+            count += diff
+                .hunks()
+                .filter(|hunk| hunk.kind == DiffHunkKind::Different)
+                .flat_map(|hunk| hunk.contents)
+                .map(|content| content.split_inclusive(|b| *b == b'\n').count())
+                .sum::<usize>();
+            if count >= limit {
+                break;
+            }
+        }
+        Ok(count)
+    }
+    .block_on()
+}
+
+fn adds_binary_from_parent(
+    store: &Arc<Store>,
+    index: &CompositeIndex,
+    commit: &Commit,
+    matcher: &dyn Matcher,
+) -> BackendResult<bool> {
+    let mut tree_diff = diff_from_parent(store, index, commit, matcher)?;
+    async {
+        while let Some(entry) = tree_diff.next().await {
+            let (path, left_value, right_value) = entry?;
+            // Only newly added files count, not modified ones.
+            if left_value.is_present() {
+                continue;
+            }
+            let right_value = materialize_tree_value(store, &path, right_value).await?;
+            if let MaterializedTreeValue::File { .. } = &right_value {
+                let content = to_file_content(&path, right_value)?;
+                if is_binary_content(&content) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+    .block_on()
+}
+
+/// Returns the files that differ between the merged parents of `commit` and
+/// the commit itself, with the parent values resolved. Files whose resolved
+/// values are the same are skipped.
+fn diff_from_parent<'a>(
+    store: &'a Arc<Store>,
+    index: &CompositeIndex,
+    commit: &Commit,
+    matcher: &'a dyn Matcher,
+) -> BackendResult<BoxStream<'a, BackendResult<(RepoPathBuf, MergedTreeValue, MergedTreeValue)>>> {
+    let parents: Vec<_> = commit.parents().try_collect()?;
+    if let [parent] = parents.as_slice() {
+        // Fast path: no need to load the root tree
+        if commit.tree_id() == parent.tree_id() {
+            return Ok(Box::pin(stream::empty()));
+        }
+    }
+    // Conflict resolution is expensive, try that only for matched files.
+    let from_tree = rewrite::merge_commit_trees_no_resolve_without_repo(store, &index, &parents)?;
+    let to_tree = commit.tree()?;
+    // TODO: handle copy tracking
+    let tree_diff = from_tree
+        .diff_stream(&to_tree, matcher)
+        // TODO: Resolve values concurrently
+        .then(move |entry| async move {
+            let (from_value, to_value) = entry.values?;
+            let from_value = resolve_file_values(store, &entry.path, from_value).await?;
+            Ok((entry.path, from_value, to_value))
+        })
+        .try_filter(|(_, from_value, to_value)| future::ready(from_value != to_value));
+    Ok(Box::pin(tree_diff))
+}
+
+/// Determines whether the content is likely binary by looking for a null
+/// character in the first 8k bytes. This is the same heuristic as Git's.
+fn is_binary_content(content: &[u8]) -> bool {
+    const PEEK_SIZE: usize = 8000;
+    content[..PEEK_SIZE.min(content.len())].contains(&b'\0')
+}
+
 fn match_lines<'a: 'b, 'b>(
     text: &'a [u8],
     pattern: &'b StringPattern,
@@ -22,6 +22,7 @@ use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use itertools::Itertools;
 use tempfile::NamedTempFile;
@@ -86,6 +87,17 @@ pub enum DefaultIndexStoreError {
     },
     #[error(transparent)]
     OpStore(#[from] OpStoreError),
+    #[error("Failed to prune commit index files")]
+    Gc(#[from] PathError),
+}
+
+/// Stats about files pruned by [`DefaultIndexStore::gc()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DefaultIndexStoreGcStats {
+    /// The number of removed links from operations to index segments.
+    pub pruned_operation_links: usize,
+    /// The number of removed index segment files.
+    pub pruned_segments: usize,
 }
 
 #[derive(Debug)]
@@ -133,6 +145,99 @@ impl DefaultIndexStore {
         Ok(())
     }
 
+    /// Prunes index files which aren't associated with the given operations.
+    ///
+    /// Operation links and segment files modified after `keep_newer` are
+    /// preserved, as are the segments reachable from the preserved links. If
+    /// `dry_run` is true, nothing is removed, but the returned stats count the
+    /// files that would be removed.
+    pub fn gc(
+        &self,
+        reachable_op_ids: &HashSet<OperationId>,
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> Result<DefaultIndexStoreGcStats, DefaultIndexStoreError> {
+        let is_new = |entry: &fs::DirEntry| -> Result<bool, PathError> {
+            let metadata = entry.metadata().context(entry.path())?;
+            let mtime = metadata.modified().expect("unsupported platform?");
+            Ok(mtime > keep_newer)
+        };
+        let remove_file = |path: &Path| -> Result<(), PathError> {
+            if dry_run {
+                tracing::trace!(?path, "would remove");
+                Ok(())
+            } else {
+                tracing::trace!(?path, "removing");
+                fs::remove_file(path).context(path)
+            }
+        };
+        let mut stats = DefaultIndexStoreGcStats::default();
+
+        let operations_dir = self.operations_dir();
+        let mut reachable_segments = HashSet::new();
+        let mut pending_segments = Vec::new();
+        for entry in operations_dir.read_dir().context(&operations_dir)? {
+            let entry = entry.context(&operations_dir)?;
+            let path = entry.path();
+            let Some(op_id) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|hex| OperationId::try_from_hex(hex).ok())
+            else {
+                continue;
+            };
+            if reachable_op_ids.contains(&op_id) || is_new(&entry)? {
+                let name =
+                    fs::read_to_string(&path).map_err(DefaultIndexStoreError::LoadAssociation)?;
+                pending_segments.push(name);
+            } else {
+                remove_file(&path)?;
+                stats.pruned_operation_links += 1;
+            }
+        }
+
+        let segments_dir = self.segments_dir();
+        while let Some(name) = pending_segments.pop() {
+            if reachable_segments.contains(&name) {
+                continue;
+            }
+            let parent_name = ReadonlyIndexSegment::read_parent_name(&segments_dir, &name)
+                .map_err(DefaultIndexStoreError::LoadIndex)?;
+            pending_segments.extend(parent_name);
+            reachable_segments.insert(name);
+        }
+        for entry in segments_dir.read_dir().context(&segments_dir)? {
+            let entry = entry.context(&segments_dir)?;
+            let path = entry.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            // Skip temporary files which may be being written.
+            if name.len() != SEGMENT_FILE_NAME_LENGTH
+                || reachable_segments.contains(name)
+                || is_new(&entry)?
+            {
+                continue;
+            }
+            remove_file(&path)?;
+            stats.pruned_segments += 1;
+        }
+        Ok(stats)
+    }
+
+    /// Returns the number of index segment files.
+    pub fn segment_file_count(&self) -> Result<usize, PathError> {
+        let dir = self.segments_dir();
+        let mut count = 0;
+        for entry in dir.read_dir().context(&dir)? {
+            let entry = entry.context(&dir)?;
+            if entry.file_name().len() == SEGMENT_FILE_NAME_LENGTH {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
     fn ensure_base_dirs(&self) -> Result<(), PathError> {
         for dir in [self.operations_dir(), self.segments_dir()] {
             file_util::create_or_reuse_dir(&dir).context(&dir)?;
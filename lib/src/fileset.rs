@@ -37,6 +37,7 @@ use crate::fileset_parser::UnaryOp;
 use crate::matchers::DifferenceMatcher;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::FileGlobsMatcher;
+use crate::matchers::FileRegexesMatcher;
 use crate::matchers::FilesMatcher;
 use crate::matchers::IntersectionMatcher;
 use crate::matchers::Matcher;
@@ -64,6 +65,9 @@ pub enum FilePatternParseError {
     /// Failed to parse glob pattern.
     #[error(transparent)]
     GlobPattern(#[from] glob::PatternError),
+    /// Failed to parse regular expression.
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
 }
 
 /// Basic pattern to match `RepoPath`.
@@ -80,6 +84,22 @@ pub enum FilePattern {
         /// Glob pattern relative to `dir`.
         pattern: glob::Pattern,
     },
+    /// Matches file (or exact) path with case-insensitive glob pattern.
+    ///
+    /// Only ASCII case differences are folded, including in `dir`.
+    FileGlobI {
+        /// Prefix directory path where the `pattern` will be evaluated.
+        dir: RepoPathBuf,
+        /// Glob pattern relative to `dir`.
+        pattern: glob::Pattern,
+    },
+    /// Matches file (or exact) path with regular expression.
+    FileRegex {
+        /// Prefix directory path where the `regex` will be evaluated.
+        dir: RepoPathBuf,
+        /// Regular expression to search in the path relative to `dir`.
+        regex: regex::Regex,
+    },
     // TODO: add more patterns:
     // - FilesInPath: files in directory, non-recursively?
     // - NameGlob or SuffixGlob: file name with glob?
@@ -105,14 +125,19 @@ impl FilePattern {
         // * string pattern syntax (+ case sensitivity?)
         //   * path: literal path (default) (default anchor: prefix)
         //   * glob: glob pattern (default anchor: file)
-        //   * regex?
+        //   * regex: regular expression (unanchored, searched in sub path)
+        //   * -i suffix: case-insensitive glob
         match kind {
             "cwd" => Self::cwd_prefix_path(path_converter, input),
             "cwd-file" | "file" => Self::cwd_file_path(path_converter, input),
             "cwd-glob" | "glob" => Self::cwd_file_glob(path_converter, input),
+            "cwd-glob-i" | "glob-i" => Self::cwd_file_glob_i(path_converter, input),
+            "cwd-regex" | "regex" => Self::cwd_file_regex(path_converter, input),
             "root" => Self::root_prefix_path(input),
             "root-file" => Self::root_file_path(input),
             "root-glob" => Self::root_file_glob(input),
+            "root-glob-i" => Self::root_file_glob_i(input),
+            "root-regex" => Self::root_file_regex(input),
             _ => Err(FilePatternParseError::InvalidKind(kind.to_owned())),
         }
     }
@@ -145,6 +170,25 @@ impl FilePattern {
         Self::file_glob_at(dir, pattern)
     }
 
+    /// Pattern that matches cwd-relative file path glob case-insensitively.
+    pub fn cwd_file_glob_i(
+        path_converter: &RepoPathUiConverter,
+        input: impl AsRef<str>,
+    ) -> Result<Self, FilePatternParseError> {
+        let (dir, pattern) = split_glob_path(input.as_ref());
+        let dir = path_converter.parse_file_path(dir)?;
+        Self::file_glob_i_at(dir, pattern)
+    }
+
+    /// Pattern that matches cwd-relative file path with regular expression.
+    pub fn cwd_file_regex(
+        path_converter: &RepoPathUiConverter,
+        input: impl AsRef<str>,
+    ) -> Result<Self, FilePatternParseError> {
+        let dir = path_converter.parse_file_path("")?;
+        Self::file_regex_at(dir, input.as_ref())
+    }
+
     /// Pattern that matches workspace-relative file (or exact) path.
     pub fn root_file_path(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
         // TODO: Let caller pass in converter for root-relative paths too
@@ -165,27 +209,67 @@ impl FilePattern {
         Self::file_glob_at(dir, pattern)
     }
 
+    /// Pattern that matches workspace-relative file path glob
+    /// case-insensitively.
+    pub fn root_file_glob_i(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
+        let (dir, pattern) = split_glob_path(input.as_ref());
+        let dir = RepoPathBuf::from_relative_path(dir)?;
+        Self::file_glob_i_at(dir, pattern)
+    }
+
+    /// Pattern that matches workspace-relative file path with regular
+    /// expression.
+    pub fn root_file_regex(input: impl AsRef<str>) -> Result<Self, FilePatternParseError> {
+        Self::file_regex_at(RepoPathBuf::root(), input.as_ref())
+    }
+
     fn file_glob_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
         if input.is_empty() {
             return Ok(FilePattern::FilePath(dir));
         }
-        // Normalize separator to '/', reject ".." which will never match
-        let normalized = RepoPathBuf::from_relative_path(input)?;
-        let pattern = glob::Pattern::new(normalized.as_internal_file_string())?;
+        let pattern = parse_file_glob(input)?;
         Ok(FilePattern::FileGlob { dir, pattern })
     }
 
+    fn file_glob_i_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
+        if input.is_empty() {
+            // Unlike file_glob_at(), this can't be mapped to FilePath because
+            // the file name should also be matched case-insensitively.
+            let Some((parent, name)) = dir.split() else {
+                return Ok(FilePattern::FilePath(dir));
+            };
+            let pattern = glob::Pattern::new(&glob::Pattern::escape(name.as_internal_str()))?;
+            let dir = parent.to_owned();
+            return Ok(FilePattern::FileGlobI { dir, pattern });
+        }
+        let pattern = parse_file_glob(input)?;
+        Ok(FilePattern::FileGlobI { dir, pattern })
+    }
+
+    fn file_regex_at(dir: RepoPathBuf, input: &str) -> Result<Self, FilePatternParseError> {
+        let regex = regex::Regex::new(input)?;
+        Ok(FilePattern::FileRegex { dir, regex })
+    }
+
     /// Returns path if this pattern represents a literal path in a workspace.
     /// Returns `None` if this is a glob pattern for example.
     pub fn as_path(&self) -> Option<&RepoPath> {
         match self {
             FilePattern::FilePath(path) => Some(path),
             FilePattern::PrefixPath(path) => Some(path),
-            FilePattern::FileGlob { .. } => None,
+            FilePattern::FileGlob { .. }
+            | FilePattern::FileGlobI { .. }
+            | FilePattern::FileRegex { .. } => None,
         }
     }
 }
 
+fn parse_file_glob(input: &str) -> Result<glob::Pattern, FilePatternParseError> {
+    // Normalize separator to '/', reject ".." which will never match
+    let normalized = RepoPathBuf::from_relative_path(input)?;
+    Ok(glob::Pattern::new(normalized.as_internal_file_string())?)
+}
+
 /// Splits `input` path into literal directory path and glob pattern.
 fn split_glob_path(input: &str) -> (&str, &str) {
     const GLOB_CHARS: &[char] = &['?', '*', '[', ']']; // see glob::Pattern::escape()
@@ -317,6 +401,8 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
     let mut file_paths = Vec::new();
     let mut prefix_paths = Vec::new();
     let mut file_globs = Vec::new();
+    let mut file_globs_i = Vec::new();
+    let mut file_regexes = Vec::new();
     let mut matchers: Vec<Option<Box<dyn Matcher>>> = Vec::new();
     for expr in expressions {
         let matcher: Box<dyn Matcher> = match expr {
@@ -330,6 +416,12 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
                     FilePattern::FileGlob { dir, pattern } => {
                         file_globs.push((dir, pattern.clone()));
                     }
+                    FilePattern::FileGlobI { dir, pattern } => {
+                        file_globs_i.push((dir, pattern.clone()));
+                    }
+                    FilePattern::FileRegex { dir, regex } => {
+                        file_regexes.push((dir, regex.clone()));
+                    }
                 }
                 continue;
             }
@@ -358,6 +450,13 @@ fn build_union_matcher(expressions: &[FilesetExpression]) -> Box<dyn Matcher> {
     if !file_globs.is_empty() {
         matchers.push(Some(Box::new(FileGlobsMatcher::new(file_globs))));
     }
+    if !file_globs_i.is_empty() {
+        let matcher = FileGlobsMatcher::new_case_insensitive(file_globs_i);
+        matchers.push(Some(Box::new(matcher)));
+    }
+    if !file_regexes.is_empty() {
+        matchers.push(Some(Box::new(FileRegexesMatcher::new(file_regexes))));
+    }
     union_all_matchers(&mut matchers)
 }
 
@@ -739,6 +838,96 @@ mod tests {
         assert!(parse(r#"root-glob:"/*""#).is_err());
     }
 
+    #[test]
+    fn test_parse_glob_i_pattern() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/cur"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter);
+
+        // without meta characters, file name should be matched
+        // case-insensitively
+        insta::assert_debug_snapshot!(
+            parse(r#"glob-i:"Foo""#).unwrap(), @r#"
+        Pattern(
+            FileGlobI {
+                dir: "cur",
+                pattern: Pattern {
+                    original: "Foo",
+                    tokens: _,
+                    is_recursive: false,
+                },
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse(r#"root-glob-i:"""#).unwrap(),
+            @r#"Pattern(FilePath(""))"#);
+
+        // with meta characters
+        insta::assert_debug_snapshot!(
+            parse(r#"cwd-glob-i:"../Foo/*.RS""#).unwrap(), @r#"
+        Pattern(
+            FileGlobI {
+                dir: "Foo",
+                pattern: Pattern {
+                    original: "*.RS",
+                    tokens: _,
+                    is_recursive: false,
+                },
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse(r#"root-glob-i:"foo/**/*.rs""#).unwrap(), @r#"
+        Pattern(
+            FileGlobI {
+                dir: "foo",
+                pattern: Pattern {
+                    original: "**/*.rs",
+                    tokens: _,
+                    is_recursive: true,
+                },
+            },
+        )
+        "#);
+        assert!(parse(r#"root-glob-i:"../*""#).is_err());
+    }
+
+    #[test]
+    fn test_parse_regex_pattern() {
+        let settings = insta_settings();
+        let _guard = settings.bind_to_scope();
+        let path_converter = RepoPathUiConverter::Fs {
+            cwd: PathBuf::from("/ws/cur"),
+            base: PathBuf::from("/ws"),
+        };
+        let parse = |text| parse_maybe_bare(&mut FilesetDiagnostics::new(), text, &path_converter);
+
+        insta::assert_debug_snapshot!(
+            parse(r#"regex:'_test\.(go|rs)$'"#).unwrap(), @r#"
+        Pattern(
+            FileRegex {
+                dir: "cur",
+                regex: Regex("_test\\.(go|rs)$"),
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse(r#"root-regex:"^src/""#).unwrap(), @r#"
+        Pattern(
+            FileRegex {
+                dir: "",
+                regex: Regex("^src/"),
+            },
+        )
+        "#);
+        assert!(parse(r#"regex:"(""#).is_err());
+    }
+
     #[test]
     fn test_parse_function() {
         let settings = insta_settings();
@@ -885,6 +1074,7 @@ mod tests {
                     is_recursive: false,
                 },
             ] {},
+            case_sensitive: true,
         }
         "#);
 
@@ -909,6 +1099,7 @@ mod tests {
                     ] {},
                 },
             },
+            case_sensitive: true,
         }
         "#);
     }
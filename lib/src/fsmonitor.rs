@@ -36,12 +36,24 @@ pub struct WatchmanConfig {
     pub register_trigger: bool,
 }
 
+/// Config for the built-in inotify filesystem monitor.
+#[derive(Default, Eq, PartialEq, Clone, Debug)]
+pub struct InotifyConfig {
+    /// The command that runs the daemon. The path to the working copy is
+    /// appended to it. If it's empty, the monitor can't be started.
+    pub daemon_command: Vec<String>,
+}
+
 /// The recognized kinds of filesystem monitors.
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub enum FsmonitorSettings {
     /// The Watchman filesystem monitor (<https://facebook.github.io/watchman/>).
     Watchman(WatchmanConfig),
 
+    /// The built-in filesystem monitor using Linux's inotify API. A daemon
+    /// process is started for each working copy when it's first queried.
+    Inotify(InotifyConfig),
+
     /// Only used in tests.
     Test {
         /// The set of changed files to pretend that the filesystem monitor is
@@ -68,6 +80,12 @@ impl FsmonitorSettings {
                         .optional()?
                         .unwrap_or_default(),
                 })),
+                "inotify" => Ok(Self::Inotify(InotifyConfig {
+                    daemon_command: config
+                        .get("core.inotify.daemon-command")
+                        .optional()?
+                        .unwrap_or_default(),
+                })),
                 "test" => Err(ConfigError::Message(
                     "cannot use test fsmonitor in real repository".to_string(),
                 )),
@@ -338,3 +356,875 @@ pub mod watchman {
         }
     }
 }
+
+/// Built-in filesystem monitor using Linux's inotify API.
+///
+/// A daemon process watches all directories in the working copy and records
+/// the paths that changed. Clients query it over a Unix domain socket. The
+/// daemon is spawned by running the configured daemon command when it's first
+/// needed, and exits after being idle for a while.
+#[cfg(target_os = "linux")]
+pub mod inotify {
+    use std::collections::HashMap;
+    use std::ffi::OsStr;
+    use std::ffi::OsString;
+    use std::fs;
+    use std::io;
+    use std::io::BufRead as _;
+    use std::io::BufReader;
+    use std::io::Read as _;
+    use std::io::Write as _;
+    use std::mem::MaybeUninit;
+    use std::net::Shutdown;
+    use std::os::fd::OwnedFd;
+    use std::os::unix::ffi::OsStrExt as _;
+    use std::os::unix::fs::DirBuilderExt as _;
+    use std::os::unix::fs::MetadataExt as _;
+    use std::os::unix::net::UnixListener;
+    use std::os::unix::net::UnixStream;
+    use std::os::unix::process::CommandExt as _;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+    use std::time::SystemTime;
+
+    use blake2::Blake2b512;
+    use digest::Digest as _;
+    use rustix::event::poll;
+    use rustix::event::PollFd;
+    use rustix::event::PollFlags;
+    use rustix::fs::inotify as sys;
+    use rustix::io::Errno;
+    use rustix::net::sockopt::get_socket_peercred;
+    use rustix::process::getuid;
+    use thiserror::Error;
+    use tracing::info;
+    use tracing::instrument;
+    use tracing::warn;
+
+    use crate::gitignore::GitIgnoreFile;
+
+    const CLOCK_PREFIX: &str = "inotify";
+    /// Directories at the root of the working copy that aren't watched.
+    const EXCLUDED_DIRS: [&str; 2] = [".git", ".jj"];
+    /// The daemon exits if nothing happens for this long.
+    const IDLE_TIMEOUT: Duration = Duration::from_secs(4 * 60 * 60);
+    const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+    /// If more paths than this have changed, the daemon forgets them and
+    /// clients have to crawl the working copy once.
+    const MAX_CHANGED_PATHS: usize = 1_000_000;
+
+    /// Represents an instance in time from the perspective of the inotify
+    /// daemon.
+    ///
+    /// Clocks are only meaningful to the daemon instance that returned them. A
+    /// clock from another instance makes the daemon report a fresh instance,
+    /// which means that the caller has to crawl the working copy.
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct Clock(String);
+
+    impl From<crate::protos::working_copy::WatchmanClock> for Clock {
+        fn from(clock: crate::protos::working_copy::WatchmanClock) -> Self {
+            use crate::protos::working_copy::watchman_clock::WatchmanClock;
+            match clock.watchman_clock {
+                Some(WatchmanClock::StringClock(string_clock)) => Self(string_clock),
+                Some(WatchmanClock::UnixTimestamp(_)) | None => Self(String::new()),
+            }
+        }
+    }
+
+    impl From<Clock> for crate::protos::working_copy::WatchmanClock {
+        fn from(clock: Clock) -> Self {
+            use crate::protos::working_copy::watchman_clock;
+            use crate::protos::working_copy::WatchmanClock;
+            let Clock(clock) = clock;
+            WatchmanClock {
+                watchman_clock: Some(watchman_clock::WatchmanClock::StringClock(clock)),
+            }
+        }
+    }
+
+    #[allow(missing_docs)]
+    #[derive(Debug, Error)]
+    pub enum Error {
+        #[error("Could not canonicalize working copy root path")]
+        CanonicalizeRootError(#[source] io::Error),
+
+        #[error("Failed to watch the working copy with inotify")]
+        WatchError(#[source] io::Error),
+
+        #[error("Failed to listen on socket {}", path.display())]
+        BindError { path: PathBuf, source: io::Error },
+
+        #[error("Cannot use socket directory {}", path.display())]
+        SocketDirError { path: PathBuf, source: io::Error },
+
+        #[error("The inotify daemon is run by another user")]
+        UntrustedDaemon,
+
+        #[error("No command to start the inotify daemon is configured")]
+        NoDaemonCommand,
+
+        #[error("Failed to start the inotify daemon")]
+        SpawnError(#[source] io::Error),
+
+        #[error("The inotify daemon exited during startup")]
+        DaemonExited,
+
+        #[error("Timed out waiting for the inotify daemon to start")]
+        StartupTimeout,
+
+        #[error("Failed to communicate with the inotify daemon")]
+        DaemonIoError(#[source] io::Error),
+
+        #[error("Invalid response from the inotify daemon")]
+        InvalidResponse,
+    }
+
+    /// Returns the path of the socket the daemon for `working_copy_path`
+    /// listens on.
+    ///
+    /// The socket is placed in a directory under `$XDG_RUNTIME_DIR` (or the
+    /// temporary directory) because socket paths are limited to about 100
+    /// bytes. The directory is only accessible by the current user, so other
+    /// users can't bind the socket first.
+    pub fn socket_path(working_copy_path: &Path) -> Result<PathBuf, Error> {
+        let root = working_copy_path
+            .canonicalize()
+            .map_err(Error::CanonicalizeRootError)?;
+        let hash = Blake2b512::digest(root.as_os_str().as_bytes());
+        let base_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map_or_else(std::env::temp_dir, PathBuf::from);
+        let dir = base_dir.join(format!("jj-inotify-{}", getuid().as_raw()));
+        create_private_dir(&dir).map_err(|source| Error::SocketDirError {
+            path: dir.clone(),
+            source,
+        })?;
+        Ok(dir.join(format!("{}.sock", hex::encode(&hash[..16]))))
+    }
+
+    /// Creates `dir` if it doesn't exist, and checks that it's a directory
+    /// owned by and only accessible to the current user.
+    fn create_private_dir(dir: &Path) -> io::Result<()> {
+        match fs::DirBuilder::new().mode(0o700).create(dir) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            result => result?,
+        }
+        let metadata = dir.symlink_metadata()?;
+        if !metadata.is_dir() || metadata.uid() != getuid().as_raw() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "not a directory owned by the current user",
+            ));
+        }
+        if metadata.mode() & 0o077 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "directory is accessible by other users",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Handle to the inotify daemon of a working copy.
+    pub struct Fsmonitor {
+        socket_path: PathBuf,
+    }
+
+    impl Fsmonitor {
+        /// Connects to the daemon watching `working_copy_path`, spawning it
+        /// with the daemon command from `config` first if it isn't running. A
+        /// newly spawned daemon has to set up watches for all directories in
+        /// the working copy, which may take some time.
+        #[instrument]
+        pub fn init(
+            working_copy_path: &Path,
+            config: &super::InotifyConfig,
+        ) -> Result<Self, Error> {
+            let monitor = Self::with_socket_path(socket_path(working_copy_path)?);
+            if monitor.is_running() {
+                return Ok(monitor);
+            }
+            let [program, args @ ..] = config.daemon_command.as_slice() else {
+                return Err(Error::NoDaemonCommand);
+            };
+            info!(?config.daemon_command, "Starting inotify daemon...");
+            let mut child = process::Command::new(program)
+                .args(args)
+                .arg(working_copy_path)
+                .current_dir(working_copy_path)
+                .stdin(process::Stdio::null())
+                .stdout(process::Stdio::null())
+                .stderr(process::Stdio::null())
+                // Don't receive signals (such as Ctrl-C) sent to the command.
+                .process_group(0)
+                .spawn()
+                .map_err(Error::SpawnError)?;
+            let start = Instant::now();
+            loop {
+                if monitor.is_running() {
+                    return Ok(monitor);
+                }
+                if child.try_wait().map_err(Error::SpawnError)?.is_some() {
+                    return Err(Error::DaemonExited);
+                }
+                if start.elapsed() > STARTUP_TIMEOUT {
+                    return Err(Error::StartupTimeout);
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        /// Creates a handle to a daemon listening on `socket_path` without
+        /// checking that it's running.
+        pub fn with_socket_path(socket_path: PathBuf) -> Self {
+            Fsmonitor { socket_path }
+        }
+
+        /// Returns whether the daemon is accepting connections.
+        pub fn is_running(&self) -> bool {
+            UnixStream::connect(&self.socket_path).is_ok()
+        }
+
+        /// Query for changed files since the previous point in time.
+        ///
+        /// The returned list of paths is relative to the working copy root.
+        /// A path to a directory means that anything in it may have changed.
+        /// If it is `None`, then the caller must crawl the entire working copy
+        /// themselves.
+        #[instrument(skip(self))]
+        pub fn query_changed_files(
+            &self,
+            previous_clock: Option<Clock>,
+        ) -> Result<(Clock, Option<Vec<PathBuf>>), Error> {
+            info!("Querying inotify daemon for changed files...");
+            let request = match previous_clock {
+                Some(Clock(clock)) => format!("query {clock}\n"),
+                None => "query\n".to_owned(),
+            };
+            let response = self.request(&request)?;
+            let response = response.strip_suffix(b"\0").unwrap_or(&response);
+            let mut fields = response.split(|&b| b == b'\0');
+            let clock = fields
+                .next()
+                .and_then(|field| std::str::from_utf8(field).ok())
+                .ok_or(Error::InvalidResponse)?;
+            let clock = Clock(clock.to_owned());
+            match fields.next() {
+                Some(b"fresh") => Ok((clock, None)),
+                Some(b"changed") => {
+                    let paths = fields
+                        .map(|field| PathBuf::from(OsStr::from_bytes(field)))
+                        .collect();
+                    Ok((clock, Some(paths)))
+                }
+                _ => Err(Error::InvalidResponse),
+            }
+        }
+
+        /// Asks the daemon to exit.
+        #[instrument(skip(self))]
+        pub fn stop(&self) -> Result<(), Error> {
+            self.request("stop\n")?;
+            Ok(())
+        }
+
+        fn request(&self, request: &str) -> Result<Vec<u8>, Error> {
+            let mut stream =
+                UnixStream::connect(&self.socket_path).map_err(Error::DaemonIoError)?;
+            // Don't trust a daemon of another user to report the changes.
+            let peer =
+                get_socket_peercred(&stream).map_err(|err| Error::DaemonIoError(err.into()))?;
+            if peer.uid != getuid() {
+                return Err(Error::UntrustedDaemon);
+            }
+            stream
+                .set_read_timeout(Some(REQUEST_TIMEOUT))
+                .map_err(Error::DaemonIoError)?;
+            stream
+                .write_all(request.as_bytes())
+                .map_err(Error::DaemonIoError)?;
+            stream
+                .shutdown(Shutdown::Write)
+                .map_err(Error::DaemonIoError)?;
+            let mut response = vec![];
+            stream
+                .read_to_end(&mut response)
+                .map_err(Error::DaemonIoError)?;
+            Ok(response)
+        }
+    }
+
+    /// The daemon process watching a working copy.
+    pub struct Daemon {
+        state: WatchState,
+        listener: UnixListener,
+        socket_path: PathBuf,
+    }
+
+    impl Daemon {
+        /// Sets up watches for all directories in `working_copy_path` except
+        /// those matched by `base_ignores`, then starts listening on
+        /// `socket_path`.
+        ///
+        /// Clients have to check tracked files in the ignored directories
+        /// themselves.
+        #[instrument(skip(base_ignores))]
+        pub fn new(
+            working_copy_path: &Path,
+            socket_path: PathBuf,
+            base_ignores: Arc<GitIgnoreFile>,
+        ) -> Result<Self, Error> {
+            let root = working_copy_path
+                .canonicalize()
+                .map_err(Error::CanonicalizeRootError)?;
+            let state = WatchState::new(root, base_ignores).map_err(Error::WatchError)?;
+            let listener = bind_socket(&socket_path).map_err(|source| Error::BindError {
+                path: socket_path.clone(),
+                source,
+            })?;
+            info!(num_dirs = state.dirs.len(), "Started inotify daemon");
+            Ok(Daemon {
+                state,
+                listener,
+                socket_path,
+            })
+        }
+
+        /// Serves requests until asked to stop, the working copy is removed,
+        /// or the daemon has been idle for a while.
+        pub fn run(mut self) -> Result<(), Error> {
+            loop {
+                let mut fds = [
+                    PollFd::new(&self.state.inotify, PollFlags::IN),
+                    PollFd::new(&self.listener, PollFlags::IN),
+                ];
+                match poll(&mut fds, IDLE_TIMEOUT.as_millis() as i32) {
+                    Ok(0) => {
+                        info!("Exiting idle inotify daemon");
+                        return Ok(());
+                    }
+                    Ok(_) => {}
+                    Err(Errno::INTR) => continue,
+                    Err(err) => return Err(Error::WatchError(err.into())),
+                }
+                let client_ready = fds[1].revents().contains(PollFlags::IN);
+                // Also read events before responding to a client so that
+                // changes made before the request are reported.
+                self.state.read_events().map_err(Error::WatchError)?;
+                if self.state.root_removed {
+                    info!("Working copy was removed; exiting inotify daemon");
+                    return Ok(());
+                }
+                if client_ready {
+                    match self.listener.accept() {
+                        Ok((stream, _)) => {
+                            if self.serve(stream) == Served::Stop {
+                                return Ok(());
+                            }
+                        }
+                        Err(err) => warn!(?err, "Failed to accept connection"),
+                    }
+                }
+            }
+        }
+
+        fn serve(&mut self, stream: UnixStream) -> Served {
+            stream.set_read_timeout(Some(REQUEST_TIMEOUT)).ok();
+            let mut request = String::new();
+            if BufReader::new(&stream).read_line(&mut request).is_err() {
+                return Served::Continue;
+            }
+            let (response, served) = match request.trim_end().split_once(' ') {
+                Some(("query", clock)) => (self.state.query(Some(clock)), Served::Continue),
+                None if request.trim_end() == "query" => (self.state.query(None), Served::Continue),
+                None if request.trim_end() == "stop" => (vec![], Served::Stop),
+                // Probably a client checking if the daemon is running.
+                _ => return Served::Continue,
+            };
+            if let Err(err) = (&stream).write_all(&response) {
+                warn!(?err, "Failed to respond to client");
+            }
+            served
+        }
+    }
+
+    /// Watches a working copy for changes in this process, without a daemon.
+    pub struct Watcher {
+        state: WatchState,
+    }
+
+    impl Watcher {
+        /// Sets up watches for all directories in `working_copy_path` except
+        /// those matched by `ignores`.
+        #[instrument(skip(ignores))]
+        pub fn new(working_copy_path: &Path, ignores: Arc<GitIgnoreFile>) -> Result<Self, Error> {
+            let root = working_copy_path
+                .canonicalize()
+                .map_err(Error::CanonicalizeRootError)?;
+            let state = WatchState::new(root, ignores).map_err(Error::WatchError)?;
+            Ok(Watcher { state })
+        }
+
+        /// Waits until something in the working copy changes, or `timeout`
+        /// elapses. Returns whether anything changed since the previous call.
+        pub fn wait(&mut self, timeout: Option<Duration>) -> Result<bool, Error> {
+            if self.state.changed.is_empty() && !self.state.lost_events {
+                let timeout = timeout.map_or(-1, |timeout| {
+                    i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX)
+                });
+                let mut fds = [PollFd::new(&self.state.inotify, PollFlags::IN)];
+                match poll(&mut fds, timeout) {
+                    Ok(_) | Err(Errno::INTR) => {}
+                    Err(err) => return Err(Error::WatchError(err.into())),
+                }
+                self.state.read_events().map_err(Error::WatchError)?;
+            }
+            let changed = !self.state.changed.is_empty() || self.state.lost_events;
+            self.state.changed.clear();
+            self.state.lost_events = false;
+            Ok(changed)
+        }
+
+        /// Returns whether the working copy root was removed or moved away.
+        pub fn is_root_removed(&self) -> bool {
+            self.state.root_removed
+        }
+    }
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            fs::remove_file(&self.socket_path).ok();
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    enum Served {
+        Continue,
+        Stop,
+    }
+
+    fn bind_socket(socket_path: &Path) -> io::Result<UnixListener> {
+        match UnixListener::bind(socket_path) {
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(socket_path).is_ok() {
+                    // Another daemon is already running.
+                    return Err(err);
+                }
+                // Left behind by a daemon that didn't exit cleanly.
+                fs::remove_file(socket_path)?;
+                UnixListener::bind(socket_path)
+            }
+            result => result,
+        }
+    }
+
+    /// The inotify watches and the paths that changed.
+    ///
+    /// Changed paths are recorded with a sequence number, which is incremented
+    /// each time a clock is handed out. The clock encodes the sequence number,
+    /// so the paths changed since a clock are those recorded with a larger
+    /// number.
+    struct WatchState {
+        root: PathBuf,
+        /// Directories matching these aren't watched.
+        base_ignores: Arc<GitIgnoreFile>,
+        inotify: OwnedFd,
+        /// Watched directories by watch descriptor, relative to the root.
+        dirs: HashMap<i32, PathBuf>,
+        instance: String,
+        seq: u64,
+        /// Clocks older than this can't be answered because events were lost.
+        valid_since: u64,
+        changed: HashMap<PathBuf, u64>,
+        /// Whether changes were forgotten since it was last reset.
+        lost_events: bool,
+        root_removed: bool,
+    }
+
+    impl WatchState {
+        fn new(root: PathBuf, base_ignores: Arc<GitIgnoreFile>) -> io::Result<Self> {
+            let inotify = sys::init(sys::CreateFlags::CLOEXEC | sys::CreateFlags::NONBLOCK)?;
+            let start_time = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            let mut state = WatchState {
+                root,
+                base_ignores,
+                inotify,
+                dirs: HashMap::new(),
+                instance: format!("{}-{}", process::id(), start_time.as_nanos()),
+                seq: 0,
+                valid_since: 0,
+                changed: HashMap::new(),
+                lost_events: false,
+                root_removed: false,
+            };
+            state.add_watches(Path::new(""))?;
+            Ok(state)
+        }
+
+        fn watch_flags() -> sys::WatchFlags {
+            sys::WatchFlags::ATTRIB
+                | sys::WatchFlags::CLOSE_WRITE
+                | sys::WatchFlags::CREATE
+                | sys::WatchFlags::DELETE
+                | sys::WatchFlags::DELETE_SELF
+                | sys::WatchFlags::MODIFY
+                | sys::WatchFlags::MOVE_SELF
+                | sys::WatchFlags::MOVED_FROM
+                | sys::WatchFlags::MOVED_TO
+                | sys::WatchFlags::DONT_FOLLOW
+                | sys::WatchFlags::EXCL_UNLINK
+                | sys::WatchFlags::ONLYDIR
+        }
+
+        fn is_excluded(path: &Path) -> bool {
+            EXCLUDED_DIRS.iter().any(|name| path == Path::new(name))
+        }
+
+        fn is_ignored_dir(&self, dir: &Path) -> bool {
+            // Paths that aren't valid UTF-8 can't be tracked anyway.
+            match dir.to_str() {
+                Some("") | None => false,
+                Some(dir) => self.base_ignores.matches(&format!("{dir}/")),
+            }
+        }
+
+        /// Watches `dir` and all directories in it, except ignored ones.
+        fn add_watches(&mut self, dir: &Path) -> io::Result<()> {
+            let mut pending = vec![dir.to_owned()];
+            while let Some(dir) = pending.pop() {
+                if self.is_ignored_dir(&dir) {
+                    continue;
+                }
+                let disk_dir = self.root.join(&dir);
+                let wd = match sys::add_watch(&self.inotify, &disk_dir, Self::watch_flags()) {
+                    Ok(wd) => wd,
+                    // Removed or replaced since it was listed. Its parent
+                    // reports that change.
+                    Err(Errno::NOENT | Errno::NOTDIR) => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                let entries = match fs::read_dir(&disk_dir) {
+                    Ok(entries) => entries,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(err) => return Err(err),
+                };
+                self.dirs.insert(wd, dir.clone());
+                for entry in entries {
+                    let entry = entry?;
+                    if entry.file_type()?.is_dir() {
+                        let path = dir.join(entry.file_name());
+                        if !Self::is_excluded(&path) {
+                            pending.push(path);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Stops watching `dir` and all directories in it.
+        fn remove_watches(&mut self, dir: &Path) {
+            let wds = self
+                .dirs
+                .iter()
+                .filter(|(_, path)| path.starts_with(dir))
+                .map(|(&wd, _)| wd)
+                .collect::<Vec<_>>();
+            for wd in wds {
+                self.dirs.remove(&wd);
+                // Fails if the directory was already deleted.
+                sys::remove_watch(&self.inotify, wd).ok();
+            }
+        }
+
+        /// Reads and applies all queued events without blocking.
+        fn read_events(&mut self) -> io::Result<()> {
+            let mut buf = [MaybeUninit::uninit(); 4096];
+            let mut events = vec![];
+            let mut reader = sys::Reader::new(&self.inotify, &mut buf);
+            loop {
+                match reader.next() {
+                    Ok(event) => events.push((
+                        event.wd(),
+                        event.events(),
+                        event
+                            .file_name()
+                            .map(|name| OsStr::from_bytes(name.to_bytes()).to_owned()),
+                    )),
+                    Err(Errno::WOULDBLOCK) => break,
+                    Err(Errno::INTR) => {}
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            for (wd, flags, name) in events {
+                self.handle_event(wd, flags, name)?;
+            }
+            Ok(())
+        }
+
+        fn handle_event(
+            &mut self,
+            wd: i32,
+            flags: sys::ReadFlags,
+            name: Option<OsString>,
+        ) -> io::Result<()> {
+            if flags.contains(sys::ReadFlags::QUEUE_OVERFLOW) {
+                warn!("inotify event queue overflowed");
+                self.forget_changes();
+                return Ok(());
+            }
+            let Some(dir) = self.dirs.get(&wd) else {
+                return Ok(());
+            };
+            let is_root = dir.as_os_str().is_empty();
+            if flags.contains(sys::ReadFlags::IGNORED) {
+                self.dirs.remove(&wd);
+                self.root_removed |= is_root;
+                return Ok(());
+            }
+            if flags.intersects(sys::ReadFlags::DELETE_SELF | sys::ReadFlags::MOVE_SELF) {
+                // The event in the parent directory records the change.
+                self.root_removed |= is_root;
+                return Ok(());
+            }
+            let Some(name) = name else {
+                return Ok(());
+            };
+            let path = dir.join(name);
+            if Self::is_excluded(&path) {
+                return Ok(());
+            }
+            if flags.contains(sys::ReadFlags::ISDIR) {
+                if flags.contains(sys::ReadFlags::MOVED_FROM) {
+                    self.remove_watches(&path);
+                }
+                if flags.intersects(sys::ReadFlags::CREATE | sys::ReadFlags::MOVED_TO) {
+                    // Anything created in the directory before it's watched is
+                    // covered by recording the directory itself.
+                    self.add_watches(&path)?;
+                }
+            }
+            self.changed.insert(path, self.seq);
+            if self.changed.len() > MAX_CHANGED_PATHS {
+                self.forget_changes();
+            }
+            Ok(())
+        }
+
+        fn forget_changes(&mut self) {
+            self.changed.clear();
+            self.valid_since = self.seq;
+            self.lost_events = true;
+        }
+
+        fn parse_clock(&self, clock: &str) -> Option<u64> {
+            let (instance, seq) = clock
+                .strip_prefix(CLOCK_PREFIX)?
+                .strip_prefix(':')?
+                .rsplit_once(':')?;
+            let seq = seq.parse().ok()?;
+            (instance == self.instance && seq >= self.valid_since && seq < self.seq).then_some(seq)
+        }
+
+        /// Returns a new clock and the paths changed since `clock`, in the
+        /// format read by [`Fsmonitor::query_changed_files()`].
+        fn query(&mut self, clock: Option<&str>) -> Vec<u8> {
+            let since = clock.and_then(|clock| self.parse_clock(clock));
+            let mut response =
+                format!("{CLOCK_PREFIX}:{}:{}\0", self.instance, self.seq).into_bytes();
+            match since {
+                None => response.extend_from_slice(b"fresh\0"),
+                Some(since) => {
+                    response.extend_from_slice(b"changed\0");
+                    for (path, _) in self.changed.iter().filter(|(_, &seq)| seq > since) {
+                        response.extend_from_slice(path.as_os_str().as_bytes());
+                        response.push(b'\0');
+                    }
+                }
+            }
+            self.seq += 1;
+            response
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use itertools::Itertools as _;
+
+        use super::*;
+
+        fn query_sorted(monitor: &Fsmonitor, clock: &Clock) -> (Clock, Option<Vec<PathBuf>>) {
+            let (clock, paths) = monitor.query_changed_files(Some(clock.clone())).unwrap();
+            (
+                clock,
+                paths.map(|paths| paths.into_iter().sorted().collect()),
+            )
+        }
+
+        fn paths(paths: &[&str]) -> Option<Vec<PathBuf>> {
+            Some(paths.iter().map(PathBuf::from).collect())
+        }
+
+        #[test]
+        fn test_create_private_dir() {
+            use std::os::unix::fs::PermissionsExt as _;
+
+            let temp_dir = testutils::new_temp_dir();
+            let dir = temp_dir.path().join("sockets");
+            create_private_dir(&dir).unwrap();
+            let mode = dir.metadata().unwrap().mode();
+            assert_eq!(mode & 0o777, 0o700);
+            // An existing private directory is reused
+            create_private_dir(&dir).unwrap();
+
+            // A directory that other users can write to is rejected
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+            assert!(create_private_dir(&dir).is_err());
+
+            // So is a symlink, even to a private directory
+            let link = temp_dir.path().join("link");
+            let target = temp_dir.path().join("target");
+            create_private_dir(&target).unwrap();
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+            assert!(create_private_dir(&link).is_err());
+        }
+
+        #[test]
+        fn test_query_changed_files() {
+            let temp_dir = testutils::new_temp_dir();
+            let root = temp_dir.path().join("repo");
+            fs::create_dir_all(root.join(".jj")).unwrap();
+            fs::create_dir_all(root.join("dir")).unwrap();
+            let socket_path = temp_dir.path().join("inotify.sock");
+            let daemon = Daemon::new(&root, socket_path.clone(), GitIgnoreFile::empty()).unwrap();
+            let handle = thread::spawn(|| daemon.run());
+            let monitor = Fsmonitor::with_socket_path(socket_path.clone());
+            assert!(monitor.is_running());
+
+            // The first query can't know what changed
+            let (clock0, changed) = monitor.query_changed_files(None).unwrap();
+            assert_eq!(changed, None);
+            let (clock1, changed) = query_sorted(&monitor, &clock0);
+            assert_eq!(changed, paths(&[]));
+
+            fs::write(root.join("file"), "").unwrap();
+            fs::write(root.join("dir/file"), "").unwrap();
+            fs::write(root.join(".jj/file"), "").unwrap();
+            fs::create_dir(root.join("new")).unwrap();
+            let (clock2, changed) = query_sorted(&monitor, &clock1);
+            assert_eq!(changed, paths(&["dir/file", "file", "new"]));
+
+            // New directories are watched
+            fs::write(root.join("new/file"), "").unwrap();
+            let (clock3, changed) = query_sorted(&monitor, &clock2);
+            assert_eq!(changed, paths(&["new/file"]));
+
+            // Renamed directories are watched at their new path
+            fs::rename(root.join("new"), root.join("renamed")).unwrap();
+            let (clock4, changed) = query_sorted(&monitor, &clock3);
+            assert_eq!(changed, paths(&["new", "renamed"]));
+            fs::write(root.join("renamed/file"), "").unwrap();
+            fs::remove_file(root.join("dir/file")).unwrap();
+            let (_clock5, changed) = query_sorted(&monitor, &clock4);
+            assert_eq!(changed, paths(&["dir/file", "renamed/file"]));
+
+            // Older clocks include all later changes
+            let (_, changed) = query_sorted(&monitor, &clock2);
+            assert_eq!(
+                changed,
+                paths(&["dir/file", "new", "new/file", "renamed", "renamed/file"])
+            );
+
+            // Clocks from another daemon require a crawl
+            let (_, changed) = query_sorted(&monitor, &Clock("inotify:1-2:0".to_owned()));
+            assert_eq!(changed, None);
+            let (_, changed) = query_sorted(&monitor, &Clock("c:123:45".to_owned()));
+            assert_eq!(changed, None);
+
+            monitor.stop().unwrap();
+            handle.join().unwrap().unwrap();
+            assert!(!socket_path.exists());
+            assert!(!monitor.is_running());
+        }
+
+        #[test]
+        fn test_query_changed_files_with_base_ignores() {
+            let temp_dir = testutils::new_temp_dir();
+            let root = temp_dir.path().join("repo");
+            fs::create_dir_all(root.join("dir/ignored")).unwrap();
+            fs::create_dir_all(root.join("ignored")).unwrap();
+            let socket_path = temp_dir.path().join("inotify.sock");
+            let base_ignores = GitIgnoreFile::empty()
+                .chain("", b"ignored/\nnew-ignored/\n")
+                .unwrap();
+            let daemon = Daemon::new(&root, socket_path.clone(), base_ignores).unwrap();
+            let handle = thread::spawn(|| daemon.run());
+            let monitor = Fsmonitor::with_socket_path(socket_path.clone());
+            let (clock0, _) = monitor.query_changed_files(None).unwrap();
+
+            // Changes in ignored directories aren't reported
+            fs::write(root.join("ignored/file"), "").unwrap();
+            fs::write(root.join("dir/ignored/file"), "").unwrap();
+            fs::write(root.join("dir/file"), "").unwrap();
+            let (clock1, changed) = query_sorted(&monitor, &clock0);
+            assert_eq!(changed, paths(&["dir/file"]));
+
+            // New ignored directories aren't watched
+            fs::create_dir(root.join("new-ignored")).unwrap();
+            let (clock2, changed) = query_sorted(&monitor, &clock1);
+            assert_eq!(changed, paths(&["new-ignored"]));
+            fs::write(root.join("new-ignored/file"), "").unwrap();
+            fs::write(root.join("file"), "").unwrap();
+            let (_clock3, changed) = query_sorted(&monitor, &clock2);
+            assert_eq!(changed, paths(&["file"]));
+
+            monitor.stop().unwrap();
+            handle.join().unwrap().unwrap();
+        }
+
+        #[test]
+        fn test_watcher() {
+            let temp_dir = testutils::new_temp_dir();
+            let root = temp_dir.path().join("repo");
+            fs::create_dir_all(root.join(".jj")).unwrap();
+            let mut watcher = Watcher::new(&root, GitIgnoreFile::empty()).unwrap();
+            assert!(!watcher.wait(Some(Duration::ZERO)).unwrap());
+
+            fs::write(root.join(".jj/file"), "").unwrap();
+            assert!(!watcher.wait(Some(Duration::ZERO)).unwrap());
+            fs::write(root.join("file"), "").unwrap();
+            assert!(watcher.wait(None).unwrap());
+            assert!(!watcher.wait(Some(Duration::ZERO)).unwrap());
+
+            assert!(!watcher.is_root_removed());
+            fs::remove_dir_all(&root).unwrap();
+            watcher.wait(None).unwrap();
+            while !watcher.is_root_removed() {
+                watcher.wait(Some(Duration::from_millis(10))).unwrap();
+            }
+        }
+
+        #[test]
+        fn test_daemon_exits_when_root_removed() {
+            let temp_dir = testutils::new_temp_dir();
+            let root = temp_dir.path().join("repo");
+            fs::create_dir_all(root.join("dir")).unwrap();
+            let socket_path = temp_dir.path().join("inotify.sock");
+            let daemon = Daemon::new(&root, socket_path.clone(), GitIgnoreFile::empty()).unwrap();
+            let handle = thread::spawn(|| daemon.run());
+
+            fs::remove_dir_all(&root).unwrap();
+            handle.join().unwrap().unwrap();
+            assert!(!socket_path.exists());
+        }
+    }
+}
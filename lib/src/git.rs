@@ -20,8 +20,11 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::default::Default;
 use std::fmt;
+use std::fs;
+use std::io;
 use std::io::Read;
 use std::num::NonZeroU32;
+use std::path::Path;
 use std::path::PathBuf;
 use std::str;
 
@@ -33,6 +36,8 @@ use thiserror::Error;
 use crate::backend::BackendError;
 use crate::backend::CommitId;
 use crate::commit::Commit;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
 use crate::git_backend::GitBackend;
 use crate::index::Index;
 use crate::object_id::ObjectId;
@@ -675,36 +680,18 @@ pub fn export_some_refs(
         &git_ref_filter,
     );
 
-    // TODO: Also check other worktrees' HEAD.
-    if let Ok(head_ref) = git_repo.find_reference("HEAD") {
-        if let Some(parsed_ref) = head_ref
-            .target()
-            .try_name()
-            .and_then(|name| str::from_utf8(name.as_bstr()).ok())
-            .and_then(parse_git_ref)
-        {
-            let old_target = head_ref.inner.target.clone();
-            let current_oid = match head_ref.into_fully_peeled_id() {
-                Ok(id) => Some(id.detach()),
-                Err(gix::reference::peel::Error::ToId(
-                    gix::refs::peel::to_id::Error::FollowToObject(
-                        gix::refs::peel::to_object::Error::Follow(
-                            gix::refs::file::find::existing::Error::NotFound { .. },
-                        ),
-                    ),
-                )) => None, // Unborn ref should be considered absent
-                Err(err) => return Err(GitExportError::from_git(err)),
-            };
-            let new_oid = if let Some((_old_oid, new_oid)) = branches_to_update.get(&parsed_ref) {
-                Some(new_oid)
-            } else if branches_to_delete.contains_key(&parsed_ref) {
-                None
-            } else {
-                current_oid.as_ref()
-            };
-            if new_oid != current_oid.as_ref() {
-                update_git_head(&git_repo, old_target, current_oid)?;
-            }
+    // Branches checked out in the main worktree or in linked worktrees (such
+    // as jj workspaces registered as worktrees) shouldn't be moved under them,
+    // so detach the HEADs before updating the branches.
+    detach_head_of_changed_branch(&git_repo, &branches_to_update, &branches_to_delete)?;
+    for worktree in git_repo.worktrees().map_err(GitExportError::from_git)? {
+        // Worktrees whose directory was deleted can't be updated anyway.
+        if let Ok(worktree_repo) = worktree.into_repo_with_possibly_inaccessible_worktree() {
+            detach_head_of_changed_branch(
+                &worktree_repo,
+                &branches_to_update,
+                &branches_to_delete,
+            )?;
         }
     }
     for (parsed_ref_name, old_oid) in branches_to_delete {
@@ -746,6 +733,47 @@ pub fn export_some_refs(
     Ok(failed_branches)
 }
 
+/// Detaches the HEAD of `git_repo` if it points to a branch that is about to be
+/// updated or deleted.
+fn detach_head_of_changed_branch(
+    git_repo: &gix::Repository,
+    branches_to_update: &BTreeMap<RefName, (Option<gix::ObjectId>, gix::ObjectId)>,
+    branches_to_delete: &BTreeMap<RefName, gix::ObjectId>,
+) -> Result<(), GitExportError> {
+    if let Ok(head_ref) = git_repo.find_reference("HEAD") {
+        if let Some(parsed_ref) = head_ref
+            .target()
+            .try_name()
+            .and_then(|name| str::from_utf8(name.as_bstr()).ok())
+            .and_then(parse_git_ref)
+        {
+            let old_target = head_ref.inner.target.clone();
+            let current_oid = match head_ref.into_fully_peeled_id() {
+                Ok(id) => Some(id.detach()),
+                Err(gix::reference::peel::Error::ToId(
+                    gix::refs::peel::to_id::Error::FollowToObject(
+                        gix::refs::peel::to_object::Error::Follow(
+                            gix::refs::file::find::existing::Error::NotFound { .. },
+                        ),
+                    ),
+                )) => None, // Unborn ref should be considered absent
+                Err(err) => return Err(GitExportError::from_git(err)),
+            };
+            let new_oid = if let Some((_old_oid, new_oid)) = branches_to_update.get(&parsed_ref) {
+                Some(new_oid)
+            } else if branches_to_delete.contains_key(&parsed_ref) {
+                None
+            } else {
+                current_oid.as_ref()
+            };
+            if new_oid != current_oid.as_ref() {
+                update_git_head(git_repo, old_target, current_oid)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn copy_exportable_local_branches_to_remote_view(
     mut_repo: &mut MutableRepo,
     remote_name: &str,
@@ -988,21 +1016,59 @@ pub fn reset_head(
     git_repo: &git2::Repository,
     wc_commit: &Commit,
 ) -> Result<(), git2::Error> {
+    let first_parent = git_head_target_of(wc_commit);
+    let git_head = mut_repo.view().git_head().clone();
+    reset_head_and_index(git_repo, &git_head, &first_parent)?;
+    mut_repo.set_git_head_target(first_parent);
+    Ok(())
+}
+
+/// Sets the HEAD of a linked Git worktree to the parent of the given
+/// working-copy commit and resets the worktree's index.
+///
+/// `git_repo` should be opened at the worktree. Unlike [`reset_head()`], the
+/// new HEAD isn't recorded in the view, which only tracks the HEAD of the main
+/// worktree.
+pub fn reset_worktree_head(
+    git_repo: &git2::Repository,
+    wc_commit: &Commit,
+) -> Result<(), git2::Error> {
+    let first_parent = git_head_target_of(wc_commit);
+    let git_head = match git_repo.head() {
+        Ok(head_ref) => head_ref
+            .target()
+            .map(|oid| RefTarget::normal(CommitId::from_bytes(oid.as_bytes())))
+            .unwrap_or_else(RefTarget::absent),
+        Err(err) if err.code() == git2::ErrorCode::UnbornBranch => RefTarget::absent(),
+        Err(err) => return Err(err),
+    };
+    reset_head_and_index(git_repo, &git_head, &first_parent)
+}
+
+/// Returns the target Git HEAD should point to when the given working-copy
+/// commit is checked out.
+fn git_head_target_of(wc_commit: &Commit) -> RefTarget {
     let first_parent_id = &wc_commit.parent_ids()[0];
-    let first_parent = if first_parent_id != mut_repo.store().root_commit_id() {
+    if first_parent_id != wc_commit.store().root_commit_id() {
         RefTarget::normal(first_parent_id.clone())
     } else {
         RefTarget::absent()
-    };
-    if first_parent.is_present() {
-        let git_head = mut_repo.view().git_head();
+    }
+}
+
+fn reset_head_and_index(
+    git_repo: &git2::Repository,
+    git_head: &RefTarget,
+    first_parent: &RefTarget,
+) -> Result<(), git2::Error> {
+    if let Some(first_parent_id) = first_parent.as_normal() {
         let new_git_commit_id = Oid::from_bytes(first_parent_id.as_bytes()).unwrap();
         let new_git_commit = git_repo.find_commit(new_git_commit_id)?;
-        if git_head != &first_parent {
+        if git_head != first_parent {
             git_repo.set_head_detached(new_git_commit_id)?;
         }
 
-        let is_same_tree = if git_head == &first_parent {
+        let is_same_tree = if git_head == first_parent {
             true
         } else if let Some(git_head_id) = git_head.as_normal() {
             let git_head_oid = Oid::from_bytes(git_head_id.as_bytes()).unwrap();
@@ -1035,7 +1101,7 @@ pub fn reset_head(
         // We can't set_head() an arbitrary unborn ref, so use reference_symbolic()
         // instead. Git CLI appears to deal with that. It would be nice if Git CLI
         // couldn't create a commit without setting a valid branch name.
-        if mut_repo.git_head().is_present() {
+        if git_head.is_present() {
             match git_repo.find_reference(UNBORN_ROOT_REF_NAME) {
                 Ok(mut git_repo_ref) => git_repo_ref.delete()?,
                 Err(err) if err.code() == git2::ErrorCode::NotFound => {}
@@ -1049,10 +1115,193 @@ pub fn reset_head(
         index.write()?;
         git_repo.cleanup_state()?;
     }
-    mut_repo.set_git_head_target(first_parent);
     Ok(())
 }
 
+#[derive(Debug, Error)]
+pub enum GitWorktreeError {
+    #[error("Git worktree named '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("'{0}' is not a valid Git worktree name")]
+    InvalidName(String),
+    #[error("Worktree path could not be interpreted as Unicode text")]
+    NonUnicodePath,
+    #[error(transparent)]
+    Path(#[from] PathError),
+}
+
+/// Registers `workspace_root` as a linked worktree of `git_repo` named `name`.
+///
+/// This creates the same files as `git worktree add`, but doesn't check out
+/// anything. The worktree HEAD stays unborn until it's set by
+/// [`reset_worktree_head()`].
+pub fn add_worktree(
+    git_repo: &gix::Repository,
+    name: &str,
+    workspace_root: &Path,
+) -> Result<(), GitWorktreeError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(GitWorktreeError::InvalidName(name.to_owned()));
+    }
+    let admin_dir = git_repo.common_dir().join("worktrees").join(name);
+    if admin_dir.exists() {
+        return Err(GitWorktreeError::AlreadyExists(name.to_owned()));
+    }
+    let dot_git_path = workspace_root.join(".git");
+    let dot_git_path_str = dot_git_path
+        .to_str()
+        .ok_or(GitWorktreeError::NonUnicodePath)?;
+    fs::create_dir_all(&admin_dir).context(&admin_dir)?;
+    let admin_dir_str = admin_dir.to_str().ok_or(GitWorktreeError::NonUnicodePath)?;
+    let files = [
+        (
+            admin_dir.join("HEAD"),
+            format!("ref: {UNBORN_ROOT_REF_NAME}\n"),
+        ),
+        (admin_dir.join("commondir"), "../..\n".to_owned()),
+        (admin_dir.join("gitdir"), format!("{dot_git_path_str}\n")),
+        (dot_git_path.clone(), format!("gitdir: {admin_dir_str}\n")),
+    ];
+    for (path, contents) in files {
+        fs::write(&path, contents).context(&path)?;
+    }
+    Ok(())
+}
+
+/// Updates the Git repo's records of worktrees after the worktree at
+/// `workspace_root` was moved there.
+///
+/// If `workspace_root` is a linked worktree, the main repo is updated to point
+/// at its new location. If it's the main worktree, whose `.git` directory
+/// moved along with it, the `.git` files of its linked worktrees are updated
+/// to point at their new administrative directories.
+pub fn update_worktree_path(workspace_root: &Path) -> Result<(), GitWorktreeError> {
+    let dot_git_path = workspace_root.join(".git");
+    if dot_git_path.is_dir() {
+        return update_linked_worktree_dot_git_files(&dot_git_path);
+    }
+    if !dot_git_path.is_file() {
+        return Ok(());
+    }
+    let Some(admin_dir) = read_dot_git_file(&dot_git_path)? else {
+        return Ok(());
+    };
+    // A .git file may also point to a separate Git dir, which has no gitdir file.
+    let gitdir_path = admin_dir.join("gitdir");
+    if !gitdir_path.is_file() || !admin_dir.join("commondir").is_file() {
+        return Ok(());
+    }
+    let dot_git_path_str = dot_git_path
+        .to_str()
+        .ok_or(GitWorktreeError::NonUnicodePath)?;
+    fs::write(&gitdir_path, format!("{dot_git_path_str}\n")).context(&gitdir_path)?;
+    Ok(())
+}
+
+/// Points the `.git` files of the linked worktrees of the repo at `git_dir` to
+/// their administrative directories under `git_dir`.
+fn update_linked_worktree_dot_git_files(git_dir: &Path) -> Result<(), GitWorktreeError> {
+    for admin_dir in list_worktree_admin_dirs(git_dir)? {
+        let Some(dot_git_path) = read_worktree_gitdir_file(&admin_dir)? else {
+            continue;
+        };
+        if !dot_git_path.is_file() {
+            continue; // The worktree was deleted, or is on unmounted storage.
+        }
+        let Some(old_admin_dir) = read_dot_git_file(&dot_git_path)? else {
+            continue;
+        };
+        if old_admin_dir.file_name() != admin_dir.file_name()
+            || same_file::is_same_file(&old_admin_dir, &admin_dir).unwrap_or(false)
+        {
+            continue;
+        }
+        let admin_dir_str = admin_dir.to_str().ok_or(GitWorktreeError::NonUnicodePath)?;
+        fs::write(&dot_git_path, format!("gitdir: {admin_dir_str}\n")).context(&dot_git_path)?;
+    }
+    Ok(())
+}
+
+/// Unregisters the linked worktree at `workspace_root` from `git_repo` by
+/// removing its administrative directory, like `git worktree prune` does once
+/// the worktree is deleted. The worktree's own files are left alone. Returns
+/// false if `workspace_root` isn't registered as a worktree of `git_repo`.
+pub fn remove_worktree(
+    git_repo: &gix::Repository,
+    workspace_root: &Path,
+) -> Result<bool, GitWorktreeError> {
+    let dot_git_path = workspace_root.join(".git");
+    for admin_dir in list_worktree_admin_dirs(git_repo.common_dir())? {
+        let Some(worktree_dot_git_path) = read_worktree_gitdir_file(&admin_dir)? else {
+            continue;
+        };
+        // The worktree may have been deleted already, so compare the paths
+        // lexically if they can't be resolved.
+        let is_same = worktree_dot_git_path == dot_git_path
+            || same_file::is_same_file(&worktree_dot_git_path, &dot_git_path).unwrap_or(false);
+        if is_same {
+            fs::remove_dir_all(&admin_dir).context(&admin_dir)?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn list_worktree_admin_dirs(git_dir: &Path) -> Result<Vec<PathBuf>, GitWorktreeError> {
+    let worktrees_dir = git_dir.join("worktrees");
+    let entries = match fs::read_dir(&worktrees_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err).context(&worktrees_dir)?,
+    };
+    let mut admin_dirs = vec![];
+    for entry in entries {
+        let path = entry.context(&worktrees_dir)?.path();
+        if path.is_dir() {
+            admin_dirs.push(path);
+        }
+    }
+    Ok(admin_dirs)
+}
+
+/// Reads the path of the worktree's `.git` file recorded in its
+/// administrative directory.
+fn read_worktree_gitdir_file(admin_dir: &Path) -> Result<Option<PathBuf>, GitWorktreeError> {
+    let gitdir_path = admin_dir.join("gitdir");
+    let contents = match fs::read_to_string(&gitdir_path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).context(&gitdir_path)?,
+    };
+    // Relative paths are relative to the administrative directory.
+    Ok(Some(admin_dir.join(contents.trim_end())))
+}
+
+/// Reads the Git dir a `.git` file points to.
+fn read_dot_git_file(dot_git_path: &Path) -> Result<Option<PathBuf>, GitWorktreeError> {
+    let contents = fs::read_to_string(dot_git_path).context(dot_git_path)?;
+    let Some(git_dir) = contents.trim_end().strip_prefix("gitdir: ") else {
+        return Ok(None);
+    };
+    // Relative paths are relative to the directory containing the .git file.
+    Ok(Some(dot_git_path.parent().unwrap().join(git_dir)))
+}
+
+/// Returns true if `workspace_root` is a linked worktree of `git_repo`, such
+/// as one registered by [`add_worktree()`].
+pub fn is_linked_worktree(git_repo: &gix::Repository, workspace_root: &Path) -> bool {
+    // The main worktree has a .git directory, linked worktrees have a file.
+    if !workspace_root.join(".git").is_file() {
+        return false;
+    }
+    let Ok(worktree_repo) = gix::open(workspace_root) else {
+        return false;
+    };
+    worktree_repo.git_dir() != worktree_repo.common_dir()
+        && worktree_repo.common_dir().canonicalize().ok()
+            == git_repo.common_dir().canonicalize().ok()
+}
+
 #[derive(Debug, Error)]
 pub enum GitRemoteManagementError {
     #[error("No git remote named '{0}'")]
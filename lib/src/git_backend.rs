@@ -48,6 +48,7 @@ use thiserror::Error;
 use crate::backend::make_root_commit;
 use crate::backend::Backend;
 use crate::backend::BackendError;
+use crate::backend::BackendGcStats;
 use crate::backend::BackendInitError;
 use crate::backend::BackendLoadError;
 use crate::backend::BackendResult;
@@ -61,6 +62,7 @@ use crate::backend::CopyRecord;
 use crate::backend::FileId;
 use crate::backend::MergedTreeId;
 use crate::backend::MillisSinceEpoch;
+use crate::backend::RecordedCopy;
 use crate::backend::SecureSig;
 use crate::backend::Signature;
 use crate::backend::SigningFn;
@@ -598,6 +600,8 @@ fn commit_from_git_without_root_parent(
         author,
         committer,
         secure_sig,
+        // If this commit has associated extra metadata, we may set this later.
+        copies: vec![],
     })
 }
 
@@ -665,6 +669,12 @@ fn serialize_extras(commit: &Commit) -> Vec<u8> {
     for predecessor in &commit.predecessors {
         proto.predecessors.push(predecessor.to_bytes());
     }
+    for copy in &commit.copies {
+        proto.copies.push(crate::protos::git_store::commit::Copy {
+            source: copy.source.as_internal_file_string().to_owned(),
+            target: copy.target.as_internal_file_string().to_owned(),
+        });
+    }
     proto.encode_to_vec()
 }
 
@@ -698,6 +708,12 @@ fn deserialize_extras(commit: &mut Commit, bytes: &[u8]) {
     for predecessor in &proto.predecessors {
         commit.predecessors.push(CommitId::from_bytes(predecessor));
     }
+    for copy in proto.copies {
+        commit.copies.push(RecordedCopy {
+            source: RepoPathBuf::from_internal_string(copy.source),
+            target: RepoPathBuf::from_internal_string(copy.target),
+        });
+    }
 }
 
 /// Returns `RefEdit` that will create a ref in `refs/jj/keep` if not exist.
@@ -734,15 +750,20 @@ fn to_ref_deletion(git_ref: gix::refs::Reference) -> gix::refs::transaction::Ref
 
 /// Recreates `refs/jj/keep` refs for the `new_heads`, and removes the other
 /// unreachable and non-head refs.
+///
+/// Returns the number of refs pointing to commits that are no longer kept. If
+/// `dry_run` is true, the refs are only counted.
 fn recreate_no_gc_refs(
     git_repo: &gix::Repository,
     new_heads: impl IntoIterator<Item = CommitId>,
     keep_newer: SystemTime,
-) -> BackendResult<()> {
+    dry_run: bool,
+) -> BackendResult<usize> {
     // Calculate diff between existing no-gc refs and new heads.
     let new_heads: HashSet<CommitId> = new_heads.into_iter().collect();
     let mut no_gc_refs_to_keep_count: usize = 0;
     let mut no_gc_refs_to_delete: Vec<gix::refs::Reference> = Vec::new();
+    let mut pruned_count: usize = 0;
     let git_references = git_repo
         .references()
         .map_err(|err| BackendError::Other(err.into()))?;
@@ -781,6 +802,9 @@ fn recreate_no_gc_refs(
         }
         // Also deletes no-gc ref of random name created by old jj.
         tracing::trace!(?git_ref, ?name_good, "will delete");
+        if !new_heads.contains(&id) {
+            pruned_count += 1;
+        }
         no_gc_refs_to_delete.push(git_ref);
     }
     tracing::info!(
@@ -789,6 +813,9 @@ fn recreate_no_gc_refs(
         no_gc_refs_to_delete_count = no_gc_refs_to_delete.len(),
         "collected reachable refs"
     );
+    if dry_run {
+        return Ok(pruned_count);
+    }
 
     // It's slow to delete packed refs one by one, so update refs all at once.
     let ref_edits = itertools::chain(
@@ -799,7 +826,7 @@ fn recreate_no_gc_refs(
         .edit_references(ref_edits)
         .map_err(|err| BackendError::Other(err.into()))?;
 
-    Ok(())
+    Ok(pruned_count)
 }
 
 fn run_git_gc(git_dir: &Path) -> Result<(), GitGcError> {
@@ -1379,13 +1406,21 @@ impl Backend for GitBackend {
     }
 
     #[tracing::instrument(skip(self, index))]
-    fn gc(&self, index: &dyn Index, keep_newer: SystemTime) -> BackendResult<()> {
+    fn gc(
+        &self,
+        index: &dyn Index,
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> BackendResult<BackendGcStats> {
         let git_repo = self.lock_git_repo();
         let new_heads = index
             .all_heads_for_gc()
             .map_err(|err| BackendError::Other(err.into()))?
             .filter(|id| *id != self.root_commit_id);
-        recreate_no_gc_refs(&git_repo, new_heads, keep_newer)?;
+        let pruned_refs = recreate_no_gc_refs(&git_repo, new_heads, keep_newer, dry_run)?;
+        if dry_run {
+            return Ok(BackendGcStats { pruned_refs });
+        }
         // TODO: remove unreachable entries from extras table if segment file
         // mtime <= keep_newer? (it won't be consistent with no-gc refs
         // preserved by the keep_newer timestamp though)
@@ -1395,7 +1430,7 @@ impl Backend for GitBackend {
         // Since "git gc" will move loose refs into packed refs, in-memory
         // packed-refs cache should be invalidated without relying on mtime.
         git_repo.refs.force_refresh_packed_buffer().ok();
-        Ok(())
+        Ok(BackendGcStats { pruned_refs })
     }
 }
 
@@ -1852,6 +1887,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         let write_commit = |commit: Commit| -> BackendResult<(CommitId, Commit)> {
@@ -1934,6 +1970,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         let write_commit = |commit: Commit| -> BackendResult<(CommitId, Commit)> {
@@ -2027,6 +2064,7 @@ mod tests {
             author: signature.clone(),
             committer: signature,
             secure_sig: None,
+            copies: vec![],
         };
         let commit_id = backend.write_commit(commit, None).block_on().unwrap().0;
         let git_refs: Vec<_> = git_repo
@@ -2104,6 +2142,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         let write_commit = |commit: Commit| -> BackendResult<(CommitId, Commit)> {
@@ -2150,6 +2189,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         let mut signer = |data: &_| {
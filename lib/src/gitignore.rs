@@ -14,9 +14,11 @@
 
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::iter;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -38,11 +40,29 @@ pub enum GitIgnoreError {
     Underlying(#[from] ignore::Error),
 }
 
+/// The ignore pattern that decided whether a path is ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GitIgnoreMatch {
+    /// The file the pattern was read from, if any.
+    pub source: Option<PathBuf>,
+    /// The line number of the pattern in the `source` file, starting at 1.
+    pub line_num: Option<usize>,
+    /// The pattern as written.
+    pub pattern: String,
+    /// Whether the pattern is negated with `!`, meaning that the path isn't
+    /// ignored.
+    pub is_negated: bool,
+}
+
 /// Models the effective contents of multiple .gitignore files.
 #[derive(Debug)]
 pub struct GitIgnoreFile {
     parent: Option<Arc<GitIgnoreFile>>,
     matcher: gitignore::Gitignore,
+    /// Line numbers of the patterns read from a source file. If the same
+    /// pattern appears more than once, the last one is recorded because it's
+    /// the one that applies.
+    line_nums: HashMap<String, usize>,
 }
 
 impl GitIgnoreFile {
@@ -50,6 +70,7 @@ impl GitIgnoreFile {
         Arc::new(GitIgnoreFile {
             parent: None,
             matcher: gitignore::Gitignore::empty(),
+            line_nums: HashMap::new(),
         })
     }
 
@@ -61,8 +82,18 @@ impl GitIgnoreFile {
         self: &Arc<GitIgnoreFile>,
         prefix: &str,
         input: &[u8],
+    ) -> Result<Arc<GitIgnoreFile>, GitIgnoreError> {
+        self.chain_from_source(prefix, None, input)
+    }
+
+    fn chain_from_source(
+        self: &Arc<GitIgnoreFile>,
+        prefix: &str,
+        source: Option<&Path>,
+        input: &[u8],
     ) -> Result<Arc<GitIgnoreFile>, GitIgnoreError> {
         let mut builder = gitignore::GitignoreBuilder::new(prefix);
+        let mut line_nums = HashMap::new();
         for (i, input_line) in input.split(|b| *b == b'\n').enumerate() {
             let line =
                 std::str::from_utf8(input_line).map_err(|err| GitIgnoreError::InvalidUtf8 {
@@ -71,9 +102,17 @@ impl GitIgnoreFile {
                     line: String::from_utf8_lossy(input_line).to_string(),
                     source: err,
                 })?;
-            // FIXME: do we need to provide the `from` argument? Is it for providing
-            // diagnostics or correctness?
-            builder.add_line(None, line)?;
+            // The source is only used to explain which pattern matched a path.
+            builder.add_line(source.map(Path::to_path_buf), line)?;
+            if source.is_some() {
+                // Trailing whitespace is trimmed from patterns the same way.
+                let pattern = if line.ends_with("\\ ") {
+                    line
+                } else {
+                    line.trim_end()
+                };
+                line_nums.insert(pattern.to_owned(), i + 1);
+            }
         }
         let matcher = builder.build()?;
         let parent = if self.matcher.is_empty() {
@@ -81,7 +120,11 @@ impl GitIgnoreFile {
         } else {
             Some(self.clone())
         };
-        Ok(Arc::new(GitIgnoreFile { parent, matcher }))
+        Ok(Arc::new(GitIgnoreFile {
+            parent,
+            matcher,
+            line_nums,
+        }))
     }
 
     /// Concatenates new `.gitignore` file at the `prefix` directory.
@@ -98,25 +141,28 @@ impl GitIgnoreFile {
                 path: file.clone(),
                 source: err,
             })?;
-            self.chain(prefix, &buf)
+            self.chain_from_source(prefix, Some(&file), &buf)
         } else {
             Ok(self.clone())
         }
     }
 
+    /// Returns the last matching pattern and the file it was added by.
+    fn matched_glob(&self, path: &str, is_dir: bool) -> Option<(&Self, &gitignore::Glob)> {
+        iter::successors(Some(self), |file| file.parent.as_deref()).find_map(|file| {
+            // TODO: the documentation warns that
+            // `matched_path_or_any_parents` is slower than `matched`;
+            // ideally, we would switch to that.
+            match file.matcher.matched_path_or_any_parents(path, is_dir) {
+                ignore::Match::None => None,
+                ignore::Match::Ignore(glob) | ignore::Match::Whitelist(glob) => Some((file, glob)),
+            }
+        })
+    }
+
     fn matches_helper(&self, path: &str, is_dir: bool) -> bool {
-        iter::successors(Some(self), |file| file.parent.as_deref())
-            .find_map(|file| {
-                // TODO: the documentation warns that
-                // `matched_path_or_any_parents` is slower than `matched`;
-                // ideally, we would switch to that.
-                match file.matcher.matched_path_or_any_parents(path, is_dir) {
-                    ignore::Match::None => None,
-                    ignore::Match::Ignore(_) => Some(true),
-                    ignore::Match::Whitelist(_) => Some(false),
-                }
-            })
-            .unwrap_or_default()
+        self.matched_glob(path, is_dir)
+            .is_some_and(|(_, glob)| !glob.is_whitelist())
     }
 
     /// Returns whether specified path (not just file!) should be ignored. This
@@ -134,10 +180,28 @@ impl GitIgnoreFile {
         };
         self.matches_helper(path, is_dir)
     }
+
+    /// Returns the last pattern matching the specified path, which decides
+    /// whether the path is ignored. Like `matches()`, this doesn't take into
+    /// account that the path may be in an ignored directory.
+    pub fn matching_pattern(&self, path: &str) -> Option<GitIgnoreMatch> {
+        let (path, is_dir) = match path.strip_suffix('/') {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+        let (file, glob) = self.matched_glob(path, is_dir)?;
+        Some(GitIgnoreMatch {
+            source: glob.from().map(Path::to_path_buf),
+            line_num: file.line_nums.get(glob.original()).copied(),
+            pattern: glob.original().to_owned(),
+            is_negated: glob.is_whitelist(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
 
     use super::*;
 
@@ -393,6 +457,56 @@ mod tests {
         assert!(!file3.matches("foo/bar/qux"));
     }
 
+    #[test]
+    fn test_gitignore_matching_pattern() {
+        let file = GitIgnoreFile::empty()
+            .chain("", b"*.o\n!keep.o\n")
+            .unwrap()
+            .chain("dir/", b"/build/\n")
+            .unwrap();
+        assert_eq!(file.matching_pattern("foo"), None);
+        assert_eq!(
+            file.matching_pattern("foo.o"),
+            Some(GitIgnoreMatch {
+                source: None,
+                line_num: None,
+                pattern: "*.o".to_owned(),
+                is_negated: false,
+            })
+        );
+        assert_matches!(
+            file.matching_pattern("keep.o"),
+            Some(GitIgnoreMatch { pattern, is_negated: true, .. }) if pattern == "!keep.o"
+        );
+        // Files in ignored directories are matched by the directory pattern
+        assert_matches!(
+            file.matching_pattern("dir/build/foo"),
+            Some(GitIgnoreMatch { pattern, is_negated: false, .. }) if pattern == "/build/"
+        );
+    }
+
+    #[test]
+    fn test_gitignore_matching_pattern_line_num() {
+        let temp_dir = testutils::new_temp_dir();
+        let path = temp_dir.path().join(".gitignore");
+        std::fs::write(&path, "# comment\n*.o\n\n*.a  \n*.o\n").unwrap();
+        let file = GitIgnoreFile::empty()
+            .chain_with_file("", path.clone())
+            .unwrap();
+        let line_num = |path: &str| file.matching_pattern(path).unwrap().line_num;
+        // The last of the identical patterns applies
+        assert_eq!(line_num("foo.o"), Some(5));
+        assert_eq!(line_num("foo.a"), Some(4));
+        assert_eq!(
+            file.matching_pattern("foo.a").unwrap().source,
+            Some(path.clone())
+        );
+
+        // Line numbers are recorded when the file is read
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(line_num("foo.o"), Some(5));
+    }
+
     #[test]
     fn test_gitignore_negative_parent_directory() {
         // The following script shows that Git ignores the file:
@@ -30,6 +30,7 @@ pub mod content_hash;
 
 pub mod annotate;
 pub mod backend;
+pub mod bisect;
 pub mod commit;
 pub mod commit_builder;
 pub mod conflicts;
@@ -59,14 +60,17 @@ pub mod index;
 pub mod local_backend;
 pub mod local_working_copy;
 pub mod lock;
+pub mod log_op_store;
 pub mod matchers;
 pub mod merge;
 pub mod merged_tree;
 pub mod object_id;
+pub mod op_bundle;
 pub mod op_heads_store;
 pub mod op_store;
 pub mod op_walk;
 pub mod operation;
+pub mod opset;
 #[allow(missing_docs)]
 pub mod protos;
 pub mod refs;
@@ -94,3 +98,4 @@ pub mod union_find;
 pub mod view;
 pub mod working_copy;
 pub mod workspace;
+pub mod workspace_store;
@@ -36,6 +36,7 @@ use tempfile::NamedTempFile;
 use crate::backend::make_root_commit;
 use crate::backend::Backend;
 use crate::backend::BackendError;
+use crate::backend::BackendGcStats;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::Commit;
@@ -47,6 +48,7 @@ use crate::backend::CopyRecord;
 use crate::backend::FileId;
 use crate::backend::MergedTreeId;
 use crate::backend::MillisSinceEpoch;
+use crate::backend::RecordedCopy;
 use crate::backend::SecureSig;
 use crate::backend::Signature;
 use crate::backend::SigningFn;
@@ -342,8 +344,13 @@ impl Backend for LocalBackend {
         Ok(Box::pin(stream::empty()))
     }
 
-    fn gc(&self, _index: &dyn Index, _keep_newer: SystemTime) -> BackendResult<()> {
-        Ok(())
+    fn gc(
+        &self,
+        _index: &dyn Index,
+        _keep_newer: SystemTime,
+        _dry_run: bool,
+    ) -> BackendResult<BackendGcStats> {
+        Ok(BackendGcStats::default())
     }
 }
 
@@ -370,10 +377,16 @@ pub fn commit_to_proto(commit: &Commit) -> crate::protos::local_store::Commit {
     proto.description = commit.description.clone();
     proto.author = Some(signature_to_proto(&commit.author));
     proto.committer = Some(signature_to_proto(&commit.committer));
+    for copy in &commit.copies {
+        proto.copies.push(crate::protos::local_store::commit::Copy {
+            source: copy.source.as_internal_file_string().to_owned(),
+            target: copy.target.as_internal_file_string().to_owned(),
+        });
+    }
     proto
 }
 
-fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
+pub(crate) fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
     // Note how .take() sets the secure_sig field to None before we encode the data.
     // Needs to be done first since proto is partially moved a bunch below
     let secure_sig = proto.secure_sig.take().map(|sig| SecureSig {
@@ -391,6 +404,14 @@ fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
         MergedTreeId::Legacy(TreeId::new(proto.root_tree[0].clone()))
     };
     let change_id = ChangeId::new(proto.change_id);
+    let copies = proto
+        .copies
+        .into_iter()
+        .map(|copy| RecordedCopy {
+            source: RepoPathBuf::from_internal_string(copy.source),
+            target: RepoPathBuf::from_internal_string(copy.target),
+        })
+        .collect();
     Commit {
         parents,
         predecessors,
@@ -400,10 +421,11 @@ fn commit_from_proto(mut proto: crate::protos::local_store::Commit) -> Commit {
         author: signature_from_proto(proto.author.unwrap_or_default()),
         committer: signature_from_proto(proto.committer.unwrap_or_default()),
         secure_sig,
+        copies,
     }
 }
 
-fn tree_to_proto(tree: &Tree) -> crate::protos::local_store::Tree {
+pub(crate) fn tree_to_proto(tree: &Tree) -> crate::protos::local_store::Tree {
     let mut proto = crate::protos::local_store::Tree::default();
     for entry in tree.entries() {
         proto.entries.push(crate::protos::local_store::tree::Entry {
@@ -414,7 +436,7 @@ fn tree_to_proto(tree: &Tree) -> crate::protos::local_store::Tree {
     proto
 }
 
-fn tree_from_proto(proto: crate::protos::local_store::Tree) -> Tree {
+pub(crate) fn tree_from_proto(proto: crate::protos::local_store::Tree) -> Tree {
     let mut tree = Tree::default();
     for proto_entry in proto.entries {
         let value = tree_value_from_proto(proto_entry.value.unwrap());
@@ -499,7 +521,7 @@ fn signature_from_proto(proto: crate::protos::local_store::commit::Signature) ->
     }
 }
 
-fn conflict_to_proto(conflict: &Conflict) -> crate::protos::local_store::Conflict {
+pub(crate) fn conflict_to_proto(conflict: &Conflict) -> crate::protos::local_store::Conflict {
     let mut proto = crate::protos::local_store::Conflict::default();
     for term in &conflict.removes {
         proto.removes.push(conflict_term_to_proto(term));
@@ -510,7 +532,7 @@ fn conflict_to_proto(conflict: &Conflict) -> crate::protos::local_store::Conflic
     proto
 }
 
-fn conflict_from_proto(proto: crate::protos::local_store::Conflict) -> Conflict {
+pub(crate) fn conflict_from_proto(proto: crate::protos::local_store::Conflict) -> Conflict {
     let mut conflict = Conflict::default();
     for term in proto.removes {
         conflict.removes.push(conflict_term_from_proto(term));
@@ -556,6 +578,7 @@ mod tests {
             author: create_signature(),
             committer: create_signature(),
             secure_sig: None,
+            copies: vec![],
         };
 
         let write_commit = |commit: Commit| -> BackendResult<(CommitId, Commit)> {
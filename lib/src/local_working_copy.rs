@@ -16,6 +16,8 @@
 #![allow(clippy::let_unit_value)]
 
 use std::any::Any;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
@@ -56,6 +58,7 @@ use crate::backend::BackendResult;
 use crate::backend::FileId;
 use crate::backend::MergedTreeId;
 use crate::backend::MillisSinceEpoch;
+use crate::backend::RecordedCopy;
 use crate::backend::SymlinkId;
 use crate::backend::TreeId;
 use crate::backend::TreeValue;
@@ -66,9 +69,13 @@ use crate::conflicts::materialize_tree_value;
 use crate::conflicts::MaterializedTreeValue;
 use crate::file_util::check_symlink_support;
 use crate::file_util::try_symlink;
+#[cfg(target_os = "linux")]
+use crate::fsmonitor::inotify;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::watchman;
 use crate::fsmonitor::FsmonitorSettings;
+#[cfg(target_os = "linux")]
+use crate::fsmonitor::InotifyConfig;
 #[cfg(feature = "watchman")]
 use crate::fsmonitor::WatchmanConfig;
 use crate::gitignore::GitIgnoreFile;
@@ -101,6 +108,8 @@ use crate::working_copy::ResetError;
 use crate::working_copy::SnapshotError;
 use crate::working_copy::SnapshotOptions;
 use crate::working_copy::SnapshotProgress;
+use crate::working_copy::UntrackedPath;
+use crate::working_copy::UntrackedReason;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
 use crate::working_copy::WorkingCopyStateError;
@@ -336,10 +345,13 @@ pub struct TreeState {
     own_mtime: MillisSinceEpoch,
     symlink_support: bool,
 
-    /// The most recent clock value returned by Watchman. Will only be set if
-    /// the repo is configured to use the Watchman filesystem monitor and
-    /// Watchman has been queried at least once.
+    /// The most recent clock value returned by the filesystem monitor. Will
+    /// only be set if the repo is configured to use Watchman or the inotify
+    /// filesystem monitor and it has been queried at least once.
     watchman_clock: Option<crate::protos::working_copy::WatchmanClock>,
+
+    /// Copies and renames detected by the most recent snapshot. Not persisted.
+    snapshot_copies: Vec<RecordedCopy>,
 }
 
 fn file_state_from_proto(proto: &crate::protos::working_copy::FileState) -> FileState {
@@ -553,6 +565,11 @@ fn can_create_new_file(disk_path: &Path) -> Result<bool, CheckoutError> {
 
 const RESERVED_DIR_NAMES: &[&str] = &[".git", ".jj"];
 
+/// Added files smaller than this aren't recorded as copies of unchanged files.
+/// Small files such as empty `__init__.py` files or license headers often have
+/// the same content by coincidence.
+const MIN_COPY_SOURCE_SIZE: u64 = 64;
+
 /// Suppose the `disk_path` exists, checks if the last component points to
 /// ".git" or ".jj" in the same parent directory.
 fn reject_reserved_existing_path(disk_path: &Path) -> Result<(), CheckoutError> {
@@ -666,6 +683,12 @@ impl TreeState {
         self.file_states.all()
     }
 
+    /// Copies and renames detected by the most recent call to `snapshot()`,
+    /// relative to the tree before that snapshot.
+    pub fn snapshot_copies(&self) -> &[RecordedCopy] {
+        &self.snapshot_copies
+    }
+
     pub fn sparse_patterns(&self) -> &Vec<RepoPathBuf> {
         &self.sparse_patterns
     }
@@ -696,6 +719,7 @@ impl TreeState {
             own_mtime: MillisSinceEpoch(0),
             symlink_support: check_symlink_support().unwrap_or(false),
             watchman_clock: None,
+            snapshot_copies: vec![],
         }
     }
 
@@ -880,6 +904,20 @@ impl TreeState {
         Ok(changed_files)
     }
 
+    #[cfg(target_os = "linux")]
+    #[instrument(skip(self))]
+    pub fn query_inotify(
+        &self,
+        config: &InotifyConfig,
+    ) -> Result<(inotify::Clock, Option<Vec<PathBuf>>), TreeStateError> {
+        let fsmonitor = inotify::Fsmonitor::init(&self.working_copy_path, config)
+            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))?;
+        let previous_clock = self.watchman_clock.clone().map(inotify::Clock::from);
+        fsmonitor
+            .query_changed_files(previous_clock)
+            .map_err(|err| TreeStateError::Fsmonitor(Box::new(err)))
+    }
+
     #[cfg(feature = "watchman")]
     #[tokio::main(flavor = "current_thread")]
     #[instrument(skip(self))]
@@ -915,12 +953,13 @@ impl TreeState {
         let FsmonitorMatcher {
             matcher: fsmonitor_matcher,
             watchman_clock,
-        } = self.make_fsmonitor_matcher(fsmonitor_settings)?;
+        } = self.make_fsmonitor_matcher(fsmonitor_settings, base_ignores)?;
         let fsmonitor_matcher = match fsmonitor_matcher.as_ref() {
             None => &EverythingMatcher,
             Some(fsmonitor_matcher) => fsmonitor_matcher.as_ref(),
         };
 
+        self.snapshot_copies.clear();
         let matcher = IntersectionMatcher::new(sparse_matcher.as_ref(), fsmonitor_matcher);
         if matcher.visit(RepoPath::root()).is_nothing() {
             // No need to iterate file states to build empty deleted_files.
@@ -947,6 +986,7 @@ impl TreeState {
                 tree_entries_tx,
                 file_states_tx,
                 present_files_tx,
+                None,
                 directory_to_visit,
                 *progress,
                 *max_new_file_size,
@@ -967,8 +1007,16 @@ impl TreeState {
                     .map(|(path, _state)| path.to_owned())
                     .collect()
             });
+        // Files that weren't tracked before, which may have been copied or moved
+        let mut added_files: HashMap<RepoPathBuf, FileId> = HashMap::new();
         trace_span!("process tree entries").in_scope(|| -> Result<(), SnapshotError> {
+            let file_states = self.file_states.all();
             while let Ok((path, tree_values)) = tree_entries_rx.recv() {
+                if let Some(TreeValue::File { id, .. }) = tree_values.as_normal() {
+                    if !file_states.contains_path(&path) {
+                        added_files.insert(path.clone(), id.clone());
+                    }
+                }
                 tree_builder.set_or_remove(path, tree_values);
             }
             Ok(())
@@ -990,9 +1038,14 @@ impl TreeState {
                 .sorted_unstable_by(|(path1, _), (path2, _)| path1.cmp(path2))
                 .collect_vec();
             is_dirty |= !changed_file_states.is_empty();
+            if !added_files.is_empty() {
+                self.snapshot_copies =
+                    self.detect_copies(&added_files, &changed_file_states, &deleted_files)?;
+            }
             self.file_states
                 .merge_in(changed_file_states, &deleted_files);
-        });
+            Ok::<_, SnapshotError>(())
+        })?;
         trace_span!("write tree").in_scope(|| {
             let new_tree_id = tree_builder.write_tree(&self.store).unwrap();
             is_dirty |= new_tree_id != self.tree_id;
@@ -1012,6 +1065,129 @@ impl TreeState {
         Ok(is_dirty)
     }
 
+    /// Finds the added files whose content is identical to a deleted file. If
+    /// several deleted files have the same content, the one with the same
+    /// mtime and size, or else the same file name, is preferred. Added files
+    /// left over once all such deleted files are matched are copies.
+    ///
+    /// Other added files are recorded as copies of a remaining tracked file
+    /// with the same content, preferably one with the same file name. Files
+    /// smaller than [`MIN_COPY_SOURCE_SIZE`] aren't matched this way.
+    fn detect_copies(
+        &self,
+        added_files: &HashMap<RepoPathBuf, FileId>,
+        changed_file_states: &[(RepoPathBuf, FileState)],
+        deleted_files: &HashSet<RepoPathBuf>,
+    ) -> Result<Vec<RecordedCopy>, SnapshotError> {
+        let added_ids: HashSet<&FileId> = added_files.values().collect();
+        let current_tree = self.current_tree()?;
+        let file_states = self.file_states.all();
+        let mut deleted_by_id: HashMap<FileId, Vec<(RepoPathBuf, FileState)>> = HashMap::new();
+        for path in deleted_files.iter().sorted() {
+            let value = current_tree.path_value(path)?;
+            let Some(TreeValue::File { id, .. }) = value.as_normal() else {
+                continue;
+            };
+            if !added_ids.contains(id) {
+                continue;
+            }
+            // Empty files would be matched with unrelated empty files.
+            let Some(state) = file_states.get(path).filter(|state| state.size > 0) else {
+                continue;
+            };
+            deleted_by_id
+                .entry(id.clone())
+                .or_default()
+                .push((path.clone(), state));
+        }
+
+        let added = changed_file_states
+            .iter()
+            .filter_map(|(path, state)| {
+                let candidates = deleted_by_id.get(added_files.get(path)?)?;
+                Some((path, state, candidates))
+            })
+            .collect_vec();
+        let is_same_state = |state: &FileState, source_state: &FileState| {
+            source_state.mtime == state.mtime && source_state.size == state.size
+        };
+        let is_same_name = |path: &RepoPath, source: &RepoPath| {
+            source.components().next_back() == path.components().next_back()
+        };
+        // Target to source, in the order of preference
+        let mut sources: BTreeMap<&RepoPath, &RepoPath> = BTreeMap::new();
+        let mut renamed: HashSet<&RepoPath> = HashSet::new();
+        for pass in 0..3 {
+            for &(path, state, candidates) in &added {
+                if sources.contains_key(path.as_ref()) {
+                    continue;
+                }
+                let source = candidates.iter().find(|(source, source_state)| {
+                    !renamed.contains(source.as_ref())
+                        && match pass {
+                            0 => is_same_state(state, source_state),
+                            1 => is_same_name(path, source),
+                            _ => true,
+                        }
+                });
+                if let Some((source, _)) = source {
+                    renamed.insert(source);
+                    sources.insert(path, source);
+                }
+            }
+        }
+        // Files copied from a renamed file
+        for &(path, _, candidates) in &added {
+            sources.entry(path).or_insert(&candidates[0].0);
+        }
+
+        // Files copied from a file that's still there. Only the files of the
+        // same size can have the same content, so only their IDs are looked up.
+        let copied = changed_file_states
+            .iter()
+            .filter(|(path, state)| {
+                state.size >= MIN_COPY_SOURCE_SIZE
+                    && added_files.contains_key(path)
+                    && !sources.contains_key(path.as_ref())
+            })
+            .collect_vec();
+        let copied_sizes: HashSet<u64> = copied.iter().map(|(_, state)| state.size).collect();
+        let mut existing_by_id: HashMap<&FileId, Vec<&RepoPath>> = HashMap::new();
+        if !copied.is_empty() {
+            for (path, state) in file_states.iter() {
+                if !copied_sizes.contains(&state.size) || deleted_files.contains(path) {
+                    continue;
+                }
+                let value = current_tree.path_value(path)?;
+                let Some(TreeValue::File { id, .. }) = value.as_normal() else {
+                    continue;
+                };
+                if let Some(id) = added_ids.get(id) {
+                    existing_by_id.entry(id).or_default().push(path);
+                }
+            }
+        }
+        for (path, _) in copied {
+            let Some(candidates) = existing_by_id.get(&added_files[path]) else {
+                continue;
+            };
+            let source = candidates
+                .iter()
+                .find(|source| is_same_name(path, source))
+                .unwrap_or(&candidates[0]);
+            sources.insert(path, source);
+        }
+
+        let copies = sources
+            .into_iter()
+            .map(|(target, source)| RecordedCopy {
+                source: source.to_owned(),
+                target: target.to_owned(),
+            })
+            .collect();
+        Ok(copies)
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn visit_directory(
         &self,
@@ -1021,6 +1197,7 @@ impl TreeState {
         tree_entries_tx: Sender<(RepoPathBuf, MergedTreeValue)>,
         file_states_tx: Sender<(RepoPathBuf, FileState)>,
         present_files_tx: Sender<RepoPathBuf>,
+        untracked_paths_tx: Option<Sender<UntrackedPath>>,
         directory_to_visit: DirectoryToVisit,
         progress: Option<&SnapshotProgress>,
         max_new_file_size: u64,
@@ -1048,8 +1225,9 @@ impl TreeState {
                 tree_entries_tx.clone(),
                 file_states_tx.clone(),
                 present_files_tx.clone(),
+                untracked_paths_tx.clone(),
             ),
-            |(tree_entries_tx, file_states_tx, present_files_tx),
+            |(tree_entries_tx, file_states_tx, present_files_tx, untracked_paths_tx),
              entry|
              -> Result<(), SnapshotError> {
                 let file_type = entry.file_type().unwrap();
@@ -1076,8 +1254,20 @@ impl TreeState {
                     if git_ignore.matches(&path.to_internal_dir_string())
                         || start_tracking_matcher.visit(&path).is_nothing()
                     {
-                        // TODO: Report this directory to the caller if there are unignored paths we
-                        // should not start tracking.
+                        if let Some(untracked_paths_tx) = untracked_paths_tx {
+                            if !matcher.visit(&path).is_nothing() {
+                                let reason =
+                                    ignore_reason(&git_ignore, &path.to_internal_dir_string())
+                                        .unwrap_or(UntrackedReason::NotAutoTracked);
+                                untracked_paths_tx
+                                    .send(UntrackedPath {
+                                        path: path.clone(),
+                                        is_dir: true,
+                                        reason,
+                                    })
+                                    .ok();
+                            }
+                        }
 
                         // If the whole directory is ignored, visit only paths we're already
                         // tracking.
@@ -1136,6 +1326,7 @@ impl TreeState {
                             tree_entries_tx.clone(),
                             file_states_tx.clone(),
                             present_files_tx.clone(),
+                            untracked_paths_tx.clone(),
                             directory_to_visit,
                             progress,
                             max_new_file_size,
@@ -1150,11 +1341,47 @@ impl TreeState {
                     {
                         // If it wasn't already tracked and it matches
                         // the ignored paths, then ignore it.
+                        if let Some(untracked_paths_tx) = untracked_paths_tx {
+                            if let Some(reason) =
+                                ignore_reason(&git_ignore, path.as_internal_file_string())
+                            {
+                                untracked_paths_tx
+                                    .send(UntrackedPath {
+                                        path,
+                                        is_dir: false,
+                                        reason,
+                                    })
+                                    .ok();
+                            }
+                        }
+                    } else if maybe_current_file_state.is_none()
+                        && conflicts::conflict_sidecar_base_path(&path).is_some()
+                    {
+                        // Sides of a conflict written next to the conflicted
+                        // file aren't part of the working copy. They're left
+                        // untracked even after the conflict is resolved.
+                        if let Some(untracked_paths_tx) = untracked_paths_tx {
+                            untracked_paths_tx
+                                .send(UntrackedPath {
+                                    path,
+                                    is_dir: false,
+                                    reason: UntrackedReason::ConflictSidecar,
+                                })
+                                .ok();
+                        }
                     } else if maybe_current_file_state.is_none()
                         && !start_tracking_matcher.matches(&path)
                     {
                         // Leave the file untracked
-                        // TODO: Report this path to the caller
+                        if let Some(untracked_paths_tx) = untracked_paths_tx {
+                            untracked_paths_tx
+                                .send(UntrackedPath {
+                                    path,
+                                    is_dir: false,
+                                    reason: UntrackedReason::NotAutoTracked,
+                                })
+                                .ok();
+                        }
                     } else {
                         let metadata = entry.metadata().map_err(|err| SnapshotError::Other {
                             message: format!("Failed to stat file {}", entry.path().display()),
@@ -1162,6 +1389,21 @@ impl TreeState {
                         })?;
                         if maybe_current_file_state.is_none() && metadata.len() > max_new_file_size
                         {
+                            // When only reporting untracked paths, the file is
+                            // reported instead of failing.
+                            if let Some(untracked_paths_tx) = untracked_paths_tx {
+                                untracked_paths_tx
+                                    .send(UntrackedPath {
+                                        path,
+                                        is_dir: false,
+                                        reason: UntrackedReason::FileTooLarge {
+                                            size: metadata.len(),
+                                            max_size: max_new_file_size,
+                                        },
+                                    })
+                                    .ok();
+                                return Ok(());
+                            }
                             // TODO: Maybe leave the file untracked instead
                             return Err(SnapshotError::NewFileTooLarge {
                                 path: entry.path().clone(),
@@ -1193,10 +1435,49 @@ impl TreeState {
         Ok(())
     }
 
+    /// Returns the paths that a snapshot with the `options` would leave
+    /// untracked, and why, without changing the tree state. Only paths
+    /// matching `matcher` are visited. The filesystem monitor isn't used, and
+    /// files exceeding `max_new_file_size` are reported instead of failing.
+    #[instrument(skip_all)]
+    pub fn untracked_paths(
+        &self,
+        options: &SnapshotOptions,
+        matcher: &dyn Matcher,
+    ) -> Result<Vec<UntrackedPath>, SnapshotError> {
+        let sparse_matcher = self.sparse_matcher();
+        let matcher = IntersectionMatcher::new(sparse_matcher.as_ref(), matcher);
+        // Only the untracked paths are collected.
+        let (tree_entries_tx, _) = channel();
+        let (file_states_tx, _) = channel();
+        let (present_files_tx, _) = channel();
+        let (untracked_paths_tx, untracked_paths_rx) = channel();
+        let directory_to_visit = DirectoryToVisit {
+            dir: RepoPathBuf::root(),
+            disk_dir: self.working_copy_path.clone(),
+            git_ignore: options.base_ignores.clone(),
+            file_states: self.file_states.all(),
+        };
+        self.visit_directory(
+            &matcher,
+            options.start_tracking_matcher,
+            &self.current_tree()?,
+            tree_entries_tx,
+            file_states_tx,
+            present_files_tx,
+            Some(untracked_paths_tx),
+            directory_to_visit,
+            options.progress,
+            options.max_new_file_size,
+        )?;
+        Ok(untracked_paths_rx.into_iter().collect())
+    }
+
     #[instrument(skip_all)]
     fn make_fsmonitor_matcher(
         &self,
         fsmonitor_settings: &FsmonitorSettings,
+        base_ignores: &GitIgnoreFile,
     ) -> Result<FsmonitorMatcher, SnapshotError> {
         let (watchman_clock, changed_files) = match fsmonitor_settings {
             FsmonitorSettings::None => (None, None),
@@ -1218,18 +1499,44 @@ impl TreeState {
                         .into(),
                 });
             }
+            #[cfg(target_os = "linux")]
+            FsmonitorSettings::Inotify(config) => match self.query_inotify(config) {
+                Ok((clock, changed_files)) => (Some(clock.into()), changed_files),
+                Err(err) => {
+                    tracing::warn!(?err, "Failed to query filesystem monitor");
+                    (None, None)
+                }
+            },
+            #[cfg(not(target_os = "linux"))]
+            FsmonitorSettings::Inotify(_) => {
+                return Err(SnapshotError::Other {
+                    message: "Failed to query the filesystem monitor".to_string(),
+                    err: "The inotify filesystem monitor is only available on Linux (consider \
+                          disabling `core.fsmonitor`)"
+                        .into(),
+                });
+            }
         };
         let matcher: Option<Box<dyn Matcher>> = match changed_files {
             None => None,
             Some(changed_files) => {
-                let repo_paths = trace_span!("processing fsmonitor paths").in_scope(|| {
+                let mut repo_paths = trace_span!("processing fsmonitor paths").in_scope(|| {
                     changed_files
                         .into_iter()
                         .filter_map(|path| RepoPathBuf::from_relative_path(path).ok())
                         .collect_vec()
                 });
 
-                Some(Box::new(FilesMatcher::new(repo_paths)))
+                // The inotify monitor reports new, removed, and renamed
+                // directories instead of the files in them. It doesn't watch
+                // ignored directories, so tracked files in them may have
+                // changed.
+                if matches!(fsmonitor_settings, FsmonitorSettings::Inotify(_)) {
+                    repo_paths.extend(self.tracked_files_in_ignored_dirs(base_ignores));
+                    Some(Box::new(PrefixMatcher::new(repo_paths)))
+                } else {
+                    Some(Box::new(FilesMatcher::new(repo_paths)))
+                }
             }
         };
         Ok(FsmonitorMatcher {
@@ -1238,6 +1545,41 @@ impl TreeState {
         })
     }
 
+    /// Returns the tracked files in directories matched by `base_ignores`.
+    fn tracked_files_in_ignored_dirs(&self, base_ignores: &GitIgnoreFile) -> Vec<RepoPathBuf> {
+        fn is_ignored_dir(
+            dir: &RepoPath,
+            base_ignores: &GitIgnoreFile,
+            cache: &mut HashMap<RepoPathBuf, bool>,
+        ) -> bool {
+            if dir.is_root() {
+                return false;
+            }
+            if let Some(&is_ignored) = cache.get(dir) {
+                return is_ignored;
+            }
+            // Nothing in an ignored directory is watched.
+            let is_ignored = dir
+                .parent()
+                .is_some_and(|parent| is_ignored_dir(parent, base_ignores, cache))
+                || base_ignores.matches(&dir.to_internal_dir_string());
+            cache.insert(dir.to_owned(), is_ignored);
+            is_ignored
+        }
+
+        let mut cache = HashMap::new();
+        self.file_states
+            .all()
+            .iter()
+            .map(|(path, _state)| path)
+            .filter(|path| {
+                path.parent()
+                    .is_some_and(|dir| is_ignored_dir(dir, base_ignores, &mut cache))
+            })
+            .map(|path| path.to_owned())
+            .collect()
+    }
+
     fn get_updated_tree_value(
         &self,
         repo_path: &RepoPath,
@@ -1321,6 +1663,10 @@ impl TreeState {
                 message: format!("Failed to open file {}", disk_path.display()),
                 err: err.into(),
             })?;
+            if is_conflict_placeholder(&content, current_tree_values) {
+                // The sides of the conflict were written to sidecar files
+                return Ok(current_tree_values.clone());
+            }
             let new_file_ids = conflicts::update_from_content(
                 &old_file_ids,
                 self.store.as_ref(),
@@ -1354,7 +1700,26 @@ impl TreeState {
                 }
             }
         } else {
-            Ok(current_tree_values.clone())
+            // The conflict includes non-file terms, so the file on disk is
+            // either the placeholder describing the conflict, or the
+            // resolved content.
+            let content = fs::read(disk_path).map_err(|err| SnapshotError::Other {
+                message: format!("Failed to open file {}", disk_path.display()),
+                err: err.into(),
+            })?;
+            if is_conflict_placeholder(&content, current_tree_values) {
+                return Ok(current_tree_values.clone());
+            }
+            let id = self
+                .store
+                .write_file(repo_path, &mut content.as_slice())
+                .await?;
+            #[cfg(windows)]
+            let executable = {
+                let () = executable; // use the variable
+                false
+            };
+            Ok(Merge::normal(TreeValue::File { id, executable }))
         }
     }
 
@@ -1430,6 +1795,92 @@ impl TreeState {
         Ok(FileState::for_file(executable, size, &metadata))
     }
 
+    /// Writes the file and symlink terms of the `conflict` at `path` to the
+    /// paths returned by `conflict_sidecar_paths()`. Other terms are skipped,
+    /// and so are paths that are tracked in the `new_tree` or where an
+    /// untracked file already exists.
+    async fn write_conflict_sidecars(
+        &self,
+        path: &RepoPath,
+        conflict: &MergedTreeValue,
+        new_tree: &MergedTree,
+    ) -> Result<(), CheckoutError> {
+        let conflict = conflict.clone().simplify();
+        let sidecar_paths = conflicts::conflict_sidecar_paths(path, conflict.num_sides());
+        for (term, sidecar_path) in iter::zip(conflict.iter(), sidecar_paths.iter()) {
+            if new_tree.path_value(sidecar_path)?.is_present() {
+                continue;
+            }
+            let disk_path = sidecar_path.to_fs_path_unchecked(&self.working_copy_path);
+            if disk_path.symlink_metadata().is_ok() {
+                continue;
+            }
+            match term {
+                Some(TreeValue::File { id, executable }) => {
+                    let mut reader = self.store.read_file_async(path, id).await?;
+                    self.write_file(&disk_path, &mut reader, *executable)?;
+                }
+                Some(TreeValue::Symlink(id)) => {
+                    let target = self.store.read_symlink_async(path, id).await?;
+                    if self.symlink_support {
+                        self.write_symlink(&disk_path, target)?;
+                    } else {
+                        self.write_file(&disk_path, &mut target.as_bytes(), false)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the files written by `write_conflict_sidecars()` for the
+    /// `conflict` at `path`. Files that were changed since, or that weren't
+    /// written for the conflict, are kept.
+    async fn remove_conflict_sidecars(
+        &self,
+        path: &RepoPath,
+        conflict: &MergedTreeValue,
+    ) -> Result<(), CheckoutError> {
+        let conflict = conflict.clone().simplify();
+        let sidecar_paths = conflicts::conflict_sidecar_paths(path, conflict.num_sides());
+        for (term, sidecar_path) in iter::zip(conflict.iter(), sidecar_paths.iter()) {
+            let disk_path = sidecar_path.to_fs_path_unchecked(&self.working_copy_path);
+            let Ok(metadata) = disk_path.symlink_metadata() else {
+                continue;
+            };
+            let is_unchanged = match term {
+                Some(TreeValue::File { id, .. }) if metadata.is_file() => {
+                    let mut expected = vec![];
+                    let mut reader = self.store.read_file_async(path, id).await?;
+                    reader
+                        .read_to_end(&mut expected)
+                        .map_err(|err| CheckoutError::Other {
+                            message: format!(
+                                "Failed to read file {}",
+                                path.as_internal_file_string()
+                            ),
+                            err: err.into(),
+                        })?;
+                    fs::read(&disk_path).is_ok_and(|content| content == expected)
+                }
+                Some(TreeValue::Symlink(id)) => {
+                    let target = self.store.read_symlink_async(path, id).await?;
+                    if metadata.is_symlink() {
+                        fs::read_link(&disk_path).is_ok_and(|link| link == Path::new(&target))
+                    } else {
+                        fs::read(&disk_path).is_ok_and(|content| content == target.as_bytes())
+                    }
+                }
+                _ => false,
+            };
+            if is_unchanged {
+                remove_conflict_sidecar(&disk_path)?;
+            }
+        }
+        Ok(())
+    }
+
     #[cfg_attr(windows, allow(unused_variables))]
     fn set_executable(&self, disk_path: &Path, executable: bool) -> Result<(), CheckoutError> {
         #[cfg(unix)]
@@ -1510,8 +1961,9 @@ impl TreeState {
                 .map(|TreeDiffEntry { path, values }| async {
                     match values {
                         Ok((before, after)) => {
-                            let result = materialize_tree_value(&self.store, &path, after).await;
-                            (path, result.map(|value| (before, value)))
+                            let result =
+                                materialize_tree_value(&self.store, &path, after.clone()).await;
+                            (path, result.map(|value| (before, after, value)))
                         }
                         Err(err) => (path, Err(err)),
                     }
@@ -1519,7 +1971,7 @@ impl TreeState {
                 .buffered(self.store.concurrency().max(1)),
         );
         while let Some((path, data)) = diff_stream.next().await {
-            let (before, after) = data?;
+            let (before, after_value, after) = data?;
             if after.is_absent() {
                 stats.removed_files += 1;
             } else if before.is_absent() {
@@ -1551,6 +2003,9 @@ impl TreeState {
                 stats.skipped_files += 1;
                 continue;
             };
+            if !before.is_resolved() {
+                self.remove_conflict_sidecars(&path, &before).await?;
+            }
             // If the path was present, check reserved path first and delete it.
             let present_file_deleted = before.is_present() && remove_old_file(&disk_path)?;
             // If not, create temporary file to test the path validity.
@@ -1597,14 +2052,25 @@ impl TreeState {
                     contents,
                     executable,
                 } => {
-                    let mut data = vec![];
-                    materialize_merge_result(&contents, &mut data)
-                        .expect("Failed to materialize conflict to in-memory buffer");
-                    self.write_conflict(&disk_path, data, executable)?
+                    if contents.iter().any(|content| content.contains(&b'\0')) {
+                        // Conflict markers can't be added to binary files, so
+                        // write the sides to separate files instead.
+                        self.write_conflict_sidecars(&path, &after_value, new_tree)
+                            .await?;
+                        let data = after_value.describe().into_bytes();
+                        self.write_conflict(&disk_path, data, false)?
+                    } else {
+                        let mut data = vec![];
+                        materialize_merge_result(&contents, &mut data)
+                            .expect("Failed to materialize conflict to in-memory buffer");
+                        self.write_conflict(&disk_path, data, executable)?
+                    }
                 }
                 MaterializedTreeValue::OtherConflict { id } => {
                     // Unless all terms are regular files, we can't do much
-                    // better than trying to describe the merge.
+                    // better than trying to describe the merge, and writing
+                    // the sides to separate files.
+                    self.write_conflict_sidecars(&path, &id, new_tree).await?;
                     let data = id.describe().into_bytes();
                     let executable = false;
                     self.write_conflict(&disk_path, data, executable)?
@@ -1680,6 +2146,31 @@ impl TreeState {
     }
 }
 
+/// Returns the reason for leaving `path` untracked if it's ignored.
+fn ignore_reason(git_ignore: &GitIgnoreFile, path: &str) -> Option<UntrackedReason> {
+    git_ignore
+        .matching_pattern(path)
+        .filter(|ignore| !ignore.is_negated)
+        .map(UntrackedReason::Ignored)
+}
+
+/// Whether `content` is the placeholder written instead of the `conflict` when
+/// its sides are written to sidecar files.
+fn is_conflict_placeholder(content: &[u8], conflict: &MergedTreeValue) -> bool {
+    content == conflict.describe().as_bytes()
+}
+
+fn remove_conflict_sidecar(disk_path: &Path) -> Result<(), CheckoutError> {
+    match fs::remove_file(disk_path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(CheckoutError::Other {
+            message: format!("Failed to remove file {}", disk_path.display()),
+            err: err.into(),
+        }),
+    }
+}
+
 fn checkout_error_for_stat_error(err: io::Error, path: &Path) -> CheckoutError {
     CheckoutError::Other {
         message: format!("Failed to stat file {}", path.display()),
@@ -1895,6 +2386,20 @@ impl LocalWorkingCopy {
             })
     }
 
+    /// Returns the paths that a snapshot would leave untracked. See
+    /// [`TreeState::untracked_paths()`].
+    pub fn untracked_paths(
+        &self,
+        options: &SnapshotOptions,
+        matcher: &dyn Matcher,
+    ) -> Result<Vec<UntrackedPath>, SnapshotError> {
+        let tree_state = self.tree_state().map_err(|err| SnapshotError::Other {
+            message: "Failed to read the working copy state".to_string(),
+            err: err.into(),
+        })?;
+        tree_state.untracked_paths(options, matcher)
+    }
+
     #[cfg(feature = "watchman")]
     pub fn is_watchman_trigger_registered(
         &self,
@@ -1984,6 +2489,13 @@ impl LockedWorkingCopy for LockedLocalWorkingCopy {
         Ok(tree_state.current_tree_id().clone())
     }
 
+    fn snapshot_copies(&self) -> Vec<RecordedCopy> {
+        self.wc.tree_state().map_or_else(
+            |_| vec![],
+            |tree_state| tree_state.snapshot_copies().to_vec(),
+        )
+    }
+
     fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError> {
         // TODO: Write a "pending_checkout" file with the new TreeId so we can
         // continue an interrupted update if we find such a file.
@@ -0,0 +1,855 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Operation store that appends operations and views to a single log file.
+//!
+//! Views are mostly stored as deltas against the previously written view, so
+//! writing an operation doesn't have to serialize all bookmarks and refs. A
+//! full view is written every [`MAX_VIEW_DELTA_CHAIN`] views to bound the cost
+//! of reading.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read as _;
+use std::io::Seek as _;
+use std::io::SeekFrom;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::SystemTime;
+
+use itertools::Itertools as _;
+use prost::Message as _;
+use tempfile::NamedTempFile;
+use thiserror::Error;
+
+use crate::content_hash::blake2b_hash;
+use crate::dag_walk;
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::lock::FileLock;
+use crate::object_id::HexPrefix;
+use crate::object_id::ObjectId;
+use crate::object_id::PrefixResolution;
+use crate::op_store::OpStore;
+use crate::op_store::OpStoreError;
+use crate::op_store::OpStoreGcStats;
+use crate::op_store::OpStoreResult;
+use crate::op_store::Operation;
+use crate::op_store::OperationId;
+use crate::op_store::RootOperationData;
+use crate::op_store::View;
+use crate::op_store::ViewId;
+use crate::protos::log_op_store::log_entry;
+use crate::protos::log_op_store::LogEntry;
+use crate::simple_op_store::apply_view_delta;
+use crate::simple_op_store::operation_from_proto;
+use crate::simple_op_store::operation_to_proto;
+use crate::simple_op_store::view_delta_to_proto;
+use crate::simple_op_store::view_from_proto;
+use crate::simple_op_store::view_to_proto;
+
+// BLAKE2b-512 hash length in bytes
+const OPERATION_ID_LENGTH: usize = 64;
+const VIEW_ID_LENGTH: usize = 64;
+
+const LOG_FILE_NAME: &str = "log";
+const LOCK_FILE_NAME: &str = "log.lock";
+const FILE_MAGIC: &[u8; 8] = b"jjoplog1";
+/// Length of the file header. The header consists of the magic bytes and a
+/// random generation number, which changes when the file is rewritten by gc.
+const HEADER_LENGTH: u64 = 24;
+
+/// Maximum number of deltas to apply to reconstruct a view.
+pub const MAX_VIEW_DELTA_CHAIN: usize = 64;
+
+#[derive(Debug, Error)]
+enum LogError {
+    #[error("Invalid operation log file header")]
+    InvalidHeader,
+    #[error("Failed to decode operation log entry at offset {offset}")]
+    Decode {
+        offset: u64,
+        #[source]
+        err: prost::DecodeError,
+    },
+    #[error(transparent)]
+    Path(#[from] PathError),
+}
+
+impl From<LogError> for OpStoreError {
+    fn from(err: LogError) -> Self {
+        OpStoreError::Other(err.into())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ViewLocation {
+    offset: u64,
+    /// Number of deltas to apply on top of a full view.
+    depth: usize,
+}
+
+/// In-memory index of the entries in the log file.
+#[derive(Debug, Default)]
+struct LogIndex {
+    generation: [u8; 16],
+    /// Length of the file that has been indexed.
+    len: u64,
+    operations: HashMap<OperationId, u64>,
+    views: HashMap<ViewId, ViewLocation>,
+    /// The last view appended to the file, which new views are diffed against.
+    last_view: Option<(ViewId, ViewLocation)>,
+}
+
+/// Operation store backed by an append-only log file.
+#[derive(Debug)]
+pub struct LogOpStore {
+    path: PathBuf,
+    root_data: RootOperationData,
+    root_operation_id: OperationId,
+    root_view_id: ViewId,
+    index: Mutex<LogIndex>,
+}
+
+impl LogOpStore {
+    /// Returns the name of the operation store type.
+    pub fn name() -> &'static str {
+        "log_op_store"
+    }
+
+    /// Creates an empty OpStore, panics if it already exists
+    pub fn init(store_path: &Path, root_data: RootOperationData) -> Self {
+        let log_path = store_path.join(LOG_FILE_NAME);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&log_path)
+            .unwrap();
+        file.write_all(&new_header()).unwrap();
+        Self::load(store_path, root_data)
+    }
+
+    /// Load an existing OpStore
+    pub fn load(store_path: &Path, root_data: RootOperationData) -> Self {
+        LogOpStore {
+            path: store_path.to_path_buf(),
+            root_data,
+            root_operation_id: OperationId::from_bytes(&[0; OPERATION_ID_LENGTH]),
+            root_view_id: ViewId::from_bytes(&[0; VIEW_ID_LENGTH]),
+            index: Mutex::new(LogIndex::default()),
+        }
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.path.join(LOG_FILE_NAME)
+    }
+
+    fn lock(&self) -> OpStoreResult<FileLock> {
+        FileLock::lock(self.path.join(LOCK_FILE_NAME))
+            .map_err(|err| OpStoreError::Other(err.into()))
+    }
+
+    /// Indexes entries appended since the last call. If the file has been
+    /// rewritten, the whole file is indexed again.
+    fn refresh_index(&self, index: &mut LogIndex) -> Result<(), LogError> {
+        let path = self.log_path();
+        let mut file = File::open(&path).context(&path)?;
+        let generation = read_header(&mut file, &path)?;
+        if generation != index.generation {
+            *index = LogIndex {
+                generation,
+                len: HEADER_LENGTH,
+                ..Default::default()
+            };
+        }
+        file.seek(SeekFrom::Start(index.len)).context(&path)?;
+        let mut buf = vec![];
+        file.read_to_end(&mut buf).context(&path)?;
+        let mut pos = 0;
+        // An incomplete entry at the end is being written by another process.
+        while let Some((entry_len, entry_buf)) = split_entry(&buf[pos..]) {
+            let offset = index.len + pos as u64;
+            let entry =
+                LogEntry::decode(entry_buf).map_err(|err| LogError::Decode { offset, err })?;
+            match entry.object {
+                Some(log_entry::Object::Operation(_)) => {
+                    index.operations.insert(OperationId::new(entry.id), offset);
+                }
+                Some(log_entry::Object::View(_)) => {
+                    let location = ViewLocation { offset, depth: 0 };
+                    let id = ViewId::new(entry.id);
+                    index.views.insert(id.clone(), location);
+                    index.last_view = Some((id, location));
+                }
+                Some(log_entry::Object::ViewDelta(delta)) => {
+                    let base_depth = index
+                        .views
+                        .get(&ViewId::new(delta.base_view_id))
+                        .map_or(0, |base| base.depth);
+                    let location = ViewLocation {
+                        offset,
+                        depth: base_depth + 1,
+                    };
+                    let id = ViewId::new(entry.id);
+                    index.views.insert(id.clone(), location);
+                    index.last_view = Some((id, location));
+                }
+                None => {}
+            }
+            pos += entry_len;
+        }
+        index.len += pos as u64;
+        Ok(())
+    }
+
+    /// Locks the index. If `find` doesn't find the entry, the index is
+    /// refreshed to pick up entries written by other processes.
+    fn index_with<T>(
+        &self,
+        find: impl Fn(&LogIndex) -> Option<T>,
+    ) -> OpStoreResult<(MutexGuard<'_, LogIndex>, Option<T>)> {
+        let mut index = self.index.lock().unwrap();
+        if let Some(found) = find(&index) {
+            return Ok((index, Some(found)));
+        }
+        self.refresh_index(&mut index)?;
+        let found = find(&index);
+        Ok((index, found))
+    }
+
+    fn read_entry(&self, index: &mut LogIndex, offset: u64) -> Result<LogEntry, LogError> {
+        let path = self.log_path();
+        let mut file = File::open(&path).context(&path)?;
+        if read_header(&mut file, &path)? != index.generation {
+            // The file was rewritten by gc. The caller should retry.
+            self.refresh_index(index)?;
+            return Err(LogError::InvalidHeader);
+        }
+        file.seek(SeekFrom::Start(offset)).context(&path)?;
+        let mut len_buf = [0; 4];
+        file.read_exact(&mut len_buf).context(&path)?;
+        let mut buf = vec![0; u32::from_le_bytes(len_buf) as usize];
+        file.read_exact(&mut buf).context(&path)?;
+        LogEntry::decode(&*buf).map_err(|err| LogError::Decode { offset, err })
+    }
+
+    /// Reads the entry for the given object. The file may have been rewritten
+    /// since the index was built, in which case it's retried once.
+    fn read_object(
+        &self,
+        index: &mut MutexGuard<'_, LogIndex>,
+        id: &impl ObjectId,
+        find: impl Fn(&LogIndex) -> Option<u64>,
+    ) -> OpStoreResult<log_entry::Object> {
+        let not_found = || OpStoreError::ObjectNotFound {
+            object_type: id.object_type(),
+            hash: id.hex(),
+            source: "No such entry in operation log".into(),
+        };
+        let offset = find(index).ok_or_else(not_found)?;
+        let entry = match self.read_entry(index, offset) {
+            Err(LogError::InvalidHeader) => {
+                let offset = find(index).ok_or_else(not_found)?;
+                self.read_entry(index, offset)?
+            }
+            result => result?,
+        };
+        if entry.id != id.as_bytes() {
+            return Err(OpStoreError::ReadObject {
+                object_type: id.object_type(),
+                hash: id.hex(),
+                source: "Operation log entry has mismatched ID".into(),
+            });
+        }
+        entry.object.ok_or_else(not_found)
+    }
+
+    /// Reconstructs the view proto by applying deltas on top of the nearest
+    /// full view.
+    fn read_view_proto(
+        &self,
+        index: &mut MutexGuard<'_, LogIndex>,
+        id: &ViewId,
+    ) -> OpStoreResult<crate::protos::op_store::View> {
+        let mut deltas = vec![];
+        let mut current_id = id.clone();
+        let base = loop {
+            if current_id == self.root_view_id {
+                break view_to_proto(&View::make_root(self.root_data.root_commit_id.clone()));
+            }
+            let find = |index: &LogIndex| index.views.get(&current_id).map(|loc| loc.offset);
+            match self.read_object(index, &current_id, find)? {
+                log_entry::Object::View(view) => break view,
+                log_entry::Object::ViewDelta(delta) => {
+                    current_id = ViewId::new(delta.base_view_id.clone());
+                    deltas.push(delta);
+                }
+                log_entry::Object::Operation(_) => {
+                    return Err(OpStoreError::ReadObject {
+                        object_type: id.object_type(),
+                        hash: id.hex(),
+                        source: "Operation log entry is not a view".into(),
+                    });
+                }
+            }
+        };
+        Ok(deltas.into_iter().rev().fold(base, apply_view_delta))
+    }
+
+    /// Encodes the view as a delta against the `base` view if that's smaller
+    /// than the full view.
+    fn view_to_log_object(
+        &self,
+        index: &mut MutexGuard<'_, LogIndex>,
+        proto: crate::protos::op_store::View,
+        base: Option<(&ViewId, ViewLocation)>,
+    ) -> OpStoreResult<(log_entry::Object, usize)> {
+        let Some((base_id, base_location)) =
+            base.filter(|(_, location)| location.depth < MAX_VIEW_DELTA_CHAIN)
+        else {
+            return Ok((log_entry::Object::View(proto), 0));
+        };
+        let base_proto = self.read_view_proto(index, base_id)?;
+        let delta = view_delta_to_proto(base_id, &base_proto, &proto);
+        if delta.encoded_len() < proto.encoded_len() {
+            Ok((log_entry::Object::ViewDelta(delta), base_location.depth + 1))
+        } else {
+            Ok((log_entry::Object::View(proto), 0))
+        }
+    }
+
+    /// Appends an entry to the log file. The caller should hold the file lock
+    /// and have refreshed the index.
+    fn append_entry(
+        &self,
+        index: &mut LogIndex,
+        id: Vec<u8>,
+        object: log_entry::Object,
+    ) -> Result<u64, LogError> {
+        let path = self.log_path();
+        let entry = LogEntry {
+            id,
+            written_millis_since_epoch: now_millis(),
+            object: Some(object),
+        };
+        let mut file = OpenOptions::new().write(true).open(&path).context(&path)?;
+        // The caller holds the lock and has refreshed the index, so anything
+        // past the last complete entry was left by an interrupted writer.
+        // Drop it so the new entry doesn't end up behind the garbage.
+        file.set_len(index.len).context(&path)?;
+        file.seek(SeekFrom::Start(index.len)).context(&path)?;
+        file.write_all(&encode_entry(&entry)).context(&path)?;
+        let offset = index.len;
+        // Index the entry we've just written, which is at the end of the file.
+        self.refresh_index(index)?;
+        Ok(offset)
+    }
+}
+
+impl OpStore for LogOpStore {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        Self::name()
+    }
+
+    fn root_operation_id(&self) -> &OperationId {
+        &self.root_operation_id
+    }
+
+    fn read_view(&self, id: &ViewId) -> OpStoreResult<View> {
+        if *id == self.root_view_id {
+            return Ok(View::make_root(self.root_data.root_commit_id.clone()));
+        }
+        let (mut index, _) = self.index_with(|index| index.views.get(id).copied())?;
+        let proto = self.read_view_proto(&mut index, id)?;
+        Ok(view_from_proto(proto))
+    }
+
+    fn write_view(&self, view: &View) -> OpStoreResult<ViewId> {
+        let id = ViewId::new(blake2b_hash(view).to_vec());
+        let _lock = self.lock()?;
+        let mut index = self.index.lock().unwrap();
+        self.refresh_index(&mut index)?;
+        if index.views.contains_key(&id) {
+            return Ok(id);
+        }
+        let last_view = index.last_view.clone();
+        let (object, _) = self.view_to_log_object(
+            &mut index,
+            view_to_proto(view),
+            last_view.as_ref().map(|(id, location)| (id, *location)),
+        )?;
+        self.append_entry(&mut index, id.to_bytes(), object)?;
+        Ok(id)
+    }
+
+    fn read_operation(&self, id: &OperationId) -> OpStoreResult<Operation> {
+        if *id == self.root_operation_id {
+            return Ok(Operation::make_root(self.root_view_id.clone()));
+        }
+        let find = |index: &LogIndex| index.operations.get(id).copied();
+        let (mut index, _) = self.index_with(find)?;
+        let log_entry::Object::Operation(proto) = self.read_object(&mut index, id, find)? else {
+            return Err(OpStoreError::ReadObject {
+                object_type: id.object_type(),
+                hash: id.hex(),
+                source: "Operation log entry is not an operation".into(),
+            });
+        };
+        let mut operation = operation_from_proto(proto);
+        if operation.parents.is_empty() {
+            operation.parents.push(self.root_operation_id.clone());
+        }
+        Ok(operation)
+    }
+
+    fn write_operation(&self, operation: &Operation) -> OpStoreResult<OperationId> {
+        assert!(!operation.parents.is_empty());
+        let id = OperationId::new(blake2b_hash(operation).to_vec());
+        let _lock = self.lock()?;
+        let mut index = self.index.lock().unwrap();
+        self.refresh_index(&mut index)?;
+        if !index.operations.contains_key(&id) {
+            let object = log_entry::Object::Operation(operation_to_proto(operation));
+            self.append_entry(&mut index, id.to_bytes(), object)?;
+        }
+        Ok(id)
+    }
+
+    fn resolve_operation_id_prefix(
+        &self,
+        prefix: &HexPrefix,
+    ) -> OpStoreResult<PrefixResolution<OperationId>> {
+        let mut index = self.index.lock().unwrap();
+        self.refresh_index(&mut index)?;
+        let matches_root = prefix.matches(&self.root_operation_id);
+        let mut matched = index
+            .operations
+            .keys()
+            .filter(|id| prefix.matches(*id))
+            .chain(matches_root.then_some(&self.root_operation_id));
+        match (matched.next(), matched.next()) {
+            (None, _) => Ok(PrefixResolution::NoMatch),
+            (Some(id), None) => Ok(PrefixResolution::SingleMatch(id.clone())),
+            (Some(_), Some(_)) => Ok(PrefixResolution::AmbiguousMatch),
+        }
+    }
+
+    fn all_operation_ids(&self) -> OpStoreResult<Vec<OperationId>> {
+        let mut index = self.index.lock().unwrap();
+        self.refresh_index(&mut index)?;
+        Ok(index.operations.keys().cloned().collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn gc(
+        &self,
+        head_ids: &[OperationId],
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> OpStoreResult<OpStoreGcStats> {
+        let read_op = |id: &OperationId| self.read_operation(id).map(|data| (id.clone(), data));
+        let reachable_ops: HashMap<OperationId, Operation> = dag_walk::dfs_ok(
+            head_ids.iter().map(read_op),
+            |(id, _)| id.clone(),
+            |(_, data)| data.parents.iter().map(read_op).collect_vec(),
+        )
+        .try_collect()?;
+        let reachable_views: HashSet<&ViewId> =
+            reachable_ops.values().map(|data| &data.view_id).collect();
+        let keep_newer_millis = keep_newer
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_millis() as i64);
+
+        let _lock = self.lock()?;
+        let mut index = self.index.lock().unwrap();
+        self.refresh_index(&mut index)?;
+        let entries = read_all_entries(&self.log_path(), &index)?;
+        let mut stats = OpStoreGcStats::default();
+        let mut kept_entries = vec![];
+        for (offset, entry) in entries {
+            let is_new = entry.written_millis_since_epoch > keep_newer_millis;
+            match &entry.object {
+                Some(log_entry::Object::Operation(_)) => {
+                    if is_new || reachable_ops.contains_key(&OperationId::new(entry.id.clone())) {
+                        kept_entries.push((offset, entry));
+                    } else {
+                        stats.pruned_operations += 1;
+                    }
+                }
+                Some(log_entry::Object::View(_) | log_entry::Object::ViewDelta(_)) => {
+                    if is_new || reachable_views.contains(&ViewId::new(entry.id.clone())) {
+                        kept_entries.push((offset, entry));
+                    } else {
+                        stats.pruned_views += 1;
+                    }
+                }
+                None => {}
+            }
+        }
+        if dry_run || (stats.pruned_operations == 0 && stats.pruned_views == 0) {
+            return Ok(stats);
+        }
+
+        // Rewrite the log file. Views are re-encoded since their delta bases
+        // may have been pruned.
+        let path = self.log_path();
+        let mut new_index = LogIndex {
+            generation: rand::random(),
+            len: HEADER_LENGTH,
+            ..Default::default()
+        };
+        let mut buf = header_with_generation(&new_index.generation);
+        for (_, mut entry) in kept_entries {
+            let offset = buf.len() as u64;
+            match entry.object.take() {
+                Some(log_entry::Object::Operation(proto)) => {
+                    new_index
+                        .operations
+                        .insert(OperationId::new(entry.id.clone()), offset);
+                    entry.object = Some(log_entry::Object::Operation(proto));
+                }
+                Some(log_entry::Object::View(_) | log_entry::Object::ViewDelta(_)) => {
+                    let id = ViewId::new(entry.id.clone());
+                    let proto = self.read_view_proto(&mut index, &id)?;
+                    // The base view is read from the old file, where it also
+                    // exists since it's kept.
+                    let base = new_index.last_view.clone();
+                    let (object, depth) = self.view_to_log_object(
+                        &mut index,
+                        proto,
+                        base.as_ref().map(|(id, location)| (id, *location)),
+                    )?;
+                    let location = ViewLocation { offset, depth };
+                    new_index.views.insert(id.clone(), location);
+                    new_index.last_view = Some((id, location));
+                    entry.object = Some(object);
+                }
+                None => continue,
+            }
+            buf.extend(encode_entry(&entry));
+        }
+        new_index.len = buf.len() as u64;
+        let write = || -> Result<(), PathError> {
+            let temp_file = NamedTempFile::new_in(&self.path).context(&self.path)?;
+            temp_file
+                .as_file()
+                .write_all(&buf)
+                .context(temp_file.path())?;
+            temp_file
+                .persist(&path)
+                .map_err(|err| err.error)
+                .context(&path)?;
+            Ok(())
+        };
+        write().map_err(|err| OpStoreError::Other(err.into()))?;
+        *index = new_index;
+        Ok(stats)
+    }
+}
+
+fn new_header() -> Vec<u8> {
+    header_with_generation(&rand::random())
+}
+
+fn header_with_generation(generation: &[u8; 16]) -> Vec<u8> {
+    [FILE_MAGIC.as_slice(), generation.as_slice()].concat()
+}
+
+fn read_header(file: &mut File, path: &Path) -> Result<[u8; 16], LogError> {
+    let mut header = [0; HEADER_LENGTH as usize];
+    file.read_exact(&mut header).context(path)?;
+    let (magic, generation) = header.split_at(FILE_MAGIC.len());
+    if magic != FILE_MAGIC {
+        return Err(LogError::InvalidHeader);
+    }
+    Ok(generation.try_into().unwrap())
+}
+
+fn encode_entry(entry: &LogEntry) -> Vec<u8> {
+    let data = entry.encode_to_vec();
+    let len = u32::try_from(data.len()).expect("operation log entry should be < 4GiB");
+    [len.to_le_bytes().as_slice(), &data].concat()
+}
+
+/// Splits the first complete entry from the buffer. Returns the length of the
+/// entry including the length prefix, and the encoded entry.
+fn split_entry(buf: &[u8]) -> Option<(usize, &[u8])> {
+    let len_bytes = buf.get(..4)?;
+    let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    let data = buf[4..].get(..len)?;
+    Some((4 + len, data))
+}
+
+/// Reads all indexed entries in file order.
+fn read_all_entries(path: &Path, index: &LogIndex) -> Result<Vec<(u64, LogEntry)>, LogError> {
+    let buf = fs::read(path).context(path)?;
+    let mut entries = vec![];
+    let mut pos = HEADER_LENGTH as usize;
+    while pos < index.len as usize {
+        let Some((entry_len, entry_buf)) = split_entry(&buf[pos..]) else {
+            break;
+        };
+        let offset = pos as u64;
+        let entry = LogEntry::decode(entry_buf).map_err(|err| LogError::Decode { offset, err })?;
+        entries.push((offset, entry));
+        pos += entry_len;
+    }
+    Ok(entries)
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+    use std::time::Duration;
+
+    use maplit::hashmap;
+    use maplit::hashset;
+
+    use super::*;
+    use crate::backend::CommitId;
+    use crate::backend::MillisSinceEpoch;
+    use crate::backend::Timestamp;
+    use crate::op_store;
+    use crate::op_store::OperationMetadata;
+    use crate::op_store::RefTarget;
+    use crate::simple_op_store::SimpleOpStore;
+
+    fn root_data() -> RootOperationData {
+        RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        }
+    }
+
+    fn create_view(i: usize) -> View {
+        let commit_id = |i: usize| CommitId::new(i.to_be_bytes().to_vec());
+        let mut view = View::empty();
+        view.head_ids = hashset! {commit_id(i)};
+        view.wc_commit_ids = hashmap! {
+            Default::default() => commit_id(i),
+        };
+        for j in 0..20 {
+            view.local_bookmarks
+                .insert(format!("bookmark{j}"), RefTarget::normal(commit_id(j)));
+        }
+        view.local_bookmarks
+            .insert("current".to_string(), RefTarget::normal(commit_id(i)));
+        view
+    }
+
+    fn create_operation(view_id: ViewId, parents: Vec<OperationId>) -> Operation {
+        let timestamp = Timestamp {
+            timestamp: MillisSinceEpoch(0),
+            tz_offset: 0,
+        };
+        Operation {
+            view_id,
+            parents,
+            metadata: OperationMetadata {
+                start_time: timestamp,
+                end_time: timestamp,
+                description: "test".to_string(),
+                hostname: "host".to_string(),
+                username: "user".to_string(),
+                is_snapshot: false,
+                tags: Default::default(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_read_write_view() {
+        let temp_dir = testutils::new_temp_dir();
+        let store = LogOpStore::init(temp_dir.path(), root_data());
+        let view_ids = (0..MAX_VIEW_DELTA_CHAIN * 2 + 1)
+            .map(|i| store.write_view(&create_view(i)).unwrap())
+            .collect_vec();
+        // Writing the same view again doesn't append anything
+        let len = fs::metadata(store.log_path()).unwrap().len();
+        assert_eq!(store.write_view(&create_view(3)).unwrap(), view_ids[3]);
+        assert_eq!(fs::metadata(store.log_path()).unwrap().len(), len);
+
+        // Views are stored as deltas with periodic full views
+        let index = store.index.lock().unwrap();
+        assert_eq!(index.views[&view_ids[0]].depth, 0);
+        assert_eq!(index.views[&view_ids[1]].depth, 1);
+        assert_eq!(index.views[&view_ids[MAX_VIEW_DELTA_CHAIN]].depth, 64);
+        assert_eq!(index.views[&view_ids[MAX_VIEW_DELTA_CHAIN + 1]].depth, 0);
+        drop(index);
+
+        // Another instance sees the same views
+        let store = LogOpStore::load(temp_dir.path(), root_data());
+        for (i, id) in view_ids.iter().enumerate() {
+            assert_eq!(store.read_view(id).unwrap(), create_view(i));
+        }
+    }
+
+    #[test]
+    fn test_read_write_operation() {
+        let temp_dir = testutils::new_temp_dir();
+        let store = LogOpStore::init(temp_dir.path(), root_data());
+        let view_id = store.write_view(&create_view(0)).unwrap();
+        let operation = create_operation(view_id, vec![store.root_operation_id().clone()]);
+        let op_id = store.write_operation(&operation).unwrap();
+        assert_eq!(store.read_operation(&op_id).unwrap(), operation);
+
+        // Writes from another instance are picked up
+        let other_store = LogOpStore::load(temp_dir.path(), root_data());
+        let operation2 = create_operation(operation.view_id.clone(), vec![op_id.clone()]);
+        let op_id2 = other_store.write_operation(&operation2).unwrap();
+        assert_eq!(store.read_operation(&op_id2).unwrap(), operation2);
+
+        let resolve = |hex: &str| {
+            store
+                .resolve_operation_id_prefix(&HexPrefix::new(hex).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            resolve(&op_id.hex()),
+            PrefixResolution::SingleMatch(op_id.clone())
+        );
+        assert_eq!(
+            resolve("000"),
+            PrefixResolution::SingleMatch(store.root_operation_id().clone())
+        );
+        assert_eq!(resolve(""), PrefixResolution::AmbiguousMatch);
+    }
+
+    #[test]
+    fn test_append_after_truncated_entry() {
+        let temp_dir = testutils::new_temp_dir();
+        let store = LogOpStore::init(temp_dir.path(), root_data());
+        let view_id = store.write_view(&create_view(0)).unwrap();
+        let operation = create_operation(view_id.clone(), vec![store.root_operation_id().clone()]);
+        let op_id = store.write_operation(&operation).unwrap();
+
+        // Simulate a writer that was interrupted in the middle of an entry
+        let complete_len = fs::metadata(store.log_path()).unwrap().len();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(store.log_path())
+            .unwrap();
+        file.write_all(&100u32.to_le_bytes()).unwrap();
+        file.write_all(b"torn").unwrap();
+        drop(file);
+
+        // The torn entry is dropped before the next entry is appended
+        let store = LogOpStore::load(temp_dir.path(), root_data());
+        let operation2 = create_operation(view_id, vec![op_id.clone()]);
+        let op_id2 = store.write_operation(&operation2).unwrap();
+        let index = store.index.lock().unwrap();
+        assert_eq!(index.operations[&op_id2], complete_len);
+        assert_eq!(index.len, fs::metadata(store.log_path()).unwrap().len());
+        drop(index);
+
+        let store = LogOpStore::load(temp_dir.path(), root_data());
+        assert_eq!(store.read_operation(&op_id).unwrap(), operation);
+        assert_eq!(store.read_operation(&op_id2).unwrap(), operation2);
+    }
+
+    #[test]
+    fn test_gc() {
+        let temp_dir = testutils::new_temp_dir();
+        let store = LogOpStore::init(temp_dir.path(), root_data());
+        let root_op_id = store.root_operation_id().clone();
+        let mut head_id = root_op_id.clone();
+        let mut op_ids = vec![];
+        for i in 0..5 {
+            let view_id = store.write_view(&create_view(i)).unwrap();
+            head_id = store
+                .write_operation(&create_operation(view_id, vec![head_id]))
+                .unwrap();
+            op_ids.push(head_id.clone());
+        }
+        // Abandon the first two operations
+        let mut operation = store.read_operation(&op_ids[2]).unwrap();
+        operation.parents = vec![root_op_id];
+        let new_id = store.write_operation(&operation).unwrap();
+        let mut operation = store.read_operation(&op_ids[3]).unwrap();
+        operation.parents = vec![new_id];
+        let new_id = store.write_operation(&operation).unwrap();
+        let mut operation = store.read_operation(&op_ids[4]).unwrap();
+        operation.parents = vec![new_id];
+        let new_head_id = store.write_operation(&operation).unwrap();
+
+        let keep_newer = SystemTime::now() + Duration::from_secs(1);
+        let stats = store
+            .gc(slice::from_ref(&new_head_id), keep_newer, true)
+            .unwrap();
+        assert_eq!(stats.pruned_operations, 5);
+        assert_eq!(stats.pruned_views, 2);
+        assert!(store.read_operation(&op_ids[0]).is_ok());
+
+        let stats = store
+            .gc(slice::from_ref(&new_head_id), keep_newer, false)
+            .unwrap();
+        assert_eq!(stats.pruned_operations, 5);
+        assert_eq!(stats.pruned_views, 2);
+        assert!(store.read_operation(&op_ids[0]).is_err());
+        assert!(store.read_view(&create_view_id(0)).is_err());
+
+        // The remaining views are readable by a new instance too
+        let store = LogOpStore::load(temp_dir.path(), root_data());
+        let operation = store.read_operation(&new_head_id).unwrap();
+        assert_eq!(store.read_view(&operation.view_id).unwrap(), create_view(4));
+    }
+
+    fn create_view_id(i: usize) -> ViewId {
+        ViewId::new(blake2b_hash(&create_view(i)).to_vec())
+    }
+
+    #[test]
+    fn test_copy_operations_from_simple_store() {
+        let temp_dir = testutils::new_temp_dir();
+        let simple_path = temp_dir.path().join("simple");
+        let log_path = temp_dir.path().join("log");
+        fs::create_dir(&simple_path).unwrap();
+        fs::create_dir(&log_path).unwrap();
+        let source = SimpleOpStore::init(&simple_path, root_data());
+        let target = LogOpStore::init(&log_path, root_data());
+
+        let mut head_id = source.root_operation_id().clone();
+        for i in 0..3 {
+            let view_id = source.write_view(&create_view(i)).unwrap();
+            head_id = source
+                .write_operation(&create_operation(view_id, vec![head_id]))
+                .unwrap();
+        }
+        let num_copied = op_store::copy_operations(&source, &target, &[head_id.clone()]).unwrap();
+        assert_eq!(num_copied, 3);
+        let operation = target.read_operation(&head_id).unwrap();
+        assert_eq!(operation, source.read_operation(&head_id).unwrap());
+        assert_eq!(
+            target.read_view(&operation.view_id).unwrap(),
+            create_view(2)
+        );
+    }
+}
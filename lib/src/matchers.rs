@@ -24,6 +24,7 @@ use itertools::Itertools as _;
 use tracing::instrument;
 
 use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
 use crate::repo_path::RepoPathComponentBuf;
 
 #[derive(PartialEq, Eq, Debug)]
@@ -244,6 +245,7 @@ fn prefix_tree_to_visit_sets(tree: &RepoPathTree<PrefixNodeKind>) -> Visit {
 #[derive(Clone, Debug)]
 pub struct FileGlobsMatcher {
     tree: RepoPathTree<Vec<glob::Pattern>>,
+    case_sensitive: bool,
 }
 
 impl FileGlobsMatcher {
@@ -254,7 +256,28 @@ impl FileGlobsMatcher {
         for (dir, pattern) in dir_patterns {
             tree.add(dir.as_ref()).value.push(pattern);
         }
-        FileGlobsMatcher { tree }
+        FileGlobsMatcher {
+            tree,
+            case_sensitive: true,
+        }
+    }
+
+    /// Creates matcher that compares both `dir`s and `pattern`s
+    /// case-insensitively.
+    ///
+    /// Only ASCII case differences are folded, which is consistent with the
+    /// `glob` crate.
+    pub fn new_case_insensitive<D: AsRef<RepoPath>>(
+        dir_patterns: impl IntoIterator<Item = (D, glob::Pattern)>,
+    ) -> Self {
+        let mut tree: RepoPathTree<Vec<glob::Pattern>> = Default::default();
+        for (dir, pattern) in dir_patterns {
+            tree.add(&fold_ascii_case(dir.as_ref())).value.push(pattern);
+        }
+        FileGlobsMatcher {
+            tree,
+            case_sensitive: false,
+        }
     }
 }
 
@@ -263,38 +286,106 @@ impl Matcher for FileGlobsMatcher {
         // TODO: glob::Pattern relies on path::is_separator() internally, but
         // RepoPath separator should be '/'. One way to address this problem is
         // to switch to globset::Glob, and use the underlying regex pattern.
-        const OPTIONS: glob::MatchOptions = glob::MatchOptions {
-            case_sensitive: true,
+        let options = glob::MatchOptions {
+            case_sensitive: self.case_sensitive,
             require_literal_separator: true,
             require_literal_leading_dot: false,
         };
         // check if any ancestor (dir, patterns) matches 'file'
+        let matches_in_tree = |file: &RepoPath| {
+            self.tree
+                .walk_to(file)
+                .take_while(|(_, tail_path)| !tail_path.is_root()) // only dirs
+                .any(|(sub, tail_path)| {
+                    let name = tail_path.as_internal_file_string();
+                    sub.value.iter().any(|pat| pat.matches_with(name, options))
+                })
+        };
+        if self.case_sensitive {
+            matches_in_tree(file)
+        } else {
+            matches_in_tree(&fold_ascii_case(file))
+        }
+    }
+
+    fn visit(&self, dir: &RepoPath) -> Visit {
+        if self.case_sensitive {
+            dir_patterns_tree_to_visit(&self.tree, dir)
+        } else {
+            // Directories are pruned by their folded names: visiting a
+            // directory that isn't on a (folded) literal prefix of the
+            // patterns returns Nothing. The original names of sub directories
+            // are unknown, so the visit sets can't list them.
+            match dir_patterns_tree_to_visit(&self.tree, &fold_ascii_case(dir)) {
+                Visit::Specific { dirs: _, files } => Visit::Specific {
+                    dirs: VisitDirs::All,
+                    files,
+                },
+                visit => visit,
+            }
+        }
+    }
+}
+
+/// Matches file paths with regular expressions.
+///
+/// Patterns are provided as `(dir, regex)` pairs, where the `regex` will be
+/// searched in the file path relative to `dir`. The path separator is always
+/// `/`.
+#[derive(Clone, Debug)]
+pub struct FileRegexesMatcher {
+    tree: RepoPathTree<Vec<regex::Regex>>,
+}
+
+impl FileRegexesMatcher {
+    pub fn new<D: AsRef<RepoPath>>(
+        dir_regexes: impl IntoIterator<Item = (D, regex::Regex)>,
+    ) -> Self {
+        let mut tree: RepoPathTree<Vec<regex::Regex>> = Default::default();
+        for (dir, regex) in dir_regexes {
+            tree.add(dir.as_ref()).value.push(regex);
+        }
+        FileRegexesMatcher { tree }
+    }
+}
+
+impl Matcher for FileRegexesMatcher {
+    fn matches(&self, file: &RepoPath) -> bool {
+        // check if any ancestor (dir, regexes) matches 'file'
         self.tree
             .walk_to(file)
             .take_while(|(_, tail_path)| !tail_path.is_root()) // only dirs
             .any(|(sub, tail_path)| {
                 let name = tail_path.as_internal_file_string();
-                sub.value.iter().any(|pat| pat.matches_with(name, OPTIONS))
+                sub.value.iter().any(|regex| regex.is_match(name))
             })
     }
 
     fn visit(&self, dir: &RepoPath) -> Visit {
-        for (sub, tail_path) in self.tree.walk_to(dir) {
-            // ancestor of 'dir' has patterns, can't narrow visit anymore
-            if !sub.value.is_empty() {
-                return Visit::Specific {
-                    dirs: VisitDirs::All,
-                    files: VisitFiles::All,
-                };
-            }
-            // 'dir' found, and is an ancestor of pattern paths
-            if tail_path.is_root() {
-                let sub_dirs = sub.entries.keys().cloned().collect();
-                return Visit::sets(sub_dirs, HashSet::new());
-            }
+        dir_patterns_tree_to_visit(&self.tree, dir)
+    }
+}
+
+fn dir_patterns_tree_to_visit<P>(tree: &RepoPathTree<Vec<P>>, dir: &RepoPath) -> Visit {
+    for (sub, tail_path) in tree.walk_to(dir) {
+        // ancestor of 'dir' has patterns, can't narrow visit anymore
+        if !sub.value.is_empty() {
+            return Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            };
+        }
+        // 'dir' found, and is an ancestor of pattern paths
+        if tail_path.is_root() {
+            let sub_dirs = sub.entries.keys().cloned().collect();
+            return Visit::sets(sub_dirs, HashSet::new());
         }
-        Visit::Nothing
     }
+    Visit::Nothing
+}
+
+fn fold_ascii_case(path: &RepoPath) -> RepoPathBuf {
+    RepoPathBuf::from_internal_string(path.as_internal_file_string().to_ascii_lowercase())
 }
 
 /// Matches paths that are matched by any of the input matchers.
@@ -817,6 +908,99 @@ mod tests {
         assert_eq!(m.visit(repo_path("bar")), Visit::Nothing);
     }
 
+    #[test]
+    fn test_fileglobsmatcher_case_insensitive() {
+        let to_pattern = |s| glob::Pattern::new(s).unwrap();
+
+        let m = FileGlobsMatcher::new_case_insensitive([
+            (repo_path("Foo"), to_pattern("*.RS")),
+            (repo_path("bar/Baz"), to_pattern("qux")),
+        ]);
+        assert!(m.matches(repo_path("foo/x.rs")));
+        assert!(m.matches(repo_path("FOO/x.Rs")));
+        assert!(!m.matches(repo_path("foo/bar/x.rs")));
+        assert!(m.matches(repo_path("BAR/baz/QUX")));
+        assert!(!m.matches(repo_path("bar/baz")));
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::Set(hashset! {}),
+            }
+        );
+        assert_eq!(
+            m.visit(repo_path("FOO")),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        );
+        assert_eq!(m.visit(repo_path("qux")), Visit::Nothing);
+        assert_eq!(m.visit(repo_path("bar/qux")), Visit::Nothing);
+        // Literal prefixes are compared case-insensitively
+        assert_eq!(
+            m.visit(repo_path("Bar")),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::Set(hashset! {}),
+            }
+        );
+        assert_eq!(
+            m.visit(repo_path("BAR/bAz")),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        );
+        assert_eq!(m.visit(repo_path("Bar/Qux")), Visit::Nothing);
+        assert_eq!(m.visit(repo_path("bar/baz2")), Visit::Nothing);
+        assert_eq!(m.visit(repo_path("fo")), Visit::Nothing);
+    }
+
+    #[test]
+    fn test_fileregexesmatcher() {
+        let to_regex = |s| regex::Regex::new(s).unwrap();
+
+        let m = FileRegexesMatcher::new([
+            (RepoPath::root(), to_regex(r"_test\.(go|rs)$")),
+            (repo_path("foo"), to_regex(r"^bar/[^/]*$")),
+        ]);
+        assert!(m.matches(repo_path("x_test.rs")));
+        assert!(m.matches(repo_path("dir/x_test.go")));
+        assert!(!m.matches(repo_path("x_test.rs/y")));
+        assert!(!m.matches(repo_path("foo")));
+        assert!(!m.matches(repo_path("foo/bar")));
+        assert!(m.matches(repo_path("foo/bar/x")));
+        assert!(!m.matches(repo_path("foo/bar/x/y")));
+        assert!(!m.matches(repo_path("baz/bar/x")));
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        );
+
+        let m = FileRegexesMatcher::new([(repo_path("foo"), to_regex(r"x"))]);
+        assert!(!m.matches(repo_path("x")));
+        assert!(m.matches(repo_path("foo/x")));
+        assert_eq!(
+            m.visit(RepoPath::root()),
+            Visit::Specific {
+                dirs: VisitDirs::Set(hashset! {RepoPathComponentBuf::from("foo")}),
+                files: VisitFiles::Set(hashset! {}),
+            }
+        );
+        assert_eq!(
+            m.visit(repo_path("foo/bar")),
+            Visit::Specific {
+                dirs: VisitDirs::All,
+                files: VisitFiles::All,
+            }
+        );
+        assert_eq!(m.visit(repo_path("bar")), Visit::Nothing);
+    }
+
     #[test]
     fn test_unionmatcher_concatenate_roots() {
         let m1 = PrefixMatcher::new([repo_path("foo"), repo_path("bar")]);
@@ -0,0 +1,640 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export and import of operations together with the objects they refer to.
+//!
+//! A bundle contains a set of operations, their views, and the commits, trees,
+//! files and symlinks reachable from those views and from the views of the
+//! parent operations of the exported set. The parent operations themselves
+//! aren't included, so they must exist in the repo the bundle is imported
+//! into, but their objects don't have to.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::Read as _;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use pollster::FutureExt as _;
+use prost::Message as _;
+use thiserror::Error;
+
+use crate::backend::BackendError;
+use crate::backend::CommitId;
+use crate::backend::ConflictId;
+use crate::backend::FileId;
+use crate::backend::SymlinkId;
+use crate::backend::TreeId;
+use crate::backend::TreeValue;
+use crate::commit::Commit;
+use crate::dag_walk;
+use crate::local_backend;
+use crate::merge::Merge;
+use crate::object_id::ObjectId;
+use crate::op_heads_store::OpHeadsStoreError;
+use crate::op_store::OpStore;
+use crate::op_store::OpStoreError;
+use crate::op_store::OperationId;
+use crate::op_store::ViewId;
+use crate::operation::Operation;
+use crate::protos::op_bundle as proto;
+use crate::protos::op_store as proto_op_store;
+use crate::repo::RepoLoader;
+use crate::repo::RepoLoaderError;
+use crate::repo_path::RepoPath;
+use crate::repo_path::RepoPathBuf;
+use crate::revset::RevsetEvaluationError;
+use crate::revset::RevsetExpression;
+use crate::simple_op_store;
+use crate::store::Store;
+use crate::tree::Tree;
+use crate::view::View;
+
+/// Error that may occur while exporting or importing an operation bundle.
+#[derive(Debug, Error)]
+pub enum OpBundleError {
+    /// Failed to read or write a commit, tree, or file object.
+    #[error(transparent)]
+    Backend(#[from] BackendError),
+    /// Failed to read or write an operation or view.
+    #[error(transparent)]
+    OpStore(#[from] OpStoreError),
+    /// Failed to update the operation heads.
+    #[error(transparent)]
+    OpHeadsStore(#[from] OpHeadsStoreError),
+    /// Failed to load the repo at an exported operation.
+    #[error(transparent)]
+    RepoLoader(#[from] RepoLoaderError),
+    /// Failed to look up the commits to export.
+    #[error(transparent)]
+    RevsetEvaluation(#[from] RevsetEvaluationError),
+    /// The bundle data couldn't be decoded.
+    #[error("Failed to decode operation bundle")]
+    Decode(#[source] prost::DecodeError),
+    /// A parent of an operation in the bundle doesn't exist in the repo.
+    #[error(
+        "Operation {op_id} has parent operation {parent_id}, which is neither in the bundle nor \
+         in the repo"
+    )]
+    MissingParent {
+        /// Operation in the bundle.
+        op_id: String,
+        /// The missing parent operation.
+        parent_id: String,
+    },
+    /// The bundle was exported from a repo with a different commit backend or
+    /// operation store, which would assign different IDs to the objects.
+    #[error(
+        "The bundle was exported from a repo with {store_type} \"{bundle_name}\", but this \
+         repo uses \"{repo_name}\""
+    )]
+    IncompatibleStore {
+        /// Kind of the store.
+        store_type: &'static str,
+        /// Store type recorded in the bundle.
+        bundle_name: String,
+        /// Store type of the repo.
+        repo_name: String,
+    },
+    /// An object referred to by the bundle doesn't exist in the repo.
+    #[error("The {object_type} {id} is neither in the bundle nor in the repo")]
+    MissingObject {
+        /// Kind of the object.
+        object_type: &'static str,
+        /// ID of the missing object.
+        id: String,
+    },
+    /// An object was written with a different ID than it had in the bundle.
+    ///
+    /// The store types are checked before importing, so this only happens if
+    /// the bundle is corrupted.
+    #[error("The {object_type} {expected} was imported as {actual}")]
+    IdMismatch {
+        /// Kind of the object.
+        object_type: &'static str,
+        /// ID in the bundle.
+        expected: String,
+        /// ID of the written object.
+        actual: String,
+    },
+}
+
+/// Operations and the objects they refer to, which can be transferred to
+/// another repo.
+#[derive(Clone, Debug, Default)]
+pub struct OperationBundle {
+    data: proto::Bundle,
+}
+
+impl OperationBundle {
+    /// Decodes a bundle written by [`OperationBundle::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, OpBundleError> {
+        let data = proto::Bundle::decode(bytes).map_err(OpBundleError::Decode)?;
+        Ok(OperationBundle { data })
+    }
+
+    /// Encodes the bundle.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.data.encode_to_vec()
+    }
+
+    /// IDs of the operations in the bundle. Parents come before children.
+    pub fn operation_ids(&self) -> impl Iterator<Item = OperationId> + '_ {
+        self.data
+            .operations
+            .iter()
+            .map(|entry| OperationId::new(entry.id.clone()))
+    }
+
+    /// Number of commits in the bundle.
+    pub fn num_commits(&self) -> usize {
+        self.data.commits.len()
+    }
+}
+
+/// Writes the given operations and the objects they added to a bundle.
+///
+/// The root operation exists in every repo, so it's never exported.
+pub fn export_operations(
+    repo_loader: &RepoLoader,
+    operations: &[Operation],
+) -> Result<OperationBundle, OpBundleError> {
+    let store = repo_loader.store();
+    let root_op_id = repo_loader.op_store().root_operation_id();
+    let ops_by_id: HashMap<&OperationId, &Operation> = operations
+        .iter()
+        .filter(|op| op.id() != root_op_id)
+        .map(|op| (op.id(), op))
+        .collect();
+    let parents_in_set = |op: &&Operation| -> Vec<&Operation> {
+        op.parent_ids()
+            .iter()
+            .filter_map(|id| ops_by_id.get(id).copied())
+            .collect()
+    };
+    let operations = dag_walk::topo_order_forward(
+        ops_by_id.values().copied(),
+        |op| op.id().clone(),
+        parents_in_set,
+    );
+
+    // Each head operation is loaded along with the exported operations it
+    // descends from, so that the commits of all those operations are indexed.
+    let parent_ids: HashSet<&OperationId> =
+        operations.iter().flat_map(|op| op.parent_ids()).collect();
+    // The commits reachable from the parent operations outside the set must
+    // exist in the destination repo, so only the commits added since then are
+    // exported. Import fails if any of the former are missing.
+    let mut commit_ids = vec![];
+    let mut seen_commit_ids = HashSet::new();
+    for head_op in operations.iter().filter(|op| !parent_ids.contains(op.id())) {
+        let mut referenced_ids = vec![];
+        let mut base_ids = vec![];
+        for op in dag_walk::dfs(vec![*head_op], |op| op.id().clone(), parents_in_set) {
+            referenced_ids.extend(op.view()?.all_referenced_commit_ids().cloned());
+            for parent in op.parents() {
+                let parent = parent?;
+                if !ops_by_id.contains_key(parent.id()) {
+                    base_ids.extend(parent.view()?.all_referenced_commit_ids().cloned());
+                }
+            }
+        }
+        let repo = repo_loader.load_at(head_op)?;
+        let expression =
+            RevsetExpression::commits(base_ids).range(&RevsetExpression::commits(referenced_ids));
+        for commit_id in expression.evaluate(repo.as_ref())?.iter() {
+            let commit_id = commit_id?;
+            if commit_id != *store.root_commit_id() && seen_commit_ids.insert(commit_id.clone()) {
+                commit_ids.push(commit_id);
+            }
+        }
+    }
+    let commits: HashMap<CommitId, Commit> = commit_ids
+        .iter()
+        .map(|id| Ok((id.clone(), store.get_commit(id)?)))
+        .collect::<Result<_, BackendError>>()?;
+    let commits = dag_walk::topo_order_forward(
+        commit_ids.iter().map(|id| &commits[id]),
+        |commit| commit.id().clone(),
+        |commit| {
+            commit
+                .parent_ids()
+                .iter()
+                .filter_map(|id| commits.get(id))
+                .collect_vec()
+        },
+    );
+
+    let mut exporter = ObjectExporter::new(store);
+    exporter.data.backend_name = store.backend_name().to_owned();
+    exporter.data.op_store_name = repo_loader.op_store().name().to_owned();
+    for commit in commits {
+        // The objects of the parent trees are either exported along with the
+        // parents or already exist in the destination repo.
+        let mut parent_trees = vec![];
+        for parent in commit.parents() {
+            for tree_id in parent?.tree_id().to_merge().iter() {
+                parent_trees.push(store.get_tree(RepoPath::root(), tree_id)?);
+            }
+        }
+        for tree_id in commit.tree_id().to_merge().iter() {
+            exporter.export_tree(RepoPath::root(), tree_id, &parent_trees)?;
+        }
+        let mut commit_proto = local_backend::commit_to_proto(commit.store_commit());
+        commit_proto.secure_sig = commit
+            .store_commit()
+            .secure_sig
+            .as_ref()
+            .map(|sig| sig.sig.clone());
+        exporter.data.commits.push(proto::CommitEntry {
+            id: commit.id().to_bytes(),
+            commit: Some(commit_proto),
+        });
+    }
+
+    let op_store = repo_loader.op_store();
+    let mut data = exporter.data;
+    for op in operations {
+        let view = op_store.read_view(op.view_id())?;
+        data.views.push(proto::ViewEntry {
+            id: op.view_id().to_bytes(),
+            view: Some(simple_op_store::view_to_proto(&view)),
+        });
+        data.operations.push(proto::OperationEntry {
+            id: op.id().to_bytes(),
+            operation: Some(simple_op_store::operation_to_proto(op.store_operation())),
+        });
+    }
+    Ok(OperationBundle { data })
+}
+
+/// Collects the trees, files, symlinks and conflicts reachable from a tree,
+/// skipping those that were already collected.
+struct ObjectExporter<'a> {
+    store: &'a Arc<Store>,
+    data: proto::Bundle,
+    tree_ids: HashSet<TreeId>,
+    file_ids: HashSet<FileId>,
+    symlink_ids: HashSet<SymlinkId>,
+    conflict_ids: HashSet<ConflictId>,
+}
+
+impl<'a> ObjectExporter<'a> {
+    fn new(store: &'a Arc<Store>) -> Self {
+        ObjectExporter {
+            store,
+            data: proto::Bundle::default(),
+            tree_ids: HashSet::new(),
+            file_ids: HashSet::new(),
+            symlink_ids: HashSet::new(),
+            conflict_ids: HashSet::new(),
+        }
+    }
+
+    /// Exports the tree and the objects it references, except for the entries
+    /// that are the same in one of the `base_trees` at the same path.
+    fn export_tree(
+        &mut self,
+        dir: &RepoPath,
+        id: &TreeId,
+        base_trees: &[Tree],
+    ) -> Result<(), BackendError> {
+        if !self.tree_ids.insert(id.clone()) {
+            return Ok(());
+        }
+        let tree = self.store.get_tree(dir, id)?;
+        for entry in tree.data().entries() {
+            let path = dir.join(entry.name());
+            let base_values = base_trees
+                .iter()
+                .filter_map(|base_tree| base_tree.value(entry.name()))
+                .collect_vec();
+            if base_values.contains(&entry.value()) {
+                continue;
+            }
+            if let TreeValue::Tree(sub_id) = entry.value() {
+                let base_sub_trees: Vec<_> = base_values
+                    .iter()
+                    .filter_map(|value| match value {
+                        TreeValue::Tree(base_id) => Some(self.store.get_tree(&path, base_id)),
+                        _ => None,
+                    })
+                    .try_collect()?;
+                self.export_tree(&path, sub_id, &base_sub_trees)?;
+            } else {
+                self.export_value(&path, entry.value())?;
+            }
+        }
+        // Added after its entries so they're written first on import.
+        self.data.trees.push(proto::TreeEntry {
+            dir: dir.as_internal_file_string().to_owned(),
+            id: id.to_bytes(),
+            tree: Some(local_backend::tree_to_proto(tree.data())),
+        });
+        Ok(())
+    }
+
+    fn export_value(&mut self, path: &RepoPath, value: &TreeValue) -> Result<(), BackendError> {
+        match value {
+            TreeValue::File { id, executable: _ } => {
+                if self.file_ids.insert(id.clone()) {
+                    let mut content = vec![];
+                    self.store
+                        .read_file(path, id)?
+                        .read_to_end(&mut content)
+                        .map_err(|err| BackendError::ReadFile {
+                            path: path.to_owned(),
+                            id: id.clone(),
+                            source: err.into(),
+                        })?;
+                    self.data.files.push(proto::FileEntry {
+                        path: path.as_internal_file_string().to_owned(),
+                        id: id.to_bytes(),
+                        content,
+                    });
+                }
+            }
+            TreeValue::Symlink(id) => {
+                if self.symlink_ids.insert(id.clone()) {
+                    let target = self.store.read_symlink(path, id)?;
+                    self.data.symlinks.push(proto::SymlinkEntry {
+                        path: path.as_internal_file_string().to_owned(),
+                        id: id.to_bytes(),
+                        target,
+                    });
+                }
+            }
+            TreeValue::Tree(id) => self.export_tree(path, id, &[])?,
+            // Submodule commits live in another repo.
+            TreeValue::GitSubmodule(_) => {}
+            TreeValue::Conflict(id) => {
+                if self.conflict_ids.insert(id.clone()) {
+                    let conflict = self.store.read_conflict(path, id)?;
+                    for term in conflict.iter().flatten() {
+                        self.export_value(path, term)?;
+                    }
+                    self.data.conflicts.push(proto::ConflictEntry {
+                        path: path.as_internal_file_string().to_owned(),
+                        id: id.to_bytes(),
+                        conflict: Some(local_backend::conflict_to_proto(
+                            &conflict.into_backend_conflict(),
+                        )),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Summary of an imported bundle.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpBundleImportStats {
+    /// Operations that didn't exist in the repo before.
+    pub new_operation_ids: Vec<OperationId>,
+    /// Number of commits that didn't exist in the repo before.
+    pub num_new_commits: usize,
+}
+
+/// Writes the objects and operations in the bundle to the repo's stores.
+///
+/// The new head operations of the bundle are added as operation heads. They
+/// will be merged with the existing heads when the repo is loaded at head next
+/// time.
+pub fn import_operations(
+    repo_loader: &RepoLoader,
+    bundle: &OperationBundle,
+) -> Result<OpBundleImportStats, OpBundleError> {
+    let store = repo_loader.store();
+    let op_store = repo_loader.op_store();
+    let data = &bundle.data;
+    let bundle_op_ids: HashSet<&[u8]> = data
+        .operations
+        .iter()
+        .map(|entry| entry.id.as_slice())
+        .collect();
+
+    // Check that the bundle can be attached to the existing operations before
+    // writing anything.
+    let mut new_op_entries = vec![];
+    for entry in &data.operations {
+        let op_id = OperationId::new(entry.id.clone());
+        match op_store.read_operation(&op_id) {
+            Ok(_) => continue,
+            Err(OpStoreError::ObjectNotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+        let op_proto = entry.operation.clone().unwrap_or_default();
+        for parent_id in &op_proto.parents {
+            if bundle_op_ids.contains(parent_id.as_slice()) {
+                continue;
+            }
+            let parent_id = OperationId::new(parent_id.clone());
+            match op_store.read_operation(&parent_id) {
+                Ok(_) => {}
+                Err(OpStoreError::ObjectNotFound { .. }) => {
+                    return Err(OpBundleError::MissingParent {
+                        op_id: op_id.hex(),
+                        parent_id: parent_id.hex(),
+                    });
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        new_op_entries.push((op_id, op_proto));
+    }
+    if new_op_entries.is_empty() {
+        return Ok(OpBundleImportStats::default());
+    }
+    check_store_name("backend", &data.backend_name, store.backend_name())?;
+    check_store_name("operation store", &data.op_store_name, op_store.name())?;
+    check_references(store, op_store, data, &new_op_entries)?;
+
+    for entry in &data.files {
+        let path = RepoPathBuf::from_internal_string(&entry.path);
+        let id = store
+            .write_file(&path, &mut entry.content.as_slice())
+            .block_on()?;
+        check_id("file", &entry.id, &id)?;
+    }
+    for entry in &data.symlinks {
+        let path = RepoPathBuf::from_internal_string(&entry.path);
+        let id = store.write_symlink(&path, &entry.target).block_on()?;
+        check_id("symlink", &entry.id, &id)?;
+    }
+    for entry in &data.conflicts {
+        let path = RepoPathBuf::from_internal_string(&entry.path);
+        let conflict =
+            local_backend::conflict_from_proto(entry.conflict.clone().unwrap_or_default());
+        let id = store.write_conflict(&path, &Merge::from_backend_conflict(conflict))?;
+        check_id("conflict", &entry.id, &id)?;
+    }
+    for entry in &data.trees {
+        let dir = RepoPathBuf::from_internal_string(&entry.dir);
+        let tree = local_backend::tree_from_proto(entry.tree.clone().unwrap_or_default());
+        let tree = store.write_tree(&dir, tree).block_on()?;
+        check_id("tree", &entry.id, tree.id())?;
+    }
+    let mut num_new_commits = 0;
+    for entry in &data.commits {
+        let commit_id = CommitId::new(entry.id.clone());
+        match store.get_commit(&commit_id) {
+            Ok(_) => continue,
+            Err(BackendError::ObjectNotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+        let mut commit_proto = entry.commit.clone().unwrap_or_default();
+        let signature = commit_proto.secure_sig.take();
+        let commit = local_backend::commit_from_proto(commit_proto);
+        // Reuse the original signature instead of signing the commit again.
+        let commit = if let Some(signature) = signature {
+            let mut sign = |_: &[u8]| Ok(signature.clone());
+            store.write_commit(commit, Some(&mut sign)).block_on()?
+        } else {
+            store.write_commit(commit, None).block_on()?
+        };
+        check_id("commit", &entry.id, commit.id())?;
+        num_new_commits += 1;
+    }
+    for entry in &data.views {
+        let view = simple_op_store::view_from_proto(entry.view.clone().unwrap_or_default());
+        let id = op_store.write_view(&view)?;
+        check_id("view", &entry.id, &id)?;
+    }
+    for (op_id, op_proto) in &new_op_entries {
+        let operation = simple_op_store::operation_from_proto(op_proto.clone());
+        let id = op_store.write_operation(&operation)?;
+        check_id("operation", op_id.as_bytes(), &id)?;
+    }
+
+    let new_parent_ids: HashSet<&[u8]> = new_op_entries
+        .iter()
+        .flat_map(|(_, op_proto)| &op_proto.parents)
+        .map(|id| id.as_slice())
+        .collect();
+    for (op_id, _) in &new_op_entries {
+        if !new_parent_ids.contains(op_id.as_bytes()) {
+            repo_loader.op_heads_store().update_op_heads(&[], op_id)?;
+        }
+    }
+    let new_operation_ids = new_op_entries
+        .into_iter()
+        .map(|(op_id, _)| op_id)
+        .collect_vec();
+    Ok(OpBundleImportStats {
+        new_operation_ids,
+        num_new_commits,
+    })
+}
+
+fn check_store_name(
+    store_type: &'static str,
+    bundle_name: &str,
+    repo_name: &str,
+) -> Result<(), OpBundleError> {
+    if bundle_name == repo_name {
+        Ok(())
+    } else {
+        Err(OpBundleError::IncompatibleStore {
+            store_type,
+            bundle_name: bundle_name.to_owned(),
+            repo_name: repo_name.to_owned(),
+        })
+    }
+}
+
+/// Checks that the views, commits and trees referred to by the new operations
+/// are either in the bundle or in the repo, so that nothing is written if the
+/// import would leave dangling references.
+fn check_references(
+    store: &Arc<Store>,
+    op_store: &Arc<dyn OpStore>,
+    data: &proto::Bundle,
+    new_op_entries: &[(OperationId, proto_op_store::Operation)],
+) -> Result<(), OpBundleError> {
+    let bundle_view_ids: HashSet<&[u8]> = data.views.iter().map(|e| e.id.as_slice()).collect();
+    let bundle_commit_ids: HashSet<&[u8]> = data.commits.iter().map(|e| e.id.as_slice()).collect();
+    let bundle_tree_ids: HashSet<&[u8]> = data.trees.iter().map(|e| e.id.as_slice()).collect();
+    let missing = |object_type, id: &[u8]| OpBundleError::MissingObject {
+        object_type,
+        id: hex::encode(id),
+    };
+    let check_commit = |id: &[u8]| -> Result<(), OpBundleError> {
+        if bundle_commit_ids.contains(id) {
+            return Ok(());
+        }
+        match store.get_commit(&CommitId::from_bytes(id)) {
+            Ok(_) => Ok(()),
+            Err(BackendError::ObjectNotFound { .. }) => Err(missing("commit", id)),
+            Err(err) => Err(err.into()),
+        }
+    };
+
+    for (_, op_proto) in new_op_entries {
+        let view_id = ViewId::new(op_proto.view_id.clone());
+        if bundle_view_ids.contains(view_id.as_bytes()) {
+            continue;
+        }
+        match op_store.read_view(&view_id) {
+            Ok(_) => {}
+            Err(OpStoreError::ObjectNotFound { .. }) => {
+                return Err(missing("view", view_id.as_bytes()));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    for entry in &data.views {
+        let view = View::new(simple_op_store::view_from_proto(
+            entry.view.clone().unwrap_or_default(),
+        ));
+        for id in view.all_referenced_commit_ids().unique() {
+            check_commit(id.as_bytes())?;
+        }
+    }
+    for entry in &data.commits {
+        let commit = local_backend::commit_from_proto(entry.commit.clone().unwrap_or_default());
+        for id in &commit.parents {
+            check_commit(id.as_bytes())?;
+        }
+        for id in commit.root_tree.to_merge().iter() {
+            if bundle_tree_ids.contains(id.as_bytes()) {
+                continue;
+            }
+            match store.get_tree(RepoPath::root(), id) {
+                Ok(_) => {}
+                Err(BackendError::ObjectNotFound { .. }) => {
+                    return Err(missing("tree", id.as_bytes()));
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_id(
+    object_type: &'static str,
+    expected: &[u8],
+    actual: &impl ObjectId,
+) -> Result<(), OpBundleError> {
+    if expected == actual.as_bytes() {
+        Ok(())
+    } else {
+        Err(OpBundleError::IdMismatch {
+            object_type,
+            expected: hex::encode(expected),
+            actual: actual.hex(),
+        })
+    }
+}
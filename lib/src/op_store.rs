@@ -32,6 +32,7 @@ use crate::backend::CommitId;
 use crate::backend::MillisSinceEpoch;
 use crate::backend::Timestamp;
 use crate::content_hash::ContentHash;
+use crate::dag_walk;
 use crate::merge::Merge;
 use crate::object_id::id_type;
 use crate::object_id::HexPrefix;
@@ -478,6 +479,15 @@ pub trait OpStore: Send + Sync + Debug {
 
     fn write_view(&self, contents: &View) -> OpStoreResult<ViewId>;
 
+    /// Writes the view, which may be stored as changes from the `base_id`
+    /// view. The base view should usually be the view of the parent
+    /// operation. Stores that don't support incremental views write the full
+    /// view.
+    fn write_view_incremental(&self, contents: &View, base_id: &ViewId) -> OpStoreResult<ViewId> {
+        let _ = base_id;
+        self.write_view(contents)
+    }
+
     fn read_operation(&self, id: &OperationId) -> OpStoreResult<Operation>;
 
     fn write_operation(&self, contents: &Operation) -> OpStoreResult<OperationId>;
@@ -488,14 +498,85 @@ pub trait OpStore: Send + Sync + Debug {
         prefix: &HexPrefix,
     ) -> OpStoreResult<PrefixResolution<OperationId>>;
 
+    /// Returns the IDs of all operations in the store, reachable or not. The
+    /// root operation isn't included.
+    fn all_operation_ids(&self) -> OpStoreResult<Vec<OperationId>>;
+
     /// Prunes unreachable operations and views.
     ///
     /// All operations and views reachable from the `head_ids` won't be
     /// removed. In addition to that, objects created after `keep_newer` will be
     /// preserved. This mitigates a risk of deleting new heads created
     /// concurrently by another process.
-    // TODO: return stats?
-    fn gc(&self, head_ids: &[OperationId], keep_newer: SystemTime) -> OpStoreResult<()>;
+    ///
+    /// If `dry_run` is true, nothing is removed, but the returned stats count
+    /// the objects that would be removed.
+    fn gc(
+        &self,
+        head_ids: &[OperationId],
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> OpStoreResult<OpStoreGcStats>;
+}
+
+/// Copies the operations reachable from `head_ids`, and their views, from the
+/// `source` store to the `target` store. Returns the number of copied
+/// operations.
+///
+/// Parent operations are copied before their children so the target store can
+/// store views incrementally. Since object IDs are content hashes, the copied
+/// objects keep their IDs.
+pub fn copy_operations(
+    source: &dyn OpStore,
+    target: &dyn OpStore,
+    head_ids: &[OperationId],
+) -> OpStoreResult<usize> {
+    let root_operation_id = source.root_operation_id();
+    let read_op = |id: &OperationId| source.read_operation(id).map(|data| (id.clone(), data));
+    let operations = dag_walk::topo_order_forward_ok(
+        head_ids
+            .iter()
+            .filter(|id| *id != root_operation_id)
+            .map(read_op),
+        |(id, _)| id.clone(),
+        |(_, data)| {
+            data.parents
+                .iter()
+                .filter(|id| *id != root_operation_id)
+                .map(read_op)
+                .collect_vec()
+        },
+    )?;
+    let root_view_id = source.read_operation(root_operation_id)?.view_id;
+    let view_ids: HashMap<&OperationId, &ViewId> = operations
+        .iter()
+        .map(|(id, data)| (id, &data.view_id))
+        .collect();
+    for (id, data) in &operations {
+        let view = source.read_view(&data.view_id)?;
+        let base_view_id = data
+            .parents
+            .first()
+            .and_then(|parent_id| view_ids.get(parent_id).copied())
+            .unwrap_or(&root_view_id);
+        let new_view_id = target.write_view_incremental(&view, base_view_id)?;
+        let new_id = target.write_operation(data)?;
+        if new_view_id != data.view_id || new_id != *id {
+            return Err(OpStoreError::Other(
+                format!("Operation {} changed ID when copied", id.hex()).into(),
+            ));
+        }
+    }
+    Ok(operations.len())
+}
+
+/// Stats about objects pruned by [`OpStore::gc()`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpStoreGcStats {
+    /// The number of pruned operations.
+    pub pruned_operations: usize,
+    /// The number of pruned views.
+    pub pruned_views: usize,
 }
 
 #[cfg(test)]
@@ -23,6 +23,8 @@ use std::sync::Arc;
 use itertools::Itertools as _;
 use thiserror::Error;
 
+use crate::backend::MillisSinceEpoch;
+use crate::backend::Timestamp;
 use crate::dag_walk;
 use crate::object_id::HexPrefix;
 use crate::object_id::PrefixResolution;
@@ -38,6 +40,8 @@ use crate::operation::Operation;
 use crate::repo::ReadonlyRepo;
 use crate::repo::Repo as _;
 use crate::repo::RepoLoader;
+use crate::time_util;
+use crate::time_util::DatePatternParseError;
 
 /// Error that may occur during evaluation of operation set expression.
 #[derive(Debug, Error)]
@@ -82,6 +86,14 @@ pub enum OpsetResolutionError {
     /// Operation ID prefix matches multiple operations.
     #[error(r#"Operation ID prefix "{0}" is ambiguous"#)]
     AmbiguousIdPrefix(String),
+    /// Invalid time in `at:` or `ago:` symbol.
+    #[error(r#"Invalid time "{expr}""#)]
+    InvalidTime {
+        /// Source expression.
+        expr: String,
+        /// Underlying parse error.
+        source: DatePatternParseError,
+    },
 }
 
 /// Resolves operation set expression without loading a repo.
@@ -143,10 +155,14 @@ fn resolve_single_op(
 ) -> Result<Operation, OpsetEvaluationError> {
     let op_symbol = op_str.trim_end_matches(['-', '+']);
     let op_postfix = &op_str[op_symbol.len()..];
-    let head_ops = op_postfix.contains('+').then(get_head_ops).transpose()?;
-    let mut operation = match op_symbol {
-        "@" => get_current_op(),
-        s => resolve_single_op_from_store(op_store, s),
+    let time = parse_time_symbol(op_symbol)?;
+    let head_ops = (op_postfix.contains('+') || time.is_some())
+        .then(get_head_ops)
+        .transpose()?;
+    let mut operation = match (op_symbol, time) {
+        (_, Some(time)) => find_op_at_time(head_ops.as_ref().unwrap(), time, op_symbol),
+        ("@", None) => get_current_op(),
+        (s, None) => resolve_single_op_from_store(op_store, s),
     }?;
     for c in op_postfix.chars() {
         let mut neighbor_ops = match c {
@@ -166,7 +182,46 @@ fn resolve_single_op(
     Ok(operation)
 }
 
-fn resolve_single_op_from_store(
+/// Parses `at:<date>` or `ago:<duration>` symbol into a point in time.
+///
+/// The date can be quoted, so `at:"yesterday 5pm"` can be written in revsets.
+fn parse_time_symbol(op_symbol: &str) -> Result<Option<MillisSinceEpoch>, OpsetEvaluationError> {
+    let Some((kind, value)) = op_symbol.split_once(':') else {
+        return Ok(None);
+    };
+    let value = ['"', '\'']
+        .iter()
+        .find_map(|&q| value.strip_prefix(q)?.strip_suffix(q))
+        .unwrap_or(value);
+    let now = chrono::Local::now();
+    let time = match kind {
+        "at" => time_util::parse_datetime(value, now),
+        "ago" => time_util::parse_past_time(value, now),
+        _ => return Ok(None),
+    };
+    let time = time.map_err(|source| OpsetResolutionError::InvalidTime {
+        expr: op_symbol.to_owned(),
+        source,
+    })?;
+    Ok(Some(time))
+}
+
+/// Finds the latest operation that had finished by the given `time`.
+fn find_op_at_time(
+    head_ops: &[Operation],
+    time: MillisSinceEpoch,
+    op_symbol: &str,
+) -> Result<Operation, OpsetEvaluationError> {
+    for op in walk_ancestors(head_ops) {
+        let op = op?;
+        if op.metadata().end_time.timestamp <= time {
+            return Ok(op);
+        }
+    }
+    Err(OpsetResolutionError::EmptyOperations(op_symbol.to_owned()).into())
+}
+
+pub(crate) fn resolve_single_op_from_store(
     op_store: &Arc<dyn OpStore>,
     op_str: &str,
 ) -> Result<Operation, OpsetEvaluationError> {
@@ -260,6 +315,58 @@ pub fn walk_ancestors(head_ops: &[Operation]) -> impl Iterator<Item = OpStoreRes
     .map_ok(|OperationByEndTime(op)| op)
 }
 
+/// Policy that determines how much of the operation history should be kept.
+///
+/// An operation is kept if it satisfies any of the criteria. If no criteria
+/// are set, all operations are kept.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OpRetention {
+    /// Keeps this number of the latest operations.
+    pub keep_count: Option<usize>,
+    /// Keeps operations that ended at or after this time.
+    pub keep_newer: Option<MillisSinceEpoch>,
+}
+
+impl OpRetention {
+    /// Returns true if no operations will be discarded by this policy.
+    pub fn is_unlimited(&self) -> bool {
+        self.keep_count.is_none() && self.keep_newer.is_none()
+    }
+}
+
+/// Finds the latest operation which can be discarded along with its ancestors
+/// according to the `retention` policy.
+///
+/// The `head_ops` and the root operation are never discarded. Returns `None`
+/// if there's nothing to discard.
+pub fn find_expired_op(
+    head_ops: &[Operation],
+    retention: &OpRetention,
+) -> OpStoreResult<Option<Operation>> {
+    if retention.is_unlimited() {
+        return Ok(None);
+    }
+    let head_ids: HashSet<_> = head_ops.iter().map(|op| op.id()).collect();
+    for (index, op) in walk_ancestors(head_ops).enumerate() {
+        let op = op?;
+        if head_ids.contains(op.id()) {
+            continue;
+        }
+        let in_count = retention.keep_count.is_some_and(|count| index < count);
+        let in_age = retention
+            .keep_newer
+            .is_some_and(|time| op.metadata().end_time.timestamp >= time);
+        if in_count || in_age {
+            continue;
+        }
+        if op.parent_ids().is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(op));
+    }
+    Ok(None)
+}
+
 /// Stats about `reparent_range()`.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ReparentStats {
@@ -332,3 +439,152 @@ pub fn reparent_range(
         unreachable_count: unreachable_ids.len(),
     })
 }
+
+/// Abandons the operations in `abandon_ids`, and reparents the remaining
+/// ancestors of the `head_ops` onto their nearest ancestors that are kept.
+///
+/// Operations that don't have to be reparented are not rewritten. The
+/// `head_ops` and the root operation must not be abandoned. Returns the new
+/// head operation ids as well as some stats.
+pub fn abandon_ops(
+    op_store: &dyn OpStore,
+    abandon_ids: &HashSet<OperationId>,
+    head_ops: &[Operation],
+) -> OpStoreResult<ReparentStats> {
+    assert!(
+        !abandon_ids.contains(op_store.root_operation_id()),
+        "root operation cannot be abandoned"
+    );
+    assert!(
+        head_ops.iter().all(|op| !abandon_ids.contains(op.id())),
+        "head operations cannot be abandoned"
+    );
+    let ops: Vec<_> = walk_ancestors(head_ops).try_collect()?;
+    // Maps abandoned operations to their nearest ancestors that are kept.
+    let mut replaced_ids: HashMap<OperationId, Vec<OperationId>> = HashMap::new();
+    let mut rewritten_ids = HashMap::new();
+    for old_op in ops.into_iter().rev() {
+        let new_parent_ids = old_op
+            .parent_ids()
+            .iter()
+            .flat_map(|id| match replaced_ids.get(id) {
+                Some(ids) => ids.clone(),
+                None => vec![rewritten_ids.get(id).unwrap_or(id).clone()],
+            })
+            .unique()
+            .collect_vec();
+        if abandon_ids.contains(old_op.id()) {
+            replaced_ids.insert(old_op.id().clone(), new_parent_ids);
+        } else if new_parent_ids != old_op.parent_ids() {
+            let mut data = old_op.store_operation().clone();
+            data.parents = new_parent_ids;
+            let new_id = op_store.write_operation(&data)?;
+            rewritten_ids.insert(old_op.id().clone(), new_id);
+        }
+    }
+
+    let new_head_ids = head_ops
+        .iter()
+        .map(|op| rewritten_ids.get(op.id()).unwrap_or(op.id()).clone())
+        .collect();
+    Ok(ReparentStats {
+        new_head_ids,
+        rewritten_count: rewritten_ids.len(),
+        unreachable_count: replaced_ids.len(),
+    })
+}
+
+/// Finds snapshot operations that can be squashed into their successors.
+///
+/// An operation is squashable if it and its only child are both snapshot
+/// operations that finished before `older_than`, and the child has no other
+/// parents. Squashing all of them collapses each run of consecutive snapshots
+/// into its newest operation.
+pub fn find_squashable_snapshot_ops(
+    head_ops: &[Operation],
+    older_than: MillisSinceEpoch,
+) -> OpStoreResult<HashSet<OperationId>> {
+    let ops: Vec<_> = walk_ancestors(head_ops).try_collect()?;
+    let mut children_map: HashMap<&OperationId, Vec<&Operation>> = HashMap::new();
+    for op in &ops {
+        for parent_id in op.parent_ids() {
+            children_map.entry(parent_id).or_default().push(op);
+        }
+    }
+    let is_old_snapshot = |op: &Operation| {
+        let metadata = op.metadata();
+        metadata.is_snapshot
+            && metadata.end_time.timestamp < older_than
+            && op.parent_ids().len() == 1
+    };
+    let squashable_ids = ops
+        .iter()
+        .filter(|op| is_old_snapshot(op))
+        .filter(|op| match children_map.get(op.id()).map(Vec::as_slice) {
+            Some([child]) => is_old_snapshot(child),
+            _ => false,
+        })
+        .map(|op| op.id().clone())
+        .collect();
+    Ok(squashable_ids)
+}
+
+/// Squashes the operations in `squash_ids` into their only children, and
+/// reparents the remaining ancestors of the `head_ops` accordingly.
+///
+/// The child operation keeps its own view, and its start time is moved back
+/// to the start time of the earliest operation squashed into it. Returns the
+/// new head operation ids as well as some stats.
+pub fn squash_ops(
+    op_store: &dyn OpStore,
+    squash_ids: &HashSet<OperationId>,
+    head_ops: &[Operation],
+) -> OpStoreResult<ReparentStats> {
+    assert!(
+        !squash_ids.contains(op_store.root_operation_id()),
+        "root operation cannot be squashed"
+    );
+    assert!(
+        head_ops.iter().all(|op| !squash_ids.contains(op.id())),
+        "head operations cannot be squashed"
+    );
+    let ops: Vec<_> = walk_ancestors(head_ops).try_collect()?;
+    // Maps squashed operations to the parents and start time to be inherited
+    // by their children.
+    let mut squashed: HashMap<OperationId, (Vec<OperationId>, Timestamp)> = HashMap::new();
+    let mut rewritten_ids = HashMap::new();
+    for old_op in ops.into_iter().rev() {
+        let mut start_time = old_op.metadata().start_time;
+        let mut new_parent_ids = vec![];
+        for id in old_op.parent_ids() {
+            if let Some((ids, time)) = squashed.get(id) {
+                new_parent_ids.extend(ids.iter().cloned());
+                start_time = start_time.min(*time);
+            } else {
+                new_parent_ids.push(rewritten_ids.get(id).unwrap_or(id).clone());
+            }
+        }
+        let new_parent_ids = new_parent_ids.into_iter().unique().collect_vec();
+        if squash_ids.contains(old_op.id()) {
+            squashed.insert(old_op.id().clone(), (new_parent_ids, start_time));
+        } else if new_parent_ids != old_op.parent_ids()
+            || start_time != old_op.metadata().start_time
+        {
+            let mut data = old_op.store_operation().clone();
+            data.parents = new_parent_ids;
+            data.metadata.start_time = start_time;
+            let new_id = op_store.write_operation(&data)?;
+            rewritten_ids.insert(old_op.id().clone(), new_id);
+        }
+    }
+
+    let new_head_ids = head_ops
+        .iter()
+        .map(|op| rewritten_ids.get(op.id()).unwrap_or(op.id()).clone())
+        .collect();
+    Ok(ReparentStats {
+        new_head_ids,
+        rewritten_count: rewritten_ids.len(),
+        unreachable_count: squashed.len(),
+    })
+}
@@ -0,0 +1,905 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Functional language for selecting a set of operations.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use itertools::Itertools as _;
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+use crate::dsl_util::collect_similar;
+use crate::dsl_util::Diagnostics;
+use crate::dsl_util::InvalidArguments;
+use crate::op_store::OpStore;
+use crate::op_store::OpStoreResult;
+use crate::op_store::OperationId;
+use crate::op_store::OperationMetadata;
+use crate::op_walk;
+use crate::op_walk::OpsetEvaluationError;
+use crate::op_walk::OpsetResolutionError;
+use crate::operation::Operation;
+use crate::revset_parser;
+use crate::revset_parser::BinaryOp;
+use crate::revset_parser::ExpressionKind;
+use crate::revset_parser::ExpressionNode;
+use crate::revset_parser::FunctionCallNode;
+use crate::revset_parser::RevsetParseError;
+use crate::revset_parser::RevsetParseErrorKind;
+use crate::revset_parser::UnaryOp;
+use crate::str_util::StringPattern;
+use crate::time_util::DatePattern;
+use crate::time_util::DatePatternContext;
+
+/// Manages diagnostic messages emitted during opset parsing and name
+/// resolution.
+pub type OpsetDiagnostics = Diagnostics<OpsetParseError>;
+
+/// Result of opset parsing and name resolution.
+pub type OpsetParseResult<T> = Result<T, OpsetParseError>;
+
+/// Error occurred during opset parsing and name resolution.
+#[derive(Debug, Error)]
+#[error("{pest_error}")]
+pub struct OpsetParseError {
+    kind: OpsetParseErrorKind,
+    pest_error: Box<pest::error::Error<revset_parser::Rule>>,
+    source: Option<Box<dyn error::Error + Send + Sync>>,
+}
+
+/// Categories of opset parsing and name resolution error.
+#[allow(missing_docs)]
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum OpsetParseErrorKind {
+    #[error("Syntax error")]
+    SyntaxError,
+    #[error(r#"Function "{name}" doesn't exist"#)]
+    NoSuchFunction {
+        name: String,
+        candidates: Vec<String>,
+    },
+    #[error(r#"Function "{name}": {message}"#)]
+    InvalidArguments { name: String, message: String },
+    #[error("{0}")]
+    Expression(String),
+}
+
+impl OpsetParseError {
+    fn new(kind: OpsetParseErrorKind, span: pest::Span<'_>) -> Self {
+        let message = kind.to_string();
+        let pest_error = Box::new(pest::error::Error::new_from_span(
+            pest::error::ErrorVariant::CustomError { message },
+            span,
+        ));
+        OpsetParseError {
+            kind,
+            pest_error,
+            source: None,
+        }
+    }
+
+    fn with_source(mut self, source: impl Into<Box<dyn error::Error + Send + Sync>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Some other expression error.
+    fn expression(message: impl Into<String>, span: pest::Span<'_>) -> Self {
+        OpsetParseError::new(OpsetParseErrorKind::Expression(message.into()), span)
+    }
+
+    /// Category of the underlying error.
+    pub fn kind(&self) -> &OpsetParseErrorKind {
+        &self.kind
+    }
+}
+
+// The opset language shares the grammar with the revset language.
+impl From<RevsetParseError> for OpsetParseError {
+    fn from(err: RevsetParseError) -> Self {
+        let kind = match err.kind() {
+            RevsetParseErrorKind::SyntaxError => OpsetParseErrorKind::SyntaxError,
+            kind => OpsetParseErrorKind::Expression(kind.to_string()),
+        };
+        OpsetParseError {
+            kind,
+            pest_error: err.into_pest_error(),
+            source: None,
+        }
+    }
+}
+
+impl From<InvalidArguments<'_>> for OpsetParseError {
+    fn from(err: InvalidArguments<'_>) -> Self {
+        let kind = OpsetParseErrorKind::InvalidArguments {
+            name: err.name.to_owned(),
+            message: err.message,
+        };
+        Self::new(kind, err.span)
+    }
+}
+
+/// Predicate on operation metadata.
+#[derive(Clone, Debug)]
+pub enum OpsetFilterPredicate {
+    /// Operations whose description matches the pattern.
+    Description(StringPattern),
+    /// Operations run by the matching user.
+    Username(StringPattern),
+    /// Operations run on the matching host.
+    Hostname(StringPattern),
+    /// Operations which only snapshot the working copy.
+    Snapshot,
+    /// Operations which ended in the time range.
+    Time(DatePattern),
+    /// Operations with a matching tag. `None` matches any tag name or value.
+    Tags {
+        /// Pattern to match tag names.
+        name: Option<StringPattern>,
+        /// Pattern to match tag values.
+        value: Option<StringPattern>,
+    },
+}
+
+impl OpsetFilterPredicate {
+    fn matches(&self, metadata: &OperationMetadata) -> bool {
+        match self {
+            OpsetFilterPredicate::Description(pattern) => pattern.matches(&metadata.description),
+            OpsetFilterPredicate::Username(pattern) => pattern.matches(&metadata.username),
+            OpsetFilterPredicate::Hostname(pattern) => pattern.matches(&metadata.hostname),
+            OpsetFilterPredicate::Snapshot => metadata.is_snapshot,
+            OpsetFilterPredicate::Time(pattern) => pattern.matches(&metadata.end_time),
+            OpsetFilterPredicate::Tags { name, value } => {
+                metadata.tags.iter().any(|(tag_name, tag_value)| {
+                    name.as_ref().map_or(true, |p| p.matches(tag_name))
+                        && value.as_ref().map_or(true, |p| p.matches(tag_value))
+                })
+            }
+        }
+    }
+}
+
+/// Operation set expression.
+#[derive(Clone, Debug)]
+pub enum OpsetExpression {
+    /// Matches nothing.
+    None,
+    /// Ancestors of the head operations.
+    All,
+    /// The root operation.
+    Root,
+    /// The current operation, `@`.
+    Current,
+    /// Operation ID or unique prefix.
+    Id(String),
+    /// Operations matching the predicate.
+    Filter(OpsetFilterPredicate),
+    /// Parents of the operations.
+    Parents(Rc<OpsetExpression>),
+    /// Children of the operations.
+    Children(Rc<OpsetExpression>),
+    /// Ancestors of the operations including themselves.
+    Ancestors(Rc<OpsetExpression>),
+    /// Descendants of the operations including themselves.
+    Descendants(Rc<OpsetExpression>),
+    /// Ancestors of `heads` excluding ancestors of `roots`.
+    Range {
+        /// Operations whose ancestors are excluded.
+        roots: Rc<OpsetExpression>,
+        /// Operations whose ancestors are included.
+        heads: Rc<OpsetExpression>,
+    },
+    /// Descendants of `roots` which are ancestors of `heads`.
+    DagRange {
+        /// Operations whose descendants are included.
+        roots: Rc<OpsetExpression>,
+        /// Operations whose ancestors are included.
+        heads: Rc<OpsetExpression>,
+    },
+    /// Operations in either set.
+    Union(Rc<OpsetExpression>, Rc<OpsetExpression>),
+    /// Operations in both sets.
+    Intersection(Rc<OpsetExpression>, Rc<OpsetExpression>),
+    /// Operations in the first set but not in the second.
+    Difference(Rc<OpsetExpression>, Rc<OpsetExpression>),
+}
+
+impl OpsetExpression {
+    /// Evaluates the expression. The "@" symbol is resolved to the single
+    /// `head_ops`, and `all()` means the ancestors of the `head_ops`.
+    ///
+    /// The operations are returned in reverse topological order.
+    pub fn evaluate(
+        &self,
+        op_store: &Arc<dyn OpStore>,
+        head_ops: &[Operation],
+    ) -> Result<Vec<Operation>, OpsetEvaluationError> {
+        let mut symbol_ops = HashMap::new();
+        self.resolve_symbols(op_store, head_ops, &mut symbol_ops)?;
+        if let Some(ops) = self.evaluate_nearby(op_store, &symbol_ops)? {
+            return Ok(sort_ops(ops)?);
+        }
+        // Operations referenced by ID may be unreachable from the heads.
+        let entry_ops = head_ops
+            .iter()
+            .chain(symbol_ops.values())
+            .cloned()
+            .collect_vec();
+        let ops: Vec<_> = op_walk::walk_ancestors(&entry_ops).try_collect()?;
+        let graph = OpGraph::new(&ops, head_ops, &symbol_ops, op_store.root_operation_id());
+        let set = graph.evaluate(self);
+        Ok(ops
+            .into_iter()
+            .zip(set)
+            .filter_map(|(op, included)| included.then_some(op))
+            .collect())
+    }
+
+    /// Evaluates the expression without loading the whole operation log if
+    /// the result is determined by the operations referenced by symbols and
+    /// their parents. Returns `None` if the expression needs the whole graph.
+    ///
+    /// The operations are returned in arbitrary order.
+    fn evaluate_nearby(
+        &self,
+        op_store: &Arc<dyn OpStore>,
+        symbol_ops: &HashMap<Option<&str>, Operation>,
+    ) -> Result<Option<Vec<Operation>>, OpsetEvaluationError> {
+        let ops = match self {
+            OpsetExpression::None => vec![],
+            OpsetExpression::Root => {
+                let root_op_id = op_store.root_operation_id();
+                let data = op_store.read_operation(root_op_id)?;
+                vec![Operation::new(op_store.clone(), root_op_id.clone(), data)]
+            }
+            OpsetExpression::Current => vec![symbol_ops[&None].clone()],
+            OpsetExpression::Id(prefix) => vec![symbol_ops[&Some(prefix.as_str())].clone()],
+            OpsetExpression::Parents(expression) => {
+                let Some(ops) = expression.evaluate_nearby(op_store, symbol_ops)? else {
+                    return Ok(None);
+                };
+                let parent_ops: Vec<_> = ops.iter().flat_map(|op| op.parents()).try_collect()?;
+                parent_ops
+                    .into_iter()
+                    .unique_by(|op| op.id().clone())
+                    .collect()
+            }
+            OpsetExpression::Union(expression1, expression2)
+            | OpsetExpression::Intersection(expression1, expression2)
+            | OpsetExpression::Difference(expression1, expression2) => {
+                let (Some(ops1), Some(ops2)) = (
+                    expression1.evaluate_nearby(op_store, symbol_ops)?,
+                    expression2.evaluate_nearby(op_store, symbol_ops)?,
+                ) else {
+                    return Ok(None);
+                };
+                let ids2: HashSet<_> = ops2.iter().map(|op| op.id().clone()).collect();
+                match self {
+                    OpsetExpression::Union(..) => ops1
+                        .into_iter()
+                        .chain(ops2)
+                        .unique_by(|op| op.id().clone())
+                        .collect(),
+                    OpsetExpression::Intersection(..) => ops1
+                        .into_iter()
+                        .filter(|op| ids2.contains(op.id()))
+                        .collect(),
+                    _ => ops1
+                        .into_iter()
+                        .filter(|op| !ids2.contains(op.id()))
+                        .collect(),
+                }
+            }
+            OpsetExpression::All
+            | OpsetExpression::Filter(_)
+            | OpsetExpression::Children(_)
+            | OpsetExpression::Ancestors(_)
+            | OpsetExpression::Descendants(_)
+            | OpsetExpression::Range { .. }
+            | OpsetExpression::DagRange { .. } => return Ok(None),
+        };
+        Ok(Some(ops))
+    }
+
+    /// Looks up operations referenced by `@` or by ID. The `@` symbol is
+    /// recorded with the key `None`.
+    fn resolve_symbols<'a>(
+        &'a self,
+        op_store: &Arc<dyn OpStore>,
+        head_ops: &[Operation],
+        symbol_ops: &mut HashMap<Option<&'a str>, Operation>,
+    ) -> Result<(), OpsetEvaluationError> {
+        match self {
+            OpsetExpression::None
+            | OpsetExpression::All
+            | OpsetExpression::Root
+            | OpsetExpression::Filter(_) => {}
+            OpsetExpression::Current => {
+                if let Entry::Vacant(entry) = symbol_ops.entry(None) {
+                    let op = match head_ops {
+                        [head_op] => head_op.clone(),
+                        [] => Err(OpsetResolutionError::EmptyOperations("@".to_owned()))?,
+                        _ => Err(OpsetResolutionError::MultipleOperations {
+                            expr: "@".to_owned(),
+                            candidates: head_ops.iter().map(|op| op.id().clone()).collect(),
+                        })?,
+                    };
+                    entry.insert(op);
+                }
+            }
+            OpsetExpression::Id(prefix) => {
+                if let Entry::Vacant(entry) = symbol_ops.entry(Some(prefix)) {
+                    let op = op_walk::resolve_single_op_from_store(op_store, prefix)?;
+                    entry.insert(op);
+                }
+            }
+            OpsetExpression::Parents(expression)
+            | OpsetExpression::Children(expression)
+            | OpsetExpression::Ancestors(expression)
+            | OpsetExpression::Descendants(expression) => {
+                expression.resolve_symbols(op_store, head_ops, symbol_ops)?;
+            }
+            OpsetExpression::Range { roots, heads }
+            | OpsetExpression::DagRange { roots, heads } => {
+                roots.resolve_symbols(op_store, head_ops, symbol_ops)?;
+                heads.resolve_symbols(op_store, head_ops, symbol_ops)?;
+            }
+            OpsetExpression::Union(expression1, expression2)
+            | OpsetExpression::Intersection(expression1, expression2)
+            | OpsetExpression::Difference(expression1, expression2) => {
+                expression1.resolve_symbols(op_store, head_ops, symbol_ops)?;
+                expression2.resolve_symbols(op_store, head_ops, symbol_ops)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sorts the given operations in reverse topological order. The walk stops
+/// once all the operations are found.
+fn sort_ops(ops: Vec<Operation>) -> OpStoreResult<Vec<Operation>> {
+    let mut remaining: HashSet<_> = ops.iter().map(|op| op.id().clone()).collect();
+    let mut sorted_ops = Vec::with_capacity(remaining.len());
+    for op in op_walk::walk_ancestors(&ops) {
+        if remaining.is_empty() {
+            break;
+        }
+        let op = op?;
+        if remaining.remove(op.id()) {
+            sorted_ops.push(op);
+        }
+    }
+    Ok(sorted_ops)
+}
+
+/// In-memory operation graph. Sets of operations are represented as flags
+/// indexed by position in reverse topological order.
+struct OpGraph<'a> {
+    ops: &'a [Operation],
+    parents: Vec<Vec<usize>>,
+    children: Vec<Vec<usize>>,
+    all: Vec<bool>,
+    root_pos: Option<usize>,
+    symbol_positions: HashMap<Option<&'a str>, usize>,
+}
+
+impl<'a> OpGraph<'a> {
+    fn new(
+        ops: &'a [Operation],
+        head_ops: &[Operation],
+        symbol_ops: &HashMap<Option<&'a str>, Operation>,
+        root_op_id: &OperationId,
+    ) -> Self {
+        let positions: HashMap<&OperationId, usize> = ops
+            .iter()
+            .enumerate()
+            .map(|(pos, op)| (op.id(), pos))
+            .collect();
+        let parents = ops
+            .iter()
+            .map(|op| op.parent_ids().iter().map(|id| positions[id]).collect_vec())
+            .collect_vec();
+        let mut children = vec![vec![]; ops.len()];
+        for (pos, parent_positions) in parents.iter().enumerate() {
+            for &parent_pos in parent_positions {
+                children[parent_pos].push(pos);
+            }
+        }
+        let symbol_positions = symbol_ops
+            .iter()
+            .map(|(&symbol, op)| (symbol, positions[op.id()]))
+            .collect();
+        let mut graph = OpGraph {
+            ops,
+            parents,
+            children,
+            all: vec![],
+            root_pos: positions.get(root_op_id).copied(),
+            symbol_positions,
+        };
+        let mut heads = vec![false; ops.len()];
+        for op in head_ops {
+            heads[positions[op.id()]] = true;
+        }
+        graph.all = graph.ancestors(heads);
+        graph
+    }
+
+    fn evaluate(&self, expression: &OpsetExpression) -> Vec<bool> {
+        match expression {
+            OpsetExpression::None => vec![false; self.ops.len()],
+            OpsetExpression::All => self.all.clone(),
+            OpsetExpression::Root => self.single(self.root_pos),
+            OpsetExpression::Current => self.single(self.symbol_positions.get(&None).copied()),
+            OpsetExpression::Id(prefix) => {
+                self.single(self.symbol_positions.get(&Some(prefix.as_str())).copied())
+            }
+            OpsetExpression::Filter(predicate) => self
+                .ops
+                .iter()
+                .zip(&self.all)
+                .map(|(op, &in_all)| in_all && predicate.matches(op.metadata()))
+                .collect(),
+            OpsetExpression::Parents(expression) => {
+                let set = self.evaluate(expression);
+                let mut result = vec![false; self.ops.len()];
+                for pos in positions_of(&set) {
+                    for &parent_pos in &self.parents[pos] {
+                        result[parent_pos] = true;
+                    }
+                }
+                result
+            }
+            OpsetExpression::Children(expression) => {
+                let set = self.evaluate(expression);
+                let mut result = vec![false; self.ops.len()];
+                for pos in positions_of(&set) {
+                    for &child_pos in &self.children[pos] {
+                        result[child_pos] |= self.all[child_pos];
+                    }
+                }
+                result
+            }
+            OpsetExpression::Ancestors(expression) => self.ancestors(self.evaluate(expression)),
+            OpsetExpression::Descendants(expression) => self.descendants(self.evaluate(expression)),
+            OpsetExpression::Range { roots, heads } => {
+                let excluded = self.ancestors(self.evaluate(roots));
+                let included = self.ancestors(self.evaluate(heads));
+                difference(included, &excluded)
+            }
+            OpsetExpression::DagRange { roots, heads } => {
+                let descendants = self.descendants(self.evaluate(roots));
+                let ancestors = self.ancestors(self.evaluate(heads));
+                intersection(descendants, &ancestors)
+            }
+            OpsetExpression::Union(expression1, expression2) => {
+                let set1 = self.evaluate(expression1);
+                let set2 = self.evaluate(expression2);
+                set1.into_iter().zip(set2).map(|(a, b)| a || b).collect()
+            }
+            OpsetExpression::Intersection(expression1, expression2) => {
+                let set1 = self.evaluate(expression1);
+                let set2 = self.evaluate(expression2);
+                intersection(set1, &set2)
+            }
+            OpsetExpression::Difference(expression1, expression2) => {
+                let set1 = self.evaluate(expression1);
+                let set2 = self.evaluate(expression2);
+                difference(set1, &set2)
+            }
+        }
+    }
+
+    fn single(&self, pos: Option<usize>) -> Vec<bool> {
+        let mut set = vec![false; self.ops.len()];
+        if let Some(pos) = pos {
+            set[pos] = true;
+        }
+        set
+    }
+
+    fn ancestors(&self, mut set: Vec<bool>) -> Vec<bool> {
+        // Parents are always positioned after their children.
+        for pos in 0..set.len() {
+            if set[pos] {
+                for &parent_pos in &self.parents[pos] {
+                    set[parent_pos] = true;
+                }
+            }
+        }
+        set
+    }
+
+    fn descendants(&self, mut set: Vec<bool>) -> Vec<bool> {
+        for pos in (0..set.len()).rev() {
+            if set[pos] {
+                for &child_pos in &self.children[pos] {
+                    set[child_pos] |= self.all[child_pos];
+                }
+            }
+        }
+        set
+    }
+}
+
+fn positions_of(set: &[bool]) -> impl Iterator<Item = usize> + '_ {
+    set.iter().positions(|&included| included)
+}
+
+fn intersection(mut set1: Vec<bool>, set2: &[bool]) -> Vec<bool> {
+    for (a, &b) in set1.iter_mut().zip(set2) {
+        *a &= b;
+    }
+    set1
+}
+
+fn difference(mut set1: Vec<bool>, set2: &[bool]) -> Vec<bool> {
+    for (a, &b) in set1.iter_mut().zip(set2) {
+        *a &= !b;
+    }
+    set1
+}
+
+type OpsetFunction = fn(
+    &mut OpsetDiagnostics,
+    &DatePatternContext,
+    &FunctionCallNode,
+) -> OpsetParseResult<OpsetExpression>;
+
+static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, OpsetFunction>> = Lazy::new(|| {
+    // Not using maplit::hashmap!{} or custom declarative macro here because
+    // code completion inside macro is quite restricted.
+    let mut map: HashMap<&'static str, OpsetFunction> = HashMap::new();
+    map.insert("none", |_diagnostics, _context, function| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::None)
+    });
+    map.insert("all", |_diagnostics, _context, function| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::All)
+    });
+    map.insert("root", |_diagnostics, _context, function| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::Root)
+    });
+    map.insert("parents", |diagnostics, context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let expression = resolve_expression(diagnostics, context, arg)?;
+        Ok(OpsetExpression::Parents(expression))
+    });
+    map.insert("children", |diagnostics, context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let expression = resolve_expression(diagnostics, context, arg)?;
+        Ok(OpsetExpression::Children(expression))
+    });
+    map.insert("ancestors", |diagnostics, context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let expression = resolve_expression(diagnostics, context, arg)?;
+        Ok(OpsetExpression::Ancestors(expression))
+    });
+    map.insert("descendants", |diagnostics, context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let expression = resolve_expression(diagnostics, context, arg)?;
+        Ok(OpsetExpression::Descendants(expression))
+    });
+    map.insert("description", |_diagnostics, _context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_string_pattern(arg)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Description(
+            pattern,
+        )))
+    });
+    map.insert("user", |_diagnostics, _context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_string_pattern(arg)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Username(
+            pattern,
+        )))
+    });
+    map.insert("hostname", |_diagnostics, _context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_string_pattern(arg)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Hostname(
+            pattern,
+        )))
+    });
+    map.insert("snapshot", |_diagnostics, _context, function| {
+        function.expect_no_arguments()?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Snapshot))
+    });
+    map.insert("time", |_diagnostics, context, function| {
+        let [arg] = function.expect_exact_arguments()?;
+        let pattern = expect_date_pattern(arg, context)?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Time(pattern)))
+    });
+    map.insert("tags", |_diagnostics, _context, function| {
+        let ([], [name_arg, value_arg]) = function.expect_arguments()?;
+        let name = name_arg.map(expect_string_pattern).transpose()?;
+        let value = value_arg.map(expect_string_pattern).transpose()?;
+        Ok(OpsetExpression::Filter(OpsetFilterPredicate::Tags {
+            name,
+            value,
+        }))
+    });
+    map
+});
+
+fn expect_pattern_with<T, E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    type_name: &str,
+    node: &ExpressionNode,
+    parse_pattern: impl FnOnce(&str, Option<&str>) -> Result<T, E>,
+) -> OpsetParseResult<T> {
+    let wrap_error = |err: E| {
+        OpsetParseError::expression(format!("Invalid {type_name}"), node.span).with_source(err)
+    };
+    match &node.kind {
+        ExpressionKind::Identifier(name) => parse_pattern(name, None).map_err(wrap_error),
+        ExpressionKind::String(name) => parse_pattern(name, None).map_err(wrap_error),
+        ExpressionKind::StringPattern { kind, value } => {
+            parse_pattern(value, Some(kind)).map_err(wrap_error)
+        }
+        _ => Err(OpsetParseError::expression(
+            format!("Expected expression of {type_name}"),
+            node.span,
+        )),
+    }
+}
+
+fn expect_string_pattern(node: &ExpressionNode) -> OpsetParseResult<StringPattern> {
+    expect_pattern_with("string pattern", node, |value, kind| match kind {
+        Some(kind) => StringPattern::from_str_kind(value, kind),
+        None => Ok(StringPattern::Substring(value.to_owned())),
+    })
+}
+
+fn expect_date_pattern(
+    node: &ExpressionNode,
+    context: &DatePatternContext,
+) -> OpsetParseResult<DatePattern> {
+    expect_pattern_with(
+        "date pattern",
+        node,
+        |value, kind| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+            match kind {
+                None => Err("Date pattern must specify 'after' or 'before'".into()),
+                Some(kind) => Ok(context.parse_relative(value, kind)?),
+            }
+        },
+    )
+}
+
+fn resolve_function(
+    diagnostics: &mut OpsetDiagnostics,
+    context: &DatePatternContext,
+    function: &FunctionCallNode,
+) -> OpsetParseResult<OpsetExpression> {
+    if let Some(func) = BUILTIN_FUNCTION_MAP.get(function.name) {
+        func(diagnostics, context, function)
+    } else {
+        Err(OpsetParseError::new(
+            OpsetParseErrorKind::NoSuchFunction {
+                name: function.name.to_owned(),
+                candidates: collect_similar(function.name, BUILTIN_FUNCTION_MAP.keys()),
+            },
+            function.name_span,
+        ))
+    }
+}
+
+fn resolve_expression(
+    diagnostics: &mut OpsetDiagnostics,
+    context: &DatePatternContext,
+    node: &ExpressionNode,
+) -> OpsetParseResult<Rc<OpsetExpression>> {
+    let expression = match &node.kind {
+        ExpressionKind::Identifier(name) => OpsetExpression::Id((*name).to_owned()),
+        ExpressionKind::String(name) => OpsetExpression::Id(name.clone()),
+        ExpressionKind::StringPattern { .. } => {
+            return Err(OpsetParseError::expression(
+                "String patterns may only be used as function arguments",
+                node.span,
+            ));
+        }
+        ExpressionKind::RemoteSymbol { .. } | ExpressionKind::AtWorkspace(_) => {
+            return Err(OpsetParseError::expression(
+                "Remote and workspace symbols aren't supported in opsets",
+                node.span,
+            ));
+        }
+        ExpressionKind::AtCurrentWorkspace => OpsetExpression::Current,
+        ExpressionKind::DagRangeAll => OpsetExpression::All,
+        ExpressionKind::RangeAll => OpsetExpression::Range {
+            roots: Rc::new(OpsetExpression::Root),
+            heads: Rc::new(OpsetExpression::All),
+        },
+        ExpressionKind::Unary(op, arg_node) => {
+            let arg = resolve_expression(diagnostics, context, arg_node)?;
+            match op {
+                UnaryOp::Negate => OpsetExpression::Difference(Rc::new(OpsetExpression::All), arg),
+                UnaryOp::DagRangePre => OpsetExpression::Ancestors(arg),
+                UnaryOp::DagRangePost => OpsetExpression::Descendants(arg),
+                UnaryOp::RangePre => OpsetExpression::Range {
+                    roots: Rc::new(OpsetExpression::Root),
+                    heads: arg,
+                },
+                UnaryOp::RangePost => OpsetExpression::Range {
+                    roots: arg,
+                    heads: Rc::new(OpsetExpression::All),
+                },
+                UnaryOp::Parents => OpsetExpression::Parents(arg),
+                UnaryOp::Children => OpsetExpression::Children(arg),
+            }
+        }
+        ExpressionKind::Binary(op, lhs_node, rhs_node) => {
+            let lhs = resolve_expression(diagnostics, context, lhs_node)?;
+            let rhs = resolve_expression(diagnostics, context, rhs_node)?;
+            match op {
+                BinaryOp::Intersection => OpsetExpression::Intersection(lhs, rhs),
+                BinaryOp::Difference => OpsetExpression::Difference(lhs, rhs),
+                BinaryOp::DagRange => OpsetExpression::DagRange {
+                    roots: lhs,
+                    heads: rhs,
+                },
+                BinaryOp::Range => OpsetExpression::Range {
+                    roots: lhs,
+                    heads: rhs,
+                },
+            }
+        }
+        ExpressionKind::UnionAll(nodes) => {
+            let expressions: Vec<_> = nodes
+                .iter()
+                .map(|node| resolve_expression(diagnostics, context, node))
+                .try_collect()?;
+            let expression = expressions
+                .into_iter()
+                .reduce(|acc, expression| Rc::new(OpsetExpression::Union(acc, expression)))
+                .unwrap();
+            return Ok(expression);
+        }
+        ExpressionKind::FunctionCall(function) => resolve_function(diagnostics, context, function)?,
+        ExpressionKind::Modifier(modifier) => {
+            return Err(OpsetParseError::expression(
+                "Modifiers aren't supported in opsets",
+                modifier.name_span,
+            ));
+        }
+        ExpressionKind::AliasExpanded(_, subst) => {
+            return resolve_expression(diagnostics, context, subst);
+        }
+    };
+    Ok(Rc::new(expression))
+}
+
+/// Parses text into `OpsetExpression`.
+///
+/// Date patterns such as `time(after:"yesterday")` are interpreted relative to
+/// the `date_pattern_context`.
+pub fn parse(
+    diagnostics: &mut OpsetDiagnostics,
+    text: &str,
+    date_pattern_context: &DatePatternContext,
+) -> OpsetParseResult<Rc<OpsetExpression>> {
+    let node = revset_parser::parse_program(text)?;
+    resolve_expression(diagnostics, date_pattern_context, &node)
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn parse_expression(text: &str) -> OpsetParseResult<Rc<OpsetExpression>> {
+        let now = chrono::DateTime::parse_from_rfc3339("2001-02-03T04:05:06+07:00").unwrap();
+        parse(&mut OpsetDiagnostics::new(), text, &now.into())
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        assert_matches!(
+            parse_expression("@").as_deref(),
+            Ok(OpsetExpression::Current)
+        );
+        assert_matches!(
+            parse_expression("abc123").as_deref(),
+            Ok(OpsetExpression::Id(id)) if id == "abc123"
+        );
+        assert_matches!(
+            parse_expression("..").as_deref(),
+            Ok(OpsetExpression::Range { roots, heads })
+                if matches!(**roots, OpsetExpression::Root)
+                    && matches!(**heads, OpsetExpression::All)
+        );
+        assert_matches!(
+            parse_expression("exact:foo").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::Expression(_))
+        );
+        assert_matches!(
+            parse_expression("foo@bar").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::Expression(_))
+        );
+        assert_matches!(
+            parse_expression("all:@").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::Expression(_))
+        );
+        assert_matches!(
+            parse_expression("@^").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::Expression(_))
+        );
+        assert_matches!(
+            parse_expression("@ |").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::SyntaxError)
+        );
+    }
+
+    #[test]
+    fn test_parse_function() {
+        assert_matches!(
+            parse_expression("snapshot()").as_deref(),
+            Ok(OpsetExpression::Filter(OpsetFilterPredicate::Snapshot))
+        );
+        assert_matches!(
+            parse_expression("description(exact:foo)").as_deref(),
+            Ok(OpsetExpression::Filter(OpsetFilterPredicate::Description(
+                StringPattern::Exact(s)
+            ))) if s == "foo"
+        );
+        assert_matches!(
+            parse_expression("user(foo)").as_deref(),
+            Ok(OpsetExpression::Filter(OpsetFilterPredicate::Username(
+                StringPattern::Substring(s)
+            ))) if s == "foo"
+        );
+        assert_matches!(
+            parse_expression("tags()").as_deref(),
+            Ok(OpsetExpression::Filter(OpsetFilterPredicate::Tags {
+                name: None,
+                value: None,
+            }))
+        );
+        assert_matches!(
+            parse_expression("tags(foo, exact:bar)").as_deref(),
+            Ok(OpsetExpression::Filter(OpsetFilterPredicate::Tags {
+                name: Some(StringPattern::Substring(_)),
+                value: Some(StringPattern::Exact(_)),
+            }))
+        );
+        assert_matches!(
+            parse_expression("time(after:yesterday)").as_deref(),
+            Ok(OpsetExpression::Filter(OpsetFilterPredicate::Time(
+                DatePattern::AtOrAfter(_)
+            )))
+        );
+        assert_matches!(
+            parse_expression("time(yesterday)").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::Expression(_))
+        );
+        assert_matches!(
+            parse_expression("snapshots()").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::NoSuchFunction { candidates, .. })
+                if candidates == ["snapshot"]
+        );
+        assert_matches!(
+            parse_expression("snapshot(x)").map_err(|err| err.kind().clone()),
+            Err(OpsetParseErrorKind::InvalidArguments { .. })
+        );
+    }
+}
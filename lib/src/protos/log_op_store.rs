@@ -0,0 +1,27 @@
+// This file is @generated by prost-build.
+/// Record appended to the log file.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LogEntry {
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    /// Time when the entry was appended. Used to not prune new objects.
+    #[prost(int64, tag = "2")]
+    pub written_millis_since_epoch: i64,
+    #[prost(oneof = "log_entry::Object", tags = "3, 4, 5")]
+    pub object: ::core::option::Option<log_entry::Object>,
+}
+/// Nested message and enum types in `LogEntry`.
+pub mod log_entry {
+    #[allow(clippy::large_enum_variant)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Object {
+        #[prost(message, tag = "3")]
+        Operation(super::super::op_store::Operation),
+        #[prost(message, tag = "4")]
+        View(super::super::op_store::View),
+        #[prost(message, tag = "5")]
+        ViewDelta(super::super::op_store::ViewDelta),
+    }
+}
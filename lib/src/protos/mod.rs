@@ -5,6 +5,12 @@ pub mod git_store {
 pub mod local_store {
     include!("local_store.rs");
 }
+pub mod log_op_store {
+    include!("log_op_store.rs");
+}
+pub mod op_bundle {
+    include!("op_bundle.rs");
+}
 pub mod op_store {
     include!("op_store.rs");
 }
@@ -126,6 +126,41 @@ pub struct View {
     #[prost(bool, tag = "10")]
     pub has_git_refs_migrated_to_remote: bool,
 }
+/// Changes from the base view. Bookmarks, tags, Git refs, and working-copy
+/// commits are keyed by name, and an entry replaces the base entry of the same
+/// name. The simple op store writes this after a header which older versions
+/// reject.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ViewDelta {
+    #[prost(bytes = "vec", tag = "1")]
+    pub base_view_id: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub added_head_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "3")]
+    pub removed_head_ids: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    #[prost(map = "string, bytes", tag = "4")]
+    pub wc_commit_ids: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::vec::Vec<u8>,
+    >,
+    #[prost(string, repeated, tag = "5")]
+    pub removed_wc_commit_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "6")]
+    pub bookmarks: ::prost::alloc::vec::Vec<Bookmark>,
+    #[prost(string, repeated, tag = "7")]
+    pub removed_bookmarks: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "8")]
+    pub tags: ::prost::alloc::vec::Vec<Tag>,
+    #[prost(string, repeated, tag = "9")]
+    pub removed_tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, repeated, tag = "10")]
+    pub git_refs: ::prost::alloc::vec::Vec<GitRef>,
+    #[prost(string, repeated, tag = "11")]
+    pub removed_git_refs: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(message, optional, tag = "12")]
+    pub git_head: ::core::option::Option<RefTarget>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Operation {
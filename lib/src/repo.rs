@@ -55,6 +55,7 @@ use crate::index::IndexStore;
 use crate::index::MutableIndex;
 use crate::index::ReadonlyIndex;
 use crate::local_backend::LocalBackend;
+use crate::log_op_store::LogOpStore;
 use crate::merge::MergeBuilder;
 use crate::object_id::HexPrefix;
 use crate::object_id::ObjectId;
@@ -86,6 +87,7 @@ use crate::rewrite::rebase_commit_with_options;
 use crate::rewrite::CommitRewriter;
 use crate::rewrite::RebaseOptions;
 use crate::rewrite::RebasedCommit;
+use crate::settings::OpStoreFormat;
 use crate::settings::RepoSettings;
 use crate::settings::UserSettings;
 use crate::signing::SignInitError;
@@ -156,8 +158,24 @@ pub enum RepoInitError {
 }
 
 impl ReadonlyRepo {
+    /// Initializes the operation store of the type specified by the
+    /// `format.op-store` and `format.view-deltas` settings.
     pub fn default_op_store_initializer() -> &'static OpStoreInitializer<'static> {
-        &|_settings, store_path, root_data| Box::new(SimpleOpStore::init(store_path, root_data))
+        &|settings, store_path, root_data| {
+            let format = settings
+                .op_store_format()
+                .map_err(|err| BackendInitError(err.into()))?;
+            let view_deltas = settings
+                .op_store_view_deltas()
+                .map_err(|err| BackendInitError(err.into()))?;
+            Ok(match format {
+                OpStoreFormat::Simple if view_deltas => {
+                    Box::new(SimpleOpStore::init_with_view_deltas(store_path, root_data))
+                }
+                OpStoreFormat::Simple => Box::new(SimpleOpStore::init(store_path, root_data)),
+                OpStoreFormat::Log => Box::new(LogOpStore::init(store_path, root_data)),
+            })
+        }
     }
 
     pub fn default_op_heads_store_initializer() -> &'static OpHeadsStoreInitializer<'static> {
@@ -201,7 +219,7 @@ impl ReadonlyRepo {
         let root_op_data = RootOperationData {
             root_commit_id: store.root_commit_id().clone(),
         };
-        let op_store = op_store_initializer(user_settings, &op_store_path, root_op_data);
+        let op_store = op_store_initializer(user_settings, &op_store_path, root_op_data)?;
         let op_store_type_path = op_store_path.join("type");
         fs::write(&op_store_type_path, op_store.name()).context(&op_store_type_path)?;
         let op_store: Arc<dyn OpStore> = Arc::from(op_store);
@@ -354,8 +372,8 @@ impl Repo for ReadonlyRepo {
 
 pub type BackendInitializer<'a> =
     dyn Fn(&UserSettings, &Path) -> Result<Box<dyn Backend>, BackendInitError> + 'a;
-pub type OpStoreInitializer<'a> =
-    dyn Fn(&UserSettings, &Path, RootOperationData) -> Box<dyn OpStore> + 'a;
+pub type OpStoreInitializer<'a> = dyn Fn(&UserSettings, &Path, RootOperationData) -> Result<Box<dyn OpStore>, BackendInitError>
+    + 'a;
 pub type OpHeadsStoreInitializer<'a> = dyn Fn(&UserSettings, &Path) -> Box<dyn OpHeadsStore> + 'a;
 pub type IndexStoreInitializer<'a> =
     dyn Fn(&UserSettings, &Path) -> Result<Box<dyn IndexStore>, BackendInitError> + 'a;
@@ -426,6 +444,12 @@ impl Default for StoreFactories {
                 Box::new(SimpleOpStore::load(store_path, root_data))
             }),
         );
+        factories.add_op_store(
+            LogOpStore::name(),
+            Box::new(|_settings, store_path, root_data| {
+                Box::new(LogOpStore::load(store_path, root_data))
+            }),
+        );
 
         // OpHeadsStores
         factories.add_op_heads_store(
@@ -166,11 +166,27 @@ pub enum RevsetFilterPredicate {
     CommitterDate(DatePattern),
     /// Commits modifying the paths specified by the fileset.
     File(FilesetExpression),
+    /// Commits modifying the paths specified by the fileset, or the paths
+    /// they were copied or renamed from in descendant commits.
+    FileFollow(FilesetExpression),
     /// Commits containing diffs matching the `text` pattern within the `files`.
     DiffContains {
         text: StringPattern,
         files: FilesetExpression,
     },
+    /// Commits adding or removing a number of lines in the range within the
+    /// `files`. Binary files are skipped.
+    DiffLines {
+        range: Range<usize>,
+        files: FilesetExpression,
+    },
+    /// Commits modifying a number of files in the range within the `files`.
+    FilesChanged {
+        range: Range<usize>,
+        files: FilesetExpression,
+    },
+    /// Commits adding binary files within the `files`.
+    AddsBinary(FilesetExpression),
     /// Commits with conflicts
     HasConflict,
     /// Custom predicates provided by extensions
@@ -586,6 +602,13 @@ pub enum ResolvedPredicateExpression {
         Box<ResolvedPredicateExpression>,
         Box<ResolvedPredicateExpression>,
     ),
+    /// Commits modifying the `files`, or the paths they were copied or renamed
+    /// from. Copies and renames are looked up in all ancestors of the `heads`,
+    /// not only in the commits the predicate is evaluated against.
+    FileFollow {
+        files: FilesetExpression,
+        heads: Box<ResolvedExpression>,
+    },
 }
 
 /// Describes evaluation plan of revset expression.
@@ -882,6 +905,13 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
         })?;
         // TODO: Drop support for multiple arguments in jj 0.28+
         let ([arg], args) = function.expect_some_arguments()?;
+        if args.is_empty() {
+            if let Some(expr) = expect_follow_fileset_expression(diagnostics, arg, ctx)? {
+                return Ok(RevsetExpression::filter(RevsetFilterPredicate::FileFollow(
+                    expr,
+                )));
+            }
+        }
         if !args.is_empty() {
             diagnostics.add_warning(RevsetParseError::expression(
                 "Multi-argument patterns syntax is deprecated; separate them with |",
@@ -899,23 +929,35 @@ static BUILTIN_FUNCTION_MAP: Lazy<HashMap<&'static str, RevsetFunction>> = Lazy:
     map.insert("diff_contains", |diagnostics, function, context| {
         let ([text_arg], [files_opt_arg]) = function.expect_arguments()?;
         let text = expect_string_pattern(diagnostics, text_arg)?;
-        let files = if let Some(files_arg) = files_opt_arg {
-            let ctx = context.workspace.as_ref().ok_or_else(|| {
-                RevsetParseError::with_span(
-                    RevsetParseErrorKind::FsPathWithoutWorkspace,
-                    files_arg.span,
-                )
-            })?;
-            expect_fileset_expression(diagnostics, files_arg, ctx.path_converter)?
-        } else {
-            // TODO: defaults to CLI path arguments?
-            // https://github.com/martinvonz/jj/issues/2933#issuecomment-1925870731
-            FilesetExpression::all()
-        };
+        let files = parse_diff_files_argument(diagnostics, files_opt_arg, context)?;
         Ok(RevsetExpression::filter(
             RevsetFilterPredicate::DiffContains { text, files },
         ))
     });
+    map.insert("diff_lines", |diagnostics, function, context| {
+        let ([range_arg], [files_opt_arg]) = function.expect_arguments()?;
+        let range = expect_count_pattern(diagnostics, range_arg)?;
+        let files = parse_diff_files_argument(diagnostics, files_opt_arg, context)?;
+        Ok(RevsetExpression::filter(RevsetFilterPredicate::DiffLines {
+            range,
+            files,
+        }))
+    });
+    map.insert("files_changed", |diagnostics, function, context| {
+        let ([range_arg], [files_opt_arg]) = function.expect_arguments()?;
+        let range = expect_count_pattern(diagnostics, range_arg)?;
+        let files = parse_diff_files_argument(diagnostics, files_opt_arg, context)?;
+        Ok(RevsetExpression::filter(
+            RevsetFilterPredicate::FilesChanged { range, files },
+        ))
+    });
+    map.insert("adds_binary", |diagnostics, function, context| {
+        let ([], [files_opt_arg]) = function.expect_arguments()?;
+        let files = parse_diff_files_argument(diagnostics, files_opt_arg, context)?;
+        Ok(RevsetExpression::filter(RevsetFilterPredicate::AddsBinary(
+            files,
+        )))
+    });
     map.insert("conflicts", |diagnostics, function, _context| {
         // TODO: Remove in jj 0.28+
         if function.name != "conflicts" {
@@ -968,15 +1010,44 @@ pub fn expect_fileset_expression(
     // substituted, but inner expressions `x & alias` aren't. If this seemed
     // weird, we can either transform AST or turn off revset aliases completely.
     revset_parser::expect_expression_with(diagnostics, node, |diagnostics, node| {
-        let mut inner_diagnostics = FilesetDiagnostics::new();
-        let expression = fileset::parse(&mut inner_diagnostics, node.span.as_str(), path_converter)
-            .map_err(|err| {
-                RevsetParseError::expression("In fileset expression", node.span).with_source(err)
-            })?;
-        diagnostics.extend_with(inner_diagnostics, |diag| {
-            RevsetParseError::expression("In fileset expression", node.span).with_source(diag)
-        });
-        Ok(expression)
+        parse_fileset_of_node(diagnostics, node, node.span.as_str(), path_converter)
+    })
+}
+
+/// Parses the `text` as a fileset expression. Errors are reported at the
+/// `node`.
+fn parse_fileset_of_node(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+    text: &str,
+    path_converter: &RepoPathUiConverter,
+) -> Result<FilesetExpression, RevsetParseError> {
+    let mut inner_diagnostics = FilesetDiagnostics::new();
+    let expression =
+        fileset::parse(&mut inner_diagnostics, text, path_converter).map_err(|err| {
+            RevsetParseError::expression("In fileset expression", node.span).with_source(err)
+        })?;
+    diagnostics.extend_with(inner_diagnostics, |diag| {
+        RevsetParseError::expression("In fileset expression", node.span).with_source(diag)
+    });
+    Ok(expression)
+}
+
+/// Parses the given `node` as `follow:"fileset"` if it is.
+fn expect_follow_fileset_expression(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+    ctx: &RevsetWorkspaceContext,
+) -> Result<Option<FilesetExpression>, RevsetParseError> {
+    revset_parser::expect_expression_with(diagnostics, node, |diagnostics, node| {
+        let ExpressionKind::StringPattern {
+            kind: "follow",
+            value,
+        } = &node.kind
+        else {
+            return Ok(None);
+        };
+        parse_fileset_of_node(diagnostics, node, value, ctx.path_converter).map(Some)
     })
 }
 
@@ -1013,6 +1084,68 @@ pub fn expect_date_pattern(
     )
 }
 
+/// Parses the given `node` as a count pattern such as `>500`, `<=20`, or `3`.
+pub fn expect_count_pattern(
+    diagnostics: &mut RevsetDiagnostics,
+    node: &ExpressionNode,
+) -> Result<Range<usize>, RevsetParseError> {
+    revset_parser::expect_pattern_with(
+        diagnostics,
+        "count pattern",
+        node,
+        |_diagnostics, value, kind| -> Result<_, Box<dyn std::error::Error + Send + Sync>> {
+            if let Some(kind) = kind {
+                return Err(format!("Invalid count pattern kind \"{kind}:\"").into());
+            }
+            parse_count_range(value)
+        },
+    )
+}
+
+fn parse_count_range(text: &str) -> Result<Range<usize>, Box<dyn std::error::Error + Send + Sync>> {
+    let text = text.trim();
+    let (op, number) = if let Some(number) = text.strip_prefix(">=") {
+        (">=", number)
+    } else if let Some(number) = text.strip_prefix("<=") {
+        ("<=", number)
+    } else if let Some(number) = text.strip_prefix('>') {
+        (">", number)
+    } else if let Some(number) = text.strip_prefix('<') {
+        ("<", number)
+    } else if let Some(number) = text.strip_prefix('=') {
+        ("=", number)
+    } else {
+        ("=", text)
+    };
+    let n: usize = number.trim().parse()?;
+    let range = match op {
+        ">=" => n..usize::MAX,
+        "<=" => 0..n.saturating_add(1),
+        ">" => n.saturating_add(1)..usize::MAX,
+        "<" => 0..n,
+        _ => n..n.saturating_add(1),
+    };
+    Ok(range)
+}
+
+/// Parses the optional fileset argument of the diff predicates. Defaults to
+/// all files.
+fn parse_diff_files_argument(
+    diagnostics: &mut RevsetDiagnostics,
+    files_opt_arg: Option<&ExpressionNode>,
+    context: &RevsetParseContext,
+) -> Result<FilesetExpression, RevsetParseError> {
+    let Some(files_arg) = files_opt_arg else {
+        // TODO: defaults to CLI path arguments?
+        // https://github.com/martinvonz/jj/issues/2933#issuecomment-1925870731
+        return Ok(FilesetExpression::all());
+    };
+    let ctx = context.workspace.as_ref().ok_or_else(|| {
+        RevsetParseError::with_span(RevsetParseErrorKind::FsPathWithoutWorkspace, files_arg.span)
+    })?;
+    expect_fileset_expression(diagnostics, files_arg, ctx.path_converter)
+}
+
 fn parse_remote_bookmarks_arguments(
     diagnostics: &mut RevsetDiagnostics,
     function: &FunctionCallNode,
@@ -2457,6 +2590,12 @@ impl VisibilityResolutionContext<'_> {
             | RevsetExpression::Latest { .. } => {
                 ResolvedPredicateExpression::Set(self.resolve(expression).into())
             }
+            RevsetExpression::Filter(RevsetFilterPredicate::FileFollow(files)) => {
+                ResolvedPredicateExpression::FileFollow {
+                    files: files.clone(),
+                    heads: self.resolve_visible_heads().into(),
+                }
+            }
             RevsetExpression::Filter(predicate) => {
                 ResolvedPredicateExpression::Filter(predicate.clone())
             }
@@ -3229,6 +3368,12 @@ mod tests {
         insta::assert_debug_snapshot!(
             parse_with_workspace(r#"file(file:"foo")"#, &WorkspaceId::default()).unwrap(),
             @r###"Filter(File(Pattern(FilePath("foo"))))"###);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files(follow:foo)", &WorkspaceId::default()).unwrap(),
+            @r#"Filter(FileFollow(Pattern(PrefixPath("foo"))))"#);
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files(follow:'(')", &WorkspaceId::default()).unwrap_err().kind(),
+            @r#"Expression("In fileset expression")"#);
         insta::assert_debug_snapshot!(
             parse_with_workspace("file(foo|bar&baz)", &WorkspaceId::default()).unwrap(), @r###"
         Filter(
@@ -3261,6 +3406,70 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_parse_diff_stat_function() {
+        insta::assert_debug_snapshot!(parse("diff_lines('<=20')").unwrap(), @r"
+        Filter(
+            DiffLines {
+                range: 0..21,
+                files: All,
+            },
+        )
+        ");
+        insta::assert_debug_snapshot!(
+            parse_with_workspace("files_changed(5, foo)", &WorkspaceId::default()).unwrap(),
+            @r#"
+        Filter(
+            FilesChanged {
+                range: 5..6,
+                files: Pattern(
+                    PrefixPath(
+                        "foo",
+                    ),
+                ),
+            },
+        )
+        "#);
+        insta::assert_debug_snapshot!(parse("adds_binary()").unwrap(), @r"
+        Filter(
+            AddsBinary(
+                All,
+            ),
+        )
+        ");
+        insta::assert_debug_snapshot!(
+            parse("diff_lines('x')").unwrap_err().kind(),
+            @r#"
+        Expression(
+            "Invalid count pattern",
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse("diff_lines(exact:'1')").unwrap_err().kind(),
+            @r#"
+        Expression(
+            "Invalid count pattern",
+        )
+        "#);
+        insta::assert_debug_snapshot!(
+            parse("diff_lines('>1', foo)").unwrap_err().kind(),
+            @"FsPathWithoutWorkspace");
+    }
+
+    #[test]
+    fn test_parse_count_range() {
+        assert_eq!(parse_count_range("3").unwrap(), 3..4);
+        assert_eq!(parse_count_range("=3").unwrap(), 3..4);
+        assert_eq!(parse_count_range(">500").unwrap(), 501..usize::MAX);
+        assert_eq!(parse_count_range(">= 2").unwrap(), 2..usize::MAX);
+        assert_eq!(parse_count_range("<3").unwrap(), 0..3);
+        assert_eq!(parse_count_range("<=3").unwrap(), 0..4);
+        assert!(parse_count_range("").is_err());
+        assert!(parse_count_range(">").is_err());
+        assert!(parse_count_range("-1").is_err());
+        assert!(parse_count_range("1..2").is_err());
+    }
+
     #[test]
     fn test_parse_revset_keyword_arguments() {
         let settings = insta_settings();
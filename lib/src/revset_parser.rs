@@ -240,6 +240,12 @@ impl RevsetParseError {
         &self.kind
     }
 
+    /// Converts into the underlying pest error, which is reused by the opset
+    /// language sharing this grammar.
+    pub(super) fn into_pest_error(self) -> Box<pest::error::Error<Rule>> {
+        self.pest_error
+    }
+
     /// Original parsing error which typically occurred in an alias expression.
     pub fn origin(&self) -> Option<&Self> {
         self.source.as_ref().and_then(|e| e.downcast_ref())
@@ -14,6 +14,7 @@
 
 #![allow(missing_docs)]
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -195,6 +196,34 @@ impl UserSettings {
             .unwrap_or_else(|_| whoami::username())
     }
 
+    /// Tags to be attached to every new operation, from the `operation.tags`
+    /// table.
+    pub fn operation_tags(&self) -> HashMap<String, String> {
+        self.config
+            .get::<HashMap<String, String>>("operation.tags")
+            .unwrap_or_default()
+    }
+
+    /// Format of the operation store of new repos, from the
+    /// `format.op-store` setting.
+    pub fn op_store_format(&self) -> Result<OpStoreFormat, config::ConfigError> {
+        Ok(self
+            .config
+            .get::<OpStoreFormat>("format.op-store")
+            .optional()?
+            .unwrap_or_default())
+    }
+
+    /// Whether new repos using the simple operation store write views as
+    /// deltas, from the `format.view-deltas` setting.
+    pub fn op_store_view_deltas(&self) -> Result<bool, config::ConfigError> {
+        Ok(self
+            .config
+            .get_bool("format.view-deltas")
+            .optional()?
+            .unwrap_or(false))
+    }
+
     pub fn push_bookmark_prefix(&self) -> String {
         self.config
             .get_string("git.push-bookmark-prefix")
@@ -303,6 +332,17 @@ impl<T> ConfigResultExt<T> for Result<T, config::ConfigError> {
     }
 }
 
+/// Format of the operation store.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpStoreFormat {
+    /// One file per operation and view.
+    #[default]
+    Simple,
+    /// Single append-only log file with incremental views.
+    Log,
+}
+
 /// A size in bytes optionally formatted/serialized with binary prefixes
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, serde::Deserialize)]
 #[serde(try_from = "String")]
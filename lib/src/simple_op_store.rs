@@ -47,6 +47,7 @@ use crate::object_id::PrefixResolution;
 use crate::op_store;
 use crate::op_store::OpStore;
 use crate::op_store::OpStoreError;
+use crate::op_store::OpStoreGcStats;
 use crate::op_store::OpStoreResult;
 use crate::op_store::Operation;
 use crate::op_store::OperationId;
@@ -64,6 +65,26 @@ use crate::op_store::WorkspaceId;
 const OPERATION_ID_LENGTH: usize = 64;
 const VIEW_ID_LENGTH: usize = 64;
 
+/// Maximum number of deltas to apply to reconstruct a view. A view that would
+/// exceed this is written in full.
+const MAX_VIEW_DELTA_CHAIN: usize = 16;
+
+/// Header of view files stored as deltas, followed by an encoded `ViewDelta`.
+///
+/// The leading zero byte is an invalid protobuf field tag, so older versions
+/// fail to decode delta files instead of reading them as empty views.
+const VIEW_DELTA_HEADER: &[u8] = b"\0jj-view-delta-v1\n";
+
+/// Name of the file marking a store that writes views as deltas. Stores
+/// without it keep writing full views so older versions can read them.
+const VIEW_DELTAS_MARKER: &str = "view_deltas";
+
+/// Contents of a view file.
+enum ViewFile {
+    Full(crate::protos::op_store::View),
+    Delta(crate::protos::op_store::ViewDelta),
+}
+
 #[derive(Debug, Error)]
 #[error("Failed to read {kind} with ID {id}")]
 struct DecodeError {
@@ -85,6 +106,7 @@ pub struct SimpleOpStore {
     root_data: RootOperationData,
     root_operation_id: OperationId,
     root_view_id: ViewId,
+    view_deltas: bool,
 }
 
 impl SimpleOpStore {
@@ -99,6 +121,16 @@ impl SimpleOpStore {
         Self::load(store_path, root_data)
     }
 
+    /// Creates an empty OpStore that stores new views as deltas against the
+    /// view of the parent operation. Older versions of jj can't read such
+    /// views. Panics if the store already exists.
+    pub fn init_with_view_deltas(store_path: &Path, root_data: RootOperationData) -> Self {
+        fs::create_dir(store_path.join("views")).unwrap();
+        fs::create_dir(store_path.join("operations")).unwrap();
+        fs::write(store_path.join(VIEW_DELTAS_MARKER), "").unwrap();
+        Self::load(store_path, root_data)
+    }
+
     /// Load an existing OpStore
     pub fn load(store_path: &Path, root_data: RootOperationData) -> Self {
         SimpleOpStore {
@@ -106,6 +138,7 @@ impl SimpleOpStore {
             root_data,
             root_operation_id: OperationId::from_bytes(&[0; OPERATION_ID_LENGTH]),
             root_view_id: ViewId::from_bytes(&[0; VIEW_ID_LENGTH]),
+            view_deltas: store_path.join(VIEW_DELTAS_MARKER).exists(),
         }
     }
 
@@ -116,6 +149,83 @@ impl SimpleOpStore {
     fn operation_path(&self, id: &OperationId) -> PathBuf {
         self.path.join("operations").join(id.hex())
     }
+
+    fn read_view_file(&self, id: &ViewId) -> OpStoreResult<ViewFile> {
+        let path = self.view_path(id);
+        let buf = fs::read(path).map_err(|err| io_to_read_error(err, id))?;
+        let to_decode_error = |err| DecodeError {
+            kind: "view",
+            id: id.hex(),
+            err,
+        };
+        if let Some(delta_buf) = buf.strip_prefix(VIEW_DELTA_HEADER) {
+            let proto =
+                crate::protos::op_store::ViewDelta::decode(delta_buf).map_err(to_decode_error)?;
+            Ok(ViewFile::Delta(proto))
+        } else {
+            let proto = crate::protos::op_store::View::decode(&*buf).map_err(to_decode_error)?;
+            Ok(ViewFile::Full(proto))
+        }
+    }
+
+    /// Returns the base view ID if the view is stored as a delta.
+    fn read_view_base_id(&self, id: &ViewId) -> OpStoreResult<Option<ViewId>> {
+        if *id == self.root_view_id {
+            return Ok(None);
+        }
+        match self.read_view_file(id)? {
+            ViewFile::Full(_) => Ok(None),
+            ViewFile::Delta(delta) => Ok(Some(ViewId::new(delta.base_view_id))),
+        }
+    }
+
+    /// Reads the view, applying deltas on top of the nearest full view.
+    /// Returns the view and the number of applied deltas.
+    fn read_view_with_depth(&self, id: &ViewId) -> OpStoreResult<(View, usize)> {
+        let mut deltas = vec![];
+        let mut current_id = id.clone();
+        let base = loop {
+            if current_id == self.root_view_id {
+                break View::make_root(self.root_data.root_commit_id.clone());
+            }
+            let delta = match self.read_view_file(&current_id)? {
+                ViewFile::Full(proto) => break view_from_proto(proto),
+                ViewFile::Delta(delta) => delta,
+            };
+            if deltas.len() >= MAX_VIEW_DELTA_CHAIN {
+                return Err(OpStoreError::ReadObject {
+                    object_type: id.object_type(),
+                    hash: id.hex(),
+                    source: "View delta chain is too long".into(),
+                });
+            }
+            current_id = ViewId::new(delta.base_view_id.clone());
+            deltas.push(delta);
+        };
+        if deltas.is_empty() {
+            return Ok((base, 0));
+        }
+        // Deltas are computed between views serialized by view_to_proto(), so
+        // the base view has to be normalized in the same way.
+        let depth = deltas.len();
+        let proto = deltas
+            .into_iter()
+            .rev()
+            .fold(view_to_proto(&base), apply_view_delta);
+        Ok((view_from_proto(proto), depth))
+    }
+
+    fn write_view_bytes(&self, id: &ViewId, buf: &[u8]) -> OpStoreResult<()> {
+        let temp_file =
+            NamedTempFile::new_in(&self.path).map_err(|err| io_to_write_error(err, "view"))?;
+        temp_file
+            .as_file()
+            .write_all(buf)
+            .map_err(|err| io_to_write_error(err, "view"))?;
+        persist_content_addressed_temp_file(temp_file, self.view_path(id))
+            .map_err(|err| io_to_write_error(err, "view"))?;
+        Ok(())
+    }
 }
 
 impl OpStore for SimpleOpStore {
@@ -132,35 +242,39 @@ impl OpStore for SimpleOpStore {
     }
 
     fn read_view(&self, id: &ViewId) -> OpStoreResult<View> {
-        if *id == self.root_view_id {
-            return Ok(View::make_root(self.root_data.root_commit_id.clone()));
-        }
-
-        let path = self.view_path(id);
-        let buf = fs::read(path).map_err(|err| io_to_read_error(err, id))?;
-
-        let proto = crate::protos::op_store::View::decode(&*buf).map_err(|err| DecodeError {
-            kind: "view",
-            id: id.hex(),
-            err,
-        })?;
-        Ok(view_from_proto(proto))
+        let (view, _depth) = self.read_view_with_depth(id)?;
+        Ok(view)
     }
 
     fn write_view(&self, view: &View) -> OpStoreResult<ViewId> {
-        let temp_file =
-            NamedTempFile::new_in(&self.path).map_err(|err| io_to_write_error(err, "view"))?;
-
-        let proto = view_to_proto(view);
-        temp_file
-            .as_file()
-            .write_all(&proto.encode_to_vec())
-            .map_err(|err| io_to_write_error(err, "view"))?;
-
         let id = ViewId::new(blake2b_hash(view).to_vec());
+        self.write_view_bytes(&id, &view_to_proto(view).encode_to_vec())?;
+        Ok(id)
+    }
 
-        persist_content_addressed_temp_file(temp_file, self.view_path(&id))
-            .map_err(|err| io_to_write_error(err, "view"))?;
+    fn write_view_incremental(&self, view: &View, base_id: &ViewId) -> OpStoreResult<ViewId> {
+        if !self.view_deltas {
+            return self.write_view(view);
+        }
+        let id = ViewId::new(blake2b_hash(view).to_vec());
+        // An existing view is rewritten as is to renew its mtime. Since only
+        // new views are stored as deltas, the deltas can't form a cycle.
+        if let Ok(buf) = fs::read(self.view_path(&id)) {
+            self.write_view_bytes(&id, &buf)?;
+            return Ok(id);
+        }
+        let base = match self.read_view_with_depth(base_id) {
+            Ok((base, depth)) if depth < MAX_VIEW_DELTA_CHAIN => base,
+            // Write a checkpoint if the chain is long or the base is unreadable.
+            Ok(_) | Err(_) => return self.write_view(view),
+        };
+        let proto = view_to_proto(view);
+        let delta = view_delta_to_proto(base_id, &view_to_proto(&base), &proto);
+        if delta.encoded_len() >= proto.encoded_len() {
+            return self.write_view(view);
+        }
+        let buf = [VIEW_DELTA_HEADER, &delta.encode_to_vec()].concat();
+        self.write_view_bytes(&id, &buf)?;
         Ok(id)
     }
 
@@ -250,8 +364,33 @@ impl OpStore for SimpleOpStore {
             .map_err(|err| OpStoreError::Other(err.into()))
     }
 
+    fn all_operation_ids(&self) -> OpStoreResult<Vec<OperationId>> {
+        let op_dir = self.path.join("operations");
+        let list = || -> io::Result<_> {
+            let mut ids = vec![];
+            for entry in op_dir.read_dir()? {
+                let Ok(name) = entry?.file_name().into_string() else {
+                    continue; // Skip invalid UTF-8
+                };
+                let Ok(id) = OperationId::try_from_hex(&name) else {
+                    continue; // Skip invalid hex (e.g. temporary files)
+                };
+                ids.push(id);
+            }
+            Ok(ids)
+        };
+        list()
+            .context(&op_dir)
+            .map_err(|err| OpStoreError::Other(err.into()))
+    }
+
     #[tracing::instrument(skip(self))]
-    fn gc(&self, head_ids: &[OperationId], keep_newer: SystemTime) -> OpStoreResult<()> {
+    fn gc(
+        &self,
+        head_ids: &[OperationId],
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> OpStoreResult<OpStoreGcStats> {
         let to_op_id = |entry: &fs::DirEntry| -> Option<OperationId> {
             let name = entry.file_name().into_string().ok()?;
             OperationId::try_from_hex(&name).ok()
@@ -260,7 +399,8 @@ impl OpStore for SimpleOpStore {
             let name = entry.file_name().into_string().ok()?;
             ViewId::try_from_hex(&name).ok()
         };
-        let remove_file_if_not_new = |entry: &fs::DirEntry| -> Result<(), PathError> {
+        // Returns true if the file is (or would be) removed.
+        let remove_file_if_not_new = |entry: &fs::DirEntry| -> Result<bool, PathError> {
             let path = entry.path();
             // Check timestamp, but there's still TOCTOU problem if an existing
             // file is renewed.
@@ -268,10 +408,14 @@ impl OpStore for SimpleOpStore {
             let mtime = metadata.modified().expect("unsupported platform?");
             if mtime > keep_newer {
                 tracing::trace!(?path, "not removing");
-                Ok(())
+                Ok(false)
+            } else if dry_run {
+                tracing::trace!(?path, "would remove");
+                Ok(true)
             } else {
                 tracing::trace!(?path, "removing");
-                fs::remove_file(&path).context(&path)
+                fs::remove_file(&path).context(&path)?;
+                Ok(true)
             }
         };
 
@@ -285,15 +429,21 @@ impl OpStore for SimpleOpStore {
             |(_, data)| data.parents.iter().map(read_op).collect_vec(),
         )
         .try_collect()?;
-        let reachable_views: HashSet<&ViewId> =
-            reachable_ops.values().map(|data| &data.view_id).collect();
+        // Views stored as deltas also need their base views.
+        let reachable_views: HashSet<ViewId> = dag_walk::dfs_ok(
+            reachable_ops.values().map(|data| Ok(data.view_id.clone())),
+            |id| id.clone(),
+            |id| self.read_view_base_id(id).transpose(),
+        )
+        .try_collect()?;
         tracing::info!(
             reachable_op_count = reachable_ops.len(),
             reachable_view_count = reachable_views.len(),
             "collected reachable objects"
         );
 
-        let prune_ops = || -> Result<(), PathError> {
+        let prune_ops = || -> Result<usize, PathError> {
+            let mut count = 0;
             let op_dir = self.path.join("operations");
             for entry in op_dir.read_dir().context(&op_dir)? {
                 let entry = entry.context(&op_dir)?;
@@ -307,30 +457,68 @@ impl OpStore for SimpleOpStore {
                 // If the operation was added after collecting reachable_views,
                 // its view mtime would also be renewed. So there's no need to
                 // update the reachable_views set to preserve the view.
-                remove_file_if_not_new(&entry)?;
+                if remove_file_if_not_new(&entry)? {
+                    count += 1;
+                }
             }
-            Ok(())
+            Ok(count)
         };
-        prune_ops().map_err(|err| OpStoreError::Other(err.into()))?;
+        let pruned_operations = prune_ops().map_err(|err| OpStoreError::Other(err.into()))?;
 
-        let prune_views = || -> Result<(), PathError> {
-            let view_dir = self.path.join("views");
+        let view_dir = self.path.join("views");
+        let list_unreachable_views = || -> Result<Vec<(ViewId, fs::DirEntry)>, PathError> {
+            let mut entries = vec![];
             for entry in view_dir.read_dir().context(&view_dir)? {
                 let entry = entry.context(&view_dir)?;
                 let Some(id) = to_view_id(&entry) else {
                     tracing::trace!(?entry, "skipping invalid file name");
                     continue;
                 };
-                if reachable_views.contains(&id) {
+                if !reachable_views.contains(&id) {
+                    entries.push((id, entry));
+                }
+            }
+            Ok(entries)
+        };
+        let unreachable_views =
+            list_unreachable_views().map_err(|err| OpStoreError::Other(err.into()))?;
+        // Unreachable views newer than keep_newer are retained, and so must be
+        // the bases of the retained views stored as deltas.
+        let is_new = |entry: &fs::DirEntry| -> Result<bool, PathError> {
+            let metadata = entry.metadata().context(entry.path())?;
+            Ok(metadata.modified().expect("unsupported platform?") > keep_newer)
+        };
+        let mut new_views = vec![];
+        for (id, entry) in &unreachable_views {
+            if is_new(entry).map_err(|err| OpStoreError::Other(err.into()))? {
+                new_views.push(id.clone());
+            }
+        }
+        let retained_bases: HashSet<ViewId> = dag_walk::dfs_ok(
+            new_views.into_iter().map(Ok),
+            |id| id.clone(),
+            |id| self.read_view_base_id(id).transpose(),
+        )
+        .try_collect()?;
+
+        let prune_views = || -> Result<usize, PathError> {
+            let mut count = 0;
+            for (id, entry) in &unreachable_views {
+                if retained_bases.contains(id) {
                     continue;
                 }
-                remove_file_if_not_new(&entry)?;
+                if remove_file_if_not_new(entry)? {
+                    count += 1;
+                }
             }
-            Ok(())
+            Ok(count)
         };
-        prune_views().map_err(|err| OpStoreError::Other(err.into()))?;
+        let pruned_views = prune_views().map_err(|err| OpStoreError::Other(err.into()))?;
 
-        Ok(())
+        Ok(OpStoreGcStats {
+            pruned_operations,
+            pruned_views,
+        })
     }
 }
 
@@ -401,7 +589,7 @@ fn operation_metadata_from_proto(
     }
 }
 
-fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operation {
+pub(crate) fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operation {
     let mut proto = crate::protos::op_store::Operation {
         view_id: operation.view_id.as_bytes().to_vec(),
         metadata: Some(operation_metadata_to_proto(&operation.metadata)),
@@ -413,7 +601,7 @@ fn operation_to_proto(operation: &Operation) -> crate::protos::op_store::Operati
     proto
 }
 
-fn operation_from_proto(proto: crate::protos::op_store::Operation) -> Operation {
+pub(crate) fn operation_from_proto(proto: crate::protos::op_store::Operation) -> Operation {
     let parents = proto.parents.into_iter().map(OperationId::new).collect();
     let view_id = ViewId::new(proto.view_id);
     let metadata = operation_metadata_from_proto(proto.metadata.unwrap_or_default());
@@ -424,7 +612,7 @@ fn operation_from_proto(proto: crate::protos::op_store::Operation) -> Operation
     }
 }
 
-fn view_to_proto(view: &View) -> crate::protos::op_store::View {
+pub(crate) fn view_to_proto(view: &View) -> crate::protos::op_store::View {
     let mut proto = crate::protos::op_store::View {
         // New/loaded view should have been migrated to the latest format
         has_git_refs_migrated_to_remote: true,
@@ -461,7 +649,7 @@ fn view_to_proto(view: &View) -> crate::protos::op_store::View {
     proto
 }
 
-fn view_from_proto(proto: crate::protos::op_store::View) -> View {
+pub(crate) fn view_from_proto(proto: crate::protos::op_store::View) -> View {
     let mut view = View::empty();
     // For compatibility with old repos before we had support for multiple working
     // copies
@@ -511,6 +699,127 @@ fn view_from_proto(proto: crate::protos::op_store::View) -> View {
     view
 }
 
+/// Computes changes from the `base` view to the `view`. Both views should be
+/// serialized by `view_to_proto()`.
+pub(crate) fn view_delta_to_proto(
+    base_id: &ViewId,
+    base: &crate::protos::op_store::View,
+    view: &crate::protos::op_store::View,
+) -> crate::protos::op_store::ViewDelta {
+    let base_head_ids: HashSet<&[u8]> = base.head_ids.iter().map(Vec::as_slice).collect();
+    let head_ids: HashSet<&[u8]> = view.head_ids.iter().map(Vec::as_slice).collect();
+    let (bookmarks, removed_bookmarks) =
+        diff_named_entries(&base.bookmarks, &view.bookmarks, |bookmark| &bookmark.name);
+    let (tags, removed_tags) = diff_named_entries(&base.tags, &view.tags, |tag| &tag.name);
+    let (git_refs, removed_git_refs) =
+        diff_named_entries(&base.git_refs, &view.git_refs, |git_ref| &git_ref.name);
+    crate::protos::op_store::ViewDelta {
+        base_view_id: base_id.to_bytes(),
+        added_head_ids: view
+            .head_ids
+            .iter()
+            .filter(|id| !base_head_ids.contains(id.as_slice()))
+            .cloned()
+            .collect(),
+        removed_head_ids: base
+            .head_ids
+            .iter()
+            .filter(|id| !head_ids.contains(id.as_slice()))
+            .cloned()
+            .collect(),
+        wc_commit_ids: view
+            .wc_commit_ids
+            .iter()
+            .filter(|(name, id)| base.wc_commit_ids.get(*name) != Some(id))
+            .map(|(name, id)| (name.clone(), id.clone()))
+            .collect(),
+        removed_wc_commit_ids: base
+            .wc_commit_ids
+            .keys()
+            .filter(|name| !view.wc_commit_ids.contains_key(*name))
+            .cloned()
+            .collect(),
+        bookmarks,
+        removed_bookmarks,
+        tags,
+        removed_tags,
+        git_refs,
+        removed_git_refs,
+        git_head: view.git_head.clone(),
+    }
+}
+
+/// Applies the changes computed by `view_delta_to_proto()` to the `base` view.
+pub(crate) fn apply_view_delta(
+    mut base: crate::protos::op_store::View,
+    delta: crate::protos::op_store::ViewDelta,
+) -> crate::protos::op_store::View {
+    let removed_head_ids: HashSet<_> = delta.removed_head_ids.into_iter().collect();
+    base.head_ids.retain(|id| !removed_head_ids.contains(id));
+    base.head_ids.extend(delta.added_head_ids);
+    for name in &delta.removed_wc_commit_ids {
+        base.wc_commit_ids.remove(name);
+    }
+    base.wc_commit_ids.extend(delta.wc_commit_ids);
+    base.bookmarks = merge_named_entries(
+        base.bookmarks,
+        delta.bookmarks,
+        &delta.removed_bookmarks,
+        |bookmark| &bookmark.name,
+    );
+    base.tags = merge_named_entries(base.tags, delta.tags, &delta.removed_tags, |tag| &tag.name);
+    base.git_refs = merge_named_entries(
+        base.git_refs,
+        delta.git_refs,
+        &delta.removed_git_refs,
+        |git_ref| &git_ref.name,
+    );
+    base.git_head = delta.git_head;
+    base
+}
+
+/// Returns the entries added or changed from `base`, and the names of the
+/// removed entries.
+fn diff_named_entries<T: Clone + PartialEq>(
+    base: &[T],
+    entries: &[T],
+    name: impl Fn(&T) -> &String,
+) -> (Vec<T>, Vec<String>) {
+    let base_map: HashMap<&String, &T> = base.iter().map(|entry| (name(entry), entry)).collect();
+    let names: HashSet<&String> = entries.iter().map(&name).collect();
+    let changed = entries
+        .iter()
+        .filter(|entry| base_map.get(name(entry)) != Some(entry))
+        .cloned()
+        .collect();
+    let removed = base
+        .iter()
+        .map(&name)
+        .filter(|entry_name| !names.contains(entry_name))
+        .cloned()
+        .collect();
+    (changed, removed)
+}
+
+fn merge_named_entries<T>(
+    base: Vec<T>,
+    changed: Vec<T>,
+    removed: &[String],
+    name: impl Fn(&T) -> &String,
+) -> Vec<T> {
+    let mut entries: BTreeMap<String, T> = base
+        .into_iter()
+        .map(|entry| (name(&entry).clone(), entry))
+        .collect();
+    for entry_name in removed {
+        entries.remove(entry_name);
+    }
+    for entry in changed {
+        entries.insert(name(&entry).clone(), entry);
+    }
+    entries.into_values().collect()
+}
+
 fn bookmark_views_to_proto_legacy(
     local_bookmarks: &BTreeMap<String, RefTarget>,
     remote_views: &BTreeMap<String, RemoteView>,
@@ -716,6 +1025,10 @@ fn remote_ref_state_from_proto(proto_value: Option<i32>) -> Option<RemoteRefStat
 
 #[cfg(test)]
 mod tests {
+    use std::iter;
+    use std::slice;
+    use std::time::Duration;
+
     use insta::assert_snapshot;
     use itertools::Itertools as _;
     use maplit::btreemap;
@@ -846,6 +1159,134 @@ mod tests {
         assert_eq!(read_operation, operation);
     }
 
+    #[test]
+    fn test_write_view_incremental() {
+        let temp_dir = testutils::new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init_with_view_deltas(temp_dir.path(), root_data);
+        let is_delta =
+            |id: &ViewId| matches!(store.read_view_file(id).unwrap(), ViewFile::Delta(_));
+        let mut view = create_view();
+        let base_id = store.write_view(&view).unwrap();
+        assert!(!is_delta(&base_id));
+
+        // Each view is stored as a delta against the previous one until the
+        // chain gets too long
+        let mut view_ids = vec![base_id];
+        let mut views = vec![view.clone()];
+        for i in 0..MAX_VIEW_DELTA_CHAIN + 1 {
+            view.head_ids = hashset! {CommitId::new(i.to_be_bytes().to_vec())};
+            let id = store
+                .write_view_incremental(&view, view_ids.last().unwrap())
+                .unwrap();
+            assert_eq!(id, ViewId::new(blake2b_hash(&view).to_vec()));
+            view_ids.push(id);
+            views.push(view.clone());
+        }
+        assert!(is_delta(&view_ids[1]));
+        assert!(is_delta(&view_ids[MAX_VIEW_DELTA_CHAIN]));
+        assert!(!is_delta(&view_ids[MAX_VIEW_DELTA_CHAIN + 1]));
+        for (id, view) in iter::zip(&view_ids, &views) {
+            assert_eq!(store.read_view(id).unwrap(), *view);
+        }
+
+        // Writing an existing view doesn't change how it's stored
+        store
+            .write_view_incremental(&views[1], &view_ids[2])
+            .unwrap();
+        assert!(is_delta(&view_ids[1]));
+        assert_eq!(store.read_view(&view_ids[1]).unwrap(), views[1]);
+
+        // Older versions can't decode delta views as full views
+        let buf = fs::read(store.view_path(&view_ids[1])).unwrap();
+        assert!(crate::protos::op_store::View::decode(&*buf).is_err());
+    }
+
+    #[test]
+    fn test_write_view_incremental_without_view_deltas() {
+        let temp_dir = testutils::new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init(temp_dir.path(), root_data);
+        let mut view = create_view();
+        let base_id = store.write_view(&view).unwrap();
+        view.head_ids.insert(CommitId::from_hex("aaa333"));
+        let id = store.write_view_incremental(&view, &base_id).unwrap();
+
+        // Existing stores keep writing full views readable by older versions
+        assert!(matches!(
+            store.read_view_file(&id).unwrap(),
+            ViewFile::Full(_)
+        ));
+        let buf = fs::read(store.view_path(&id)).unwrap();
+        assert!(crate::protos::op_store::View::decode(&*buf).is_ok());
+    }
+
+    #[test]
+    fn test_gc_keeps_view_delta_base() {
+        let temp_dir = testutils::new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init_with_view_deltas(temp_dir.path(), root_data);
+        let base_view = create_view();
+        let base_view_id = store.write_view(&base_view).unwrap();
+        let mut view = base_view.clone();
+        view.head_ids.insert(CommitId::from_hex("aaa333"));
+        let view_id = store.write_view_incremental(&view, &base_view_id).unwrap();
+
+        // Only the operation with the delta view is reachable
+        let mut operation = create_operation();
+        operation.view_id = view_id.clone();
+        operation.parents = vec![store.root_operation_id().clone()];
+        let op_id = store.write_operation(&operation).unwrap();
+        let stats = store
+            .gc(
+                slice::from_ref(&op_id),
+                SystemTime::now() + Duration::from_secs(1),
+                false,
+            )
+            .unwrap();
+        assert_eq!(stats.pruned_views, 0);
+        assert_eq!(store.read_view(&view_id).unwrap(), view);
+    }
+
+    #[test]
+    fn test_gc_keeps_base_of_new_view_delta() {
+        let temp_dir = testutils::new_temp_dir();
+        let root_data = RootOperationData {
+            root_commit_id: CommitId::from_hex("000000"),
+        };
+        let store = SimpleOpStore::init_with_view_deltas(temp_dir.path(), root_data);
+        let base_view = create_view();
+        let base_view_id = store.write_view(&base_view).unwrap();
+        let mut view = base_view.clone();
+        view.head_ids.insert(CommitId::from_hex("aaa333"));
+        let view_id = store.write_view_incremental(&view, &base_view_id).unwrap();
+
+        // Neither view is reachable, but the delta view is newer than the
+        // threshold. Make the base view look old.
+        let old_time = SystemTime::now() - Duration::from_secs(3600);
+        fs::File::options()
+            .write(true)
+            .open(store.view_path(&base_view_id))
+            .unwrap()
+            .set_modified(old_time)
+            .unwrap();
+        let stats = store
+            .gc(
+                slice::from_ref(store.root_operation_id()),
+                old_time + Duration::from_secs(1),
+                false,
+            )
+            .unwrap();
+        assert_eq!(stats.pruned_views, 0);
+        assert_eq!(store.read_view(&view_id).unwrap(), view);
+    }
+
     #[test]
     fn test_bookmark_views_legacy_roundtrip() {
         let new_remote_ref = |target: &RefTarget| RemoteRef {
@@ -15,19 +15,26 @@
 #![allow(missing_docs)]
 
 use std::any::Any;
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::io::Read;
+use std::slice;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::time::SystemTime;
 
 use clru::CLruCache;
+use futures::future;
+use futures::stream;
 use futures::stream::BoxStream;
+use futures::StreamExt as _;
+use futures::TryStreamExt as _;
 use pollster::FutureExt;
 
 use crate::backend;
 use crate::backend::Backend;
+use crate::backend::BackendGcStats;
 use crate::backend::BackendResult;
 use crate::backend::ChangeId;
 use crate::backend::CommitId;
@@ -38,6 +45,7 @@ use crate::backend::MergedTreeId;
 use crate::backend::SigningFn;
 use crate::backend::SymlinkId;
 use crate::backend::TreeId;
+use crate::backend::TreeValue;
 use crate::commit::Commit;
 use crate::index::Index;
 use crate::merge::Merge;
@@ -81,6 +89,12 @@ impl Store {
         })
     }
 
+    /// Name of the backend implementation, as returned by
+    /// [`Backend::name()`].
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
     pub fn backend_impl(&self) -> &dyn Any {
         self.backend.as_any()
     }
@@ -89,13 +103,68 @@ impl Store {
         &self.signer
     }
 
+    /// Returns the copies from `root` to `head`.
+    ///
+    /// If `root` is the only parent of `head`, the copies recorded on `head`
+    /// take precedence over the ones the backend detects for the same targets.
     pub fn get_copy_records(
-        &self,
+        self: &Arc<Self>,
         paths: Option<&[RepoPathBuf]>,
         root: &CommitId,
         head: &CommitId,
     ) -> BackendResult<BoxStream<BackendResult<CopyRecord>>> {
-        self.backend.get_copy_records(paths, root, head)
+        let recorded = self.get_recorded_copy_records(paths, root, head)?;
+        let detected = self.backend.get_copy_records(paths, root, head)?;
+        if recorded.is_empty() {
+            return Ok(detected);
+        }
+        let targets: HashSet<RepoPathBuf> = recorded
+            .iter()
+            .map(|record| record.target.clone())
+            .collect();
+        let detected =
+            detected.try_filter(move |record| future::ready(!targets.contains(&record.target)));
+        Ok(Box::pin(
+            stream::iter(recorded.into_iter().map(Ok)).chain(detected),
+        ))
+    }
+
+    fn get_recorded_copy_records(
+        self: &Arc<Self>,
+        paths: Option<&[RepoPathBuf]>,
+        root: &CommitId,
+        head: &CommitId,
+    ) -> BackendResult<Vec<CopyRecord>> {
+        let head_commit = self.get_commit(head)?;
+        if head_commit.copies().is_empty() || head_commit.parent_ids() != slice::from_ref(root) {
+            return Ok(vec![]);
+        }
+        let root_tree = self.get_commit(root)?.tree()?;
+        let head_tree = head_commit.tree()?;
+        let mut records = vec![];
+        for copy in head_commit.copies() {
+            if !paths.map_or(true, |paths| paths.contains(&copy.target)) {
+                continue;
+            }
+            // The recorded copies may no longer apply if the commit was
+            // rewritten onto another parent or its files were changed.
+            let source_value = root_tree.path_value(&copy.source)?;
+            let Some(TreeValue::File { id, .. }) = source_value.as_normal() else {
+                continue;
+            };
+            let target_value = head_tree.path_value(&copy.target)?;
+            let Some(TreeValue::File { .. }) = target_value.as_normal() else {
+                continue;
+            };
+            records.push(CopyRecord {
+                target: copy.target.clone(),
+                target_commit: head.clone(),
+                source: copy.source.clone(),
+                source_file: id.clone(),
+                source_commit: root.clone(),
+            });
+        }
+        Ok(records)
     }
 
     pub fn commit_id_length(&self) -> usize {
@@ -288,7 +357,12 @@ impl Store {
         TreeBuilder::new(self.clone(), base_tree_id)
     }
 
-    pub fn gc(&self, index: &dyn Index, keep_newer: SystemTime) -> BackendResult<()> {
-        self.backend.gc(index, keep_newer)
+    pub fn gc(
+        &self,
+        index: &dyn Index,
+        keep_newer: SystemTime,
+        dry_run: bool,
+    ) -> BackendResult<BackendGcStats> {
+        self.backend.gc(index, keep_newer, dry_run)
     }
 }
@@ -14,6 +14,8 @@
 
 //! Provides support for parsing and matching date ranges.
 
+use std::time::Duration;
+
 use chrono::DateTime;
 use chrono::FixedOffset;
 use chrono::Local;
@@ -21,6 +23,7 @@ use chrono::TimeZone;
 use chrono_english::parse_date_string;
 use chrono_english::DateError;
 use chrono_english::Dialect;
+use chrono_english::Interval;
 use thiserror::Error;
 
 use crate::backend::MillisSinceEpoch;
@@ -70,6 +73,12 @@ pub enum DatePatternParseError {
     /// Failed to parse timestamp.
     #[error(transparent)]
     ParseError(#[from] DateError),
+    /// The time is in the future where a past time is expected.
+    #[error(r#"Time "{0}" is in the future"#)]
+    FutureTime(String),
+    /// The duration is negative or has no fixed length.
+    #[error(r#"Invalid duration "{0}""#)]
+    InvalidDuration(String),
 }
 
 /// Represents an range of dates that may be matched against.
@@ -122,8 +131,70 @@ impl DatePattern {
     }
 }
 
+/// Parses a string into a point in time. Relative dates such as "yesterday"
+/// are resolved against `now`.
+pub fn parse_datetime<Tz: TimeZone>(
+    s: &str,
+    now: DateTime<Tz>,
+) -> Result<MillisSinceEpoch, DatePatternParseError>
+where
+    Tz::Offset: Copy,
+{
+    let d = parse_date_string(s, now, Dialect::Us)?;
+    Ok(MillisSinceEpoch(d.timestamp_millis()))
+}
+
+/// Parses a string into a point in time at or before `now`.
+///
+/// In addition to the date formats accepted by [`DatePattern`], a bare
+/// duration such as "2 weeks" is interpreted as the time that long ago.
+pub fn parse_past_time<Tz: TimeZone>(
+    s: &str,
+    now: DateTime<Tz>,
+) -> Result<MillisSinceEpoch, DatePatternParseError>
+where
+    Tz::Offset: Copy,
+{
+    let d = parse_date_string(s, now, Dialect::Us)?;
+    if d <= now {
+        return Ok(MillisSinceEpoch(d.timestamp_millis()));
+    }
+    // "2 weeks" is parsed as a future time
+    if chrono_english::parse_duration(s).is_ok() {
+        if let Ok(d) = parse_date_string(&format!("{s} ago"), now, Dialect::Us) {
+            if d <= now {
+                return Ok(MillisSinceEpoch(d.timestamp_millis()));
+            }
+        }
+    }
+    Err(DatePatternParseError::FutureTime(s.to_owned()))
+}
+
+/// Parses a duration such as "2 seconds", "5m", or "1 week".
+///
+/// Durations given in milliseconds, such as "500ms", are also accepted.
+/// Durations in months are rejected because their length varies.
+pub fn parse_duration(s: &str) -> Result<Duration, DatePatternParseError> {
+    // chrono-english reads "ms" as minutes
+    if let Some(millis) = s.strip_suffix("ms") {
+        if let Ok(millis) = millis.trim_end().parse() {
+            return Ok(Duration::from_millis(millis));
+        }
+    }
+    let seconds = match chrono_english::parse_duration(s)? {
+        Interval::Seconds(seconds) => seconds,
+        Interval::Days(days) => days.saturating_mul(24 * 60 * 60),
+        Interval::Months(_) => return Err(DatePatternParseError::InvalidDuration(s.to_owned())),
+    };
+    let seconds =
+        u64::try_from(seconds).map_err(|_| DatePatternParseError::InvalidDuration(s.to_owned()))?;
+    Ok(Duration::from_secs(seconds))
+}
+
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
+
     use super::*;
 
     fn test_equal<Tz: TimeZone>(now: DateTime<Tz>, expression: &str, should_equal_time: &str)
@@ -189,4 +260,64 @@ mod tests {
         test_equal(now, "yesterday 10am", "2023-12-31T18:00:00Z");
         test_equal(now, "yesterday 10:30", "2023-12-31T18:30:00Z");
     }
+
+    #[test]
+    fn test_parse_datetime() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00-08:00").unwrap();
+        let parse = |s| {
+            let millis = parse_datetime(s, now).unwrap();
+            DateTime::from_timestamp_millis(millis.0)
+                .unwrap()
+                .to_rfc3339()
+        };
+        assert_eq!(parse("2023-03-25 12:00"), "2023-03-25T20:00:00+00:00");
+        assert_eq!(parse("yesterday 5pm"), "2024-01-01T01:00:00+00:00");
+        assert_eq!(parse("2 hours ago"), "2024-01-01T06:00:00+00:00");
+        assert!(parse_datetime("bogus", now).is_err());
+    }
+
+    #[test]
+    fn test_parse_past_time() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00-08:00").unwrap();
+        let parse = |s| {
+            let millis = parse_past_time(s, now).unwrap();
+            DateTime::from_timestamp_millis(millis.0)
+                .unwrap()
+                .to_rfc3339()
+        };
+        assert_eq!(parse("now"), "2024-01-01T08:00:00+00:00");
+        assert_eq!(parse("2 weeks ago"), "2023-12-18T08:00:00+00:00");
+        assert_eq!(parse("2 weeks"), "2023-12-18T08:00:00+00:00");
+        assert_eq!(parse("3 hours"), "2024-01-01T05:00:00+00:00");
+        assert_eq!(parse("2023-03-25"), "2023-03-25T08:00:00+00:00");
+        assert!(parse_past_time("bogus", now).is_err());
+        assert_matches!(
+            parse_past_time("2024-03-25", now),
+            Err(DatePatternParseError::FutureTime(_))
+        );
+        assert_matches!(
+            parse_past_time("tomorrow", now),
+            Err(DatePatternParseError::FutureTime(_))
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let parse = |s| parse_duration(s).unwrap();
+        assert_eq!(parse("500ms"), Duration::from_millis(500));
+        assert_eq!(parse("1s"), Duration::from_secs(1));
+        assert_eq!(parse("2 seconds"), Duration::from_secs(2));
+        assert_eq!(parse("1m"), Duration::from_secs(60));
+        assert_eq!(parse("2 minutes"), Duration::from_secs(120));
+        assert_eq!(parse("1 hour"), Duration::from_secs(3600));
+        assert_eq!(parse("1 week"), Duration::from_secs(7 * 24 * 3600));
+        assert_matches!(
+            parse_duration("1 month"),
+            Err(DatePatternParseError::InvalidDuration(_))
+        );
+        assert_matches!(
+            parse_duration("bogus"),
+            Err(DatePatternParseError::ParseError(_))
+        );
+    }
 }
@@ -69,6 +69,8 @@ impl Transaction {
         self.mut_repo.base_repo()
     }
 
+    /// Attaches a metadata tag to the operation to be created, replacing any
+    /// existing tag with the same key.
     pub fn set_tag(&mut self, key: String, value: String) {
         self.op_metadata.tags.insert(key, value);
     }
@@ -123,7 +125,10 @@ impl Transaction {
         let base_repo = mut_repo.base_repo().clone();
         let (mut_index, view) = mut_repo.consume();
 
-        let view_id = base_repo.op_store().write_view(view.store_view()).unwrap();
+        let view_id = base_repo
+            .op_store()
+            .write_view_incremental(view.store_view(), base_repo.operation().view_id())
+            .unwrap();
         self.op_metadata.description = description.into();
         self.op_metadata.end_time = self.end_time.unwrap_or_else(Timestamp::now);
         let parents = self.parent_ops.iter().map(|op| op.id().clone()).collect();
@@ -164,7 +169,7 @@ pub fn create_op_metadata(
         hostname,
         username,
         is_snapshot,
-        tags: Default::default(),
+        tags: user_settings.operation_tags(),
     }
 }
 
@@ -26,11 +26,13 @@ use tracing::instrument;
 
 use crate::backend::BackendError;
 use crate::backend::MergedTreeId;
+use crate::backend::RecordedCopy;
 use crate::commit::Commit;
 use crate::dag_walk;
 use crate::fsmonitor::FsmonitorSettings;
 use crate::gitignore::GitIgnoreError;
 use crate::gitignore::GitIgnoreFile;
+use crate::gitignore::GitIgnoreMatch;
 use crate::matchers::EverythingMatcher;
 use crate::matchers::Matcher;
 use crate::op_heads_store::OpHeadsStoreError;
@@ -115,6 +117,13 @@ pub trait LockedWorkingCopy {
     /// Snapshot the working copy and return the tree id.
     fn snapshot(&mut self, options: &SnapshotOptions) -> Result<MergedTreeId, SnapshotError>;
 
+    /// Copies and renames detected by the last call to `snapshot()`, relative
+    /// to the tree before that snapshot. Implementations that don't detect
+    /// copies return nothing.
+    fn snapshot_copies(&self) -> Vec<RecordedCopy> {
+        vec![]
+    }
+
     /// Check out the specified commit in the working copy.
     fn check_out(&mut self, commit: &Commit) -> Result<CheckoutStats, CheckoutError>;
 
@@ -240,6 +249,37 @@ impl SnapshotOptions<'_> {
 /// A callback for getting progress updates.
 pub type SnapshotProgress<'a> = dyn Fn(&RepoPath) + 'a + Sync;
 
+/// A path that a snapshot leaves untracked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UntrackedPath {
+    /// The path of the file or directory.
+    pub path: RepoPathBuf,
+    /// Whether the path is a directory, in which case none of the untracked
+    /// files in it are tracked.
+    pub is_dir: bool,
+    /// Why the path is left untracked.
+    pub reason: UntrackedReason,
+}
+
+/// Why a snapshot leaves a path untracked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UntrackedReason {
+    /// The path is ignored by the given pattern.
+    Ignored(GitIgnoreMatch),
+    /// The path doesn't match `SnapshotOptions::start_tracking_matcher`.
+    NotAutoTracked,
+    /// The file name looks like a side of a conflict written next to the
+    /// conflicted file.
+    ConflictSidecar,
+    /// The file is larger than `SnapshotOptions::max_new_file_size`.
+    FileTooLarge {
+        /// The size of the file.
+        size: u64,
+        /// The maximum allowed size.
+        max_size: u64,
+    },
+}
+
 /// Stats about a checkout operation on a working copy. All "files" mentioned
 /// below may also be symlinks or materialized conflicts.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -342,14 +382,31 @@ impl WorkingCopyFreshness {
         locked_wc: &dyn LockedWorkingCopy,
         wc_commit: &Commit,
         repo: &ReadonlyRepo,
+    ) -> Result<Self, OpStoreError> {
+        Self::check_stale_state(
+            locked_wc.old_tree_id(),
+            locked_wc.old_operation_id(),
+            wc_commit,
+            repo,
+        )
+    }
+
+    /// Determine the freshness of a working copy whose state recorded the
+    /// given tree and operation, relative to the target commit. This can be
+    /// used to inspect working copies that aren't locked, such as those of
+    /// other workspaces.
+    pub fn check_stale_state(
+        wc_tree_id: &MergedTreeId,
+        wc_operation_id: &OperationId,
+        wc_commit: &Commit,
+        repo: &ReadonlyRepo,
     ) -> Result<Self, OpStoreError> {
         // Check if the working copy's tree matches the repo's view
-        let wc_tree_id = locked_wc.old_tree_id();
         if wc_commit.tree_id() == wc_tree_id {
             // The working copy isn't stale, and no need to reload the repo.
             Ok(Self::Fresh)
         } else {
-            let wc_operation = repo.loader().load_operation(locked_wc.old_operation_id())?;
+            let wc_operation = repo.loader().load_operation(wc_operation_id)?;
             let repo_operation = repo.operation();
             let ancestor_op = dag_walk::closest_common_node_ok(
                 [Ok(wc_operation.clone())],
@@ -21,8 +21,10 @@ use std::io;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::slice;
 use std::sync::Arc;
 
+use itertools::Itertools as _;
 use thiserror::Error;
 
 use crate::backend::BackendInitError;
@@ -59,6 +61,8 @@ use crate::working_copy::LockedWorkingCopy;
 use crate::working_copy::WorkingCopy;
 use crate::working_copy::WorkingCopyFactory;
 use crate::working_copy::WorkingCopyStateError;
+use crate::workspace_store::SimpleWorkspaceStore;
+use crate::workspace_store::WorkspaceStoreError;
 
 #[derive(Error, Debug)]
 pub enum WorkspaceInitError {
@@ -78,6 +82,8 @@ pub enum WorkspaceInitError {
     Backend(#[from] BackendInitError),
     #[error(transparent)]
     SignInit(#[from] SignInitError),
+    #[error(transparent)]
+    WorkspaceStore(#[from] WorkspaceStoreError),
 }
 
 #[derive(Error, Debug)]
@@ -96,6 +102,20 @@ pub enum WorkspaceLoadError {
     Path(#[from] PathError),
 }
 
+#[derive(Error, Debug)]
+pub enum WorkspaceMoveError {
+    #[error("The destination ({0}) already exists")]
+    DestinationExists(PathBuf),
+    #[error("Repo path could not be interpreted as Unicode text")]
+    NonUnicodePath,
+    #[error("The location of some workspaces sharing the repo is unknown")]
+    UnknownWorkspaces(Vec<WorkspaceId>),
+    #[error(transparent)]
+    Path(#[from] PathError),
+    #[error(transparent)]
+    WorkspaceStore(#[from] WorkspaceStoreError),
+}
+
 /// The combination of a repo and a working copy.
 ///
 /// Represents the combination of a repo and working copy, i.e. what's typically
@@ -122,6 +142,16 @@ fn create_jj_dir(workspace_root: &Path) -> Result<PathBuf, WorkspaceInitError> {
     }
 }
 
+/// Points the `.jj/repo` file in `jj_dir` at the repo directory shared with
+/// another workspace.
+fn write_repo_pointer(jj_dir: &Path, repo_dir: &str) -> Result<(), PathError> {
+    let repo_file_path = jj_dir.join("repo");
+    let mut repo_file = File::create(&repo_file_path).context(&repo_file_path)?;
+    repo_file
+        .write_all(repo_dir.as_bytes())
+        .context(&repo_file_path)
+}
+
 fn init_working_copy(
     user_settings: &UserSettings,
     repo: &Arc<ReadonlyRepo>,
@@ -321,6 +351,8 @@ impl Workspace {
             )?;
             let repo_loader = repo.loader().clone();
             let workspace = Workspace::new(workspace_root, repo_dir, working_copy, repo_loader)?;
+            SimpleWorkspaceStore::load(workspace.repo_path())
+                .add(workspace.workspace_id(), workspace.workspace_root())?;
             Ok((workspace, repo))
         })()
         .inspect_err(|_err| {
@@ -359,16 +391,12 @@ impl Workspace {
         let jj_dir = create_jj_dir(workspace_root)?;
 
         let repo_dir = repo_path.canonicalize().context(repo_path)?;
-        let repo_file_path = jj_dir.join("repo");
-        let mut repo_file = File::create(&repo_file_path).context(&repo_file_path)?;
-        repo_file
-            .write_all(
-                repo_dir
-                    .to_str()
-                    .ok_or(WorkspaceInitError::NonUnicodePath)?
-                    .as_bytes(),
-            )
-            .context(&repo_file_path)?;
+        write_repo_pointer(
+            &jj_dir,
+            repo_dir
+                .to_str()
+                .ok_or(WorkspaceInitError::NonUnicodePath)?,
+        )?;
 
         let (working_copy, repo) = init_working_copy(
             user_settings,
@@ -384,6 +412,8 @@ impl Workspace {
             working_copy,
             repo.loader().clone(),
         )?;
+        SimpleWorkspaceStore::load(workspace.repo_path())
+            .add(workspace.workspace_id(), workspace.workspace_root())?;
         Ok((workspace, repo))
     }
 
@@ -414,6 +444,87 @@ impl Workspace {
         &self.repo_loader
     }
 
+    /// Moves the workspace directory to `new_root`, which must not exist.
+    ///
+    /// The workspace store is updated to the new location before the
+    /// directory is moved. If the repo lives inside this workspace, the
+    /// `.jj/repo` files of the other workspaces are updated to point at its
+    /// new location. That fails with `UnknownWorkspaces` if any workspace in
+    /// the `repo` view isn't recorded in the store, since its `.jj/repo` file
+    /// couldn't be found. Returns the canonical path of the new workspace
+    /// root, from which the workspace should be loaded again.
+    pub fn move_to(&self, repo: &dyn Repo, new_root: &Path) -> Result<PathBuf, WorkspaceMoveError> {
+        if new_root.exists() {
+            return Err(WorkspaceMoveError::DestinationExists(new_root.to_owned()));
+        }
+        // The working copy state can't be loaded once the directory is moved.
+        let workspace_id = self.workspace_id().clone();
+        let old_root = self
+            .workspace_root
+            .canonicalize()
+            .context(&self.workspace_root)?;
+        let old_repo_path = self.repo_path.canonicalize().context(&self.repo_path)?;
+        // The destination doesn't exist yet, so only its parent can be
+        // canonicalized.
+        let new_root = match (new_root.parent(), new_root.file_name()) {
+            (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+                parent.canonicalize().context(parent)?.join(name)
+            }
+            _ => new_root.to_owned(),
+        };
+        let new_repo_path = match old_repo_path.strip_prefix(&old_root) {
+            Ok(relative_repo_path) => Some(new_root.join(relative_repo_path)),
+            Err(_) => None,
+        };
+        let new_repo_path_str = new_repo_path
+            .as_ref()
+            .map(|path| path.to_str().ok_or(WorkspaceMoveError::NonUnicodePath))
+            .transpose()?;
+
+        let store = SimpleWorkspaceStore::load(&old_repo_path);
+        let other_workspace_paths = if new_repo_path.is_some() {
+            let workspace_paths: HashMap<_, _> = store.workspace_paths()?.into_iter().collect();
+            let unknown_ids = repo
+                .view()
+                .wc_commit_ids()
+                .keys()
+                .filter(|id| **id != workspace_id && !workspace_paths.contains_key(id))
+                .cloned()
+                .collect_vec();
+            if !unknown_ids.is_empty() {
+                return Err(WorkspaceMoveError::UnknownWorkspaces(unknown_ids));
+            }
+            workspace_paths
+                .into_iter()
+                .filter(|(id, _)| *id != workspace_id)
+                .collect()
+        } else {
+            vec![]
+        };
+
+        // Record the new location first so that the workspace can still be
+        // found if we're interrupted after the directory is moved.
+        let old_store_path = store.get_workspace_path(&workspace_id)?;
+        store.add(&workspace_id, &new_root)?;
+        if let Err(err) = fs::rename(&old_root, &new_root).context(&new_root) {
+            match &old_store_path {
+                Some(path) => store.add(&workspace_id, path)?,
+                None => store.forget(slice::from_ref(&workspace_id))?,
+            }
+            return Err(err.into());
+        }
+
+        if let Some(new_repo_path_str) = new_repo_path_str {
+            for (_, workspace_root) in other_workspace_paths {
+                let jj_dir = workspace_root.join(".jj");
+                if jj_dir.join("repo").is_file() {
+                    write_repo_pointer(&jj_dir, new_repo_path_str)?;
+                }
+            }
+        }
+        Ok(new_root)
+    }
+
     pub fn working_copy(&self) -> &dyn WorkingCopy {
         self.working_copy.as_ref()
     }
@@ -606,6 +717,7 @@ impl WorkspaceLoader for DefaultWorkspaceLoader {
             working_copy,
             repo_loader,
         )?;
+        record_workspace_path(&workspace);
         Ok(workspace)
     }
 
@@ -626,6 +738,22 @@ impl WorkspaceLoader for DefaultWorkspaceLoader {
     }
 }
 
+/// Records the location of a loaded workspace in the workspace store if none
+/// is recorded yet, so workspaces created before the store existed can be
+/// found by other workspaces. An existing entry isn't overwritten: a copy of
+/// the workspace directory would otherwise take over the workspace's entry.
+/// Failures are ignored since the repo may be read-only.
+fn record_workspace_path(workspace: &Workspace) {
+    let store = SimpleWorkspaceStore::load(&workspace.repo_path);
+    let workspace_id = workspace.workspace_id();
+    if !matches!(store.get_workspace_path(workspace_id), Ok(None)) {
+        return;
+    }
+    if let Ok(workspace_root) = workspace.workspace_root.canonicalize() {
+        store.add(workspace_id, &workspace_root).ok();
+    }
+}
+
 pub fn default_working_copy_factories() -> WorkingCopyFactories {
     let mut factories = WorkingCopyFactories::new();
     factories.insert(
@@ -0,0 +1,176 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Records where the workspaces of a repo live on disk.
+//!
+//! The view only knows about the workspaces' working-copy commits. This store
+//! keeps track of their root directories so commands can find (and fix up)
+//! other workspaces sharing the same repo.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use tempfile::NamedTempFile;
+use thiserror::Error;
+
+use crate::file_util::IoResultExt as _;
+use crate::file_util::PathError;
+use crate::lock::FileLock;
+use crate::lock::FileLockError;
+use crate::op_store::WorkspaceId;
+
+/// Error that may occur while reading or updating the workspace store.
+#[derive(Debug, Error)]
+pub enum WorkspaceStoreError {
+    /// The store file couldn't be parsed.
+    #[error("Failed to parse workspace store {path}")]
+    Corrupt {
+        /// Path to the store file.
+        path: PathBuf,
+        /// Underlying error.
+        #[source]
+        source: serde_json::Error,
+    },
+    /// A workspace path couldn't be stored as text.
+    #[error("Workspace path could not be interpreted as Unicode text")]
+    NonUnicodePath,
+    /// Failed to lock the store.
+    #[error(transparent)]
+    Lock(#[from] FileLockError),
+    /// Failed to read or write the store.
+    #[error(transparent)]
+    Path(#[from] PathError),
+}
+
+/// Stores the root directory of each workspace in
+/// `.jj/repo/workspace_store/index.json`.
+///
+/// Workspaces created before the store existed are recorded the next time
+/// they're loaded.
+#[derive(Clone, Debug)]
+pub struct SimpleWorkspaceStore {
+    dir: PathBuf,
+}
+
+impl SimpleWorkspaceStore {
+    /// Returns the store of the repo at `repo_path`. The store directory is
+    /// created lazily.
+    pub fn load(repo_path: &Path) -> Self {
+        SimpleWorkspaceStore {
+            dir: repo_path.join("workspace_store"),
+        }
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("index.json")
+    }
+
+    fn lock(&self) -> Result<FileLock, WorkspaceStoreError> {
+        match fs::create_dir(&self.dir) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => Err(err).context(&self.dir)?,
+        }
+        Ok(FileLock::lock(self.dir.join("lock"))?)
+    }
+
+    fn read_index(&self) -> Result<BTreeMap<String, String>, WorkspaceStoreError> {
+        let path = self.index_path();
+        match fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|source| WorkspaceStoreError::Corrupt { path, source }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(err) => Err(err).context(&path)?,
+        }
+    }
+
+    fn write_index(&self, index: &BTreeMap<String, String>) -> Result<(), WorkspaceStoreError> {
+        let path = self.index_path();
+        let data = serde_json::to_vec_pretty(index).expect("string map should serialize");
+        let mut temp_file = NamedTempFile::new_in(&self.dir).context(&self.dir)?;
+        temp_file.write_all(&data).context(temp_file.path())?;
+        temp_file
+            .persist(&path)
+            .map_err(|err| err.error)
+            .context(&path)?;
+        Ok(())
+    }
+
+    fn update_index(
+        &self,
+        f: impl FnOnce(&mut BTreeMap<String, String>) -> Result<(), WorkspaceStoreError>,
+    ) -> Result<(), WorkspaceStoreError> {
+        let _lock = self.lock()?;
+        let mut index = self.read_index()?;
+        f(&mut index)?;
+        self.write_index(&index)
+    }
+
+    /// Records (or replaces) the root directory of the given workspace.
+    pub fn add(&self, workspace_id: &WorkspaceId, path: &Path) -> Result<(), WorkspaceStoreError> {
+        let path_str = path.to_str().ok_or(WorkspaceStoreError::NonUnicodePath)?;
+        self.update_index(|index| {
+            index.insert(workspace_id.as_str().to_owned(), path_str.to_owned());
+            Ok(())
+        })
+    }
+
+    /// Removes the given workspaces from the store. Unknown workspaces are
+    /// ignored.
+    pub fn forget(&self, workspace_ids: &[WorkspaceId]) -> Result<(), WorkspaceStoreError> {
+        self.update_index(|index| {
+            for workspace_id in workspace_ids {
+                index.remove(workspace_id.as_str());
+            }
+            Ok(())
+        })
+    }
+
+    /// Moves the recorded path of workspace `old_id` to `new_id`.
+    pub fn rename(
+        &self,
+        old_id: &WorkspaceId,
+        new_id: &WorkspaceId,
+    ) -> Result<(), WorkspaceStoreError> {
+        self.update_index(|index| {
+            if let Some(path) = index.remove(old_id.as_str()) {
+                index.insert(new_id.as_str().to_owned(), path);
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the recorded root directory of the given workspace.
+    pub fn get_workspace_path(
+        &self,
+        workspace_id: &WorkspaceId,
+    ) -> Result<Option<PathBuf>, WorkspaceStoreError> {
+        let index = self.read_index()?;
+        Ok(index.get(workspace_id.as_str()).map(PathBuf::from))
+    }
+
+    /// Returns all recorded workspaces and their root directories, sorted by
+    /// workspace name.
+    pub fn workspace_paths(&self) -> Result<Vec<(WorkspaceId, PathBuf)>, WorkspaceStoreError> {
+        let index = self.read_index()?;
+        Ok(index
+            .into_iter()
+            .map(|(name, path)| (WorkspaceId::new(name), PathBuf::from(path)))
+            .collect())
+    }
+}
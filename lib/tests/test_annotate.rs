@@ -33,6 +33,7 @@ use jj_lib::revset::RevsetExpression;
 use jj_lib::settings::UserSettings;
 use testutils::create_tree;
 use testutils::TestRepo;
+use testutils::TestRepoBackend;
 
 fn create_commit_fn<'a>(
     mut_repo: &'a mut MutableRepo,
@@ -70,7 +71,19 @@ fn annotate_within(
     domain: &Rc<ResolvedRevsetExpression>,
     file_path: &RepoPath,
 ) -> String {
-    let annotation = get_annotation_for_file(repo, commit, domain, file_path).unwrap();
+    let ignored = RevsetExpression::none();
+    let annotation = get_annotation_for_file(repo, commit, domain, &ignored, file_path).unwrap();
+    format_annotation(repo, &annotation)
+}
+
+fn annotate_ignoring(
+    repo: &dyn Repo,
+    commit: &Commit,
+    ignored: &Rc<ResolvedRevsetExpression>,
+    file_path: &RepoPath,
+) -> String {
+    let domain = RevsetExpression::all();
+    let annotation = get_annotation_for_file(repo, commit, &domain, ignored, file_path).unwrap();
     format_annotation(repo, &annotation)
 }
 
@@ -86,8 +99,10 @@ fn annotate_parent_tree(repo: &dyn Repo, commit: &Commit, file_path: &RepoPath)
         value => panic!("unexpected path value: {value:?}"),
     };
     let domain = RevsetExpression::all();
+    let ignored = RevsetExpression::none();
     let annotation =
-        get_annotation_with_file_content(repo, commit.id(), &domain, file_path, text).unwrap();
+        get_annotation_with_file_content(repo, commit.id(), &domain, &ignored, file_path, text)
+            .unwrap();
     format_annotation(repo, &annotation)
 }
 
@@ -103,6 +118,19 @@ fn format_annotation(repo: &dyn Repo, annotation: &FileAnnotation) -> String {
     output
 }
 
+fn format_annotation_origins(repo: &dyn Repo, annotation: &FileAnnotation) -> String {
+    let mut output = String::new();
+    for (origin, line) in annotation.line_origins() {
+        let origin = origin.unwrap();
+        let commit = repo.store().get_commit(&origin.commit_id).unwrap();
+        let desc = commit.description().trim_end();
+        let path = origin.path.as_internal_file_string();
+        let line_number = origin.line_number + 1;
+        write!(output, "{desc}: {path}:{line_number}: {line}").unwrap();
+    }
+    output
+}
+
 #[test]
 fn test_annotate_linear() {
     let settings = testutils::user_settings();
@@ -385,3 +413,109 @@ fn test_annotate_file_directory_transition() {
     commit2: 2
     "#);
 }
+
+#[test]
+fn test_annotate_ignored() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let file_path = RepoPath::from_internal_string("file");
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut create_commit = create_commit_fn(tx.repo_mut(), &settings);
+    let content1 = "a\nb\nc\n";
+    let content2 = "A\nb\nC\nd\n";
+    let content3 = "A\nb\nC\nd\ne\n";
+    let tree1 = create_tree(repo, &[(file_path, content1)]);
+    let tree2 = create_tree(repo, &[(file_path, content2)]);
+    let tree3 = create_tree(repo, &[(file_path, content3)]);
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    let commit3 = create_commit("commit3", &[commit2.id()], tree3.id());
+    drop(create_commit);
+
+    insta::assert_snapshot!(annotate(tx.repo(), &commit3, file_path), @r"
+    commit2: A
+    commit1: b
+    commit2: C
+    commit2: d
+    commit3: e
+    ");
+
+    // Changed lines are attributed to the parent of the ignored commit, but
+    // the added line can't be.
+    let ignored = RevsetExpression::commit(commit2.id().clone());
+    insta::assert_snapshot!(annotate_ignoring(tx.repo(), &commit3, &ignored, file_path), @r"
+    commit1: A
+    commit1: b
+    commit1: C
+    commit2: d
+    commit3: e
+    ");
+
+    // Ignoring the commit that added the lines is no-op.
+    let ignored = RevsetExpression::commit(commit1.id().clone());
+    insta::assert_snapshot!(annotate_ignoring(tx.repo(), &commit3, &ignored, file_path), @r"
+    commit2: A
+    commit1: b
+    commit2: C
+    commit2: d
+    commit3: e
+    ");
+}
+
+#[test]
+fn test_annotate_rename() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init_with_backend(TestRepoBackend::Git);
+    let repo = &test_repo.repo;
+
+    let root_commit_id = repo.store().root_commit_id();
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("new");
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut create_commit = create_commit_fn(tx.repo_mut(), &settings);
+    let content1 = "1\n2\n3\n4\n5\n6\n";
+    let content2 = "1\n2\n3\n4\n5\n6\n7\n";
+    let content3 = "0\n1\n2\n3\n4\n5\n6\n7\n";
+    let tree1 = create_tree(repo, &[(old_path, content1)]);
+    let tree2 = create_tree(repo, &[(new_path, content2)]);
+    let tree3 = create_tree(repo, &[(new_path, content3)]);
+    let commit1 = create_commit("commit1", &[root_commit_id], tree1.id());
+    let commit2 = create_commit("commit2", &[commit1.id()], tree2.id());
+    let commit3 = create_commit("commit3", &[commit2.id()], tree3.id());
+    drop(create_commit);
+
+    let domain = RevsetExpression::all();
+    let ignored = RevsetExpression::none();
+    let annotation =
+        get_annotation_for_file(tx.repo(), &commit3, &domain, &ignored, new_path).unwrap();
+    insta::assert_snapshot!(format_annotation_origins(tx.repo(), &annotation), @r"
+    commit3: new:1: 0
+    commit1: old:1: 1
+    commit1: old:2: 2
+    commit1: old:3: 3
+    commit1: old:4: 4
+    commit1: old:5: 5
+    commit1: old:6: 6
+    commit2: new:7: 7
+    ");
+
+    // Renames are followed even if the commits are hidden
+    tx.repo_mut().remove_head(commit3.id());
+    let annotation =
+        get_annotation_for_file(tx.repo(), &commit3, &domain, &ignored, new_path).unwrap();
+    insta::assert_snapshot!(format_annotation_origins(tx.repo(), &annotation), @r"
+    commit3: new:1: 0
+    commit1: old:1: 1
+    commit1: old:2: 2
+    commit1: old:3: 3
+    commit1: old:4: 4
+    commit1: old:5: 5
+    commit1: old:6: 6
+    commit2: new:7: 7
+    ");
+}
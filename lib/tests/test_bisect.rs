@@ -0,0 +1,181 @@
+// Copyright 2024 The Jujutsu Authors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use jj_lib::bisect::next_step;
+use jj_lib::bisect::BisectState;
+use jj_lib::bisect::BisectionResult;
+use jj_lib::commit::Commit;
+use jj_lib::repo::Repo;
+use testutils::CommitGraphBuilder;
+use testutils::TestRepo;
+
+fn next_commit_id(repo: &dyn Repo, state: &BisectState) -> Option<jj_lib::backend::CommitId> {
+    match next_step(repo, state).unwrap() {
+        BisectionResult::Next { commit_id, .. } => Some(commit_id),
+        BisectionResult::Done(_) => None,
+    }
+}
+
+fn ids(commits: &[&Commit]) -> Vec<jj_lib::backend::CommitId> {
+    commits.iter().map(|commit| commit.id().clone()).collect()
+}
+
+#[test]
+fn test_bisect_linear() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut graph_builder = CommitGraphBuilder::new(&settings, tx.repo_mut());
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
+    let commit4 = graph_builder.commit_with_parents(&[&commit3]);
+    let commit5 = graph_builder.commit_with_parents(&[&commit4]);
+    let repo = tx.repo();
+
+    // 2..5 are candidates. Testing 3 splits them into {2, 3} and {4, 5}.
+    let mut state = BisectState {
+        good: ids(&[&commit1]),
+        bad: ids(&[&commit5]),
+        ..Default::default()
+    };
+    assert_eq!(
+        next_step(repo, &state).unwrap(),
+        BisectionResult::Next {
+            commit_id: commit3.id().clone(),
+            num_candidates: 4,
+        }
+    );
+
+    state.mark_bad(commit3.id().clone());
+    assert_eq!(next_commit_id(repo, &state).as_ref(), Some(commit2.id()));
+
+    state.mark_good(commit2.id().clone());
+    assert_eq!(
+        next_step(repo, &state).unwrap(),
+        BisectionResult::Done(ids(&[&commit3]))
+    );
+
+    // Skipped commits can't be determined to be good or bad
+    let mut state = BisectState {
+        good: ids(&[&commit1]),
+        bad: ids(&[&commit3]),
+        ..Default::default()
+    };
+    assert_eq!(next_commit_id(repo, &state).as_ref(), Some(commit2.id()));
+    state.mark_skipped(commit2.id().clone());
+    assert_eq!(
+        next_step(repo, &state).unwrap(),
+        BisectionResult::Done(ids(&[&commit3, &commit2]))
+    );
+
+    // Bad commit is an ancestor of good commit
+    let state = BisectState {
+        good: ids(&[&commit4]),
+        bad: ids(&[&commit2]),
+        ..Default::default()
+    };
+    assert_eq!(
+        next_step(repo, &state).unwrap(),
+        BisectionResult::Done(vec![])
+    );
+}
+
+#[test]
+fn test_bisect_merges() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // 7
+    // |\
+    // 4 6
+    // | |
+    // 3 5
+    // |/
+    // 2
+    // |
+    // 1
+    let mut tx = repo.start_transaction(&settings);
+    let mut graph_builder = CommitGraphBuilder::new(&settings, tx.repo_mut());
+    let commit1 = graph_builder.initial_commit();
+    let commit2 = graph_builder.commit_with_parents(&[&commit1]);
+    let commit3 = graph_builder.commit_with_parents(&[&commit2]);
+    let commit4 = graph_builder.commit_with_parents(&[&commit3]);
+    let commit5 = graph_builder.commit_with_parents(&[&commit2]);
+    let commit6 = graph_builder.commit_with_parents(&[&commit5]);
+    let commit7 = graph_builder.commit_with_parents(&[&commit4, &commit6]);
+    let repo = tx.repo();
+
+    // Candidates are 2..7. Each side branch has 3 ancestors in the set
+    // including 2, which is the best split.
+    let mut state = BisectState {
+        good: ids(&[&commit1]),
+        bad: ids(&[&commit7]),
+        ..Default::default()
+    };
+    let next_id = next_commit_id(repo, &state).unwrap();
+    assert!(next_id == *commit4.id() || next_id == *commit6.id());
+
+    // If 4 is good, only 5, 6 (and 7) remain. 2 and 3 are good.
+    state.mark_good(commit4.id().clone());
+    assert_eq!(next_commit_id(repo, &state).as_ref(), Some(commit6.id()));
+    state.mark_bad(commit6.id().clone());
+    assert_eq!(next_commit_id(repo, &state).as_ref(), Some(commit5.id()));
+    state.mark_bad(commit5.id().clone());
+    assert_eq!(
+        next_step(repo, &state).unwrap(),
+        BisectionResult::Done(ids(&[&commit5]))
+    );
+
+    // Descendants of bad commits can't be the first bad commit
+    let mut state = BisectState {
+        good: ids(&[&commit1]),
+        bad: ids(&[&commit7, &commit3]),
+        ..Default::default()
+    };
+    assert_eq!(next_commit_id(repo, &state).as_ref(), Some(commit2.id()));
+    state.mark_good(commit2.id().clone());
+    assert_eq!(
+        next_step(repo, &state).unwrap(),
+        BisectionResult::Done(ids(&[&commit3]))
+    );
+}
+
+#[test]
+fn test_bisect_state_persistence() {
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+    let repo_path = test_repo.repo_path();
+
+    assert_eq!(BisectState::load(repo_path).unwrap(), None);
+    assert!(!BisectState::remove(repo_path).unwrap());
+
+    let root_id = repo.store().root_commit_id().clone();
+    let mut state = BisectState::default();
+    state.mark_good(root_id.clone());
+    state.current = Some(root_id.clone());
+    state.save(repo_path).unwrap();
+    assert_eq!(BisectState::load(repo_path).unwrap(), Some(state.clone()));
+
+    // Marking again moves the commit to the other set
+    state.mark_bad(root_id.clone());
+    assert!(state.good.is_empty());
+    assert_eq!(state.bad, vec![root_id]);
+
+    assert!(BisectState::remove(repo_path).unwrap());
+    assert_eq!(BisectState::load(repo_path).unwrap(), None);
+}
@@ -14,6 +14,8 @@
 
 use indoc::indoc;
 use jj_lib::backend::FileId;
+use jj_lib::conflicts::conflict_sidecar_base_path;
+use jj_lib::conflicts::conflict_sidecar_paths;
 use jj_lib::conflicts::extract_as_single_hunk;
 use jj_lib::conflicts::materialize_merge_result;
 use jj_lib::conflicts::parse_conflict;
@@ -1058,6 +1060,49 @@ fn test_update_conflict_from_content_simplified_conflict() {
     );
 }
 
+#[test]
+fn test_conflict_sidecar_paths() {
+    let path = RepoPath::from_internal_string("dir/file");
+    let sidecar_paths = conflict_sidecar_paths(path, 2);
+    assert_eq!(
+        sidecar_paths.map(|path| path.as_internal_file_string()),
+        Merge::from_removes_adds(
+            vec!["dir/file.jjconflict-base"],
+            vec!["dir/file.jjconflict-side-1", "dir/file.jjconflict-side-2"]
+        )
+    );
+    for sidecar_path in sidecar_paths.iter() {
+        assert_eq!(
+            conflict_sidecar_base_path(sidecar_path).as_deref(),
+            Some(path)
+        );
+    }
+
+    let sidecar_paths = conflict_sidecar_paths(path, 3);
+    assert_eq!(
+        sidecar_paths.map(|path| path.as_internal_file_string()),
+        Merge::from_removes_adds(
+            vec!["dir/file.jjconflict-base-1", "dir/file.jjconflict-base-2"],
+            vec![
+                "dir/file.jjconflict-side-1",
+                "dir/file.jjconflict-side-2",
+                "dir/file.jjconflict-side-3"
+            ]
+        )
+    );
+
+    let base_path = |path| conflict_sidecar_base_path(RepoPath::from_internal_string(path));
+    assert_eq!(base_path("file"), None);
+    assert_eq!(base_path("file.jjconflict-"), None);
+    assert_eq!(base_path("file.jjconflict-side-"), None);
+    assert_eq!(base_path("file.jjconflict-side-x"), None);
+    assert_eq!(base_path(".jjconflict-base"), None);
+    assert_eq!(
+        base_path("a.jjconflict-base.jjconflict-base"),
+        Some(RepoPath::from_internal_string("a.jjconflict-base").to_owned())
+    );
+}
+
 fn materialize_conflict_string(
     store: &Store,
     path: &RepoPath,
@@ -1582,6 +1582,84 @@ fn test_export_refs_current_bookmark_changed() {
     assert!(git_repo.head_detached().unwrap());
 }
 
+#[test]
+fn test_export_refs_worktree_bookmark_changed() {
+    // If we update a bookmark that is checked out in a linked worktree, the
+    // worktree's HEAD gets detached
+    let test_data = GitRepoData::create();
+    let git_settings = GitSettings::default();
+    let git_repo = test_data.git_repo;
+    let commit1 = empty_git_commit(&git_repo, "refs/heads/main", &[]);
+    let worktree_root = git_repo
+        .workdir()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .join("worktree");
+    std::fs::create_dir(&worktree_root).unwrap();
+    let git_backend = test_data
+        .repo
+        .store()
+        .backend_impl()
+        .downcast_ref::<GitBackend>()
+        .unwrap();
+    git::add_worktree(&git_backend.git_repo(), "worktree", &worktree_root).unwrap();
+    assert!(git::is_linked_worktree(
+        &git_backend.git_repo(),
+        &worktree_root
+    ));
+    assert!(!git::is_linked_worktree(
+        &git_backend.git_repo(),
+        git_repo.workdir().unwrap()
+    ));
+    let worktree_repo = git2::Repository::open(&worktree_root).unwrap();
+    assert!(worktree_repo.is_worktree());
+    worktree_repo.set_head("refs/heads/main").unwrap();
+
+    let mut tx = test_data.repo.start_transaction(&test_data.settings);
+    let mut_repo = tx.repo_mut();
+    git::import_refs(mut_repo, &git_settings).unwrap();
+    let new_commit = create_random_commit(mut_repo, &test_data.settings)
+        .set_parents(vec![jj_id(&commit1)])
+        .write()
+        .unwrap();
+    mut_repo.set_local_bookmark_target("main", RefTarget::normal(new_commit.id().clone()));
+    assert!(git::export_refs(mut_repo).unwrap().is_empty());
+    assert_eq!(
+        git_repo
+            .find_reference("refs/heads/main")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id(),
+        git_id(&new_commit)
+    );
+    // The worktree's HEAD still points to the old commit
+    assert!(worktree_repo.head_detached().unwrap());
+    assert_eq!(worktree_repo.head().unwrap().target(), Some(commit1.id()));
+
+    // Resetting the worktree's HEAD doesn't change the HEAD of the main worktree
+    let main_head = git_repo
+        .find_reference("HEAD")
+        .unwrap()
+        .symbolic_target()
+        .map(str::to_owned);
+    let wc_commit = create_random_commit(mut_repo, &test_data.settings)
+        .set_parents(vec![new_commit.id().clone()])
+        .write()
+        .unwrap();
+    git::reset_worktree_head(&worktree_repo, &wc_commit).unwrap();
+    assert_eq!(
+        worktree_repo.head().unwrap().target(),
+        Some(git_id(&new_commit))
+    );
+    assert_eq!(
+        git_repo.find_reference("HEAD").unwrap().symbolic_target(),
+        main_head.as_deref()
+    );
+    assert!(mut_repo.git_head().is_absent());
+}
+
 #[test_case(false; "without moved placeholder ref")]
 #[test_case(true; "with moved placeholder ref")]
 fn test_export_refs_unborn_git_bookmark(move_placeholder_ref: bool) {
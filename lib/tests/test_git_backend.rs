@@ -59,7 +59,7 @@ fn collect_no_gc_refs(git_repo_path: &Path) -> HashSet<CommitId> {
 }
 
 fn get_copy_records(
-    store: &Store,
+    store: &Arc<Store>,
     paths: Option<&[RepoPathBuf]>,
     a: &Commit,
     b: &Commit,
@@ -156,7 +156,7 @@ fn test_gc() {
     // Empty index, but all kept by file modification time
     // (Beware that this invokes "git gc" and refs will be packed.)
     repo.store()
-        .gc(base_index.as_index(), SystemTime::UNIX_EPOCH)
+        .gc(base_index.as_index(), SystemTime::UNIX_EPOCH, false)
         .unwrap();
     assert_eq!(
         collect_no_gc_refs(git_repo_path),
@@ -177,7 +177,7 @@ fn test_gc() {
     let now = || SystemTime::now() + Duration::from_secs(1);
 
     // All reachable: redundant no-gc refs will be removed
-    repo.store().gc(repo.index(), now()).unwrap();
+    repo.store().gc(repo.index(), now(), false).unwrap();
     assert_eq!(
         collect_no_gc_refs(git_repo_path),
         hashset! {
@@ -196,7 +196,18 @@ fn test_gc() {
     mut_index.add_commit(&commit_e);
     mut_index.add_commit(&commit_f);
     mut_index.add_commit(&commit_h);
-    repo.store().gc(mut_index.as_index(), now()).unwrap();
+    let dry_run_stats = repo.store().gc(mut_index.as_index(), now(), true).unwrap();
+    assert_eq!(
+        collect_no_gc_refs(git_repo_path),
+        hashset! {
+            commit_d.id().clone(),
+            commit_g.id().clone(),
+            commit_h.id().clone(),
+        },
+    );
+    let stats = repo.store().gc(mut_index.as_index(), now(), false).unwrap();
+    assert_eq!(stats, dry_run_stats);
+    assert_eq!(stats.pruned_refs, 1);
     assert_eq!(
         collect_no_gc_refs(git_repo_path),
         hashset! {
@@ -212,7 +223,7 @@ fn test_gc() {
     mut_index.add_commit(&commit_b);
     mut_index.add_commit(&commit_c);
     mut_index.add_commit(&commit_f);
-    repo.store().gc(mut_index.as_index(), now()).unwrap();
+    repo.store().gc(mut_index.as_index(), now(), false).unwrap();
     assert_eq!(
         collect_no_gc_refs(git_repo_path),
         hashset! {
@@ -224,7 +235,7 @@ fn test_gc() {
     // B|C|F are no longer reachable
     let mut mut_index = base_index.start_modification();
     mut_index.add_commit(&commit_a);
-    repo.store().gc(mut_index.as_index(), now()).unwrap();
+    repo.store().gc(mut_index.as_index(), now(), false).unwrap();
     assert_eq!(
         collect_no_gc_refs(git_repo_path),
         hashset! {
@@ -233,7 +244,9 @@ fn test_gc() {
     );
 
     // All unreachable
-    repo.store().gc(base_index.as_index(), now()).unwrap();
+    repo.store()
+        .gc(base_index.as_index(), now(), false)
+        .unwrap();
     assert_eq!(collect_no_gc_refs(git_repo_path), hashset! {});
 }
 
@@ -15,6 +15,8 @@
 use std::collections::HashSet;
 use std::fs;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use assert_matches::assert_matches;
 use jj_lib::backend::ChangeId;
@@ -25,6 +27,7 @@ use jj_lib::default_index::AsCompositeIndex as _;
 use jj_lib::default_index::CompositeIndex;
 use jj_lib::default_index::DefaultIndexStore;
 use jj_lib::default_index::DefaultIndexStoreError;
+use jj_lib::default_index::DefaultIndexStoreGcStats;
 use jj_lib::default_index::DefaultMutableIndex;
 use jj_lib::default_index::DefaultReadonlyIndex;
 use jj_lib::index::Index as _;
@@ -744,6 +747,73 @@ fn test_reindex_missing_commit() {
     assert_matches!(err, DefaultIndexStoreError::IndexCommits { op_id, .. } if op_id == *bad_op_id);
 }
 
+#[test]
+fn test_index_store_gc() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let test_env = &test_repo.env;
+    let repo_0 = &test_repo.repo;
+    let operations_dir = test_repo.repo_path().join("index").join("operations");
+
+    // Set up operation graph:
+    //
+    // B C
+    // |/
+    // A
+    // 0 (initial)
+    let mut tx = repo_0.start_transaction(&settings);
+    let commit_a = write_random_commit(tx.repo_mut(), &settings);
+    let repo_a = tx.commit("op A").unwrap();
+    let mut tx = repo_a.start_transaction(&settings);
+    let commit_b = write_random_commit(tx.repo_mut(), &settings);
+    let repo_b = tx.commit("op B").unwrap();
+    let mut tx = repo_a.start_transaction(&settings);
+    write_random_commit(tx.repo_mut(), &settings);
+    let repo_c = tx.commit("op C").unwrap();
+
+    let default_index_store: &DefaultIndexStore =
+        repo_b.index_store().as_any().downcast_ref().unwrap();
+    let num_links = operations_dir.read_dir().unwrap().count();
+    let num_segments = default_index_store.segment_file_count().unwrap();
+
+    // All kept by file modification time
+    let reachable_op_ids = HashSet::from([repo_b.op_id().clone()]);
+    let stats = default_index_store
+        .gc(&reachable_op_ids, SystemTime::UNIX_EPOCH, false)
+        .unwrap();
+    assert_eq!(stats, DefaultIndexStoreGcStats::default());
+
+    // Dry run doesn't remove files
+    let now = SystemTime::now() + Duration::from_secs(1);
+    let dry_run_stats = default_index_store
+        .gc(&reachable_op_ids, now, true)
+        .unwrap();
+    assert_eq!(operations_dir.read_dir().unwrap().count(), num_links);
+    assert_eq!(
+        default_index_store.segment_file_count().unwrap(),
+        num_segments
+    );
+
+    // Only the index of B is kept
+    let stats = default_index_store
+        .gc(&reachable_op_ids, now, false)
+        .unwrap();
+    assert_eq!(stats, dry_run_stats);
+    assert_eq!(stats.pruned_operation_links, num_links - 1);
+    assert!(stats.pruned_segments > 0);
+    assert!(operations_dir.join(repo_b.op_id().hex()).is_file());
+    assert!(!operations_dir.join(repo_c.op_id().hex()).exists());
+    assert_eq!(
+        default_index_store.segment_file_count().unwrap(),
+        num_segments - stats.pruned_segments
+    );
+
+    // The remaining index can be loaded
+    let repo = test_env.load_repo_at_head(&settings, test_repo.repo_path());
+    assert!(repo.index().has_id(commit_a.id()));
+    assert!(repo.index().has_id(commit_b.id()));
+}
+
 /// Test that .jj/repo/index/type is created when the repo is created.
 #[test]
 fn test_index_store_type() {
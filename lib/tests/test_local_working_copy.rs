@@ -29,7 +29,10 @@ use jj_lib::backend::TreeValue;
 use jj_lib::file_util::check_symlink_support;
 use jj_lib::file_util::try_symlink;
 use jj_lib::fsmonitor::FsmonitorSettings;
+use jj_lib::gitignore::GitIgnoreMatch;
 use jj_lib::local_working_copy::LocalWorkingCopy;
+use jj_lib::matchers::EverythingMatcher;
+use jj_lib::matchers::FilesMatcher;
 use jj_lib::merge::Merge;
 use jj_lib::merge::MergedTreeValue;
 use jj_lib::merged_tree::MergedTree;
@@ -47,6 +50,8 @@ use jj_lib::working_copy::CheckoutError;
 use jj_lib::working_copy::CheckoutStats;
 use jj_lib::working_copy::SnapshotError;
 use jj_lib::working_copy::SnapshotOptions;
+use jj_lib::working_copy::UntrackedPath;
+use jj_lib::working_copy::UntrackedReason;
 use jj_lib::workspace::default_working_copy_factories;
 use jj_lib::workspace::LockedWorkspace;
 use jj_lib::workspace::Workspace;
@@ -830,6 +835,131 @@ fn test_snapshot_racy_timestamps() {
     }
 }
 
+#[test]
+fn test_snapshot_detects_copies() {
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let write_file = |path: &str, contents: &str| {
+        let disk_path = workspace_root.join(path);
+        std::fs::create_dir_all(disk_path.parent().unwrap()).unwrap();
+        std::fs::write(disk_path, contents).unwrap();
+    };
+    write_file("moved", "moved\n");
+    write_file("dir/copied", "copied\n");
+    write_file("same1", "same\n");
+    write_file("same2", "same\n");
+    write_file("empty", "");
+    write_file("modified", "modified\n");
+    test_workspace.snapshot().unwrap();
+
+    let rename = |from: &str, to: &str| {
+        let to = workspace_root.join(to);
+        std::fs::create_dir_all(to.parent().unwrap()).unwrap();
+        std::fs::rename(workspace_root.join(from), to).unwrap();
+    };
+    rename("moved", "sub/moved");
+    // The copy sorts first, but the renamed file keeps its mtime
+    std::fs::copy(workspace_root.join("dir/copied"), workspace_root.join("a")).unwrap();
+    rename("dir/copied", "copied");
+    rename("same1", "same3");
+    rename("empty", "empty2");
+    std::fs::remove_file(workspace_root.join("modified")).unwrap();
+    write_file("modified2", "changed\n");
+
+    let mut locked_ws = test_workspace
+        .workspace
+        .start_working_copy_mutation()
+        .unwrap();
+    locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    let copies = locked_ws
+        .locked_wc()
+        .snapshot_copies()
+        .into_iter()
+        .map(|copy| {
+            (
+                copy.source.as_internal_file_string().to_owned(),
+                copy.target.as_internal_file_string().to_owned(),
+            )
+        })
+        .collect_vec();
+    assert_eq!(
+        copies,
+        [
+            ("dir/copied", "a"),
+            ("dir/copied", "copied"),
+            ("same1", "same3"),
+            ("moved", "sub/moved"),
+        ]
+        .map(|(source, target)| (source.to_owned(), target.to_owned()))
+    );
+
+    // Nothing is detected once the copies are snapshotted
+    locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    assert_eq!(locked_ws.locked_wc().snapshot_copies(), []);
+}
+
+#[test]
+fn test_snapshot_detects_plain_copies() {
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let write_file = |path: &str, contents: &str| {
+        let disk_path = workspace_root.join(path);
+        std::fs::create_dir_all(disk_path.parent().unwrap()).unwrap();
+        std::fs::write(disk_path, contents).unwrap();
+    };
+    let long_contents = "a line long enough to be worth recording copies of\n".repeat(2);
+    write_file("file", &long_contents);
+    write_file("dir/file", &long_contents);
+    write_file("other", &format!("{long_contents}other\n"));
+    write_file("small", "small\n");
+    test_workspace.snapshot().unwrap();
+
+    // Plain copies, without deleting anything
+    let copy = |from: &str, to: &str| {
+        let to = workspace_root.join(to);
+        std::fs::create_dir_all(to.parent().unwrap()).unwrap();
+        std::fs::copy(workspace_root.join(from), to).unwrap();
+    };
+    copy("other", "sub/copy");
+    copy("file", "sub/file");
+    copy("small", "small2");
+
+    let mut locked_ws = test_workspace
+        .workspace
+        .start_working_copy_mutation()
+        .unwrap();
+    locked_ws
+        .locked_wc()
+        .snapshot(&SnapshotOptions::empty_for_test())
+        .unwrap();
+    let copies = locked_ws
+        .locked_wc()
+        .snapshot_copies()
+        .into_iter()
+        .map(|copy| {
+            (
+                copy.source.as_internal_file_string().to_owned(),
+                copy.target.as_internal_file_string().to_owned(),
+            )
+        })
+        .collect_vec();
+    // Of the sources with the same content, the first one with the same file
+    // name is picked. Small files aren't matched.
+    assert_eq!(
+        copies,
+        [("other", "sub/copy"), ("dir/file", "sub/file")]
+            .map(|(source, target)| (source.to_owned(), target.to_owned()))
+    );
+}
+
 #[cfg(unix)]
 #[test]
 fn test_snapshot_special_file() {
@@ -1899,3 +2029,99 @@ fn test_snapshot_max_new_file_size() {
         "the failure should be attributed to new file size"
     );
 }
+
+#[test]
+fn test_untracked_paths() {
+    let settings = testutils::user_settings();
+    let mut test_workspace = TestWorkspace::init(&settings);
+    let workspace_root = test_workspace.workspace.workspace_root().to_owned();
+    let gitignore_path = RepoPath::from_internal_string(".gitignore");
+    let tracked_path = RepoPath::from_internal_string("tracked.txt");
+    let ignored_path = RepoPath::from_internal_string("file.bak");
+    let ignored_dir_path = RepoPath::from_internal_string("target");
+    let not_tracked_path = RepoPath::from_internal_string("file.rs");
+    let large_path = RepoPath::from_internal_string("large.txt");
+    let new_path = RepoPath::from_internal_string("new.txt");
+    testutils::write_working_copy_file(&workspace_root, gitignore_path, "*.bak\ntarget/\n");
+    testutils::write_working_copy_file(&workspace_root, tracked_path, "tracked");
+    test_workspace.snapshot().unwrap();
+    testutils::write_working_copy_file(&workspace_root, ignored_path, "");
+    std::fs::create_dir(ignored_dir_path.to_fs_path_unchecked(&workspace_root)).unwrap();
+    testutils::write_working_copy_file(&workspace_root, not_tracked_path, "");
+    testutils::write_working_copy_file(&workspace_root, large_path, "a lot of text");
+    testutils::write_working_copy_file(&workspace_root, new_path, "new");
+
+    let start_tracking_matcher = FilesMatcher::new([large_path, new_path]);
+    let options = SnapshotOptions {
+        start_tracking_matcher: &start_tracking_matcher,
+        max_new_file_size: 10,
+        ..SnapshotOptions::empty_for_test()
+    };
+    let wc: &LocalWorkingCopy = test_workspace
+        .workspace
+        .working_copy()
+        .as_any()
+        .downcast_ref()
+        .unwrap();
+    let untracked_paths = wc
+        .untracked_paths(&options, &EverythingMatcher)
+        .unwrap()
+        .into_iter()
+        .sorted_by(|a, b| a.path.cmp(&b.path))
+        .collect_vec();
+    let gitignore_source = Some(gitignore_path.to_fs_path_unchecked(&workspace_root));
+    assert_eq!(
+        untracked_paths,
+        vec![
+            UntrackedPath {
+                path: ignored_path.to_owned(),
+                is_dir: false,
+                reason: UntrackedReason::Ignored(GitIgnoreMatch {
+                    source: gitignore_source.clone(),
+                    line_num: Some(1),
+                    pattern: "*.bak".to_owned(),
+                    is_negated: false,
+                }),
+            },
+            UntrackedPath {
+                path: not_tracked_path.to_owned(),
+                is_dir: false,
+                reason: UntrackedReason::NotAutoTracked,
+            },
+            UntrackedPath {
+                path: large_path.to_owned(),
+                is_dir: false,
+                reason: UntrackedReason::FileTooLarge {
+                    size: 13,
+                    max_size: 10,
+                },
+            },
+            UntrackedPath {
+                path: ignored_dir_path.to_owned(),
+                is_dir: true,
+                reason: UntrackedReason::Ignored(GitIgnoreMatch {
+                    source: gitignore_source,
+                    line_num: Some(2),
+                    pattern: "target/".to_owned(),
+                    is_negated: false,
+                }),
+            },
+        ]
+    );
+
+    // Only paths matching the matcher are visited
+    let untracked_paths = wc
+        .untracked_paths(&options, &FilesMatcher::new([ignored_path]))
+        .unwrap();
+    assert_eq!(
+        untracked_paths
+            .iter()
+            .map(|path| path.path.as_ref())
+            .collect_vec(),
+        [ignored_path]
+    );
+
+    // The tree state isn't changed
+    let tree = test_workspace.snapshot().unwrap();
+    assert!(tree.path_value(new_path).unwrap().is_present());
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
 use std::path::Path;
 use std::slice;
 use std::sync::Arc;
@@ -20,17 +21,29 @@ use std::time::SystemTime;
 use assert_matches::assert_matches;
 use itertools::Itertools as _;
 use jj_lib::backend::CommitId;
+use jj_lib::backend::MillisSinceEpoch;
 use jj_lib::object_id::ObjectId;
+use jj_lib::op_bundle;
+use jj_lib::op_bundle::OpBundleError;
+use jj_lib::op_bundle::OperationBundle;
+use jj_lib::op_store::OpStoreGcStats;
 use jj_lib::op_walk;
+use jj_lib::op_walk::OpRetention;
 use jj_lib::op_walk::OpsetEvaluationError;
 use jj_lib::op_walk::OpsetResolutionError;
 use jj_lib::operation::Operation;
+use jj_lib::opset;
+use jj_lib::opset::OpsetDiagnostics;
 use jj_lib::repo::ReadonlyRepo;
 use jj_lib::repo::Repo;
+use jj_lib::repo_path::RepoPath;
 use jj_lib::settings::UserSettings;
 use testutils::create_random_commit;
+use testutils::create_tree;
+use testutils::read_file;
 use testutils::write_random_commit;
 use testutils::TestRepo;
+use testutils::TestRepoBackend;
 
 fn list_dir(dir: &Path) -> Vec<String> {
     std::fs::read_dir(dir)
@@ -103,6 +116,38 @@ fn test_consecutive_operations() {
     assert_eq!(list_dir(&op_heads_dir), vec![op_id2.hex()]);
 }
 
+#[test]
+fn test_operation_tags() {
+    let config = testutils::base_config()
+        .add_source(config::File::from_str(
+            r#"
+                operation.tags.build = "1234"
+                operation.tags.bot = "ci"
+            "#,
+            config::FileFormat::Toml,
+        ))
+        .build()
+        .unwrap();
+    let settings = UserSettings::from_config(config);
+    let test_repo = TestRepo::init();
+    let repo = &test_repo.repo;
+
+    // Tags from the settings are attached to the operation, and can be added
+    // or overridden by the transaction.
+    let mut tx = repo.start_transaction(&settings);
+    tx.set_tag("bot".to_owned(), "release".to_owned());
+    tx.set_tag("ticket".to_owned(), "JJ-42".to_owned());
+    let repo = tx.commit("transaction").unwrap();
+    let tags = &repo.operation().metadata().tags;
+    assert_eq!(
+        tags.iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .sorted()
+            .collect_vec(),
+        [("bot", "release"), ("build", "1234"), ("ticket", "JJ-42")]
+    );
+}
+
 #[test]
 fn test_concurrent_operations() {
     // Test that consecutive operations result in multiple op-heads on disk until
@@ -520,6 +565,67 @@ fn test_resolve_current_op() {
     );
 }
 
+#[test]
+fn test_resolve_op_at_time() {
+    let settings_at = |time: &str| {
+        UserSettings::from_config(
+            testutils::base_config()
+                .add_source(config::File::from_str(
+                    &format!("debug.operation-timestamp = '{time}'"),
+                    config::FileFormat::Toml,
+                ))
+                .build()
+                .unwrap(),
+        )
+    };
+    let settings = settings_at("2001-02-03T04:00:00+07:00");
+    let test_repo = TestRepo::init_with_settings(&settings);
+    let repo_0 = test_repo.repo;
+    let repo_a = repo_0
+        .start_transaction(&settings_at("2001-02-03T05:00:00+07:00"))
+        .commit("op A")
+        .unwrap();
+    let repo_b = repo_a
+        .start_transaction(&settings_at("2001-02-03T06:00:00+07:00"))
+        .commit("op B")
+        .unwrap();
+    let resolve = |op_str: &str| op_walk::resolve_op_with_repo(&repo_b, op_str);
+
+    // The latest operation that had finished by the time
+    assert_eq!(
+        resolve("at:2001-02-03T05:00:00+07:00").unwrap(),
+        *repo_a.operation()
+    );
+    assert_eq!(
+        resolve("at:2001-02-03T05:59:59+07:00").unwrap(),
+        *repo_a.operation()
+    );
+    assert_eq!(
+        resolve("at:'2001-02-03 06:30:00+07:00'").unwrap(),
+        *repo_b.operation()
+    );
+    assert_eq!(
+        resolve(r#"at:"2001-02-03T04:30:00+07:00""#).unwrap(),
+        *repo_0.operation()
+    );
+    assert_eq!(
+        resolve("at:2001-02-03T05:30:00+07:00-").unwrap(),
+        *repo_0.operation()
+    );
+    assert_eq!(resolve("ago:1 hour").unwrap(), *repo_b.operation());
+    // The root operation is older than anything
+    assert_eq!(
+        resolve("at:2000-01-01").unwrap(),
+        repo_0.loader().root_operation()
+    );
+    assert_matches!(
+        resolve("at:bogus"),
+        Err(OpsetEvaluationError::OpsetResolution(
+            OpsetResolutionError::InvalidTime { .. }
+        ))
+    );
+}
+
 #[test]
 fn test_resolve_op_parents_children() {
     // Use monotonic timestamp to stabilize merge order of transactions
@@ -647,19 +753,35 @@ fn test_gc() {
     assert_eq!(expected_view_entries.len(), 5);
 
     // No heads, but all kept by file modification time
-    op_store.gc(&[], SystemTime::UNIX_EPOCH).unwrap();
+    let stats = op_store.gc(&[], SystemTime::UNIX_EPOCH, false).unwrap();
+    assert_eq!(stats, OpStoreGcStats::default());
     assert_eq!(list_dir(&op_dir), expected_op_entries);
     assert_eq!(list_dir(&view_dir), expected_view_entries);
 
     // All reachable from heads
     let now = SystemTime::now();
     let head_ids = [repo_d.op_id().clone(), repo_f.op_id().clone()];
-    op_store.gc(&head_ids, now).unwrap();
+    op_store.gc(&head_ids, now, false).unwrap();
     assert_eq!(list_dir(&op_dir), expected_op_entries);
     assert_eq!(list_dir(&view_dir), expected_view_entries);
 
     // E|F are no longer reachable, but E's view is still reachable
-    op_store.gc(slice::from_ref(repo_d.op_id()), now).unwrap();
+    let dry_run_stats = op_store
+        .gc(slice::from_ref(repo_d.op_id()), now, true)
+        .unwrap();
+    assert_eq!(list_dir(&op_dir), expected_op_entries);
+    assert_eq!(list_dir(&view_dir), expected_view_entries);
+    let stats = op_store
+        .gc(slice::from_ref(repo_d.op_id()), now, false)
+        .unwrap();
+    assert_eq!(stats, dry_run_stats);
+    assert_eq!(
+        stats,
+        OpStoreGcStats {
+            pruned_operations: 2,
+            pruned_views: 1,
+        }
+    );
     expected_op_entries
         .retain(|name| *name != repo_e.op_id().hex() && *name != repo_f.op_id().hex());
     expected_view_entries.retain(|name| *name != repo_f.operation().view_id().hex());
@@ -667,7 +789,9 @@ fn test_gc() {
     assert_eq!(list_dir(&view_dir), expected_view_entries);
 
     // B|C|D are no longer reachable
-    op_store.gc(slice::from_ref(repo_a.op_id()), now).unwrap();
+    op_store
+        .gc(slice::from_ref(repo_a.op_id()), now, false)
+        .unwrap();
     expected_op_entries.retain(|name| {
         *name != repo_b.op_id().hex()
             && *name != repo_c.op_id().hex()
@@ -685,3 +809,450 @@ fn test_gc() {
     assert_eq!(expected_op_entries.len(), 1);
     assert_eq!(expected_view_entries.len(), 1);
 }
+
+#[test]
+fn test_find_expired_op() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+
+    // Set up linear operation graph:
+    // D
+    // C
+    // B
+    // A
+    // 0 (initial)
+    let random_tx = |repo: &Arc<ReadonlyRepo>| {
+        let mut tx = repo.start_transaction(&settings);
+        write_random_commit(tx.repo_mut(), &settings);
+        tx
+    };
+    let repo_a = random_tx(&repo_0).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a).commit("op B").unwrap();
+    let repo_c = random_tx(&repo_b).commit("op C").unwrap();
+    let repo_d = random_tx(&repo_c).commit("op D").unwrap();
+
+    let find = |keep_count, keep_newer: Option<i64>| {
+        let retention = OpRetention {
+            keep_count,
+            keep_newer: keep_newer.map(MillisSinceEpoch),
+        };
+        op_walk::find_expired_op(slice::from_ref(repo_d.operation()), &retention)
+            .unwrap()
+            .map(|op| op.id().clone())
+    };
+
+    // No criteria, nothing expires
+    assert_eq!(find(None, None), None);
+
+    // Keep the latest 2 operations
+    assert_eq!(find(Some(2), None), Some(repo_b.op_id().clone()));
+    // The head operation is always kept
+    assert_eq!(find(Some(0), None), Some(repo_c.op_id().clone()));
+    // The root operation isn't expired
+    assert_eq!(find(Some(4), None), None);
+    assert_eq!(find(Some(10), None), None);
+
+    // Keep operations by time
+    assert_eq!(find(None, Some(0)), None);
+    assert_eq!(find(None, Some(i64::MAX)), Some(repo_c.op_id().clone()));
+
+    // Operations satisfying either criterion are kept
+    assert_eq!(find(Some(2), Some(0)), None);
+    assert_eq!(find(Some(3), Some(i64::MAX)), Some(repo_a.op_id().clone()));
+}
+
+#[test]
+fn test_abandon_ops() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+    let loader = repo_0.loader();
+    let op_store = repo_0.op_store();
+
+    let read_op = |id| loader.load_operation(id).unwrap();
+
+    fn op_parents<const N: usize>(op: &Operation) -> [Operation; N] {
+        let parents: Vec<_> = op.parents().try_collect().unwrap();
+        parents.try_into().unwrap()
+    }
+
+    // Set up bookmarky operation graph:
+    // G
+    // |\
+    // | F
+    // E |
+    // D |
+    // |/
+    // C
+    // B
+    // A
+    // 0 (initial)
+    let random_tx = |repo: &Arc<ReadonlyRepo>| {
+        let mut tx = repo.start_transaction(&settings);
+        write_random_commit(tx.repo_mut(), &settings);
+        tx
+    };
+    let repo_a = random_tx(&repo_0).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a).commit("op B").unwrap();
+    let repo_c = random_tx(&repo_b).commit("op C").unwrap();
+    let repo_d = random_tx(&repo_c).commit("op D").unwrap();
+    let tx_e = random_tx(&repo_d);
+    let tx_f = random_tx(&repo_c);
+    let repo_g = testutils::commit_transactions(&settings, vec![tx_e, tx_f]);
+    let [op_e, op_f] = op_parents(repo_g.operation());
+
+    // Abandon nothing
+    let stats = op_walk::abandon_ops(
+        op_store.as_ref(),
+        &HashSet::new(),
+        slice::from_ref(repo_g.operation()),
+    )
+    .unwrap();
+    assert_eq!(stats.new_head_ids, vec![repo_g.op_id().clone()]);
+    assert_eq!(stats.rewritten_count, 0);
+    assert_eq!(stats.unreachable_count, 0);
+
+    // Abandon B|D|F:
+    // G'
+    // |\
+    // E'|
+    // |/
+    // C'
+    // A
+    // 0 (initial)
+    let abandon_ids = [repo_b.op_id(), repo_d.op_id(), op_f.id()]
+        .into_iter()
+        .cloned()
+        .collect();
+    let stats = op_walk::abandon_ops(
+        op_store.as_ref(),
+        &abandon_ids,
+        slice::from_ref(repo_g.operation()),
+    )
+    .unwrap();
+    assert_eq!(stats.new_head_ids.len(), 1);
+    assert_eq!(stats.rewritten_count, 3);
+    assert_eq!(stats.unreachable_count, 3);
+    let new_op_g = read_op(&stats.new_head_ids[0]);
+    assert_eq!(new_op_g.metadata(), repo_g.operation().metadata());
+    assert_eq!(new_op_g.view_id(), repo_g.operation().view_id());
+    let [new_op_e, new_op_c] = op_parents(&new_op_g);
+    assert_eq!(new_op_e.metadata(), op_e.metadata());
+    assert_eq!(new_op_e.parent_ids(), slice::from_ref(new_op_c.id()));
+    assert_eq!(new_op_c.metadata(), repo_c.operation().metadata());
+    assert_eq!(new_op_c.parent_ids(), slice::from_ref(repo_a.op_id()));
+
+    // Abandon C, which is a fork point (ignoring G):
+    // F'
+    // B
+    // A
+    // 0 (initial)
+    let abandon_ids = [repo_c.op_id().clone()].into_iter().collect();
+    let stats =
+        op_walk::abandon_ops(op_store.as_ref(), &abandon_ids, slice::from_ref(&op_f)).unwrap();
+    assert_eq!(stats.rewritten_count, 1);
+    assert_eq!(stats.unreachable_count, 1);
+    let new_op_f = read_op(&stats.new_head_ids[0]);
+    assert_eq!(new_op_f.parent_ids(), slice::from_ref(repo_b.op_id()));
+}
+
+#[test]
+fn test_squash_snapshot_ops() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+    let loader = repo_0.loader();
+    let op_store = repo_0.op_store();
+
+    // Set up linear operation graph:
+    // F
+    // E (snapshot)
+    // D (snapshot)
+    // C (snapshot)
+    // B
+    // A (snapshot)
+    // 0 (initial)
+    let random_tx = |repo: &Arc<ReadonlyRepo>, is_snapshot| {
+        let mut tx = repo.start_transaction(&settings);
+        write_random_commit(tx.repo_mut(), &settings);
+        tx.set_is_snapshot(is_snapshot);
+        tx
+    };
+    let repo_a = random_tx(&repo_0, true).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a, false).commit("op B").unwrap();
+    let repo_c = random_tx(&repo_b, true).commit("op C").unwrap();
+    let repo_d = random_tx(&repo_c, true).commit("op D").unwrap();
+    let repo_e = random_tx(&repo_d, true).commit("op E").unwrap();
+    let repo_f = random_tx(&repo_e, false).commit("op F").unwrap();
+    let head_ops = slice::from_ref(repo_f.operation());
+
+    // Nothing is old enough
+    let squash_ids = op_walk::find_squashable_snapshot_ops(head_ops, MillisSinceEpoch(0)).unwrap();
+    assert!(squash_ids.is_empty());
+
+    // C and D are squashed into E. A is kept because its child isn't a snapshot.
+    let squash_ids =
+        op_walk::find_squashable_snapshot_ops(head_ops, MillisSinceEpoch(i64::MAX)).unwrap();
+    assert_eq!(
+        squash_ids,
+        HashSet::from([repo_c.op_id().clone(), repo_d.op_id().clone()])
+    );
+
+    // F'
+    // E' (snapshot, started at C)
+    // B
+    // A (snapshot)
+    // 0 (initial)
+    let stats = op_walk::squash_ops(op_store.as_ref(), &squash_ids, head_ops).unwrap();
+    assert_eq!(stats.rewritten_count, 2);
+    assert_eq!(stats.unreachable_count, 2);
+    let new_op_f = loader.load_operation(&stats.new_head_ids[0]).unwrap();
+    assert_eq!(new_op_f.metadata(), repo_f.operation().metadata());
+    assert_eq!(new_op_f.view_id(), repo_f.operation().view_id());
+    let new_op_e = new_op_f.parents().exactly_one().ok().unwrap().unwrap();
+    assert_eq!(new_op_e.view_id(), repo_e.operation().view_id());
+    assert_eq!(new_op_e.parent_ids(), slice::from_ref(repo_b.op_id()));
+    assert_eq!(
+        new_op_e.metadata().start_time,
+        repo_c.operation().metadata().start_time
+    );
+    assert_eq!(
+        new_op_e.metadata().end_time,
+        repo_e.operation().metadata().end_time
+    );
+}
+
+#[test]
+fn test_evaluate_opset() {
+    let settings = testutils::user_settings();
+    let test_repo = TestRepo::init();
+    let repo_0 = test_repo.repo;
+    let op_store = repo_0.op_store();
+
+    // Set up operation graph:
+    // D (snapshot)
+    // | C (tagged)
+    // |/
+    // B
+    // A
+    // 0 (initial)
+    let random_tx = |repo: &Arc<ReadonlyRepo>| {
+        let mut tx = repo.start_transaction(&settings);
+        write_random_commit(tx.repo_mut(), &settings);
+        tx
+    };
+    let repo_a = random_tx(&repo_0).commit("op A").unwrap();
+    let repo_b = random_tx(&repo_a).commit("op B").unwrap();
+    // C is dated in the past so that the concurrent operations C and D are
+    // ordered deterministically.
+    let settings_c = UserSettings::from_config(
+        testutils::base_config()
+            .add_source(config::File::from_str(
+                "debug.operation-timestamp = '2001-02-03T04:05:06+07:00'",
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap(),
+    );
+    let mut tx_c = repo_b.start_transaction(&settings_c);
+    write_random_commit(tx_c.repo_mut(), &settings_c);
+    tx_c.set_tag("ci".to_owned(), "nightly".to_owned());
+    let repo_c = tx_c.commit("op C").unwrap();
+    let mut tx_d = random_tx(&repo_b);
+    tx_d.set_is_snapshot(true);
+    let repo_d = tx_d.commit("snapshot working copy").unwrap();
+    let head_ops = [repo_c.operation().clone(), repo_d.operation().clone()];
+
+    let evaluate = |text: &str, head_ops: &[Operation]| {
+        let now = chrono::DateTime::parse_from_rfc3339("2001-02-03T04:05:06+07:00").unwrap();
+        let expression = opset::parse(&mut OpsetDiagnostics::new(), text, &now.into()).unwrap();
+        expression
+            .evaluate(op_store, head_ops)
+            .map(|ops| ops.iter().map(|op| op.id().clone()).collect_vec())
+    };
+    let ids =
+        |repos: &[&Arc<ReadonlyRepo>]| repos.iter().map(|repo| repo.op_id().clone()).collect_vec();
+
+    // Symbols
+    let c = slice::from_ref(repo_c.operation());
+    let d = slice::from_ref(repo_d.operation());
+    assert_eq!(evaluate("@", d).unwrap(), ids(&[&repo_d]));
+    assert_matches!(
+        evaluate("@", &head_ops),
+        Err(OpsetEvaluationError::OpsetResolution(
+            OpsetResolutionError::MultipleOperations { .. }
+        ))
+    );
+    assert_eq!(evaluate(&repo_c.op_id().hex(), d).unwrap(), ids(&[&repo_c]));
+    assert_eq!(evaluate("root()", d).unwrap(), ids(&[&repo_0]));
+
+    // Ancestry operators
+    assert_eq!(evaluate("@-", d).unwrap(), ids(&[&repo_b]));
+    assert_eq!(evaluate("@--+", d).unwrap(), ids(&[&repo_b]));
+    assert_eq!(evaluate("@-+", d).unwrap(), ids(&[&repo_d]));
+    assert_eq!(
+        evaluate(&format!("{}+", repo_b.op_id().hex()), &head_ops).unwrap(),
+        ids(&[&repo_d, &repo_c])
+    );
+    assert_eq!(
+        evaluate("..@", d).unwrap(),
+        ids(&[&repo_d, &repo_b, &repo_a])
+    );
+    assert_eq!(
+        evaluate("::@", d).unwrap(),
+        ids(&[&repo_d, &repo_b, &repo_a, &repo_0])
+    );
+    assert_eq!(
+        evaluate(&format!("{}::", repo_b.op_id().hex()), &head_ops).unwrap(),
+        ids(&[&repo_d, &repo_c, &repo_b])
+    );
+    assert_eq!(
+        evaluate(&format!("{}..", repo_b.op_id().hex()), &head_ops).unwrap(),
+        ids(&[&repo_d, &repo_c])
+    );
+    assert_eq!(
+        evaluate(&format!("{}::@", repo_a.op_id().hex()), d).unwrap(),
+        ids(&[&repo_d, &repo_b, &repo_a])
+    );
+    assert_eq!(evaluate("@--..@", d).unwrap(), ids(&[&repo_d, &repo_b]));
+    assert_eq!(evaluate("@-- | @", d).unwrap(), ids(&[&repo_d, &repo_a]));
+    assert_eq!(
+        evaluate("root() | @- | @-", d).unwrap(),
+        ids(&[&repo_b, &repo_0])
+    );
+    assert_eq!(evaluate("(@ | @-) & @-", d).unwrap(), ids(&[&repo_b]));
+    assert_eq!(evaluate("(@ | @-) ~ @-", d).unwrap(), ids(&[&repo_d]));
+    assert_eq!(
+        evaluate("..", &head_ops).unwrap(),
+        ids(&[&repo_d, &repo_c, &repo_b, &repo_a])
+    );
+
+    // Predicates
+    assert_eq!(
+        evaluate("description(exact:'op B')", &head_ops).unwrap(),
+        ids(&[&repo_b])
+    );
+    assert_eq!(
+        evaluate("description(glob:'op *') ~ ::@-", c).unwrap(),
+        ids(&[&repo_c])
+    );
+    assert_eq!(evaluate("snapshot()", &head_ops).unwrap(), ids(&[&repo_d]));
+    assert_eq!(evaluate("tags()", &head_ops).unwrap(), ids(&[&repo_c]));
+    assert_eq!(
+        evaluate("tags(exact:ci, night)", &head_ops).unwrap(),
+        ids(&[&repo_c])
+    );
+    assert_eq!(evaluate("tags(ci, weekly)", &head_ops).unwrap(), ids(&[]));
+    assert_eq!(
+        evaluate("user(test-username) & hostname(host.example.com) & @-", d).unwrap(),
+        ids(&[&repo_b])
+    );
+    assert_eq!(
+        evaluate("time(after:'2000-01-01') & ~root()", d).unwrap(),
+        ids(&[&repo_d, &repo_b, &repo_a])
+    );
+    assert_eq!(
+        evaluate("time(before:'2000-01-01')", d).unwrap(),
+        ids(&[&repo_0])
+    );
+}
+
+#[test]
+fn test_export_import_operations() {
+    let settings = testutils::user_settings();
+    let source_repo = TestRepo::init_with_backend(TestRepoBackend::Local);
+    let repo = &source_repo.repo;
+    let file_path = RepoPath::from_internal_string("dir/file");
+
+    let mut tx = repo.start_transaction(&settings);
+    let tree = create_tree(repo, &[(file_path, "contents")]);
+    let commit = tx
+        .repo_mut()
+        .new_commit(
+            &settings,
+            vec![repo.store().root_commit_id().clone()],
+            tree.id(),
+        )
+        .write()
+        .unwrap();
+    let repo = tx.commit("add commit").unwrap();
+
+    let ops: Vec<_> = op_walk::walk_ancestors(slice::from_ref(repo.operation()))
+        .try_collect()
+        .unwrap();
+    let bundle = op_bundle::export_operations(repo.loader(), &ops).unwrap();
+    let bundle = OperationBundle::from_bytes(&bundle.to_bytes()).unwrap();
+    // The root operation is never exported
+    assert_eq!(bundle.operation_ids().count(), ops.len() - 1);
+    assert_eq!(bundle.num_commits(), 1);
+
+    let dest_repo = TestRepo::init_with_backend(TestRepoBackend::Local);
+    let loader = dest_repo.repo.loader();
+    let stats = op_bundle::import_operations(loader, &bundle).unwrap();
+    assert_eq!(stats.new_operation_ids.len(), ops.len() - 1);
+    assert_eq!(stats.num_new_commits, 1);
+
+    let dest_repo_at_head = loader.load_at_head(&settings).unwrap();
+    assert!(dest_repo_at_head.view().heads().contains(commit.id()));
+    let imported_commit = dest_repo_at_head.store().get_commit(commit.id()).unwrap();
+    assert_eq!(imported_commit, commit);
+    let imported_tree = imported_commit.tree().unwrap();
+    let file_id = imported_tree
+        .path_value(file_path)
+        .unwrap()
+        .into_resolved()
+        .unwrap();
+    let Some(jj_lib::backend::TreeValue::File { id, .. }) = file_id else {
+        panic!("unexpected tree value: {file_id:?}");
+    };
+    assert_eq!(
+        read_file(dest_repo_at_head.store(), file_path, &id),
+        b"contents"
+    );
+
+    // Importing the same bundle again is a no-op
+    let stats = op_bundle::import_operations(loader, &bundle).unwrap();
+    assert_eq!(stats, Default::default());
+
+    // Only the commits added since the parent operation are exported
+    let other_path = RepoPath::from_internal_string("dir/other");
+    let mut tx = repo.start_transaction(&settings);
+    let child_tree = create_tree(&repo, &[(file_path, "contents"), (other_path, "other")]);
+    let child_commit = tx
+        .repo_mut()
+        .new_commit(&settings, vec![commit.id().clone()], child_tree.id())
+        .write()
+        .unwrap();
+    let child_repo = tx.commit("add child commit").unwrap();
+    let child_bundle =
+        op_bundle::export_operations(child_repo.loader(), slice::from_ref(child_repo.operation()))
+            .unwrap();
+    assert_eq!(child_bundle.operation_ids().count(), 1);
+    assert_eq!(child_bundle.num_commits(), 1);
+    let stats = op_bundle::import_operations(loader, &child_bundle).unwrap();
+    assert_eq!(stats.new_operation_ids, [child_repo.op_id().clone()]);
+    assert_eq!(stats.num_new_commits, 1);
+    let dest_repo_at_head = loader.load_at_head(&settings).unwrap();
+    assert!(dest_repo_at_head.view().heads().contains(child_commit.id()));
+    let imported_tree = dest_repo_at_head
+        .store()
+        .get_commit(child_commit.id())
+        .unwrap()
+        .tree()
+        .unwrap();
+    assert_eq!(imported_tree.id(), child_tree.id());
+
+    // Nothing is written if the bundle was exported from another backend
+    let other_repo = TestRepo::init_with_backend(TestRepoBackend::Test);
+    let other_loader = other_repo.repo.loader();
+    assert_matches!(
+        op_bundle::import_operations(other_loader, &bundle),
+        Err(OpBundleError::IncompatibleStore { .. })
+    );
+    assert_eq!(
+        other_loader.op_heads_store().get_op_heads().unwrap(),
+        [other_repo.repo.op_id().clone()]
+    );
+    assert!(other_loader.store().get_commit(commit.id()).is_err());
+}
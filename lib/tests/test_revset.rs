@@ -3747,6 +3747,201 @@ fn test_evaluate_expression_diff_contains() {
     );
 }
 
+#[test]
+fn test_evaluate_expression_diff_stats() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init(&settings);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.repo_mut();
+
+    let file_path1 = RepoPath::from_internal_string("file1");
+    let file_path2 = RepoPath::from_internal_string("file2");
+    let binary_path = RepoPath::from_internal_string("binary");
+    let tree1 = create_tree(repo, &[(file_path1, "1\n2\n3\n"), (file_path2, "a\n")]);
+    let tree2 = create_tree(repo, &[(file_path1, "1\n2\n3\n4\n"), (file_path2, "a\n")]);
+    let tree3 = create_tree(
+        repo,
+        &[
+            (file_path1, "x\n"),
+            (file_path2, "a\n"),
+            (binary_path, "\0binary"),
+        ],
+    );
+    let tree4 = create_tree(repo, &[(file_path1, "x\n"), (file_path2, "a\n")]);
+    let tree5 = create_tree(
+        repo,
+        &[
+            (file_path1, "x\n"),
+            (file_path2, "a\n"),
+            (binary_path, "\0modified\n"),
+        ],
+    );
+
+    let mut create_commit = |parent_ids, tree_id| {
+        mut_repo
+            .new_commit(&settings, parent_ids, tree_id)
+            .write()
+            .unwrap()
+    };
+    let commit1 = create_commit(vec![repo.store().root_commit_id().clone()], tree1.id());
+    let commit2 = create_commit(vec![commit1.id().clone()], tree2.id());
+    let commit3 = create_commit(vec![commit2.id().clone()], tree3.id());
+    let commit4 = create_commit(vec![commit3.id().clone()], tree4.id());
+    let commit5 = create_commit(vec![commit3.id().clone()], tree5.id());
+
+    let query = |revset_str: &str| {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            revset_str,
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    // Commits 1-5 add or remove 4, 1, 5, 0, and 0 lines respectively.
+    // Binary files aren't counted.
+    assert_eq!(query("diff_lines('>4')"), vec![commit3.id().clone()]);
+    assert_eq!(
+        query("diff_lines('>=4')"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(query("diff_lines(1)"), vec![commit2.id().clone()]);
+    assert_eq!(
+        query("diff_lines('<=1') & ~empty()"),
+        vec![
+            commit5.id().clone(),
+            commit4.id().clone(),
+            commit2.id().clone(),
+        ]
+    );
+    assert_eq!(
+        query("diff_lines(0) & ~empty()"),
+        vec![commit5.id().clone(), commit4.id().clone()]
+    );
+    assert_eq!(
+        query("diff_lines('>1', file1)"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+
+    assert_eq!(
+        query("files_changed('>1')"),
+        vec![commit3.id().clone(), commit1.id().clone()]
+    );
+    assert_eq!(
+        query("files_changed(1)"),
+        vec![
+            commit5.id().clone(),
+            commit4.id().clone(),
+            commit2.id().clone(),
+        ]
+    );
+    assert_eq!(query("files_changed('<1')"), query("empty()"));
+    assert_eq!(
+        query("files_changed(1, file1)"),
+        vec![
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+
+    // Removal or modification of binary file doesn't count
+    assert_eq!(query("adds_binary()"), vec![commit3.id().clone()]);
+    assert_eq!(query("adds_binary(file1 | file2)"), vec![]);
+}
+
+#[test]
+fn test_evaluate_expression_files_follow() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init_with_backend(&settings, TestRepoBackend::Git);
+    let repo = &test_workspace.repo;
+
+    let mut tx = repo.start_transaction(&settings);
+    let mut_repo = tx.repo_mut();
+
+    let old_path = RepoPath::from_internal_string("old");
+    let new_path = RepoPath::from_internal_string("new");
+    let other_path = RepoPath::from_internal_string("other");
+    let content1 = "1\n2\n3\n4\n5\n6\n7\n8\n";
+    let content2 = "1\n2\n3\n4\n5\n6\n7\n8\n9\n";
+    let tree1 = create_tree(repo, &[(old_path, content1)]);
+    let tree2 = create_tree(repo, &[(old_path, content2)]);
+    let tree3 = create_tree(repo, &[(new_path, content2)]);
+    let tree4 = create_tree(repo, &[(new_path, content2), (other_path, "other\n")]);
+    let tree5 = create_tree(repo, &[(new_path, content1), (other_path, "other\n")]);
+    let tree6 = create_tree(repo, &[(old_path, "sibling\n")]);
+
+    let mut create_commit = |parent_ids, tree_id| {
+        mut_repo
+            .new_commit(&settings, parent_ids, tree_id)
+            .write()
+            .unwrap()
+    };
+    let commit1 = create_commit(vec![repo.store().root_commit_id().clone()], tree1.id());
+    let commit2 = create_commit(vec![commit1.id().clone()], tree2.id());
+    // Sibling branch that modifies the old path, and which is visited after
+    // the renaming commit
+    let commit6 = create_commit(vec![commit1.id().clone()], tree6.id());
+    let commit3 = create_commit(vec![commit2.id().clone()], tree3.id());
+    let commit4 = create_commit(vec![commit3.id().clone()], tree4.id());
+    let commit5 = create_commit(vec![commit4.id().clone()], tree5.id());
+
+    let query = |revset_str: &str| {
+        resolve_commit_ids_in_workspace(
+            mut_repo,
+            revset_str,
+            &test_workspace.workspace,
+            Some(test_workspace.workspace.workspace_root()),
+        )
+    };
+
+    assert_eq!(
+        query("files(new)"),
+        vec![commit5.id().clone(), commit3.id().clone()]
+    );
+    assert_eq!(
+        query("files(follow:new)"),
+        vec![
+            commit5.id().clone(),
+            commit3.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    // The rename is followed even if it's outside of the candidates
+    assert_eq!(
+        query(&format!("files(follow:new) & ~{}", commit3.id().hex())),
+        vec![
+            commit5.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    assert_eq!(
+        query(&format!("files(follow:new) & ::{}", commit2.id().hex())),
+        vec![commit2.id().clone(), commit1.id().clone()]
+    );
+    // Nothing to follow
+    assert_eq!(query("files(follow:other)"), vec![commit4.id().clone()]);
+    assert_eq!(
+        query("files(follow:'old')"),
+        vec![
+            commit3.id().clone(),
+            commit6.id().clone(),
+            commit2.id().clone(),
+            commit1.id().clone(),
+        ]
+    );
+    // The sibling branch isn't an ancestor of the rename, so the old path
+    // isn't followed there
+    assert_eq!(
+        query(&format!("files(follow:new) & {}", commit6.id().hex())),
+        vec![]
+    );
+}
+
 #[test]
 fn test_evaluate_expression_file_merged_parents() {
     let settings = testutils::user_settings();
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::path::Path;
+use std::slice;
 use std::thread;
 
 use assert_matches::assert_matches;
@@ -21,7 +23,10 @@ use jj_lib::workspace::default_working_copy_factories;
 use jj_lib::workspace::default_working_copy_factory;
 use jj_lib::workspace::Workspace;
 use jj_lib::workspace::WorkspaceLoadError;
+use jj_lib::workspace::WorkspaceMoveError;
+use jj_lib::workspace_store::SimpleWorkspaceStore;
 use testutils::TestEnvironment;
+use testutils::TestRepoBackend;
 use testutils::TestWorkspace;
 
 #[test]
@@ -90,6 +95,181 @@ fn test_init_additional_workspace() {
     assert_eq!(same_workspace.workspace_root(), ws2.workspace_root());
 }
 
+#[test]
+fn test_move_workspace() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init_with_backend(&settings, TestRepoBackend::Local);
+    let workspace = &test_workspace.workspace;
+    let env_root = test_workspace.env.root().canonicalize().unwrap();
+
+    let ws2_id = WorkspaceId::new("ws2".to_string());
+    let ws2_root = env_root.join("ws2_root");
+    std::fs::create_dir(&ws2_root).unwrap();
+    Workspace::init_workspace_with_existing_repo(
+        &settings,
+        &ws2_root,
+        workspace.repo_path(),
+        &test_workspace.repo,
+        &*default_working_copy_factory(),
+        ws2_id.clone(),
+    )
+    .unwrap();
+    let store = SimpleWorkspaceStore::load(workspace.repo_path());
+    assert_eq!(
+        store.workspace_paths().unwrap(),
+        vec![
+            (WorkspaceId::default(), env_root.join("repo")),
+            (ws2_id.clone(), ws2_root.clone()),
+        ]
+    );
+
+    // The destination must not exist
+    assert_matches!(
+        workspace.move_to(test_workspace.repo.as_ref(), &ws2_root),
+        Err(WorkspaceMoveError::DestinationExists(_))
+    );
+
+    // Moving the workspace containing the repo updates the other workspace
+    let moved_root = workspace
+        .move_to(test_workspace.repo.as_ref(), &env_root.join("moved"))
+        .unwrap();
+    assert_eq!(moved_root, env_root.join("moved"));
+    let store = SimpleWorkspaceStore::load(&moved_root.join(".jj").join("repo"));
+    assert_eq!(
+        store.get_workspace_path(&WorkspaceId::default()).unwrap(),
+        Some(moved_root.clone())
+    );
+    let ws2 = Workspace::load(
+        &settings,
+        &ws2_root,
+        &test_workspace.env.default_store_factories(),
+        &default_working_copy_factories(),
+    )
+    .unwrap();
+    assert_eq!(ws2.repo_path(), moved_root.join(".jj").join("repo"));
+
+    // Moving a workspace that doesn't contain the repo only updates the store
+    let moved_ws2_root = ws2
+        .move_to(test_workspace.repo.as_ref(), &env_root.join("moved_ws2"))
+        .unwrap();
+    assert_eq!(
+        store.get_workspace_path(&ws2_id).unwrap(),
+        Some(moved_ws2_root.clone())
+    );
+    let ws2 = Workspace::load(
+        &settings,
+        &moved_ws2_root,
+        &test_workspace.env.default_store_factories(),
+        &default_working_copy_factories(),
+    )
+    .unwrap();
+    assert_eq!(ws2.repo_path(), moved_root.join(".jj").join("repo"));
+}
+
+#[test]
+fn test_move_workspace_with_unknown_workspace() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init_with_backend(&settings, TestRepoBackend::Local);
+    let workspace = &test_workspace.workspace;
+    let env_root = test_workspace.env.root().canonicalize().unwrap();
+
+    let ws2_id = WorkspaceId::new("ws2".to_string());
+    let ws2_root = env_root.join("ws2_root");
+    std::fs::create_dir(&ws2_root).unwrap();
+    Workspace::init_workspace_with_existing_repo(
+        &settings,
+        &ws2_root,
+        workspace.repo_path(),
+        &test_workspace.repo,
+        &*default_working_copy_factory(),
+        ws2_id.clone(),
+    )
+    .unwrap();
+    let repo = workspace.repo_loader().load_at_head(&settings).unwrap();
+
+    // Pretend ws2 was created before the store existed
+    let store = SimpleWorkspaceStore::load(workspace.repo_path());
+    store.forget(slice::from_ref(&ws2_id)).unwrap();
+    assert_matches!(
+        workspace.move_to(repo.as_ref(), &env_root.join("moved")),
+        Err(WorkspaceMoveError::UnknownWorkspaces(ids)) if ids == [ws2_id.clone()]
+    );
+    assert!(workspace.workspace_root().exists());
+    assert_eq!(
+        store.get_workspace_path(&WorkspaceId::default()).unwrap(),
+        Some(env_root.join("repo"))
+    );
+
+    // Loading ws2 records its location, after which the move succeeds
+    Workspace::load(
+        &settings,
+        &ws2_root,
+        &test_workspace.env.default_store_factories(),
+        &default_working_copy_factories(),
+    )
+    .unwrap();
+    assert_eq!(
+        store.get_workspace_path(&ws2_id).unwrap(),
+        Some(ws2_root.clone())
+    );
+    let moved_root = workspace
+        .move_to(repo.as_ref(), &env_root.join("moved"))
+        .unwrap();
+    let ws2 = Workspace::load(
+        &settings,
+        &ws2_root,
+        &test_workspace.env.default_store_factories(),
+        &default_working_copy_factories(),
+    )
+    .unwrap();
+    assert_eq!(ws2.repo_path(), moved_root.join(".jj").join("repo"));
+}
+
+#[test]
+fn test_load_copied_workspace_keeps_recorded_path() {
+    let settings = testutils::user_settings();
+    let test_workspace = TestWorkspace::init(&settings);
+    let workspace = &test_workspace.workspace;
+    let env_root = test_workspace.env.root().canonicalize().unwrap();
+
+    let ws2_id = WorkspaceId::new("ws2".to_string());
+    let ws2_root = env_root.join("ws2_root");
+    std::fs::create_dir(&ws2_root).unwrap();
+    Workspace::init_workspace_with_existing_repo(
+        &settings,
+        &ws2_root,
+        workspace.repo_path(),
+        &test_workspace.repo,
+        &*default_working_copy_factory(),
+        ws2_id.clone(),
+    )
+    .unwrap();
+
+    // Loading a copy of the workspace directory doesn't take over its entry
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir(to).unwrap();
+        for entry in from.read_dir().unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+    let copy_root = env_root.join("ws2_copy");
+    copy_dir(&ws2_root, &copy_root);
+    Workspace::load(
+        &settings,
+        &copy_root,
+        &test_workspace.env.default_store_factories(),
+        &default_working_copy_factories(),
+    )
+    .unwrap();
+    let store = SimpleWorkspaceStore::load(workspace.repo_path());
+    assert_eq!(store.get_workspace_path(&ws2_id).unwrap(), Some(ws2_root));
+}
+
 /// Test cross-thread access to a workspace, which requires it to be Send
 #[test]
 fn test_sendable() {
@@ -32,6 +32,7 @@ use futures::stream::BoxStream;
 use jj_lib::backend::make_root_commit;
 use jj_lib::backend::Backend;
 use jj_lib::backend::BackendError;
+use jj_lib::backend::BackendGcStats;
 use jj_lib::backend::BackendResult;
 use jj_lib::backend::ChangeId;
 use jj_lib::backend::Commit;
@@ -346,7 +347,12 @@ impl Backend for TestBackend {
         Ok(Box::pin(stream::empty()))
     }
 
-    fn gc(&self, _index: &dyn Index, _keep_newer: SystemTime) -> BackendResult<()> {
-        Ok(())
+    fn gc(
+        &self,
+        _index: &dyn Index,
+        _keep_newer: SystemTime,
+        _dry_run: bool,
+    ) -> BackendResult<BackendGcStats> {
+        Ok(BackendGcStats::default())
     }
 }